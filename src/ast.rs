@@ -1,3 +1,7 @@
+pub mod dot;
+pub mod json;
+pub mod sexpr;
+
 use crate::parser::statement::Statement;
 
 pub fn print_ast(statements: Vec<Statement>) {
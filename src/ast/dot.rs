@@ -0,0 +1,203 @@
+use crate::parser::statement::{
+    Expression, ExpressionKind, MatchArm, NodeId, Param, Pattern, PatternKind, Statement,
+    StatementKind,
+};
+
+// Graphviz rendering of the tree. Nodes are named after their `NodeId` and
+// edges are labelled with the child's role, e.g. `condition` or `else`.
+pub fn print_dot(statements: &[Statement]) {
+    let mut writer = DotWriter::default();
+    writer.line("digraph ast {".to_string());
+    writer.line("    node [shape=box, fontname=\"monospace\"];".to_string());
+    writer.line("    program [label=\"Program\"];".to_string());
+    for stmt in statements {
+        writer.statement(stmt);
+        writer.line(format!("    program -> n{};", stmt.id.0));
+    }
+    writer.line("}".to_string());
+    print!("{}", writer.out);
+}
+
+#[derive(Default)]
+struct DotWriter {
+    out: String,
+}
+
+impl DotWriter {
+    fn line(&mut self, line: String) {
+        self.out.push_str(&line);
+        self.out.push('\n');
+    }
+
+    fn node(&mut self, id: NodeId, label: String) {
+        self.line(format!("    n{} [label=\"{}\"];", id.0, escape(&label)));
+    }
+
+    fn edge(&mut self, from: NodeId, to: NodeId, role: &str) {
+        if role.is_empty() {
+            self.line(format!("    n{} -> n{};", from.0, to.0));
+        } else {
+            self.line(format!(
+                "    n{} -> n{} [label=\"{}\"];",
+                from.0, to.0, role
+            ));
+        }
+    }
+
+    fn child_statements(&mut self, parent: NodeId, statements: &[Statement], role: &str) {
+        for stmt in statements {
+            self.statement(stmt);
+            self.edge(parent, stmt.id, role);
+        }
+    }
+
+    fn child_expression(&mut self, parent: NodeId, expr: &Expression, role: &str) {
+        self.expression(expr);
+        self.edge(parent, expr.id, role);
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        let id = stmt.id;
+        match &stmt.kind {
+            StatementKind::VarDeclaration {
+                constant,
+                name,
+                var_type,
+                value,
+            } => {
+                let keyword = if *constant { "const" } else { "let" };
+                let label = match var_type {
+                    Some(t) => format!("VarDeclaration {} {}: {}", keyword, name, t),
+                    None => format!("VarDeclaration {} {}", keyword, name),
+                };
+                self.node(id, label);
+                self.child_expression(id, value, "value");
+            }
+            StatementKind::FunctionCall { name, args } => {
+                self.node(id, format!("FunctionCall {}", name));
+                for arg in args {
+                    self.child_expression(id, arg, "arg");
+                }
+            }
+            StatementKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.node(id, "If".to_string());
+                self.child_expression(id, condition, "condition");
+                self.child_statements(id, then_branch, "then");
+                if let Some(else_branch) = else_branch {
+                    self.child_statements(id, std::slice::from_ref(else_branch), "else");
+                }
+            }
+            StatementKind::Match { scrutinee, arms } => {
+                self.node(id, "Match".to_string());
+                self.child_expression(id, scrutinee, "scrutinee");
+                for arm in arms {
+                    self.arm(arm);
+                    self.edge(id, arm.id, "arm");
+                }
+            }
+            StatementKind::Block(stmts) => {
+                self.node(id, "Block".to_string());
+                self.child_statements(id, stmts, "");
+            }
+            StatementKind::Import { names, path } => {
+                self.node(
+                    id,
+                    format!("Import {{{}}} from {:?}", names.join(", "), path),
+                );
+            }
+            StatementKind::Export(decl) => {
+                self.node(id, "Export".to_string());
+                self.child_statements(id, std::slice::from_ref(decl), "");
+            }
+            StatementKind::Expression(expr) => {
+                self.node(id, "Expression".to_string());
+                self.child_expression(id, expr, "");
+            }
+        }
+    }
+
+    fn arm(&mut self, arm: &MatchArm) {
+        self.node(arm.id, "MatchArm".to_string());
+        for pattern in &arm.patterns {
+            self.pattern(pattern);
+            self.edge(arm.id, pattern.id, "pattern");
+        }
+        self.child_statements(arm.id, &arm.body, "body");
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        let label = match &pattern.kind {
+            PatternKind::StringLiteral(s) => format!("Pattern {:?}", s),
+            _ => format!("Pattern {}", pattern),
+        };
+        self.node(pattern.id, label);
+    }
+
+    fn param(&mut self, param: &Param) {
+        self.node(param.id, format!("Param {}", param));
+    }
+
+    fn expression(&mut self, expr: &Expression) {
+        let id = expr.id;
+        match &expr.kind {
+            ExpressionKind::Identifier(name) => self.node(id, format!("Identifier {}", name)),
+            ExpressionKind::Number(num) => self.node(id, format!("Number {}", num)),
+            ExpressionKind::StringLiteral(s) => self.node(id, format!("StringLiteral {:?}", s)),
+            ExpressionKind::Boolean(b) => self.node(id, format!("Boolean {}", b)),
+            ExpressionKind::BinaryOp(left, op, right) => {
+                self.node(id, format!("BinaryOp {}", op));
+                self.child_expression(id, left, "left");
+                self.child_expression(id, right, "right");
+            }
+            ExpressionKind::LogicalOp(left, op, right) => {
+                self.node(id, format!("LogicalOp {}", op));
+                self.child_expression(id, left, "left");
+                self.child_expression(id, right, "right");
+            }
+            ExpressionKind::ComparisonOp(left, op, right) => {
+                self.node(id, format!("ComparisonOp {}", op));
+                self.child_expression(id, left, "left");
+                self.child_expression(id, right, "right");
+            }
+            ExpressionKind::FunctionCall { name, args } => {
+                self.node(id, format!("FunctionCall {}", name));
+                for arg in args {
+                    self.child_expression(id, arg, "arg");
+                }
+            }
+            ExpressionKind::Call { callee, args } => {
+                self.node(id, "Call".to_string());
+                self.child_expression(id, callee, "callee");
+                for arg in args {
+                    self.child_expression(id, arg, "arg");
+                }
+            }
+            ExpressionKind::Lambda { params, body } => {
+                self.node(id, "Lambda".to_string());
+                for param in params {
+                    self.param(param);
+                    self.edge(id, param.id, "param");
+                }
+                self.child_expression(id, body, "body");
+            }
+            ExpressionKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.node(id, "Conditional".to_string());
+                self.child_expression(id, condition, "condition");
+                self.child_expression(id, then_branch, "then");
+                self.child_expression(id, else_branch, "else");
+            }
+        }
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
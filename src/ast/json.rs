@@ -0,0 +1,585 @@
+use std::collections::HashSet;
+
+use crate::checker::types::Type;
+use crate::json::Json;
+use crate::lexer::token::Span;
+use crate::parser::statement::{
+    ComparisonOperator, Expression, ExpressionKind, LogicalOperator, MatchArm, NodeId, Operator,
+    Param, Pattern, PatternKind, Statement, StatementKind,
+};
+
+// Bumped whenever the schema (documented in README.md) changes incompatibly.
+pub const SCHEMA_VERSION: u32 = 1;
+
+// { "version": 1, "statements": [ ... ] }
+pub fn to_json(statements: &[Statement]) -> Json {
+    Json::Object(vec![
+        ("version".to_string(), Json::Number(SCHEMA_VERSION as f64)),
+        ("statements".to_string(), statements_to_json(statements)),
+    ])
+}
+
+fn statements_to_json(statements: &[Statement]) -> Json {
+    Json::Array(statements.iter().map(statement_to_json).collect())
+}
+
+fn expressions_to_json(expressions: &[Expression]) -> Json {
+    Json::Array(expressions.iter().map(expression_to_json).collect())
+}
+
+fn strings_to_json(strings: &[String]) -> Json {
+    Json::Array(strings.iter().cloned().map(Json::String).collect())
+}
+
+fn span_to_json(span: Span) -> Json {
+    Json::Object(vec![
+        ("start".to_string(), Json::Number(span.start as f64)),
+        ("end".to_string(), Json::Number(span.end as f64)),
+        ("line".to_string(), Json::Number(span.line as f64)),
+        ("column".to_string(), Json::Number(span.column as f64)),
+    ])
+}
+
+// Every node object starts with its kind (if any), id and span.
+fn node(kind: Option<&str>, id: NodeId, span: Span, fields: Vec<(&str, Json)>) -> Json {
+    let mut object = Vec::new();
+    if let Some(kind) = kind {
+        object.push(("kind".to_string(), Json::String(kind.to_string())));
+    }
+    object.push(("id".to_string(), Json::Number(id.0 as f64)));
+    object.push(("span".to_string(), span_to_json(span)));
+    for (key, value) in fields {
+        object.push((key.to_string(), value));
+    }
+    Json::Object(object)
+}
+
+fn statement_to_json(stmt: &Statement) -> Json {
+    let (kind, fields) = match &stmt.kind {
+        StatementKind::VarDeclaration {
+            constant,
+            name,
+            var_type,
+            value,
+        } => (
+            "VarDeclaration",
+            vec![
+                ("constant", Json::Bool(*constant)),
+                ("name", Json::String(name.clone())),
+                (
+                    "var_type",
+                    var_type.clone().map(Json::String).unwrap_or(Json::Null),
+                ),
+                ("value", expression_to_json(value)),
+            ],
+        ),
+        StatementKind::FunctionCall { name, args } => (
+            "FunctionCall",
+            vec![
+                ("name", Json::String(name.clone())),
+                ("args", expressions_to_json(args)),
+            ],
+        ),
+        StatementKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => (
+            "If",
+            vec![
+                ("condition", expression_to_json(condition)),
+                ("then_branch", statements_to_json(then_branch)),
+                (
+                    "else_branch",
+                    match else_branch {
+                        Some(else_branch) => statement_to_json(else_branch),
+                        None => Json::Null,
+                    },
+                ),
+            ],
+        ),
+        StatementKind::Match { scrutinee, arms } => (
+            "Match",
+            vec![
+                ("scrutinee", expression_to_json(scrutinee)),
+                ("arms", Json::Array(arms.iter().map(arm_to_json).collect())),
+            ],
+        ),
+        StatementKind::Block(stmts) => ("Block", vec![("body", statements_to_json(stmts))]),
+        StatementKind::Import { names, path } => (
+            "Import",
+            vec![
+                ("names", strings_to_json(names)),
+                ("path", Json::String(path.clone())),
+            ],
+        ),
+        StatementKind::Export(decl) => ("Export", vec![("declaration", statement_to_json(decl))]),
+        StatementKind::Expression(expr) => {
+            ("Expression", vec![("expression", expression_to_json(expr))])
+        }
+    };
+    node(Some(kind), stmt.id, stmt.span, fields)
+}
+
+fn arm_to_json(arm: &MatchArm) -> Json {
+    node(
+        None,
+        arm.id,
+        arm.span,
+        vec![
+            (
+                "patterns",
+                Json::Array(arm.patterns.iter().map(pattern_to_json).collect()),
+            ),
+            ("body", statements_to_json(&arm.body)),
+        ],
+    )
+}
+
+fn pattern_to_json(pattern: &Pattern) -> Json {
+    let (kind, fields) = match &pattern.kind {
+        PatternKind::Number(num) => ("Number", vec![("value", Json::Number(*num))]),
+        PatternKind::StringLiteral(s) => {
+            ("StringLiteral", vec![("value", Json::String(s.clone()))])
+        }
+        PatternKind::Boolean(b) => ("Boolean", vec![("value", Json::Bool(*b))]),
+        PatternKind::Range {
+            start,
+            end,
+            inclusive,
+        } => (
+            "Range",
+            vec![
+                ("start", Json::Number(*start)),
+                ("end", Json::Number(*end)),
+                ("inclusive", Json::Bool(*inclusive)),
+            ],
+        ),
+        PatternKind::Wildcard => ("Wildcard", vec![]),
+    };
+    node(Some(kind), pattern.id, pattern.span, fields)
+}
+
+fn param_to_json(param: &Param) -> Json {
+    node(
+        None,
+        param.id,
+        param.span,
+        vec![
+            ("name", Json::String(param.name.clone())),
+            (
+                "param_type",
+                param
+                    .param_type
+                    .clone()
+                    .map(Json::String)
+                    .unwrap_or(Json::Null),
+            ),
+        ],
+    )
+}
+
+fn expression_to_json(expr: &Expression) -> Json {
+    let (kind, fields) = match &expr.kind {
+        ExpressionKind::Identifier(name) => {
+            ("Identifier", vec![("name", Json::String(name.clone()))])
+        }
+        ExpressionKind::Number(num) => ("Number", vec![("value", Json::Number(*num))]),
+        ExpressionKind::StringLiteral(s) => {
+            ("StringLiteral", vec![("value", Json::String(s.clone()))])
+        }
+        ExpressionKind::Boolean(b) => ("Boolean", vec![("value", Json::Bool(*b))]),
+        ExpressionKind::BinaryOp(left, op, right) => {
+            ("BinaryOp", operands_to_json(left, op.to_string(), right))
+        }
+        ExpressionKind::LogicalOp(left, op, right) => {
+            ("LogicalOp", operands_to_json(left, op.to_string(), right))
+        }
+        ExpressionKind::ComparisonOp(left, op, right) => (
+            "ComparisonOp",
+            operands_to_json(left, op.to_string(), right),
+        ),
+        ExpressionKind::FunctionCall { name, args } => (
+            "FunctionCall",
+            vec![
+                ("name", Json::String(name.clone())),
+                ("args", expressions_to_json(args)),
+            ],
+        ),
+        ExpressionKind::Call { callee, args } => (
+            "Call",
+            vec![
+                ("callee", expression_to_json(callee)),
+                ("args", expressions_to_json(args)),
+            ],
+        ),
+        ExpressionKind::Lambda { params, body } => (
+            "Lambda",
+            vec![
+                (
+                    "params",
+                    Json::Array(params.iter().map(param_to_json).collect()),
+                ),
+                ("body", expression_to_json(body)),
+            ],
+        ),
+        ExpressionKind::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => (
+            "Conditional",
+            vec![
+                ("condition", expression_to_json(condition)),
+                ("then_branch", expression_to_json(then_branch)),
+                ("else_branch", expression_to_json(else_branch)),
+            ],
+        ),
+    };
+    node(Some(kind), expr.id, expr.span, fields)
+}
+
+fn operands_to_json(
+    left: &Expression,
+    operator: String,
+    right: &Expression,
+) -> Vec<(&'static str, Json)> {
+    vec![
+        ("operator", Json::String(operator)),
+        ("left", expression_to_json(left)),
+        ("right", expression_to_json(right)),
+    ]
+}
+
+// Rebuilds the AST from a document produced by `to_json` or by an external
+// tool. `id` and `span` are optional on input; missing spans default to zero.
+// Ids are moved up by `next_id`, so that a document decoded into a module
+// graph keeps clear of the ids other modules already have, and missing ids
+// are numbered after the largest one. The same id twice is an error.
+#[derive(Default)]
+pub struct Decoder {
+    pub next_id: u32,
+    base: u32,
+    seen: HashSet<u32>, // ids of the input
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Decoder::default()
+    }
+
+    pub fn decode(&mut self, json: &Json) -> Result<Vec<Statement>, String> {
+        let version = number_field(json, "version")?;
+        if version != SCHEMA_VERSION as f64 {
+            return Err(format!(
+                "Unsupported schema version {}, expected {}",
+                version, SCHEMA_VERSION
+            ));
+        }
+        self.base = self.next_id;
+        self.seen.clear();
+        if let Some(largest) = largest_id(json)? {
+            self.next_id = self.offset(largest)? + 1;
+        }
+        self.statements(field(json, "statements")?)
+    }
+
+    fn offset(&self, id: u32) -> Result<u32, String> {
+        self.base
+            .checked_add(id)
+            .filter(|&id| id < u32::MAX)
+            .ok_or_else(|| format!("Node id {} is too large", id))
+    }
+
+    fn statements(&mut self, json: &Json) -> Result<Vec<Statement>, String> {
+        as_array(json)?
+            .iter()
+            .map(|stmt| self.statement(stmt))
+            .collect()
+    }
+
+    fn expressions(&mut self, json: &Json) -> Result<Vec<Expression>, String> {
+        as_array(json)?
+            .iter()
+            .map(|expr| self.expression(expr))
+            .collect()
+    }
+
+    fn boxed(&mut self, json: &Json, key: &str) -> Result<Box<Expression>, String> {
+        Ok(Box::new(self.expression(field(json, key)?)?))
+    }
+
+    fn id(&mut self, json: &Json) -> Result<NodeId, String> {
+        match json.get("id") {
+            Some(id) => {
+                let id = as_index(id, "id")?;
+                if !self.seen.insert(id) {
+                    return Err(format!("Duplicate node id {}", id));
+                }
+                Ok(NodeId(self.offset(id)?))
+            }
+            None => {
+                let id = NodeId(self.next_id);
+                self.next_id += 1;
+                Ok(id)
+            }
+        }
+    }
+
+    fn statement(&mut self, json: &Json) -> Result<Statement, String> {
+        let id = self.id(json)?;
+        let span = span(json)?;
+        let kind = match string_field(json, "kind")? {
+            "VarDeclaration" => StatementKind::VarDeclaration {
+                constant: match json.get("constant") {
+                    Some(_) => bool_field(json, "constant")?,
+                    None => false,
+                },
+                name: string_field(json, "name")?.to_string(),
+                var_type: type_field(json, "var_type")?,
+                value: self.expression(field(json, "value")?)?,
+            },
+            "FunctionCall" => StatementKind::FunctionCall {
+                name: string_field(json, "name")?.to_string(),
+                args: self.expressions(field(json, "args")?)?,
+            },
+            "If" => StatementKind::If {
+                condition: self.expression(field(json, "condition")?)?,
+                then_branch: self.statements(field(json, "then_branch")?)?,
+                else_branch: match json.get("else_branch") {
+                    Some(Json::Null) | None => None,
+                    Some(else_branch) => Some(Box::new(self.statement(else_branch)?)),
+                },
+            },
+            "Match" => StatementKind::Match {
+                scrutinee: self.expression(field(json, "scrutinee")?)?,
+                arms: as_array(field(json, "arms")?)?
+                    .iter()
+                    .map(|arm| self.arm(arm))
+                    .collect::<Result<_, _>>()?,
+            },
+            "Block" => StatementKind::Block(self.statements(field(json, "body")?)?),
+            "Import" => StatementKind::Import {
+                names: as_array(field(json, "names")?)?
+                    .iter()
+                    .map(|name| as_str(name, "names").map(str::to_string))
+                    .collect::<Result<_, _>>()?,
+                path: string_field(json, "path")?.to_string(),
+            },
+            "Export" => {
+                StatementKind::Export(Box::new(self.statement(field(json, "declaration")?)?))
+            }
+            "Expression" => StatementKind::Expression(self.expression(field(json, "expression")?)?),
+            other => return Err(format!("Unknown statement kind `{}`", other)),
+        };
+        Ok(Statement { kind, span, id })
+    }
+
+    fn arm(&mut self, json: &Json) -> Result<MatchArm, String> {
+        let id = self.id(json)?;
+        Ok(MatchArm {
+            patterns: as_array(field(json, "patterns")?)?
+                .iter()
+                .map(|pattern| self.pattern(pattern))
+                .collect::<Result<_, _>>()?,
+            body: self.statements(field(json, "body")?)?,
+            span: span(json)?,
+            id,
+        })
+    }
+
+    fn pattern(&mut self, json: &Json) -> Result<Pattern, String> {
+        let id = self.id(json)?;
+        let kind = match string_field(json, "kind")? {
+            "Number" => PatternKind::Number(number_field(json, "value")?),
+            "StringLiteral" => PatternKind::StringLiteral(string_field(json, "value")?.to_string()),
+            "Boolean" => PatternKind::Boolean(bool_field(json, "value")?),
+            "Range" => PatternKind::Range {
+                start: number_field(json, "start")?,
+                end: number_field(json, "end")?,
+                inclusive: bool_field(json, "inclusive")?,
+            },
+            "Wildcard" => PatternKind::Wildcard,
+            other => return Err(format!("Unknown pattern kind `{}`", other)),
+        };
+        Ok(Pattern {
+            kind,
+            span: span(json)?,
+            id,
+        })
+    }
+
+    fn param(&mut self, json: &Json) -> Result<Param, String> {
+        let id = self.id(json)?;
+        Ok(Param {
+            name: string_field(json, "name")?.to_string(),
+            param_type: type_field(json, "param_type")?,
+            span: span(json)?,
+            id,
+        })
+    }
+
+    fn expression(&mut self, json: &Json) -> Result<Expression, String> {
+        let id = self.id(json)?;
+        let span = span(json)?;
+        let kind = match string_field(json, "kind")? {
+            "Identifier" => ExpressionKind::Identifier(string_field(json, "name")?.to_string()),
+            "Number" => ExpressionKind::Number(number_field(json, "value")?),
+            "StringLiteral" => {
+                ExpressionKind::StringLiteral(string_field(json, "value")?.to_string())
+            }
+            "Boolean" => ExpressionKind::Boolean(bool_field(json, "value")?),
+            "BinaryOp" => {
+                let op = match string_field(json, "operator")? {
+                    "+" => Operator::Add,
+                    "-" => Operator::Sub,
+                    "*" => Operator::Mul,
+                    "/" => Operator::Div,
+                    other => return Err(format!("Unknown binary operator `{}`", other)),
+                };
+                ExpressionKind::BinaryOp(self.boxed(json, "left")?, op, self.boxed(json, "right")?)
+            }
+            "LogicalOp" => {
+                let op = match string_field(json, "operator")? {
+                    "&&" => LogicalOperator::And,
+                    "||" => LogicalOperator::Or,
+                    other => return Err(format!("Unknown logical operator `{}`", other)),
+                };
+                ExpressionKind::LogicalOp(self.boxed(json, "left")?, op, self.boxed(json, "right")?)
+            }
+            "ComparisonOp" => {
+                let op = match string_field(json, "operator")? {
+                    "==" => ComparisonOperator::Equal,
+                    "!=" => ComparisonOperator::NotEqual,
+                    "<" => ComparisonOperator::LessThan,
+                    ">" => ComparisonOperator::GreaterThan,
+                    "<=" => ComparisonOperator::LessThanOrEqual,
+                    ">=" => ComparisonOperator::GreaterThanOrEqual,
+                    other => return Err(format!("Unknown comparison operator `{}`", other)),
+                };
+                ExpressionKind::ComparisonOp(
+                    self.boxed(json, "left")?,
+                    op,
+                    self.boxed(json, "right")?,
+                )
+            }
+            "FunctionCall" => ExpressionKind::FunctionCall {
+                name: string_field(json, "name")?.to_string(),
+                args: self.expressions(field(json, "args")?)?,
+            },
+            "Call" => ExpressionKind::Call {
+                callee: self.boxed(json, "callee")?,
+                args: self.expressions(field(json, "args")?)?,
+            },
+            "Lambda" => ExpressionKind::Lambda {
+                params: as_array(field(json, "params")?)?
+                    .iter()
+                    .map(|param| self.param(param))
+                    .collect::<Result<_, _>>()?,
+                body: self.boxed(json, "body")?,
+            },
+            "Conditional" => ExpressionKind::Conditional {
+                condition: self.boxed(json, "condition")?,
+                then_branch: self.boxed(json, "then_branch")?,
+                else_branch: self.boxed(json, "else_branch")?,
+            },
+            other => return Err(format!("Unknown expression kind `{}`", other)),
+        };
+        Ok(Expression { kind, span, id })
+    }
+}
+
+// The largest `id` of any node in the document, if any has one.
+fn largest_id(json: &Json) -> Result<Option<u32>, String> {
+    let mut largest = None;
+    match json {
+        Json::Object(fields) => {
+            for (key, value) in fields {
+                let id = match (key.as_str(), value) {
+                    ("id", Json::Number(_)) => Some(as_index(value, "id")?),
+                    _ => largest_id(value)?,
+                };
+                largest = largest.max(id);
+            }
+        }
+        Json::Array(items) => {
+            for item in items {
+                largest = largest.max(largest_id(item)?);
+            }
+        }
+        _ => {}
+    }
+    Ok(largest)
+}
+
+fn span(json: &Json) -> Result<Span, String> {
+    match json.get("span") {
+        Some(span) => Ok(Span {
+            start: as_index(field(span, "start")?, "start")? as usize,
+            end: as_index(field(span, "end")?, "end")? as usize,
+            line: as_index(field(span, "line")?, "line")? as usize,
+            column: as_index(field(span, "column")?, "column")? as usize,
+        }),
+        None => Ok(Span::default()),
+    }
+}
+
+fn field<'a>(json: &'a Json, key: &str) -> Result<&'a Json, String> {
+    match json {
+        Json::Object(_) => json
+            .get(key)
+            .ok_or_else(|| format!("Missing field `{}`", key)),
+        _ => Err(format!("Expected an object with field `{}`", key)),
+    }
+}
+
+fn string_field<'a>(json: &'a Json, key: &str) -> Result<&'a str, String> {
+    as_str(field(json, key)?, key)
+}
+
+fn optional_string_field(json: &Json, key: &str) -> Result<Option<String>, String> {
+    match json.get(key) {
+        Some(Json::Null) | None => Ok(None),
+        Some(value) => Ok(Some(as_str(value, key)?.to_string())),
+    }
+}
+
+// A type annotation, which must name one of the language's types.
+fn type_field(json: &Json, key: &str) -> Result<Option<String>, String> {
+    let name = optional_string_field(json, key)?;
+    match &name {
+        Some(type_name) if Type::from_name(type_name) == Type::Unknown => {
+            Err(format!("Field `{}` has unknown type `{}`", key, type_name))
+        }
+        _ => Ok(name),
+    }
+}
+
+fn number_field(json: &Json, key: &str) -> Result<f64, String> {
+    field(json, key)?
+        .as_f64()
+        .ok_or_else(|| format!("Field `{}` must be a number", key))
+}
+
+fn bool_field(json: &Json, key: &str) -> Result<bool, String> {
+    field(json, key)?
+        .as_bool()
+        .ok_or_else(|| format!("Field `{}` must be a boolean", key))
+}
+
+fn as_str<'a>(json: &'a Json, key: &str) -> Result<&'a str, String> {
+    json.as_str()
+        .ok_or_else(|| format!("Field `{}` must be a string", key))
+}
+
+fn as_array(json: &Json) -> Result<&[Json], String> {
+    json.as_array()
+        .ok_or_else(|| "Expected an array".to_string())
+}
+
+fn as_index(json: &Json, key: &str) -> Result<u32, String> {
+    match json.as_f64() {
+        Some(num) if num >= 0.0 && num.fract() == 0.0 && num <= u32::MAX as f64 => Ok(num as u32),
+        _ => Err(format!("Field `{}` must be a non-negative integer", key)),
+    }
+}
@@ -0,0 +1,140 @@
+use crate::parser::statement::{
+    Expression, ExpressionKind, MatchArm, Param, Pattern, PatternKind, Statement, StatementKind,
+};
+
+// Compact S-expression rendering, one top-level statement per line:
+// let x: i32 = 100 * 100 + 2;  =>  (let x i32 (+ (* 100 100) 2))
+pub fn print_sexpr(statements: &[Statement]) {
+    for stmt in statements {
+        println!("{}", statement(stmt));
+    }
+}
+
+fn list(head: &str, items: impl IntoIterator<Item = String>) -> String {
+    let mut parts = vec![head.to_string()];
+    parts.extend(items);
+    format!("({})", parts.join(" "))
+}
+
+fn block(statements: &[Statement]) -> String {
+    list("block", statements.iter().map(statement))
+}
+
+fn statement(stmt: &Statement) -> String {
+    match &stmt.kind {
+        StatementKind::VarDeclaration {
+            constant,
+            name,
+            var_type,
+            value,
+        } => {
+            let mut items = vec![name.clone()];
+            items.extend(var_type.clone());
+            items.push(expression(value));
+            list(if *constant { "const" } else { "let" }, items)
+        }
+        StatementKind::FunctionCall { name, args } => call(name.clone(), args),
+        StatementKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            let mut items = vec![expression(condition), block(then_branch)];
+            items.extend(else_branch.as_deref().map(statement));
+            list("if", items)
+        }
+        StatementKind::Match { scrutinee, arms } => {
+            let mut items = vec![expression(scrutinee)];
+            items.extend(arms.iter().map(arm));
+            list("match", items)
+        }
+        StatementKind::Block(stmts) => block(stmts),
+        StatementKind::Import { names, path } => list(
+            "import",
+            [format!("({})", names.join(" ")), format!("{:?}", path)],
+        ),
+        StatementKind::Export(decl) => list("export", [statement(decl)]),
+        StatementKind::Expression(expr) => expression(expr),
+    }
+}
+
+fn arm(arm: &MatchArm) -> String {
+    let patterns = format!(
+        "({})",
+        arm.patterns
+            .iter()
+            .map(pattern)
+            .collect::<Vec<String>>()
+            .join(" ")
+    );
+    list("arm", [patterns, block(&arm.body)])
+}
+
+fn pattern(pattern: &Pattern) -> String {
+    match &pattern.kind {
+        PatternKind::Number(num) => format!("{}", num),
+        PatternKind::StringLiteral(s) => format!("{:?}", s),
+        PatternKind::Boolean(b) => format!("{}", b),
+        PatternKind::Range {
+            start,
+            end,
+            inclusive,
+        } => {
+            let op_str = if *inclusive { "..=" } else { ".." };
+            list(op_str, [start.to_string(), end.to_string()])
+        }
+        PatternKind::Wildcard => "_".to_string(),
+    }
+}
+
+fn param(param: &Param) -> String {
+    match &param.param_type {
+        Some(t) => format!("({} {})", param.name, t),
+        None => param.name.clone(),
+    }
+}
+
+fn call(callee: String, args: &[Expression]) -> String {
+    let mut items = vec![callee];
+    items.extend(args.iter().map(expression));
+    list("call", items)
+}
+
+fn expression(expr: &Expression) -> String {
+    match &expr.kind {
+        ExpressionKind::Identifier(name) => name.clone(),
+        ExpressionKind::Number(num) => format!("{}", num),
+        ExpressionKind::StringLiteral(s) => format!("{:?}", s),
+        ExpressionKind::Boolean(b) => format!("{}", b),
+        ExpressionKind::BinaryOp(left, op, right) => {
+            list(&op.to_string(), [expression(left), expression(right)])
+        }
+        ExpressionKind::LogicalOp(left, op, right) => {
+            list(&op.to_string(), [expression(left), expression(right)])
+        }
+        ExpressionKind::ComparisonOp(left, op, right) => {
+            list(&op.to_string(), [expression(left), expression(right)])
+        }
+        ExpressionKind::FunctionCall { name, args } => call(name.clone(), args),
+        ExpressionKind::Call { callee, args } => call(expression(callee), args),
+        ExpressionKind::Lambda { params, body } => {
+            let params_str = format!(
+                "({})",
+                params.iter().map(param).collect::<Vec<String>>().join(" ")
+            );
+            list("lambda", [params_str, expression(body)])
+        }
+        ExpressionKind::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => list(
+            "if",
+            [
+                expression(condition),
+                expression(then_branch),
+                expression(else_branch),
+            ],
+        ),
+    }
+}
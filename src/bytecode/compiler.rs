@@ -0,0 +1,473 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::bytecode::instruction::{Function, Instruction, Program};
+use crate::bytecode::value::Value;
+use crate::checker::types::Type;
+use crate::parser::statement::{
+    ComparisonOperator, Expression, ExpressionKind, LogicalOperator, MatchArm, Operator, Param,
+    Pattern, Statement, StatementKind,
+};
+
+#[derive(Default)]
+struct Scope {
+    names: HashMap<String, u16>, // name -> slot in the enclosing function
+    // Lambda bodies look names up when called, by which time the rest of the
+    // scope has been declared, so they are compiled when the scope closes.
+    deferred: Vec<Lambda>,
+}
+
+struct Lambda {
+    index: usize, // into `functions`
+    name: String,
+    expr: Expression,
+}
+
+// A function being compiled, with its block scopes.
+struct State {
+    function: Function,
+    scopes: Vec<Scope>,
+}
+
+#[derive(Default)]
+pub struct Compiler {
+    states: Vec<State>, // innermost function last
+    functions: Vec<Option<Function>>,
+    lambda_name: Option<String>, // the variable the next lambda is bound to
+    line: usize,
+    error: Option<String>,
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Compiler::default()
+    }
+
+    pub fn compile(statements: &[Statement]) -> Result<Program, String> {
+        let mut compiler = Compiler::new();
+        compiler.functions.push(None);
+        compiler.states.push(State {
+            function: Function {
+                name: "<script>".to_string(),
+                ..Function::default()
+            },
+            scopes: Vec::new(),
+        });
+        compiler.push_scope();
+
+        // imported names are bound before the module runs
+        let mut imports = Vec::new();
+        for stmt in statements {
+            if let StatementKind::Import { names, .. } = &stmt.kind {
+                for name in names {
+                    imports.push((name.clone(), compiler.declare(name)));
+                }
+            }
+        }
+
+        for stmt in statements {
+            compiler.statement(stmt);
+        }
+        compiler.emit(Instruction::Unit);
+        compiler.emit(Instruction::Return);
+
+        let globals = compiler.scope().names.clone();
+        compiler.pop_scope();
+        let script = compiler.states.pop().map(|state| state.function);
+        compiler.functions[0] = script;
+
+        if let Some(error) = compiler.error {
+            return Err(error);
+        }
+        Ok(Program {
+            functions: compiler
+                .functions
+                .into_iter()
+                .map(|function| Rc::new(function.unwrap_or_default()))
+                .collect(),
+            imports,
+            globals,
+        })
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        self.line = stmt.span.line;
+        match &stmt.kind {
+            StatementKind::VarDeclaration {
+                name,
+                var_type,
+                value,
+                ..
+            } => {
+                // the value is compiled before the name exists, so
+                // `let x = x + 1;` refers to an outer `x`
+                if let ExpressionKind::Lambda { .. } = value.kind {
+                    self.lambda_name = Some(name.clone());
+                }
+                self.expression(value);
+                self.wrap(var_type);
+                let slot = self.declare(name);
+                self.emit(Instruction::Store(slot));
+            }
+            StatementKind::FunctionCall { name, args } => {
+                self.call(name, args);
+                self.emit(Instruction::Pop);
+            }
+            StatementKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expression(condition);
+                let to_else = self.emit_jump(Instruction::JumpIfFalse(0));
+                self.block(then_branch);
+                match else_branch {
+                    Some(else_branch) => {
+                        let to_end = self.emit_jump(Instruction::Jump(0));
+                        self.patch(to_else);
+                        self.statement(else_branch);
+                        self.patch(to_end);
+                    }
+                    None => self.patch(to_else),
+                }
+            }
+            StatementKind::Match { scrutinee, arms } => {
+                self.expression(scrutinee);
+                self.match_arms(arms);
+            }
+            StatementKind::Block(stmts) => self.block(stmts),
+            // bound by `compile`
+            StatementKind::Import { .. } => {}
+            StatementKind::Export(stmt) => self.statement(stmt),
+            StatementKind::Expression(expr) => {
+                self.expression(expr);
+                self.emit(Instruction::Pop);
+            }
+        }
+    }
+
+    /*
+        <scrutinee>
+    arm:
+        Test p1, JumpIfTrue body
+        Test p2, JumpIfTrue body
+        Jump next arm
+    body:
+        Pop, <block>, Jump end
+        ...
+        Pop
+    end:
+    */
+    fn match_arms(&mut self, arms: &[MatchArm]) {
+        let mut to_end = Vec::new();
+        for arm in arms {
+            let mut to_body = Vec::new();
+            for pattern in &arm.patterns {
+                let index = self.pattern(pattern.clone());
+                self.emit(Instruction::Test(index));
+                to_body.push(self.emit_jump(Instruction::JumpIfTrue(0)));
+            }
+            let to_next = self.emit_jump(Instruction::Jump(0));
+
+            for jump in to_body {
+                self.patch(jump);
+            }
+            self.emit(Instruction::Pop);
+            self.block(&arm.body);
+            to_end.push(self.emit_jump(Instruction::Jump(0)));
+            self.patch(to_next);
+        }
+        self.emit(Instruction::Pop);
+        for jump in to_end {
+            self.patch(jump);
+        }
+    }
+
+    fn block(&mut self, stmts: &[Statement]) {
+        self.push_scope();
+        for stmt in stmts {
+            self.statement(stmt);
+        }
+        self.pop_scope();
+    }
+
+    fn expression(&mut self, expr: &Expression) {
+        self.line = expr.span.line;
+        match &expr.kind {
+            ExpressionKind::Identifier(name) => match self.resolve(name) {
+                Some((depth, slot)) => self.emit(Instruction::Load { depth, slot }),
+                None => {
+                    let index = self.constant(Value::String(name.as_str().into()));
+                    self.emit(Instruction::Undefined(index));
+                }
+            },
+            ExpressionKind::Number(num) => {
+                let index = self.constant(Value::Number(*num));
+                self.emit(Instruction::Constant(index));
+            }
+            ExpressionKind::StringLiteral(s) => {
+                let index = self.constant(Value::String(s.as_str().into()));
+                self.emit(Instruction::Constant(index));
+            }
+            ExpressionKind::Boolean(true) => self.emit(Instruction::True),
+            ExpressionKind::Boolean(false) => self.emit(Instruction::False),
+            ExpressionKind::BinaryOp(left, op, right) => {
+                self.expression(left);
+                self.expression(right);
+                self.emit(match op {
+                    Operator::Add => Instruction::Add,
+                    Operator::Sub => Instruction::Sub,
+                    Operator::Mul => Instruction::Mul,
+                    Operator::Div => Instruction::Div,
+                });
+            }
+            ExpressionKind::ComparisonOp(left, op, right) => {
+                self.expression(left);
+                self.expression(right);
+                self.emit(match op {
+                    ComparisonOperator::Equal => Instruction::Equal,
+                    ComparisonOperator::NotEqual => Instruction::NotEqual,
+                    ComparisonOperator::LessThan => Instruction::Less,
+                    ComparisonOperator::GreaterThan => Instruction::Greater,
+                    ComparisonOperator::LessThanOrEqual => Instruction::LessEqual,
+                    ComparisonOperator::GreaterThanOrEqual => Instruction::GreaterEqual,
+                });
+            }
+            ExpressionKind::LogicalOp(left, op, right) => {
+                // the right operand only runs when the left one does not
+                // decide the result
+                self.expression(left);
+                let to_short = self.emit_jump(match op {
+                    LogicalOperator::And => Instruction::JumpIfFalse(0),
+                    LogicalOperator::Or => Instruction::JumpIfTrue(0),
+                });
+                self.expression(right);
+                self.emit(Instruction::Truthy);
+                let to_end = self.emit_jump(Instruction::Jump(0));
+                self.patch(to_short);
+                self.emit(match op {
+                    LogicalOperator::And => Instruction::False,
+                    LogicalOperator::Or => Instruction::True,
+                });
+                self.patch(to_end);
+            }
+            ExpressionKind::FunctionCall { name, args } => self.call(name, args),
+            ExpressionKind::Call { callee, args } => {
+                self.expression(callee);
+                for arg in args {
+                    self.expression(arg);
+                }
+                let argc = self.index(args.len(), u8::MAX as usize, "arguments");
+                self.emit(Instruction::Call(argc as u8));
+            }
+            ExpressionKind::Lambda { .. } => {
+                let index = self.functions.len();
+                self.functions.push(None);
+                let name = self
+                    .lambda_name
+                    .take()
+                    .unwrap_or_else(|| format!("lambda@{}:{}", expr.span.line, expr.span.column));
+                self.scope().deferred.push(Lambda {
+                    index,
+                    name,
+                    expr: expr.clone(),
+                });
+                let index = self.index(index, u16::MAX as usize, "functions");
+                self.emit(Instruction::Closure(index));
+            }
+            ExpressionKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expression(condition);
+                let to_else = self.emit_jump(Instruction::JumpIfFalse(0));
+                self.expression(then_branch);
+                let to_end = self.emit_jump(Instruction::Jump(0));
+                self.patch(to_else);
+                self.expression(else_branch);
+                self.patch(to_end);
+            }
+        }
+    }
+
+    // Calls through a name fall back to the builtins when it is not declared.
+    fn call(&mut self, name: &str, args: &[Expression]) {
+        let callee = self.resolve(name);
+        if let Some((depth, slot)) = callee {
+            self.emit(Instruction::Load { depth, slot });
+        }
+        for arg in args {
+            self.expression(arg);
+        }
+        let argc = self.index(args.len(), u8::MAX as usize, "arguments") as u8;
+        match callee {
+            Some(_) => self.emit(Instruction::Call(argc)),
+            None if name == "print" => self.emit(Instruction::Print(argc)),
+            None => {
+                let index = self.constant(Value::String(name.into()));
+                self.emit(Instruction::UndefinedFunction(index));
+            }
+        }
+    }
+
+    fn lambda(&mut self, lambda: Lambda) {
+        let ExpressionKind::Lambda { params, body } = &lambda.expr.kind else {
+            return;
+        };
+        self.line = lambda.expr.span.line;
+        self.states.push(State {
+            function: Function {
+                name: lambda.name,
+                params: params.iter().map(|param| param.name.clone()).collect(),
+                ..Function::default()
+            },
+            scopes: Vec::new(),
+        });
+        self.push_scope();
+
+        // arguments arrive in the first slots and are wrapped like declarations
+        let slots: Vec<u16> = params
+            .iter()
+            .map(|param| self.declare(&param.name))
+            .collect();
+        for (Param { param_type, .. }, &slot) in params.iter().zip(&slots) {
+            if wrap_type(param_type).is_some() {
+                self.emit(Instruction::Load { depth: 0, slot });
+                self.wrap(param_type);
+                self.emit(Instruction::Store(slot));
+            }
+        }
+
+        self.expression(body);
+        self.emit(Instruction::Return);
+        self.pop_scope();
+
+        let function = self.states.pop().map(|state| state.function);
+        self.functions[lambda.index] = function;
+    }
+
+    fn wrap(&mut self, var_type: &Option<String>) {
+        if let Some(ty) = wrap_type(var_type) {
+            self.emit(Instruction::Wrap(ty));
+        }
+    }
+
+    fn push_scope(&mut self) {
+        self.state().scopes.push(Scope::default());
+    }
+
+    fn pop_scope(&mut self) {
+        while let Some(lambda) = self.scope().deferred.pop() {
+            self.lambda(lambda);
+        }
+        self.state().scopes.pop();
+    }
+
+    fn declare(&mut self, name: &str) -> u16 {
+        let locals = &mut self.state().function.locals;
+        locals.push(name.to_string());
+        let slot = locals.len() - 1;
+        let slot = self.index(slot, u16::MAX as usize, "locals");
+        self.scope().names.insert(name.to_string(), slot);
+        slot
+    }
+
+    // The function depth and slot `name` refers to.
+    fn resolve(&self, name: &str) -> Option<(u16, u16)> {
+        self.states
+            .iter()
+            .rev()
+            .enumerate()
+            .find_map(|(depth, state)| {
+                let slot = state
+                    .scopes
+                    .iter()
+                    .rev()
+                    .find_map(|scope| scope.names.get(name).copied())?;
+                Some((depth as u16, slot))
+            })
+    }
+
+    fn constant(&mut self, value: Value) -> u16 {
+        let constants = &mut self.state().function.constants;
+        let index = match constants.iter().position(|c| same_constant(c, &value)) {
+            Some(index) => index,
+            None => {
+                constants.push(value);
+                constants.len() - 1
+            }
+        };
+        self.index(index, u16::MAX as usize, "constants")
+    }
+
+    fn pattern(&mut self, pattern: Pattern) -> u16 {
+        let patterns = &mut self.state().function.patterns;
+        patterns.push(pattern);
+        let index = patterns.len() - 1;
+        self.index(index, u16::MAX as usize, "patterns")
+    }
+
+    // Narrows a table index to its operand size, recording an error if it
+    // does not fit.
+    fn index(&mut self, index: usize, max: usize, what: &str) -> u16 {
+        if index > max && self.error.is_none() {
+            self.error = Some(format!(
+                "Too many {}, at most {} are supported",
+                what,
+                max + 1
+            ));
+        }
+        index as u16
+    }
+
+    fn emit(&mut self, instruction: Instruction) {
+        let line = self.line;
+        let function = &mut self.state().function;
+        function.code.push(instruction);
+        function.lines.push(line);
+    }
+
+    // Emits a jump to be patched later; returns its offset.
+    fn emit_jump(&mut self, instruction: Instruction) -> usize {
+        self.emit(instruction);
+        self.state().function.code.len() - 1
+    }
+
+    // Points the jump at `offset` to the next instruction.
+    fn patch(&mut self, offset: usize) {
+        let code = &mut self.state().function.code;
+        let target = code.len() as u32;
+        match &mut code[offset] {
+            Instruction::Jump(to) | Instruction::JumpIfFalse(to) | Instruction::JumpIfTrue(to) => {
+                *to = target
+            }
+            _ => {}
+        }
+    }
+
+    fn state(&mut self) -> &mut State {
+        self.states
+            .last_mut()
+            .expect("no function is being compiled")
+    }
+
+    fn scope(&mut self) -> &mut Scope {
+        self.state().scopes.last_mut().expect("no scope is open")
+    }
+}
+
+// Only sized integer types change the value they hold.
+fn wrap_type(var_type: &Option<String>) -> Option<Type> {
+    let ty = Type::from_name(var_type.as_deref()?);
+    ty.bounds().map(|_| ty)
+}
+
+// Numbers are compared by bits so `0` and `-0` stay distinct.
+fn same_constant(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(l), Value::Number(r)) => l.to_bits() == r.to_bits(),
+        (Value::String(l), Value::String(r)) => l == r,
+        _ => false,
+    }
+}
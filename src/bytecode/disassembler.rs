@@ -0,0 +1,89 @@
+use std::fmt::Write;
+
+use crate::bytecode::instruction::{Function, Instruction, Program};
+use crate::bytecode::value::Value;
+
+// Lists every function of the program, one instruction per line:
+//
+//     == 1 double (a) ==
+//     0000    1  Load 0 0          ; a
+//     0001    |  Constant 0        ; 2
+//
+// with the offset, the source line (`|` when unchanged) and the operands
+// resolved against the function's tables.
+pub fn disassemble(program: &Program) -> String {
+    let mut out = String::new();
+    for (index, function) in program.functions.iter().enumerate() {
+        if index > 0 {
+            out.push('\n');
+        }
+        let _ = writeln!(
+            out,
+            "== {} {} ({}) ==",
+            index,
+            function.name,
+            function.params.join(", ")
+        );
+        let mut last_line = None;
+        for (offset, instruction) in function.code.iter().enumerate() {
+            let line = function.lines[offset];
+            let line = if last_line == Some(line) {
+                "|".to_string()
+            } else {
+                line.to_string()
+            };
+            last_line = Some(function.lines[offset]);
+
+            let text = instruction_text(instruction);
+            match comment(program, function, instruction) {
+                Some(comment) => {
+                    let _ = writeln!(out, "{:04} {:>4}  {:<16} ; {}", offset, line, text, comment);
+                }
+                None => {
+                    let _ = writeln!(out, "{:04} {:>4}  {}", offset, line, text);
+                }
+            }
+        }
+    }
+    out
+}
+
+fn instruction_text(instruction: &Instruction) -> String {
+    match instruction {
+        Instruction::Constant(index) => format!("Constant {}", index),
+        Instruction::Load { depth, slot } => format!("Load {} {}", depth, slot),
+        Instruction::Store(slot) => format!("Store {}", slot),
+        Instruction::Wrap(ty) => format!("Wrap {}", ty),
+        Instruction::Test(index) => format!("Test {}", index),
+        Instruction::Jump(target) => format!("Jump {:04}", target),
+        Instruction::JumpIfFalse(target) => format!("JumpIfFalse {:04}", target),
+        Instruction::JumpIfTrue(target) => format!("JumpIfTrue {:04}", target),
+        Instruction::Closure(index) => format!("Closure {}", index),
+        Instruction::Call(argc) => format!("Call {}", argc),
+        Instruction::Print(argc) => format!("Print {}", argc),
+        Instruction::Undefined(index) => format!("Undefined {}", index),
+        Instruction::UndefinedFunction(index) => format!("UndefinedFunction {}", index),
+        other => format!("{:?}", other),
+    }
+}
+
+// What an operand refers to. Slots of enclosing functions are not named as
+// the closure's function is only known at runtime.
+fn comment(program: &Program, function: &Function, instruction: &Instruction) -> Option<String> {
+    match instruction {
+        Instruction::Constant(index)
+        | Instruction::Undefined(index)
+        | Instruction::UndefinedFunction(index) => {
+            Some(match &function.constants[*index as usize] {
+                Value::String(s) => format!("{:?}", s),
+                value => value.to_string(),
+            })
+        }
+        Instruction::Load { depth: 0, slot } | Instruction::Store(slot) => {
+            Some(function.locals[*slot as usize].clone())
+        }
+        Instruction::Test(index) => Some(function.patterns[*index as usize].to_string()),
+        Instruction::Closure(index) => Some(program.functions[*index as usize].name.clone()),
+        _ => None,
+    }
+}
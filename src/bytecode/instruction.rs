@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::bytecode::value::Value;
+use crate::checker::types::Type;
+use crate::parser::statement::Pattern;
+
+// Instructions of the stack machine. Operands index the tables of the
+// function being run; jump targets are instruction offsets in it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    Constant(u16), // push constants[i]
+    True,
+    False,
+    Unit,
+    Load { depth: u16, slot: u16 }, // local of the function `depth` levels out
+    Store(u16),                     // pop into a local of the current function
+    Wrap(Type),                     // truncate a number to a sized integer type
+    Pop,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
+    Truthy,    // replace the top with its truthiness
+    Test(u16), // push whether the top matches patterns[i], keeping it
+    Jump(u32),
+    JumpIfFalse(u32), // pop and jump if falsy
+    JumpIfTrue(u32),  // pop and jump if truthy
+    Closure(u16),     // push program functions[i] over the current locals
+    Call(u8),         // callee below `n` arguments
+    Print(u8),
+    Undefined(u16),         // fail, constants[i] names an unbound variable
+    UndefinedFunction(u16), // fail, constants[i] names an unbound function
+    Return,
+}
+
+// The script of a module or the body of a lambda.
+#[derive(Debug, Default)]
+pub struct Function {
+    pub name: String, // `<script>`, the variable a lambda is bound to, or `lambda@line:col`
+    pub params: Vec<String>,
+    pub code: Vec<Instruction>,
+    pub lines: Vec<usize>,     // source line of each instruction
+    pub constants: Vec<Value>, // numbers and strings
+    pub patterns: Vec<Pattern>,
+    pub locals: Vec<String>, // slot names, parameters first
+}
+
+// A compiled module. Every declaration gets its own slot in the function it
+// appears in; slots are never reused, so closures can share them.
+#[derive(Debug)]
+pub struct Program {
+    pub functions: Vec<Rc<Function>>,  // the script is functions[0]
+    pub imports: Vec<(String, u16)>,   // script slots filled before it runs
+    pub globals: HashMap<String, u16>, // top-level names -> script slots
+}
@@ -0,0 +1,93 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::bytecode::instruction::Function;
+
+// Runtime values of the VM. They behave like the interpreter's values, but
+// functions are compiled code over a chain of slot frames.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Number(f64),
+    String(Rc<str>),
+    Bool(bool),
+    Function(Rc<Closure>),
+    Unit,
+}
+
+pub struct Closure {
+    pub function: Rc<Function>,
+    pub env: Rc<Env>, // locals of the enclosing function call
+}
+
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Closure({})", self.function.name)
+    }
+}
+
+// Locals of one function call. `None` until the declaration has run.
+pub struct Env {
+    pub function: Rc<Function>,
+    pub slots: RefCell<Vec<Option<Value>>>,
+    pub parent: Option<Rc<Env>>,
+}
+
+impl Env {
+    pub fn new(function: Rc<Function>, parent: Option<Rc<Env>>) -> Self {
+        Env {
+            slots: RefCell::new(vec![None; function.locals.len()]),
+            function,
+            parent,
+        }
+    }
+
+    // The frame `depth` levels out from this one.
+    pub fn ancestor(self: &Rc<Env>, depth: u16) -> Option<Rc<Env>> {
+        let mut env = self.clone();
+        for _ in 0..depth {
+            env = env.parent.clone()?;
+        }
+        Some(env)
+    }
+}
+
+impl Value {
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Number(num) => *num != 0.0,
+            Value::String(s) => !s.is_empty(),
+            Value::Bool(b) => *b,
+            Value::Function(_) => true,
+            Value::Unit => false,
+        }
+    }
+}
+
+// Closures compare by identity.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Number(l), Value::Number(r)) => l == r,
+            (Value::String(l), Value::String(r)) => l == r,
+            (Value::Bool(l), Value::Bool(r)) => l == r,
+            (Value::Function(l), Value::Function(r)) => Rc::ptr_eq(l, r),
+            (Value::Unit, Value::Unit) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(num) => write!(f, "{}", num),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Function(closure) => {
+                write!(f, "<function ({})>", closure.function.params.join(", "))
+            }
+            Value::Unit => write!(f, "()"),
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::bytecode::compiler::Compiler;
+use crate::bytecode::instruction::{Instruction, Program};
+use crate::bytecode::value::{Closure, Env, Value};
+use crate::diagnostic::Diagnostic;
+use crate::module::module::ModuleGraph;
+use crate::parser::statement::PatternKind;
+
+struct CallFrame {
+    ip: usize,
+    env: Rc<Env>, // locals of the call, and the function being run
+}
+
+// Runs compiled programs. Errors carry the same messages as the
+// interpreter's.
+#[derive(Default)]
+pub struct Vm {
+    stack: Vec<Value>,
+    frames: Vec<CallFrame>,
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        Vm::default()
+    }
+
+    // Runs the script of `program` and returns its top-level bindings.
+    pub fn run(
+        &mut self,
+        program: &Program,
+        imports: &HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>, String> {
+        let env = Rc::new(Env::new(program.functions[0].clone(), None));
+        for (name, slot) in &program.imports {
+            env.slots.borrow_mut()[*slot as usize] = imports.get(name).cloned();
+        }
+
+        self.stack.clear();
+        self.frames.push(CallFrame {
+            ip: 0,
+            env: env.clone(),
+        });
+        if let Err(error) = self.execute(program) {
+            self.frames.clear();
+            self.stack.clear();
+            return Err(error);
+        }
+
+        let slots = env.slots.borrow();
+        Ok(program
+            .globals
+            .iter()
+            .filter_map(|(name, &slot)| Some((name.clone(), slots[slot as usize].clone()?)))
+            .collect())
+    }
+
+    // Compiles and runs every module of the graph, dependencies first, like
+    // `Interpreter::run_graph`.
+    pub fn run_graph(graph: &ModuleGraph) -> Result<(), Diagnostic> {
+        let mut exports: Vec<HashMap<String, Value>> = Vec::new();
+
+        for module in &graph.modules {
+            let program = Compiler::compile(&module.statements)
+                .map_err(|e| Diagnostic::error(e).in_file(&module.name))?;
+
+            let mut imports = HashMap::new();
+            for import in &module.imports {
+                for name in &import.names {
+                    if let Some(value) = exports[import.module].get(name) {
+                        imports.insert(name.clone(), value.clone());
+                    }
+                }
+            }
+
+            let mut globals = Vm::new()
+                .run(&program, &imports)
+                .map_err(|e| Diagnostic::error(e).in_file(&module.name))?;
+            let module_exports = module
+                .exports
+                .iter()
+                .filter_map(|name| Some((name.clone(), globals.remove(name)?)))
+                .collect();
+            exports.push(module_exports);
+        }
+
+        Ok(())
+    }
+
+    fn execute(&mut self, program: &Program) -> Result<(), String> {
+        loop {
+            let frame = self.frames.last_mut().expect("no function is running");
+            let function = frame.env.function.clone();
+            let instruction = &function.code[frame.ip];
+            frame.ip += 1;
+
+            match instruction {
+                Instruction::Constant(index) => {
+                    self.stack.push(function.constants[*index as usize].clone())
+                }
+                Instruction::True => self.stack.push(Value::Bool(true)),
+                Instruction::False => self.stack.push(Value::Bool(false)),
+                Instruction::Unit => self.stack.push(Value::Unit),
+                Instruction::Load { depth, slot } => {
+                    let env = self
+                        .env()
+                        .ancestor(*depth)
+                        .expect("missing enclosing frame");
+                    let value = env.slots.borrow()[*slot as usize].clone();
+                    match value {
+                        Some(value) => self.stack.push(value),
+                        None => {
+                            let name = &env.function.locals[*slot as usize];
+                            return Err(format!("Undefined variable `{}`", name));
+                        }
+                    }
+                }
+                Instruction::Store(slot) => {
+                    let value = self.pop();
+                    self.env().slots.borrow_mut()[*slot as usize] = Some(value);
+                }
+                Instruction::Wrap(ty) => {
+                    if let Some(Value::Number(num)) = self.stack.last_mut() {
+                        *num = ty.wrap(*num);
+                    }
+                }
+                Instruction::Pop => {
+                    self.pop();
+                }
+                Instruction::Add | Instruction::Sub | Instruction::Mul | Instruction::Div => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let value = arithmetic(instruction, left, right)?;
+                    self.stack.push(value);
+                }
+                Instruction::Equal | Instruction::NotEqual => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let equal = left == right;
+                    self.stack
+                        .push(Value::Bool(equal == (*instruction == Instruction::Equal)));
+                }
+                Instruction::Less
+                | Instruction::Greater
+                | Instruction::LessEqual
+                | Instruction::GreaterEqual => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let ordering = match (&left, &right) {
+                        (Value::Number(l), Value::Number(r)) => l.partial_cmp(r),
+                        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+                        _ => None,
+                    }
+                    .ok_or_else(|| format!("Cannot compare {} with {}", left, right))?;
+                    let result = match instruction {
+                        Instruction::Less => ordering.is_lt(),
+                        Instruction::Greater => ordering.is_gt(),
+                        Instruction::LessEqual => ordering.is_le(),
+                        _ => ordering.is_ge(),
+                    };
+                    self.stack.push(Value::Bool(result));
+                }
+                Instruction::Truthy => {
+                    let value = self.pop();
+                    self.stack.push(Value::Bool(value.is_truthy()));
+                }
+                Instruction::Test(index) => {
+                    let pattern = &function.patterns[*index as usize];
+                    let value = self.stack.last().expect("stack underflow");
+                    let matched = matches_pattern(&pattern.kind, value);
+                    self.stack.push(Value::Bool(matched));
+                }
+                Instruction::Jump(target) => self.jump(*target),
+                Instruction::JumpIfFalse(target) => {
+                    if !self.pop().is_truthy() {
+                        self.jump(*target);
+                    }
+                }
+                Instruction::JumpIfTrue(target) => {
+                    if self.pop().is_truthy() {
+                        self.jump(*target);
+                    }
+                }
+                Instruction::Closure(index) => {
+                    let closure = Closure {
+                        function: program.functions[*index as usize].clone(),
+                        env: self.env(),
+                    };
+                    self.stack.push(Value::Function(Rc::new(closure)));
+                }
+                Instruction::Call(argc) => self.call(*argc as usize)?,
+                Instruction::Print(argc) => {
+                    let args = self.stack.split_off(self.stack.len() - *argc as usize);
+                    let line = args
+                        .iter()
+                        .map(|value| format!("{}", value))
+                        .collect::<Vec<String>>()
+                        .join(" ");
+                    println!("{}", line);
+                    self.stack.push(Value::Unit);
+                }
+                Instruction::Undefined(index) => {
+                    return Err(format!(
+                        "Undefined variable `{}`",
+                        function.constants[*index as usize]
+                    ))
+                }
+                Instruction::UndefinedFunction(index) => {
+                    return Err(format!(
+                        "Undefined function `{}`",
+                        function.constants[*index as usize]
+                    ))
+                }
+                Instruction::Return => {
+                    self.frames.pop();
+                    if self.frames.is_empty() {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    fn call(&mut self, argc: usize) -> Result<(), String> {
+        let args = self.stack.split_off(self.stack.len() - argc);
+        let closure = match self.pop() {
+            Value::Function(closure) => closure,
+            other => return Err(format!("{} is not a function", other)),
+        };
+
+        let function = closure.function.clone();
+        if function.params.len() != args.len() {
+            return Err(format!(
+                "Expected {} argument(s) but {} were given",
+                function.params.len(),
+                args.len()
+            ));
+        }
+
+        let env = Env::new(function, Some(closure.env.clone()));
+        for (slot, arg) in env.slots.borrow_mut().iter_mut().zip(args) {
+            *slot = Some(arg);
+        }
+        self.frames.push(CallFrame {
+            ip: 0,
+            env: Rc::new(env),
+        });
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("stack underflow")
+    }
+
+    fn jump(&mut self, target: u32) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.ip = target as usize;
+        }
+    }
+
+    fn env(&self) -> Rc<Env> {
+        self.frames
+            .last()
+            .expect("no function is running")
+            .env
+            .clone()
+    }
+}
+
+fn arithmetic(instruction: &Instruction, left: Value, right: Value) -> Result<Value, String> {
+    match (left, instruction, right) {
+        (Value::Number(l), Instruction::Add, Value::Number(r)) => Ok(Value::Number(l + r)),
+        (Value::Number(l), Instruction::Sub, Value::Number(r)) => Ok(Value::Number(l - r)),
+        (Value::Number(l), Instruction::Mul, Value::Number(r)) => Ok(Value::Number(l * r)),
+        (Value::Number(_), Instruction::Div, Value::Number(0.0)) => {
+            Err("Division by zero".to_string())
+        }
+        (Value::Number(l), Instruction::Div, Value::Number(r)) => Ok(Value::Number(l / r)),
+        (Value::String(l), Instruction::Add, Value::String(r)) => {
+            Ok(Value::String(format!("{}{}", l, r).into()))
+        }
+        (l, instruction, r) => {
+            let op = match instruction {
+                Instruction::Add => "+",
+                Instruction::Sub => "-",
+                Instruction::Mul => "*",
+                _ => "/",
+            };
+            Err(format!("Cannot apply `{}` to {} and {}", op, l, r))
+        }
+    }
+}
+
+fn matches_pattern(pattern: &PatternKind, value: &Value) -> bool {
+    match (pattern, value) {
+        (PatternKind::Wildcard, _) => true,
+        (PatternKind::Number(n), Value::Number(v)) => n == v,
+        (PatternKind::StringLiteral(s), Value::String(v)) => **s == **v,
+        (PatternKind::Boolean(b), Value::Bool(v)) => b == v,
+        (
+            PatternKind::Range {
+                start,
+                end,
+                inclusive,
+            },
+            Value::Number(v),
+        ) => *v >= *start && if *inclusive { *v <= *end } else { *v < *end },
+        _ => false,
+    }
+}
@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+
+use crate::checker::types::Type;
+use crate::diagnostic::Diagnostic;
+use crate::lexer::token::Span;
+use crate::module::module::ModuleGraph;
+use crate::parser::statement::{
+    Expression, ExpressionKind, MatchArm, NodeId, Operator, Pattern, PatternKind, Statement,
+    StatementKind,
+};
+
+#[derive(Default)]
+pub struct Checker {
+    scopes: Vec<HashMap<String, Type>>,
+    types: HashMap<NodeId, Type>, // inferred type of every expression
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        Checker::default()
+    }
+
+    // Checks a single module; imported names are left unknown.
+    pub fn check(statements: &[Statement]) -> (HashMap<NodeId, Type>, Vec<Diagnostic>) {
+        let mut checker = Checker::new();
+        checker.scopes.push(HashMap::new());
+        for stmt in statements {
+            checker.check_statement(stmt);
+        }
+        (checker.types, checker.diagnostics)
+    }
+
+    // Checks every module of the graph, dependencies first, binding imported
+    // names to the types their modules exported. Returns the expression types
+    // of each module.
+    pub fn check_graph(graph: &ModuleGraph) -> (Vec<HashMap<NodeId, Type>>, Vec<Diagnostic>) {
+        let mut exports: Vec<HashMap<String, Type>> = Vec::new();
+        let mut types = Vec::new();
+        let mut diagnostics = Vec::new();
+
+        for module in &graph.modules {
+            let mut imports = HashMap::new();
+            for import in &module.imports {
+                for name in &import.names {
+                    if let Some(ty) = exports[import.module].get(name) {
+                        imports.insert(name.clone(), ty.clone());
+                    }
+                }
+            }
+
+            let mut checker = Checker::new();
+            checker.scopes.push(imports);
+            for stmt in &module.statements {
+                checker.check_statement(stmt);
+            }
+
+            let module_exports = module
+                .exports
+                .iter()
+                .map(|name| (name.clone(), checker.lookup(name)))
+                .collect();
+            exports.push(module_exports);
+
+            for diagnostic in checker.diagnostics {
+                diagnostics.push(diagnostic.in_file(&module.name));
+            }
+            types.push(checker.types);
+        }
+
+        (types, diagnostics)
+    }
+
+    fn check_block(&mut self, statements: &[Statement]) {
+        self.scopes.push(HashMap::new());
+        for stmt in statements {
+            self.check_statement(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn check_statement(&mut self, stmt: &Statement) {
+        match &stmt.kind {
+            StatementKind::VarDeclaration {
+                name,
+                var_type,
+                value,
+                ..
+            } => {
+                let value_type = self.check_expression(value);
+                let ty = match var_type {
+                    Some(t) => {
+                        let declared = Type::from_name(t);
+                        if declared.unify(&value_type).is_none() {
+                            self.error(
+                                format!(
+                                    "Mismatched types: `{}` is declared as {} but initialized with {}",
+                                    name, declared, value_type
+                                ),
+                                value.span,
+                            );
+                        }
+                        declared
+                    }
+                    None => value_type,
+                };
+                self.declare(name, ty);
+            }
+            StatementKind::FunctionCall { name, args } => {
+                let callee_type = self.lookup(name);
+                self.check_call(name, callee_type, args, stmt.span);
+            }
+            StatementKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.check_expression(condition);
+                self.check_block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.check_statement(else_branch);
+                }
+            }
+            StatementKind::Match { scrutinee, arms } => {
+                let scrutinee_type = self.check_expression(scrutinee);
+                let mut has_wildcard = false;
+                for arm in arms {
+                    if has_wildcard {
+                        self.warning("Unreachable match arm after `_`".to_string(), arm.span);
+                    }
+                    for pattern in &arm.patterns {
+                        let pattern_type = match pattern.kind {
+                            PatternKind::Number(_) | PatternKind::Range { .. } => Type::Number,
+                            PatternKind::StringLiteral(_) => Type::String,
+                            PatternKind::Boolean(_) => Type::Bool,
+                            PatternKind::Wildcard => {
+                                has_wildcard = true;
+                                continue;
+                            }
+                        };
+                        if scrutinee_type.unify(&pattern_type).is_none() {
+                            self.error(
+                                format!(
+                                    "Mismatched types: pattern `{}` is {} but the match scrutinee is {}",
+                                    pattern, pattern_type, scrutinee_type
+                                ),
+                                pattern.span,
+                            );
+                        }
+                    }
+                    self.check_block(&arm.body);
+                }
+                if !has_wildcard {
+                    self.check_exhaustive(&scrutinee_type, arms, stmt.span);
+                }
+            }
+            StatementKind::Block(stmts) => self.check_block(stmts),
+            // imported names are bound by `check_graph`
+            StatementKind::Import { .. } => {}
+            StatementKind::Export(stmt) => self.check_statement(stmt),
+            StatementKind::Expression(expr) => {
+                self.check_expression(expr);
+            }
+        }
+    }
+
+    // Only integer and bool scrutinees have a domain small enough to check.
+    fn check_exhaustive(&mut self, scrutinee_type: &Type, arms: &[MatchArm], span: Span) {
+        let patterns = arms.iter().flat_map(|arm| &arm.patterns);
+        let exhaustive = match scrutinee_type {
+            Type::Bool => {
+                let kinds: Vec<&PatternKind> = patterns.map(|pattern| &pattern.kind).collect();
+                kinds.contains(&&PatternKind::Boolean(true))
+                    && kinds.contains(&&PatternKind::Boolean(false))
+            }
+            t if t.is_numeric() => match t.bounds() {
+                Some((min, max)) => covers_range(patterns, min, max),
+                None => false,
+            },
+            _ => return,
+        };
+
+        if !exhaustive {
+            self.warning(
+                format!(
+                    "Non-exhaustive match on {}: consider adding a `_` arm",
+                    scrutinee_type
+                ),
+                span,
+            );
+        }
+    }
+
+    fn check_expression(&mut self, expr: &Expression) -> Type {
+        let ty = self.infer(expr);
+        self.types.insert(expr.id, ty.clone());
+        ty
+    }
+
+    fn infer(&mut self, expr: &Expression) -> Type {
+        match &expr.kind {
+            ExpressionKind::Identifier(name) => self.lookup(name),
+            ExpressionKind::Number(_) => Type::Number,
+            ExpressionKind::StringLiteral(_) => Type::String,
+            ExpressionKind::Boolean(_) => Type::Bool,
+            ExpressionKind::BinaryOp(left, op, right) => {
+                let left = self.check_expression(left);
+                let right = self.check_expression(right);
+                match left.unify(&right) {
+                    Some(Type::String) if *op == Operator::Add => Type::String,
+                    Some(t) if t.is_numeric() || t == Type::Unknown => t,
+                    _ => {
+                        self.error(
+                            format!(
+                                "Mismatched operand types for `{}`: {} and {}",
+                                op, left, right
+                            ),
+                            expr.span,
+                        );
+                        Type::Unknown
+                    }
+                }
+            }
+            ExpressionKind::ComparisonOp(left, _, right) => {
+                let left = self.check_expression(left);
+                let right = self.check_expression(right);
+                if left.unify(&right).is_none() {
+                    self.error(format!("Cannot compare {} with {}", left, right), expr.span);
+                }
+                Type::Bool
+            }
+            ExpressionKind::LogicalOp(left, _, right) => {
+                self.check_expression(left);
+                self.check_expression(right);
+                Type::Bool
+            }
+            ExpressionKind::FunctionCall { name, args } => {
+                let callee_type = self.lookup(name);
+                self.check_call(name, callee_type, args, expr.span)
+            }
+            ExpressionKind::Call { callee, args } => {
+                let callee_type = self.check_expression(callee);
+                self.check_call(&format!("{}", callee), callee_type, args, expr.span)
+            }
+            ExpressionKind::Lambda { params, body } => {
+                let param_types: Vec<Type> = params
+                    .iter()
+                    .map(|param| match &param.param_type {
+                        Some(t) => Type::from_name(t),
+                        None => Type::Unknown,
+                    })
+                    .collect();
+
+                self.scopes.push(HashMap::new());
+                for (param, ty) in params.iter().zip(&param_types) {
+                    self.declare(&param.name, ty.clone());
+                }
+                let ret = self.check_expression(body);
+                self.scopes.pop();
+
+                Type::Function {
+                    params: param_types,
+                    ret: Box::new(ret),
+                }
+            }
+            ExpressionKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.check_expression(condition);
+                let then_type = self.check_expression(then_branch);
+                let else_type = self.check_expression(else_branch);
+                match then_type.unify(&else_type) {
+                    Some(t) => t,
+                    None => {
+                        self.error(
+                            format!(
+                                "Mismatched types in conditional branches: {} and {}",
+                                then_type, else_type
+                            ),
+                            expr.span,
+                        );
+                        Type::Unknown
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_call(
+        &mut self,
+        callee: &str,
+        callee_type: Type,
+        args: &[Expression],
+        span: Span,
+    ) -> Type {
+        let arg_types: Vec<Type> = args.iter().map(|arg| self.check_expression(arg)).collect();
+
+        match callee_type {
+            Type::Function { params, ret } => {
+                if params.len() != arg_types.len() {
+                    self.error(
+                        format!(
+                            "`{}` expects {} argument(s) but {} were given",
+                            callee,
+                            params.len(),
+                            arg_types.len()
+                        ),
+                        span,
+                    );
+                }
+                for ((param, arg_type), arg) in params.iter().zip(&arg_types).zip(args) {
+                    if param.unify(arg_type).is_none() {
+                        self.error(
+                            format!(
+                                "Mismatched types: `{}` expects {} but got {}",
+                                callee, param, arg_type
+                            ),
+                            arg.span,
+                        );
+                    }
+                }
+                *ret
+            }
+            Type::Unknown => Type::Unknown,
+            t => {
+                self.error(format!("`{}` is {} and cannot be called", callee, t), span);
+                Type::Unknown
+            }
+        }
+    }
+
+    fn error(&mut self, message: String, span: Span) {
+        self.diagnostics.push(Diagnostic::error(message).at(span));
+    }
+
+    fn warning(&mut self, message: String, span: Span) {
+        self.diagnostics.push(Diagnostic::warning(message).at(span));
+    }
+
+    fn declare(&mut self, name: &str, ty: Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), ty);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Type {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).cloned())
+            .unwrap_or(Type::Unknown)
+    }
+}
+
+// Whether the integer patterns cover every value in `min..=max`.
+fn covers_range<'a>(patterns: impl Iterator<Item = &'a Pattern>, min: f64, max: f64) -> bool {
+    let mut intervals: Vec<(f64, f64)> = patterns
+        .filter_map(|pattern| match pattern.kind {
+            PatternKind::Number(n) if n.fract() == 0.0 => Some((n, n)),
+            PatternKind::Range {
+                start,
+                end,
+                inclusive,
+            } => Some((
+                start.ceil(),
+                if inclusive {
+                    end.floor()
+                } else {
+                    end.ceil() - 1.0
+                },
+            )),
+            _ => None,
+        })
+        .collect();
+    intervals.retain(|(start, end)| start <= end);
+    intervals.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut next = min;
+    for (start, end) in intervals {
+        if start > next {
+            return false;
+        }
+        next = next.max(end + 1.0);
+    }
+    next > max
+}
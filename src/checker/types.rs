@@ -0,0 +1,122 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    Number, // numeric literals and `number`
+    String,
+    Bool,
+    Function { params: Vec<Type>, ret: Box<Type> },
+    Unknown,
+}
+
+impl Type {
+    pub fn from_name(name: &str) -> Type {
+        match name {
+            "i8" => Type::I8,
+            "i16" => Type::I16,
+            "i32" => Type::I32,
+            "i64" => Type::I64,
+            "u8" => Type::U8,
+            "u16" => Type::U16,
+            "u32" => Type::U32,
+            "u64" => Type::U64,
+            "number" => Type::Number,
+            "string" => Type::String,
+            "bool" => Type::Bool,
+            _ => Type::Unknown,
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Type::I8
+                | Type::I16
+                | Type::I32
+                | Type::I64
+                | Type::U8
+                | Type::U16
+                | Type::U32
+                | Type::U64
+                | Type::Number
+        )
+    }
+
+    // Inclusive value range of a sized integer type.
+    pub fn bounds(&self) -> Option<(f64, f64)> {
+        match self {
+            Type::I8 => Some((i8::MIN as f64, i8::MAX as f64)),
+            Type::I16 => Some((i16::MIN as f64, i16::MAX as f64)),
+            Type::I32 => Some((i32::MIN as f64, i32::MAX as f64)),
+            Type::I64 => Some((i64::MIN as f64, i64::MAX as f64)),
+            Type::U8 => Some((0.0, u8::MAX as f64)),
+            Type::U16 => Some((0.0, u16::MAX as f64)),
+            Type::U32 => Some((0.0, u32::MAX as f64)),
+            Type::U64 => Some((0.0, u64::MAX as f64)),
+            _ => None,
+        }
+    }
+
+    // Truncates `value` to this type's width, wrapping on overflow.
+    pub fn wrap(&self, value: f64) -> f64 {
+        let int = value as i64;
+        match self {
+            Type::I8 => int as i8 as f64,
+            Type::I16 => int as i16 as f64,
+            Type::I32 => int as i32 as f64,
+            Type::I64 => int as f64,
+            Type::U8 => int as u8 as f64,
+            Type::U16 => int as u16 as f64,
+            Type::U32 => int as u32 as f64,
+            Type::U64 => int as u64 as f64,
+            _ => value,
+        }
+    }
+
+    // Returns the common type of `self` and `other`, if any.
+    // A `number` unifies with any sized integer type.
+    pub fn unify(&self, other: &Type) -> Option<Type> {
+        match (self, other) {
+            (Type::Unknown, t) | (t, Type::Unknown) => Some(t.clone()),
+            (Type::Number, t) | (t, Type::Number) if t.is_numeric() => Some(t.clone()),
+            (l, r) if l == r => Some(l.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Type::Function { params, ret } => {
+                let params_str = params
+                    .iter()
+                    .map(|param| format!("{}", param))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                return write!(f, "({}) => {}", params_str, ret);
+            }
+            Type::I8 => "i8",
+            Type::I16 => "i16",
+            Type::I32 => "i32",
+            Type::I64 => "i64",
+            Type::U8 => "u8",
+            Type::U16 => "u16",
+            Type::U32 => "u32",
+            Type::U64 => "u64",
+            Type::Number => "number",
+            Type::String => "string",
+            Type::Bool => "bool",
+            Type::Unknown => "unknown",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -0,0 +1,5 @@
+pub mod asm;
+pub mod c;
+pub mod js;
+pub mod sourcemap;
+pub mod wat;
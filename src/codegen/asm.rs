@@ -0,0 +1,1047 @@
+use std::collections::HashMap;
+
+use crate::checker::types::Type;
+use crate::diagnostic::Diagnostic;
+use crate::ir::instruction::{
+    BlockId, Constant, Function, InstructionKind, Module, Terminator, ValueId,
+};
+use crate::parser::statement::{ComparisonOperator, Operator};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Syntax {
+    #[default]
+    Att,
+    Intel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Reg {
+    Rax,
+    Rbx,
+    Rcx,
+    Rdx,
+    Rsi,
+    Rdi,
+    Rbp,
+    Rsp,
+    R8,
+    R9,
+    R11,
+    R12,
+    R13,
+    R14,
+    R15,
+}
+
+use Reg::*;
+
+// System V passes the first six integer arguments in these.
+const ARGUMENTS: [Reg; 6] = [Rdi, Rsi, Rdx, Rcx, R8, R9];
+
+// Values live in the callee-saved registers, which calls leave alone, or
+// in stack slots. `rax`, `rcx`, `rdx` and `r11` are scratch registers.
+const ALLOCATABLE: [Reg; 5] = [Rbx, R12, R13, R14, R15];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Size {
+    Byte,
+    Word,
+    Long,
+    Quad,
+}
+
+impl Reg {
+    fn name(self, size: Size) -> &'static str {
+        let names = match self {
+            Rax => ["al", "ax", "eax", "rax"],
+            Rbx => ["bl", "bx", "ebx", "rbx"],
+            Rcx => ["cl", "cx", "ecx", "rcx"],
+            Rdx => ["dl", "dx", "edx", "rdx"],
+            Rsi => ["sil", "si", "esi", "rsi"],
+            Rdi => ["dil", "di", "edi", "rdi"],
+            Rbp => ["bpl", "bp", "ebp", "rbp"],
+            Rsp => ["spl", "sp", "esp", "rsp"],
+            R8 => ["r8b", "r8w", "r8d", "r8"],
+            R9 => ["r9b", "r9w", "r9d", "r9"],
+            R11 => ["r11b", "r11w", "r11d", "r11"],
+            R12 => ["r12b", "r12w", "r12d", "r12"],
+            R13 => ["r13b", "r13w", "r13d", "r13"],
+            R14 => ["r14b", "r14w", "r14d", "r14"],
+            R15 => ["r15b", "r15w", "r15d", "r15"],
+        };
+        names[size as usize]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Reg(Reg, Size),
+    Imm(i64),
+    Mem(Reg, i32),   // the quadword at an offset from a register
+    Global(String),  // the quadword at a symbol
+    Address(String), // the address of a symbol, for `lea`
+    Label(String),   // a jump or call target
+    Indirect(Reg),   // a call through a register
+}
+
+fn reg(reg: Reg) -> Operand {
+    Operand::Reg(reg, Size::Quad)
+}
+
+fn label(label: &str) -> Operand {
+    Operand::Label(label.to_string())
+}
+
+impl Operand {
+    fn is_memory(&self) -> bool {
+        matches!(self, Operand::Mem(..) | Operand::Global(_))
+    }
+
+    fn att(&self) -> String {
+        match self {
+            Operand::Reg(reg, size) => format!("%{}", reg.name(*size)),
+            Operand::Imm(value) => format!("${}", value),
+            Operand::Mem(base, 0) => format!("(%{})", base.name(Size::Quad)),
+            Operand::Mem(base, offset) => format!("{}(%{})", offset, base.name(Size::Quad)),
+            Operand::Global(symbol) | Operand::Address(symbol) => format!("{}(%rip)", symbol),
+            Operand::Label(symbol) => symbol.clone(),
+            Operand::Indirect(reg) => format!("*%{}", reg.name(Size::Quad)),
+        }
+    }
+
+    fn intel(&self) -> String {
+        match self {
+            Operand::Reg(reg, size) => reg.name(*size).to_string(),
+            Operand::Imm(value) => value.to_string(),
+            Operand::Mem(base, 0) => format!("QWORD PTR [{}]", base.name(Size::Quad)),
+            Operand::Mem(base, offset) if *offset < 0 => {
+                format!("QWORD PTR [{}-{}]", base.name(Size::Quad), -offset)
+            }
+            Operand::Mem(base, offset) => {
+                format!("QWORD PTR [{}+{}]", base.name(Size::Quad), offset)
+            }
+            Operand::Global(symbol) => format!("QWORD PTR [rip+{}]", symbol),
+            Operand::Address(symbol) => format!("[rip+{}]", symbol),
+            Operand::Label(symbol) => symbol.clone(),
+            Operand::Indirect(reg) => reg.name(Size::Quad).to_string(),
+        }
+    }
+}
+
+// An assembly line. Operands are in Intel order, destination first.
+enum Line {
+    Blank,
+    Label(String),
+    Directive(String),
+    Op {
+        att: String,
+        intel: String,
+        operands: Vec<Operand>,
+    },
+}
+
+fn render(lines: &[Line], syntax: Syntax) -> String {
+    let mut out = String::new();
+    for line in lines {
+        match line {
+            Line::Blank => out.push('\n'),
+            Line::Label(label) => {
+                out.push_str(label);
+                out.push_str(":\n");
+            }
+            Line::Directive(directive) => {
+                out.push_str("    ");
+                out.push_str(directive);
+                out.push('\n');
+            }
+            Line::Op {
+                att,
+                intel,
+                operands,
+            } => {
+                let (mnemonic, operands): (_, Vec<String>) = match syntax {
+                    Syntax::Att => (att, operands.iter().rev().map(Operand::att).collect()),
+                    Syntax::Intel => (intel, operands.iter().map(Operand::intel).collect()),
+                };
+                if operands.is_empty() {
+                    out.push_str(&format!("    {}\n", mnemonic));
+                } else {
+                    out.push_str(&format!("    {:<7} {}\n", mnemonic, operands.join(", ")));
+                }
+            }
+        }
+    }
+    out
+}
+
+/*
+Lowers the IR of a graph of integer programs to x86-64 assembly for the GNU
+assembler, to be linked with the C library:
+
+    let double = (a: i64) => a * 2;     // main.fn.double:
+    print(double(21));                  //     pushq   %rbp
+                                        //     movq    %rsp, %rbp
+                                        //     ...
+                                        //     imulq   %rcx, %rax
+
+Numbers are 64-bit integers, so `number` division truncates, and strings
+are only printed. The entry module's code is `main`; each dependency's runs
+first, from a function of its own. Values are allocated to registers by a
+linear scan over the blocks in order, and functions follow the System V
+calling convention: arguments in `rdi`, `rsi`, `rdx`, `rcx`, `r8` and `r9`
+then on the stack, the result in `rax`. `print` calls `printf`.
+*/
+pub fn generate(modules: &[Module], syntax: Syntax) -> Result<String, Vec<Diagnostic>> {
+    let mut generator = Generator::default();
+    for module in modules {
+        generator.check(module);
+    }
+    if !generator.diagnostics.is_empty() {
+        return Err(generator.diagnostics);
+    }
+
+    let mut lines = Vec::new();
+    if syntax == Syntax::Intel {
+        lines.push(Line::Directive(".intel_syntax noprefix".to_string()));
+    }
+    lines.push(Line::Directive(".text".to_string()));
+    let inits: Vec<String> = modules[..modules.len().saturating_sub(1)]
+        .iter()
+        .map(|module| format!("{}.init", symbol_stem(&module.name)))
+        .collect();
+    for (index, module) in modules.iter().enumerate() {
+        let entry = index + 1 == modules.len();
+        let stem = symbol_stem(&module.name);
+        for (position, function) in module.functions.iter().enumerate() {
+            let symbol = match (position, entry) {
+                (0, true) => "main".to_string(),
+                (0, false) => format!("{}.init", stem),
+                _ => function_symbol(&stem, &function.name),
+            };
+            lines.push(Line::Blank);
+            if symbol == "main" {
+                lines.push(Line::Directive(".globl main".to_string()));
+            }
+            let mut emitter = Emitter::new(&mut generator, module, index, function, symbol);
+            if position == 0 && entry {
+                emitter.calls = inits.clone();
+            }
+            lines.extend(emitter.function());
+        }
+    }
+    lines.extend(generator.runtime(modules));
+    lines.extend(generator.data(modules));
+    lines.push(Line::Blank);
+    lines.push(Line::Directive(
+        ".section .note.GNU-stack,\"\",@progbits".to_string(),
+    ));
+    Ok(render(&lines, syntax))
+}
+
+#[derive(Default)]
+struct Generator {
+    strings: Vec<String>, // string constants and `printf` formats, `.Lstr<i>`
+    booleans: bool,       // whether `true` and `false` are printed
+    divisions: Vec<bool>, // whether each module divides
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Generator {
+    // Reports what the backend cannot lower: anything but integers,
+    // booleans, functions without captures and printed strings.
+    fn check(&mut self, module: &Module) {
+        for (position, function) in module.functions.iter().enumerate() {
+            let types = types(function);
+            let is_string = |value: &ValueId| types.get(value) == Some(&Type::String);
+            for instruction in function.instructions() {
+                let what = match &instruction.kind {
+                    InstructionKind::Const(Constant::Number(num))
+                        if num.fract() != 0.0 || num.abs() >= 9.2e18 =>
+                    {
+                        format!("The number `{}`", num)
+                    }
+                    InstructionKind::Binary(_, left, right)
+                        if is_string(left) || is_string(right) =>
+                    {
+                        "An operation on strings".to_string()
+                    }
+                    InstructionKind::Compare(_, left, right)
+                        if is_string(left) || is_string(right) =>
+                    {
+                        "Comparing strings".to_string()
+                    }
+                    InstructionKind::Truthy(operand) if is_string(operand) => {
+                        "The truthiness of a string".to_string()
+                    }
+                    InstructionKind::Closure(index, captures) if !captures.is_empty() => {
+                        let names = &module.functions[*index].captures;
+                        format!("A closure capturing `{}`", names.join("`, `"))
+                    }
+                    InstructionKind::Print(args) => match args
+                        .iter()
+                        .map(|arg| types.get(arg).cloned().unwrap_or(Type::Unknown))
+                        .find(|ty| print_format(ty).is_none())
+                    {
+                        Some(ty) => format!("Printing a value of type `{}`", ty),
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                let place = match position {
+                    0 => "at the top level".to_string(),
+                    _ => format!("in `{}`", function.name),
+                };
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "{} {} is not supported by the x86-64 backend",
+                        what, place
+                    ))
+                    .in_file(&module.name),
+                );
+            }
+        }
+    }
+
+    // The label of a string in the read-only data.
+    fn string(&mut self, string: &str) -> String {
+        let index = match self.strings.iter().position(|s| s == string) {
+            Some(index) => index,
+            None => {
+                self.strings.push(string.to_string());
+                self.strings.len() - 1
+            }
+        };
+        format!(".Lstr{}", index)
+    }
+
+    // A division by zero jumps to its module's stub, which fails as the
+    // interpreter does.
+    fn runtime(&mut self, modules: &[Module]) -> Vec<Line> {
+        let mut lines = Vec::new();
+        if !self.divisions.iter().any(|&divides| divides) {
+            return lines;
+        }
+        let message = self.string("Error: %s: Division by zero\n");
+        for (index, module) in modules.iter().enumerate() {
+            if !self.divisions.get(index).copied().unwrap_or(false) {
+                continue;
+            }
+            let name = self.string(&module.name);
+            lines.push(Line::Blank);
+            lines.push(Line::Label(format!(".Ldivide_by_zero{}", index)));
+            lines.push(q("lea", vec![reg(Rsi), Operand::Address(name)]));
+            lines.push(op("jmp", "jmp", vec![label("astc.divide_by_zero")]));
+        }
+        lines.push(Line::Blank);
+        lines.push(Line::Label("astc.divide_by_zero".to_string()));
+        // never returns, so `rbx` need not be saved
+        lines.push(q("mov", vec![reg(Rbx), reg(Rsi)]));
+        lines.push(op("xorl", "xor", vec![long(Rdi), long(Rdi)]));
+        lines.push(op("call", "call", vec![label("fflush")]));
+        lines.push(op("movl", "mov", vec![long(Rdi), Operand::Imm(2)]));
+        lines.push(q("lea", vec![reg(Rsi), Operand::Address(message)]));
+        lines.push(q("mov", vec![reg(Rdx), reg(Rbx)]));
+        lines.push(op("xorl", "xor", vec![long(Rax), long(Rax)]));
+        lines.push(op("call", "call", vec![label("dprintf")]));
+        lines.push(op("movl", "mov", vec![long(Rdi), Operand::Imm(1)]));
+        lines.push(op("call", "call", vec![label("exit")]));
+        lines
+    }
+
+    fn data(&self, modules: &[Module]) -> Vec<Line> {
+        let mut lines = Vec::new();
+        let globals: Vec<&String> = modules.iter().flat_map(|m| &m.globals).collect();
+        if !globals.is_empty() {
+            lines.push(Line::Blank);
+            lines.push(Line::Directive(".bss".to_string()));
+            lines.push(Line::Directive(".p2align 3".to_string()));
+            for global in globals {
+                lines.push(Line::Label(global_symbol(global)));
+                lines.push(Line::Directive(".zero 8".to_string()));
+            }
+        }
+        if !self.strings.is_empty() || self.booleans {
+            lines.push(Line::Blank);
+            lines.push(Line::Directive(".section .rodata".to_string()));
+            for (index, string) in self.strings.iter().enumerate() {
+                lines.push(Line::Label(format!(".Lstr{}", index)));
+                lines.push(Line::Directive(format!(".string \"{}\"", escape(string))));
+            }
+            if self.booleans {
+                lines.push(Line::Label(".Ltrue".to_string()));
+                lines.push(Line::Directive(".string \"true\"".to_string()));
+                lines.push(Line::Label(".Lfalse".to_string()));
+                lines.push(Line::Directive(".string \"false\"".to_string()));
+            }
+        }
+        lines
+    }
+}
+
+fn op(att: &str, intel: &str, operands: Vec<Operand>) -> Line {
+    Line::Op {
+        att: att.to_string(),
+        intel: intel.to_string(),
+        operands,
+    }
+}
+
+// A quadword instruction, `q`-suffixed in AT&T syntax.
+fn q(name: &str, operands: Vec<Operand>) -> Line {
+    op(&format!("{}q", name), name, operands)
+}
+
+fn long(reg: Reg) -> Operand {
+    Operand::Reg(reg, Size::Long)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Location {
+    Reg(Reg),
+    Slot(usize),
+}
+
+struct Emitter<'a> {
+    generator: &'a mut Generator,
+    module: &'a Module,
+    module_index: usize,
+    function: &'a Function,
+    symbol: String,
+    types: HashMap<ValueId, Type>,
+    locations: HashMap<ValueId, Location>,
+    saved: Vec<Reg>, // callee-saved registers the function uses
+    slots: usize,
+    calls: Vec<String>, // functions `main` calls first, the dependencies' code
+    lines: Vec<Line>,
+    stubs: Vec<Line>, // moves into the phis of a branch target
+}
+
+impl<'a> Emitter<'a> {
+    fn new(
+        generator: &'a mut Generator,
+        module: &'a Module,
+        module_index: usize,
+        function: &'a Function,
+        symbol: String,
+    ) -> Self {
+        let (locations, slots) = allocate(function);
+        let saved = ALLOCATABLE
+            .iter()
+            .copied()
+            .filter(|r| locations.values().any(|l| *l == Location::Reg(*r)))
+            .collect();
+        Emitter {
+            generator,
+            module,
+            module_index,
+            function,
+            symbol,
+            types: types(function),
+            locations,
+            saved,
+            slots,
+            calls: Vec::new(),
+            lines: Vec::new(),
+            stubs: Vec::new(),
+        }
+    }
+
+    fn function(mut self) -> Vec<Line> {
+        self.lines.push(Line::Label(self.symbol.clone()));
+        self.prologue();
+        for call in std::mem::take(&mut self.calls) {
+            self.emit(op("call", "call", vec![label(&call)]));
+        }
+        for (index, block) in self.function.blocks.iter().enumerate() {
+            if index > 0 {
+                self.lines.push(Line::Label(self.block_label(block.id)));
+            }
+            for instruction in &block.instructions {
+                self.instruction(instruction.value, &instruction.kind);
+            }
+            self.terminator(block.id, &block.terminator);
+        }
+        let mut lines = self.lines;
+        lines.append(&mut self.stubs);
+        lines
+    }
+
+    fn emit(&mut self, line: Line) {
+        self.lines.push(line);
+    }
+
+    fn block_label(&self, block: BlockId) -> String {
+        format!(".L{}.bb{}", self.symbol, block.0)
+    }
+
+    fn location(&self, value: ValueId) -> Operand {
+        match self.locations.get(&value) {
+            Some(Location::Reg(r)) => reg(*r),
+            Some(Location::Slot(slot)) => {
+                Operand::Mem(Rbp, -8 * (self.saved.len() + slot + 1) as i32)
+            }
+            None => unreachable!("value {:?} has no location", value),
+        }
+    }
+
+    // The frame holds the saved registers, then the stack slots, and keeps
+    // `rsp` 16-byte aligned for calls.
+    fn prologue(&mut self) {
+        self.emit(q("push", vec![reg(Rbp)]));
+        self.emit(q("mov", vec![reg(Rbp), reg(Rsp)]));
+        for r in self.saved.clone() {
+            self.emit(q("push", vec![reg(r)]));
+        }
+        let mut size = 8 * self.slots;
+        if (self.saved.len() + self.slots) % 2 == 1 {
+            size += 8;
+        }
+        if size > 0 {
+            self.emit(q("sub", vec![reg(Rsp), Operand::Imm(size as i64)]));
+        }
+        // arguments first, before anything clobbers their registers
+        let params: Vec<(usize, ValueId)> = self
+            .function
+            .instructions()
+            .filter_map(|instruction| match instruction.kind {
+                InstructionKind::Param(index) => Some((index, instruction.value)),
+                _ => None,
+            })
+            .collect();
+        for (index, value) in params {
+            let source = match ARGUMENTS.get(index) {
+                Some(&r) => reg(r),
+                None => Operand::Mem(Rbp, 16 + 8 * (index - ARGUMENTS.len()) as i32),
+            };
+            let destination = self.location(value);
+            self.mov(destination, source);
+        }
+    }
+
+    fn epilogue(&mut self) {
+        if self.saved.is_empty() {
+            self.emit(op("leave", "leave", Vec::new()));
+        } else {
+            let offset = -8 * self.saved.len() as i32;
+            self.emit(q("lea", vec![reg(Rsp), Operand::Mem(Rbp, offset)]));
+            for r in self.saved.clone().into_iter().rev() {
+                self.emit(q("pop", vec![reg(r)]));
+            }
+            self.emit(q("pop", vec![reg(Rbp)]));
+        }
+        self.emit(op("ret", "ret", Vec::new()));
+    }
+
+    // A quadword move between any two operands, through `rax` when both are
+    // in memory.
+    fn mov(&mut self, destination: Operand, source: Operand) {
+        if destination == source {
+            return;
+        }
+        match source {
+            Operand::Imm(value) if i32::try_from(value).is_err() => {
+                let scratch = match destination {
+                    Operand::Reg(r, _) => r,
+                    _ => Rax,
+                };
+                self.emit(op("movabsq", "movabs", vec![reg(scratch), source]));
+                self.mov(destination, reg(scratch));
+            }
+            Operand::Address(_) => match destination {
+                Operand::Reg(..) => self.emit(q("lea", vec![destination, source])),
+                _ => {
+                    self.emit(q("lea", vec![reg(Rax), source]));
+                    self.emit(q("mov", vec![destination, reg(Rax)]));
+                }
+            },
+            _ if destination.is_memory() && source.is_memory() => {
+                self.emit(q("mov", vec![reg(Rax), source]));
+                self.emit(q("mov", vec![destination, reg(Rax)]));
+            }
+            _ => self.emit(q("mov", vec![destination, source])),
+        }
+    }
+
+    fn load(&mut self, r: Reg, value: ValueId) {
+        let source = self.location(value);
+        self.mov(reg(r), source);
+    }
+
+    // Stores `rax` into the value's location.
+    fn store(&mut self, value: ValueId) {
+        let destination = self.location(value);
+        self.mov(destination, reg(Rax));
+    }
+
+    fn is_unsigned(&self, value: ValueId) -> bool {
+        self.types.get(&value) == Some(&Type::U64)
+    }
+
+    fn instruction(&mut self, value: ValueId, kind: &InstructionKind) {
+        match kind {
+            InstructionKind::Const(constant) => {
+                let source = match constant {
+                    Constant::Number(num) => Operand::Imm(*num as i64),
+                    Constant::Bool(b) => Operand::Imm(*b as i64),
+                    Constant::Unit => Operand::Imm(0),
+                    Constant::String(s) => Operand::Address(self.generator.string(s)),
+                };
+                let destination = self.location(value);
+                self.mov(destination, source);
+            }
+            // moved into place by the prologue
+            InstructionKind::Param(_) => {}
+            InstructionKind::Copy(source) => {
+                let (destination, source) = (self.location(value), self.location(*source));
+                self.mov(destination, source);
+            }
+            InstructionKind::Binary(Operator::Div, left, right) => {
+                self.load(Rcx, *right);
+                self.emit(q("test", vec![reg(Rcx), reg(Rcx)]));
+                let target = format!(".Ldivide_by_zero{}", self.module_index);
+                self.emit(op("je", "je", vec![label(&target)]));
+                self.mark_division();
+                self.load(Rax, *left);
+                if self.is_unsigned(value) {
+                    self.emit(op("xorl", "xor", vec![long(Rdx), long(Rdx)]));
+                    self.emit(q("div", vec![reg(Rcx)]));
+                } else {
+                    self.emit(op("cqto", "cqo", Vec::new()));
+                    self.emit(q("idiv", vec![reg(Rcx)]));
+                }
+                self.store(value);
+            }
+            InstructionKind::Binary(operator, left, right) => {
+                let name = match operator {
+                    Operator::Add => "add",
+                    Operator::Sub => "sub",
+                    _ => "imul",
+                };
+                self.load(Rax, *left);
+                let right = self.location(*right);
+                self.emit(q(name, vec![reg(Rax), right]));
+                self.store(value);
+            }
+            InstructionKind::Compare(operator, left, right) => {
+                let unsigned = self.is_unsigned(*left) || self.is_unsigned(*right);
+                let condition = match (operator, unsigned) {
+                    (ComparisonOperator::Equal, _) => "e",
+                    (ComparisonOperator::NotEqual, _) => "ne",
+                    (ComparisonOperator::LessThan, false) => "l",
+                    (ComparisonOperator::GreaterThan, false) => "g",
+                    (ComparisonOperator::LessThanOrEqual, false) => "le",
+                    (ComparisonOperator::GreaterThanOrEqual, false) => "ge",
+                    (ComparisonOperator::LessThan, true) => "b",
+                    (ComparisonOperator::GreaterThan, true) => "a",
+                    (ComparisonOperator::LessThanOrEqual, true) => "be",
+                    (ComparisonOperator::GreaterThanOrEqual, true) => "ae",
+                };
+                self.load(Rax, *left);
+                let right = self.location(*right);
+                self.emit(q("cmp", vec![reg(Rax), right]));
+                self.set(condition);
+                self.store(value);
+            }
+            InstructionKind::Truthy(operand) => {
+                let operand = self.location(*operand);
+                self.emit(q("cmp", vec![operand, Operand::Imm(0)]));
+                self.set("ne");
+                self.store(value);
+            }
+            InstructionKind::Wrap(ty, operand) => {
+                self.load(Rax, *operand);
+                let byte = Operand::Reg(Rax, Size::Byte);
+                let word = Operand::Reg(Rax, Size::Word);
+                let line = match ty {
+                    Type::I8 => Some(op("movsbq", "movsx", vec![reg(Rax), byte])),
+                    Type::I16 => Some(op("movswq", "movsx", vec![reg(Rax), word])),
+                    Type::I32 => Some(op("movslq", "movsxd", vec![reg(Rax), long(Rax)])),
+                    Type::U8 => Some(op("movzbl", "movzx", vec![long(Rax), byte])),
+                    Type::U16 => Some(op("movzwl", "movzx", vec![long(Rax), word])),
+                    Type::U32 => Some(op("movl", "mov", vec![long(Rax), long(Rax)])),
+                    _ => None,
+                };
+                if let Some(line) = line {
+                    self.emit(line);
+                }
+                self.store(value);
+            }
+            InstructionKind::Call(callee, args) => {
+                self.call(*callee, args);
+                self.store(value);
+            }
+            InstructionKind::Print(args) => self.print(args),
+            InstructionKind::Closure(index, _) => {
+                let stem = symbol_stem(&self.module.name);
+                let symbol = function_symbol(&stem, &self.module.functions[*index].name);
+                let destination = self.location(value);
+                self.mov(destination, Operand::Address(symbol));
+            }
+            InstructionKind::LoadGlobal(global) => {
+                let destination = self.location(value);
+                self.mov(destination, Operand::Global(global_symbol(global)));
+            }
+            InstructionKind::StoreGlobal(global, operand) => {
+                let source = self.location(*operand);
+                self.mov(Operand::Global(global_symbol(global)), source);
+            }
+            // moved into by the predecessors
+            InstructionKind::Phi(_) => {}
+            // rejected by `check`, as only closures with captures use them
+            InstructionKind::Capture(_)
+            | InstructionKind::Cell
+            | InstructionKind::Load(_)
+            | InstructionKind::Store(..) => unreachable!("closure captures are not lowered"),
+        }
+    }
+
+    fn mark_division(&mut self) {
+        let divisions = &mut self.generator.divisions;
+        if divisions.len() <= self.module_index {
+            divisions.resize(self.module_index + 1, false);
+        }
+        divisions[self.module_index] = true;
+    }
+
+    // Sets `rax` to 1 if the flags meet the condition, else to 0.
+    fn set(&mut self, condition: &str) {
+        let set = format!("set{}", condition);
+        self.emit(op(&set, &set, vec![Operand::Reg(Rax, Size::Byte)]));
+        let byte = Operand::Reg(Rax, Size::Byte);
+        self.emit(op("movzbl", "movzx", vec![long(Rax), byte]));
+    }
+
+    // Calls a closure created in this function directly, and any other
+    // function value through its address. Arguments past the sixth go on
+    // the stack, padded to keep it aligned.
+    fn call(&mut self, callee: ValueId, args: &[ValueId]) {
+        let stacked = args.len().saturating_sub(ARGUMENTS.len());
+        let padding = stacked % 2 == 1;
+        if padding {
+            self.emit(q("sub", vec![reg(Rsp), Operand::Imm(8)]));
+        }
+        for &arg in args[ARGUMENTS.len().min(args.len())..].iter().rev() {
+            let source = self.location(arg);
+            self.emit(q("push", vec![source]));
+        }
+        for (&arg, &r) in args.iter().zip(ARGUMENTS.iter()) {
+            self.load(r, arg);
+        }
+        let direct = match self.function.instruction(callee).map(|i| &i.kind) {
+            Some(InstructionKind::Closure(index, _)) => {
+                let stem = symbol_stem(&self.module.name);
+                Some(function_symbol(&stem, &self.module.functions[*index].name))
+            }
+            _ => None,
+        };
+        match direct {
+            Some(symbol) => self.emit(op("call", "call", vec![label(&symbol)])),
+            None => {
+                self.load(R11, callee);
+                self.emit(op("call", "call", vec![Operand::Indirect(R11)]));
+            }
+        }
+        let popped = 8 * (stacked + padding as usize);
+        if popped > 0 {
+            self.emit(q("add", vec![reg(Rsp), Operand::Imm(popped as i64)]));
+        }
+    }
+
+    // One `printf` per value, each followed by a space or, for the last
+    // one, a newline.
+    fn print(&mut self, args: &[ValueId]) {
+        if args.is_empty() {
+            let format = self.generator.string("\n");
+            self.printf(format);
+            return;
+        }
+        for (index, &arg) in args.iter().enumerate() {
+            let ty = self.types.get(&arg).cloned().unwrap_or(Type::Unknown);
+            let separator = if index + 1 == args.len() { "\n" } else { " " };
+            let specifier = print_format(&ty).expect("checked by `check`");
+            let format = self
+                .generator
+                .string(&format!("{}{}", specifier, separator));
+            if ty == Type::Bool {
+                self.generator.booleans = true;
+                self.load(Rax, arg);
+                self.emit(q("lea", vec![reg(Rsi), Operand::Address(".Ltrue".into())]));
+                self.emit(q("lea", vec![reg(Rcx), Operand::Address(".Lfalse".into())]));
+                self.emit(q("test", vec![reg(Rax), reg(Rax)]));
+                self.emit(op("cmoveq", "cmove", vec![reg(Rsi), reg(Rcx)]));
+            } else {
+                self.load(Rsi, arg);
+            }
+            self.printf(format);
+        }
+    }
+
+    fn printf(&mut self, format: String) {
+        self.emit(q("lea", vec![reg(Rdi), Operand::Address(format)]));
+        // no vector registers hold arguments
+        self.emit(op("xorl", "xor", vec![long(Rax), long(Rax)]));
+        self.emit(op("call", "call", vec![label("printf")]));
+    }
+
+    fn terminator(&mut self, block: BlockId, terminator: &Terminator) {
+        let next = BlockId(block.0 + 1);
+        match terminator {
+            Terminator::Jump(target) => {
+                self.phi_moves(block, *target);
+                if *target != next {
+                    let target = self.block_label(*target);
+                    self.emit(op("jmp", "jmp", vec![label(&target)]));
+                }
+            }
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let then_label = self.edge(block, *then);
+                let otherwise_label = self.edge(block, *otherwise);
+                let condition = self.location(*condition);
+                self.emit(q("cmp", vec![condition, Operand::Imm(0)]));
+                if otherwise_label == self.block_label(next) {
+                    self.emit(op("jne", "jne", vec![label(&then_label)]));
+                } else if then_label == self.block_label(next) {
+                    self.emit(op("je", "je", vec![label(&otherwise_label)]));
+                } else {
+                    self.emit(op("jne", "jne", vec![label(&then_label)]));
+                    self.emit(op("jmp", "jmp", vec![label(&otherwise_label)]));
+                }
+            }
+            Terminator::Return(result) => {
+                match result {
+                    Some(result) => self.load(Rax, *result),
+                    None => self.emit(op("xorl", "xor", vec![long(Rax), long(Rax)])),
+                }
+                self.epilogue();
+            }
+        }
+    }
+
+    // Where a branch to `target` goes: the block itself, or a stub moving
+    // values into its phis first.
+    fn edge(&mut self, block: BlockId, target: BlockId) -> String {
+        let has_phis = self
+            .function
+            .block(target)
+            .instructions
+            .iter()
+            .any(|instruction| matches!(instruction.kind, InstructionKind::Phi(_)));
+        if !has_phis {
+            return self.block_label(target);
+        }
+        let stub = format!(".L{}.bb{}.bb{}", self.symbol, block.0, target.0);
+        let lines = std::mem::take(&mut self.lines);
+        self.lines.push(Line::Label(stub.clone()));
+        self.phi_moves(block, target);
+        let target = self.block_label(target);
+        self.emit(op("jmp", "jmp", vec![label(&target)]));
+        let stub_lines = std::mem::replace(&mut self.lines, lines);
+        self.stubs.extend(stub_lines);
+        stub
+    }
+
+    // Moves the values a block passes into the phis of its successor, all at
+    // once: a phi may read the value another one replaces.
+    fn phi_moves(&mut self, block: BlockId, target: BlockId) {
+        let mut moves: Vec<(Operand, Operand)> = Vec::new();
+        for instruction in &self.function.block(target).instructions {
+            if let InstructionKind::Phi(operands) = &instruction.kind {
+                if let Some(&(_, operand)) = operands.iter().find(|(p, _)| *p == block) {
+                    let destination = self.location(instruction.value);
+                    let source = self.location(operand);
+                    if destination != source {
+                        moves.push((destination, source));
+                    }
+                }
+            }
+        }
+        while !moves.is_empty() {
+            let ready = moves
+                .iter()
+                .position(|(destination, _)| moves.iter().all(|(_, s)| s != destination));
+            match ready {
+                Some(index) => {
+                    let (destination, source) = moves.remove(index);
+                    self.mov(destination, source);
+                }
+                // a cycle: set one destination's value aside in `r11`
+                None => {
+                    let destination = moves[0].0.clone();
+                    self.mov(reg(R11), destination.clone());
+                    for (_, source) in &mut moves {
+                        if *source == destination {
+                            *source = reg(R11);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The type the checker inferred for each value.
+fn types(function: &Function) -> HashMap<ValueId, Type> {
+    function
+        .instructions()
+        .map(|instruction| (instruction.value, instruction.ty.clone()))
+        .collect()
+}
+
+// The `printf` conversion printing a value of the type like the
+// interpreter, if there is one. Booleans are printed as strings.
+fn print_format(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::U64 => Some("%lu"),
+        Type::String | Type::Bool => Some("%s"),
+        ty if ty.is_numeric() => Some("%ld"),
+        _ => None,
+    }
+}
+
+/*
+Linear scan register allocation, after Poletto and Sarkar. Each value is
+live from its definition to its last use in the order the blocks are laid
+out, which covers every path as the graph has no loops. A phi is live from
+the end of its first predecessor, where it is moved into, and its operands
+until the end of theirs. When the registers run out the value live the
+longest goes to a stack slot.
+*/
+fn allocate(function: &Function) -> (HashMap<ValueId, Location>, usize) {
+    let mut position = 0;
+    let mut starts: HashMap<ValueId, usize> = HashMap::new();
+    let mut ends: HashMap<ValueId, usize> = HashMap::new();
+    let mut block_ends: HashMap<BlockId, usize> = HashMap::new();
+    fn use_at(ends: &mut HashMap<ValueId, usize>, value: ValueId, at: usize) {
+        let end = ends.entry(value).or_insert(at);
+        *end = (*end).max(at);
+    }
+    for block in &function.blocks {
+        for instruction in &block.instructions {
+            position += 1;
+            if !instruction.defines_value() {
+                // still read at this position
+            } else if let InstructionKind::Param(_) = instruction.kind {
+                starts.insert(instruction.value, 0);
+            } else {
+                starts.insert(instruction.value, position);
+            }
+            if !matches!(instruction.kind, InstructionKind::Phi(_)) {
+                for operand in instruction.kind.operands() {
+                    use_at(&mut ends, operand, position);
+                }
+            }
+        }
+        position += 1;
+        block_ends.insert(block.id, position);
+        let mut terminator = block.terminator.clone();
+        if let Some(operand) = terminator.operand_mut() {
+            use_at(&mut ends, *operand, position);
+        }
+    }
+    for block in &function.blocks {
+        for instruction in &block.instructions {
+            if let InstructionKind::Phi(operands) = &instruction.kind {
+                for &(predecessor, operand) in operands {
+                    let end = block_ends[&predecessor];
+                    use_at(&mut ends, operand, end);
+                    let start = starts.get_mut(&instruction.value).expect("phi defined");
+                    *start = (*start).min(end);
+                }
+            }
+        }
+    }
+
+    let mut intervals: Vec<(usize, usize, ValueId)> = starts
+        .iter()
+        .map(|(&value, &start)| {
+            let end = ends.get(&value).copied().unwrap_or(start).max(start);
+            (start, end, value)
+        })
+        .collect();
+    intervals.sort();
+
+    let mut locations = HashMap::new();
+    let mut slots = 0;
+    let mut free: Vec<Reg> = ALLOCATABLE.iter().rev().copied().collect();
+    let mut active: Vec<(usize, ValueId, Reg)> = Vec::new(); // by end
+    for (start, end, value) in intervals {
+        active.retain(|&(active_end, _, r)| {
+            if active_end < start {
+                free.push(r);
+                false
+            } else {
+                true
+            }
+        });
+        free.sort_by_key(|r| std::cmp::Reverse(ALLOCATABLE.iter().position(|a| a == r)));
+        if let Some(r) = free.pop() {
+            locations.insert(value, Location::Reg(r));
+            active.push((end, value, r));
+        } else {
+            let (last_end, last_value, r) = *active.last().expect("all registers active");
+            if last_end > end {
+                locations.insert(last_value, Location::Slot(slots));
+                locations.insert(value, Location::Reg(r));
+                active.pop();
+                active.push((end, value, r));
+            } else {
+                locations.insert(value, Location::Slot(slots));
+            }
+            slots += 1;
+        }
+        active.sort_by_key(|&(end, value, _)| (end, value));
+    }
+    (locations, slots)
+}
+
+fn stem(name: &str) -> String {
+    let file = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    file.strip_suffix(".astc").unwrap_or(file).to_string()
+}
+
+// The module's stem as a symbol, to prefix its functions and globals.
+fn symbol_stem(name: &str) -> String {
+    sanitize(&stem(name), '_')
+}
+
+// `<stem>.fn.<name>`, with the `@` and `:` of `lambda@line:col` replaced.
+// Variable names have no dots, so functions, globals (`<stem>.var.<name>`)
+// and module code (`<stem>.init`) never clash, with each other or with C.
+fn function_symbol(stem: &str, name: &str) -> String {
+    format!("{}.fn.{}", stem, sanitize(name, '.'))
+}
+
+// Globals are named `<stem>.<name>` in the IR.
+fn global_symbol(global: &str) -> String {
+    let (stem, name) = global.rsplit_once('.').unwrap_or(("", global));
+    format!("{}.var.{}", sanitize(stem, '_'), name)
+}
+
+fn sanitize(name: &str, replacement: char) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' => c,
+            '.' if replacement == '.' => c,
+            _ => replacement,
+        })
+        .collect()
+}
+
+fn escape(string: &str) -> String {
+    let mut escaped = String::new();
+    for c in string.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\{:03o}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
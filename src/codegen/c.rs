@@ -0,0 +1,1378 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::module::module::{Module, ModuleGraph};
+use crate::parser::statement::{
+    ComparisonOperator, Expression, ExpressionKind, LogicalOperator, MatchArm, NodeId, Operator,
+    Param, Pattern, PatternKind, Statement, StatementKind,
+};
+use crate::resolver::resolution::{DeclarationKind, Resolution};
+use crate::resolver::resolver::Resolver;
+
+const RUNTIME: &str = include_str!("c/runtime.c");
+
+// C keywords, and names from the headers or the generated code that a
+// variable must not hide. They get a `_` suffix, as do names starting with
+// `astc_`, which the runtime and generated temporaries use.
+const RESERVED: &[&str] = &[
+    "_Bool",
+    "_Complex",
+    "_Imaginary",
+    "args",
+    "auto",
+    "bool",
+    "break",
+    "captures",
+    "case",
+    "char",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "enum",
+    "EOF",
+    "errno",
+    "extern",
+    "float",
+    "for",
+    "goto",
+    "INFINITY",
+    "inline",
+    "int",
+    "int16_t",
+    "int32_t",
+    "int64_t",
+    "int8_t",
+    "isinf",
+    "isnan",
+    "long",
+    "main",
+    "NAN",
+    "NULL",
+    "register",
+    "restrict",
+    "short",
+    "signbit",
+    "signed",
+    "size_t",
+    "sizeof",
+    "static",
+    "stderr",
+    "stdin",
+    "stdout",
+    "struct",
+    "switch",
+    "typedef",
+    "uint16_t",
+    "uint32_t",
+    "uint64_t",
+    "uint8_t",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+];
+
+// How a value is held in C. Values whose type is known statically use the
+// plain C type; the rest, and every function, use the tagged `astc_value`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Repr {
+    Value,
+    Number,            // double
+    Int(&'static str), // the ASTC type name, `i32` is an `int32_t`
+    Bool,
+    String, // astc_string
+}
+
+impl Repr {
+    fn c_type(self) -> String {
+        match self {
+            Repr::Value => "astc_value".to_string(),
+            Repr::Number => "double".to_string(),
+            Repr::Int(name) => {
+                let (sign, bits) = name.split_at(1);
+                let prefix = if sign == "u" { "uint" } else { "int" };
+                format!("{}{}_t", prefix, bits)
+            }
+            Repr::Bool => "bool".to_string(),
+            Repr::String => "astc_string".to_string(),
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, Repr::Number | Repr::Int(_))
+    }
+
+    // What a declared type stores, if it can be stored unboxed.
+    fn from_type(name: Option<&str>) -> Option<Repr> {
+        match name? {
+            "i8" => Some(Repr::Int("i8")),
+            "i16" => Some(Repr::Int("i16")),
+            "i32" => Some(Repr::Int("i32")),
+            "i64" => Some(Repr::Int("i64")),
+            "u8" => Some(Repr::Int("u8")),
+            "u16" => Some(Repr::Int("u16")),
+            "u32" => Some(Repr::Int("u32")),
+            "u64" => Some(Repr::Int("u64")),
+            "number" => Some(Repr::Number),
+            "string" => Some(Repr::String),
+            "bool" => Some(Repr::Bool),
+            _ => None,
+        }
+    }
+}
+
+// A generated C expression. `precedence` follows the C grammar, from 1 for
+// the comma operator to 16 for calls and primary expressions.
+#[derive(Debug, Clone)]
+struct Code {
+    text: String,
+    repr: Repr,
+    precedence: u8,
+}
+
+impl Code {
+    fn new(text: String, repr: Repr, precedence: u8) -> Self {
+        Code {
+            text,
+            repr,
+            precedence,
+        }
+    }
+
+    // The text, parenthesized if it binds looser than `min`.
+    fn at(&self, min: u8) -> String {
+        if self.precedence < min {
+            format!("({})", self.text)
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Storage {
+    Local(Repr),
+    Cell,   // `astc_value *`, shared with closures
+    Global, // file-scope `astc_value`
+}
+
+#[derive(Debug, Clone)]
+struct Variable {
+    name: String, // in the generated C
+    storage: Storage,
+    owner: Option<NodeId>, // the declaring lambda, `None` for module code
+}
+
+// A C function being generated: a lambda, or the code of a module.
+#[derive(Default)]
+struct Function {
+    lambda: Option<NodeId>,
+    body: String,
+    indent: usize,
+    temps: Vec<String>, // declarations of spilled operands
+}
+
+/*
+Emits the whole graph as one C99 file: the runtime, then for each module,
+dependencies first, its globals, the functions of its lambdas and the code
+of its top level. Dependencies run from an init function each, called at
+the start of `main`, which holds the entry module.
+
+Variables declared at the top of a module that closures use, or that other
+modules import, are file-scope globals. Other variables closures use live in
+heap cells shared with them. Everything else is a plain C local, typed by
+what it holds:
+
+    let x: u8 = big + 10;    // uint8_t x = astc_to_u8(big + 10.0);
+*/
+pub fn generate(graph: &ModuleGraph) -> String {
+    let mut generator = Generator::default();
+    let mut out = format!(
+        "/* Generated by astc from {}. */\n\n{}",
+        graph.entry().map(|m| m.name.as_str()).unwrap_or(""),
+        RUNTIME
+    );
+
+    let mut inits = Vec::new();
+    for (index, module) in graph.modules.iter().enumerate() {
+        let entry = index + 1 == graph.modules.len();
+        out.push_str(&format!("\n/* {} */\n", module.name));
+        out.push_str(&generator.module(module, entry, &inits));
+        if !entry {
+            inits.push(format!("{}_init", generator.stem));
+        }
+    }
+    out
+}
+
+#[derive(Default)]
+struct Generator {
+    names: HashSet<String>,                // file-scope names in use
+    exports: Vec<HashMap<String, String>>, // global of each export, per module
+    stem: String,                          // prefix of the current module's names
+    resolution: Resolution,
+    declarations: HashMap<NodeId, usize>, // declaring node -> declaration
+    top_level: HashSet<usize>,            // declared by a module's statements
+    variables: HashMap<usize, Variable>,
+    captured: HashSet<usize>, // declarations used from another function
+    captures: HashMap<NodeId, Vec<usize>>, // outer cells each lambda uses
+    uses: Vec<(NodeId, Vec<NodeId>)>, // name uses and their enclosing lambdas
+    functions: Vec<String>,   // generated lambda functions
+    stack: Vec<Function>,
+}
+
+impl Generator {
+    fn module(&mut self, module: &Module, entry: bool, inits: &[String]) -> String {
+        self.stem = self.file_name(&stem(&module.name));
+        (self.resolution, _) = Resolver::resolve(&module.statements);
+        self.declarations = self
+            .resolution
+            .declarations
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| d.kind != DeclarationKind::Import)
+            .map(|(index, d)| (d.node, index))
+            .collect();
+        self.top_level = module
+            .statements
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StatementKind::Export(inner) => self.declarations.get(&inner.id),
+                _ => self.declarations.get(&stmt.id),
+            })
+            .copied()
+            .collect();
+        self.variables.clear();
+        self.captured.clear();
+        self.captures.clear();
+        self.functions.clear();
+
+        self.analyze(&module.statements, &mut Vec::new());
+        for (node, stack) in std::mem::take(&mut self.uses) {
+            self.capture(node, &stack);
+        }
+
+        // top-level names shared with closures or other modules are globals
+        let mut globals = Vec::new();
+        let mut exports = HashMap::new();
+        for stmt in &module.statements {
+            let (exported, stmt) = match &stmt.kind {
+                StatementKind::Export(inner) => (true, &**inner),
+                _ => (false, stmt),
+            };
+            let StatementKind::VarDeclaration { name, .. } = &stmt.kind else {
+                continue;
+            };
+            let Some(&decl) = self.declarations.get(&stmt.id) else {
+                continue;
+            };
+            if exported || self.captured.contains(&decl) {
+                let global = self.file_name(&format!("{}_{}", self.stem, name));
+                globals.push(format!("static astc_value {};\n", global));
+                if exported {
+                    exports.insert(name.clone(), global.clone());
+                }
+                self.variables.insert(
+                    decl,
+                    Variable {
+                        name: global,
+                        storage: Storage::Global,
+                        owner: None,
+                    },
+                );
+            }
+        }
+        self.bind_imports(module);
+        self.exports.push(exports);
+
+        self.stack.push(Function {
+            indent: 1,
+            ..Function::default()
+        });
+        self.line(&format!("astc_module = {};", c_string(&module.name)));
+        self.block_body(&module.statements);
+        let code = self.stack.pop().unwrap_or_default();
+
+        let mut out = String::new();
+        if !globals.is_empty() {
+            out.push('\n');
+            out.push_str(&globals.concat());
+        }
+        for function in &self.functions {
+            out.push('\n');
+            out.push_str(function);
+        }
+        out.push('\n');
+        if entry {
+            out.push_str("int main(void) {\n");
+            for init in inits {
+                out.push_str(&format!("    {}();\n", init));
+            }
+        } else {
+            out.push_str(&format!("static void {}_init(void) {{\n", self.stem));
+        }
+        out.push_str(&function_body(&code));
+        if entry {
+            out.push_str("    return 0;\n");
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    // Imported names read the exporting module's globals.
+    fn bind_imports(&mut self, module: &Module) {
+        for (index, decl) in self.resolution.declarations.iter().enumerate() {
+            if decl.kind != DeclarationKind::Import {
+                continue;
+            }
+            let global = module
+                .imports
+                .iter()
+                .filter(|import| import.names.contains(&decl.name))
+                .find_map(|import| self.exports[import.module].get(&decl.name));
+            if let Some(global) = global {
+                self.variables.insert(
+                    index,
+                    Variable {
+                        name: global.clone(),
+                        storage: Storage::Global,
+                        owner: None,
+                    },
+                );
+            }
+        }
+    }
+
+    // Finds the declarations used from a function other than the one they
+    // are declared in, and which outer cells each lambda needs. `stack` holds
+    // the enclosing lambdas.
+    fn analyze(&mut self, statements: &[Statement], stack: &mut Vec<NodeId>) {
+        for stmt in statements {
+            self.analyze_statement(stmt, stack);
+        }
+    }
+
+    fn analyze_statement(&mut self, stmt: &Statement, stack: &mut Vec<NodeId>) {
+        match &stmt.kind {
+            StatementKind::VarDeclaration { value, .. } => {
+                self.declare_owner(stmt.id, stack);
+                self.analyze_expression(value, stack);
+            }
+            StatementKind::FunctionCall { args, .. } => {
+                self.analyze_use(stmt.id, stack);
+                for arg in args {
+                    self.analyze_expression(arg, stack);
+                }
+            }
+            StatementKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.analyze_expression(condition, stack);
+                self.analyze(then_branch, stack);
+                if let Some(branch) = else_branch {
+                    self.analyze_statement(branch, stack);
+                }
+            }
+            StatementKind::Match { scrutinee, arms } => {
+                self.analyze_expression(scrutinee, stack);
+                for arm in arms {
+                    self.analyze(&arm.body, stack);
+                }
+            }
+            StatementKind::Block(statements) => self.analyze(statements, stack),
+            StatementKind::Export(inner) => self.analyze_statement(inner, stack),
+            StatementKind::Expression(expr) => self.analyze_expression(expr, stack),
+            StatementKind::Import { .. } => {}
+        }
+    }
+
+    fn analyze_expression(&mut self, expr: &Expression, stack: &mut Vec<NodeId>) {
+        match &expr.kind {
+            ExpressionKind::Identifier(_) => self.analyze_use(expr.id, stack),
+            ExpressionKind::FunctionCall { args, .. } => {
+                self.analyze_use(expr.id, stack);
+                for arg in args {
+                    self.analyze_expression(arg, stack);
+                }
+            }
+            ExpressionKind::BinaryOp(left, _, right)
+            | ExpressionKind::LogicalOp(left, _, right)
+            | ExpressionKind::ComparisonOp(left, _, right) => {
+                self.analyze_expression(left, stack);
+                self.analyze_expression(right, stack);
+            }
+            ExpressionKind::Call { callee, args } => {
+                self.analyze_expression(callee, stack);
+                for arg in args {
+                    self.analyze_expression(arg, stack);
+                }
+            }
+            ExpressionKind::Lambda { params, body } => {
+                stack.push(expr.id);
+                for param in params {
+                    self.declare_owner(param.id, stack);
+                }
+                self.analyze_expression(body, stack);
+                stack.pop();
+            }
+            ExpressionKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.analyze_expression(condition, stack);
+                self.analyze_expression(then_branch, stack);
+                self.analyze_expression(else_branch, stack);
+            }
+            ExpressionKind::Number(_)
+            | ExpressionKind::StringLiteral(_)
+            | ExpressionKind::Boolean(_) => {}
+        }
+    }
+
+    // Owners are recorded as placeholder locals; the real storage is picked
+    // when the declaration is generated.
+    fn declare_owner(&mut self, node: NodeId, stack: &[NodeId]) {
+        if let Some(&decl) = self.declarations.get(&node) {
+            self.variables.insert(
+                decl,
+                Variable {
+                    name: String::new(),
+                    storage: Storage::Local(Repr::Value),
+                    owner: stack.last().copied(),
+                },
+            );
+        }
+    }
+
+    // Lambdas may use names declared after them, so uses are looked at once
+    // every owner is known.
+    fn analyze_use(&mut self, node: NodeId, stack: &[NodeId]) {
+        self.uses.push((node, stack.to_vec()));
+    }
+
+    fn capture(&mut self, node: NodeId, stack: &[NodeId]) {
+        let Some(&decl) = self.resolution.uses.get(&node) else {
+            return;
+        };
+        // imports are globals, used directly from anywhere
+        let Some(owner) = self.variables.get(&decl).map(|v| v.owner) else {
+            return;
+        };
+        if owner == stack.last().copied() {
+            return;
+        }
+        self.captured.insert(decl);
+
+        // every lambda between the owner and the use passes the cell along;
+        // shared top-level names are globals instead
+        if self.top_level.contains(&decl) {
+            return;
+        }
+        let start = match owner {
+            Some(owner) => stack
+                .iter()
+                .position(|&id| id == owner)
+                .map_or(0, |i| i + 1),
+            None => 0,
+        };
+        for lambda in &stack[start..] {
+            let captures = self.captures.entry(*lambda).or_default();
+            if !captures.contains(&decl) {
+                captures.push(decl);
+            }
+        }
+    }
+
+    fn block_body(&mut self, statements: &[Statement]) {
+        // cells exist before any closure that might use them is created
+        for stmt in statements {
+            let stmt = match &stmt.kind {
+                StatementKind::Export(inner) => inner,
+                _ => stmt,
+            };
+            if let StatementKind::VarDeclaration { name, .. } = &stmt.kind {
+                let Some(&decl) = self.declarations.get(&stmt.id) else {
+                    continue;
+                };
+                if self.captured.contains(&decl) && !self.is_global(decl) {
+                    let name = self.local_name(decl, name);
+                    self.line(&format!("astc_value *{} = astc_cell();", name));
+                    self.set_variable(decl, name, Storage::Cell);
+                }
+            }
+        }
+
+        for (index, stmt) in statements.iter().enumerate() {
+            // compound statements are set apart by blank lines
+            if index > 0 && (is_compound(stmt) || is_compound(&statements[index - 1])) {
+                self.current().body.push('\n');
+            }
+            self.statement(stmt);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match &stmt.kind {
+            StatementKind::VarDeclaration {
+                name,
+                var_type,
+                value,
+                ..
+            } => self.declaration(stmt.id, name, var_type.as_deref(), value),
+            StatementKind::FunctionCall { name, args } => {
+                let call = self.function_call(stmt.id, name, args);
+                self.line(&format!("{};", call.text));
+            }
+            StatementKind::Expression(expr) => {
+                let code = self.expression(expr, None);
+                if matches!(
+                    expr.kind,
+                    ExpressionKind::FunctionCall { .. } | ExpressionKind::Call { .. }
+                ) {
+                    self.line(&format!("{};", code.text));
+                } else {
+                    self.line(&format!("(void){};", code.at(14)));
+                }
+            }
+            StatementKind::If { .. } => {
+                self.indent();
+                self.if_statement(stmt);
+                self.current().body.push('\n');
+            }
+            StatementKind::Match { scrutinee, arms } => {
+                let code = self.expression(scrutinee, None);
+                self.line("{");
+                self.current().indent += 1;
+                // a leading wildcard arm always runs, nothing tests `match`
+                if let Some(arm) = arms.first().filter(|arm| is_wildcard(arm)) {
+                    if has_call(scrutinee) {
+                        self.line(&format!("(void){};", code.at(14)));
+                    }
+                    self.block_body(&arm.body);
+                } else {
+                    self.line(&format!("{} match = {};", code.repr.c_type(), code.text));
+                    self.match_arms(arms, code.repr);
+                }
+                self.current().indent -= 1;
+                self.line("}");
+            }
+            StatementKind::Block(statements) => {
+                self.line("{");
+                self.block(statements);
+                self.line("}");
+            }
+            StatementKind::Export(inner) => self.statement(inner),
+            // imported names read the exporting module's globals
+            StatementKind::Import { .. } => {}
+        }
+    }
+
+    /*
+    The value is stored unboxed when its representation allows, wrapped to
+    the declared integer width:
+
+        let a: i32 = 2 * 3;     // int32_t a = astc_to_i32(2.0 * 3.0);
+        let s = "x";            // astc_string s = ASTC_STR("x");
+        let f = (v) => v;       // astc_value f = astc_closure_new(...);
+    */
+    fn declaration(
+        &mut self,
+        node: NodeId,
+        name: &str,
+        var_type: Option<&str>,
+        value: &Expression,
+    ) {
+        let hint = matches!(value.kind, ExpressionKind::Lambda { .. }).then_some(name);
+        let code = self.expression(value, hint);
+        let Some(&decl) = self.declarations.get(&node) else {
+            return;
+        };
+
+        let storage = self.variables.get(&decl).map(|v| v.storage.clone());
+        match storage {
+            Some(Storage::Global) => {
+                let global = self.variables[&decl].name.clone();
+                let value = wrap_value(code, var_type);
+                self.line(&format!("{} = {};", global, value.text));
+            }
+            Some(Storage::Cell) => {
+                let cell = self.variables[&decl].name.clone();
+                let value = wrap_value(code, var_type);
+                self.line(&format!("*{} = {};", cell, value.text));
+            }
+            _ => {
+                let code = match (Repr::from_type(var_type), code.repr) {
+                    (Some(Repr::Int(to)), Repr::Int(from)) if to == from => code,
+                    (Some(Repr::Int(to)), repr) if repr.is_numeric() => {
+                        Code::new(format!("astc_to_{}({})", to, code.text), Repr::Int(to), 16)
+                    }
+                    (Some(Repr::Number), Repr::Int(_)) => Code::new(code.text, Repr::Number, 16),
+                    _ => wrap_value(code, var_type),
+                };
+                let name = self.local_name(decl, name);
+                self.line(&format!("{} {} = {};", code.repr.c_type(), name, code.text));
+                // keeps `-Wunused-variable` quiet about locals never read
+                if !self.resolution.uses.values().any(|&used| used == decl) {
+                    self.line(&format!("(void){};", name));
+                }
+                self.set_variable(decl, name, Storage::Local(code.repr));
+            }
+        }
+    }
+
+    // Continues the current line, so `else if` chains stay flat.
+    fn if_statement(&mut self, stmt: &Statement) {
+        let StatementKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        } = &stmt.kind
+        else {
+            return;
+        };
+
+        let condition = truthy(self.expression(condition, None));
+        self.current()
+            .body
+            .push_str(&format!("if ({}) {{\n", condition.text));
+        self.block(then_branch);
+        self.indent();
+        self.current().body.push('}');
+        match else_branch.as_deref() {
+            Some(
+                branch @ Statement {
+                    kind: StatementKind::If { .. },
+                    ..
+                },
+            ) => {
+                self.current().body.push_str(" else ");
+                self.if_statement(branch);
+            }
+            Some(Statement {
+                kind: StatementKind::Block(statements),
+                ..
+            }) => {
+                self.current().body.push_str(" else {\n");
+                self.block(statements);
+                self.indent();
+                self.current().body.push('}');
+            }
+            Some(branch) => {
+                self.current().body.push_str(" else {\n");
+                self.block(std::slice::from_ref(branch));
+                self.indent();
+                self.current().body.push('}');
+            }
+            None => {}
+        }
+    }
+
+    // An `if` chain over `match`; a wildcard arm becomes the final `else`
+    // and ends the chain, since later arms cannot match.
+    fn match_arms(&mut self, arms: &[MatchArm], repr: Repr) {
+        let mut first = true;
+        for arm in arms {
+            let wildcard = is_wildcard(arm);
+            let grouped = arm.patterns.len() > 1;
+            let conditions: Vec<String> = arm
+                .patterns
+                .iter()
+                .map(|p| pattern_condition(p, repr, grouped))
+                .collect();
+            let head = match (first, wildcard) {
+                (true, _) => format!("if ({}) {{", conditions.join(" || ")),
+                (false, false) => format!("}} else if ({}) {{", conditions.join(" || ")),
+                (false, true) => "} else {".to_string(),
+            };
+            self.line(&head);
+            self.block(&arm.body);
+            first = false;
+            if wildcard {
+                break;
+            }
+        }
+        if !first {
+            self.line("}");
+        }
+    }
+
+    fn block(&mut self, statements: &[Statement]) {
+        self.current().indent += 1;
+        self.block_body(statements);
+        self.current().indent -= 1;
+    }
+
+    // `hint` names the function of a lambda bound by a declaration.
+    fn expression(&mut self, expr: &Expression, hint: Option<&str>) -> Code {
+        match &expr.kind {
+            ExpressionKind::Identifier(name) => self.read(expr.id, name, false),
+            ExpressionKind::Number(num) => number(*num),
+            ExpressionKind::StringLiteral(s) => {
+                Code::new(format!("ASTC_STR({})", c_string(s)), Repr::String, 16)
+            }
+            ExpressionKind::Boolean(b) => Code::new(b.to_string(), Repr::Bool, 16),
+            ExpressionKind::BinaryOp(left, op, right) => {
+                let nonzero = nonzero(right);
+                let (mut operands, prefix) = self.operands(&[left, right]);
+                let right = operands.pop().unwrap();
+                let left = operands.pop().unwrap();
+                sequenced(prefix, arithmetic(left, op, right, nonzero))
+            }
+            ExpressionKind::ComparisonOp(left, op, right) => {
+                let (mut operands, prefix) = self.operands(&[left, right]);
+                let right = operands.pop().unwrap();
+                let left = operands.pop().unwrap();
+                sequenced(prefix, comparison(left, op, right))
+            }
+            ExpressionKind::LogicalOp(left, op, right) => {
+                let (symbol, precedence) = match op {
+                    LogicalOperator::And => ("&&", 5),
+                    LogicalOperator::Or => ("||", 4),
+                };
+                let left = truthy(self.expression(left, None));
+                let right = truthy(self.expression(right, None));
+                Code::new(
+                    format!(
+                        "{} {} {}",
+                        left.at(precedence),
+                        symbol,
+                        right.at(precedence + 1)
+                    ),
+                    Repr::Bool,
+                    precedence,
+                )
+            }
+            ExpressionKind::FunctionCall { name, args } => self.function_call(expr.id, name, args),
+            ExpressionKind::Call { callee, args } => {
+                let mut operands = vec![&**callee];
+                operands.extend(args);
+                let (operands, prefix) = self.operands(&operands);
+                let mut operands = operands.into_iter();
+                let callee = boxed(operands.next().unwrap());
+                let call = call(&callee.at(16), operands.map(boxed).collect());
+                sequenced(prefix, call)
+            }
+            ExpressionKind::Lambda { params, body } => self.lambda(expr, params, body, hint),
+            ExpressionKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = truthy(self.expression(condition, None));
+                let then_branch = self.expression(then_branch, None);
+                let else_branch = self.expression(else_branch, None);
+                let (then_branch, else_branch) = unify(then_branch, else_branch);
+                let repr = then_branch.repr;
+                Code::new(
+                    format!(
+                        "{} ? {} : {}",
+                        condition.at(4),
+                        then_branch.at(3),
+                        else_branch.at(3)
+                    ),
+                    repr,
+                    3,
+                )
+            }
+        }
+    }
+
+    // `print` is the builtin only when no declaration of that name is in
+    // scope; the interpreter looks the callee up after evaluating arguments.
+    fn function_call(&mut self, node: NodeId, name: &str, args: &[Expression]) -> Code {
+        let operands: Vec<&Expression> = args.iter().collect();
+        let (operands, prefix) = self.operands(&operands);
+        let args = operands.into_iter().map(boxed).collect();
+
+        let code = if self.resolution.uses.contains_key(&node) {
+            let callee = self.read(node, name, true);
+            call(&callee.text, args)
+        } else {
+            Code::new(format!("astc_print({})", arguments(args)), Repr::Value, 16)
+        };
+        sequenced(prefix, code)
+    }
+
+    fn read(&mut self, node: NodeId, name: &str, callee: bool) -> Code {
+        let Some(variable) = self
+            .resolution
+            .uses
+            .get(&node)
+            .and_then(|decl| self.variables.get(decl))
+        else {
+            return Code::new("astc_unit()".to_string(), Repr::Value, 16);
+        };
+
+        let load = if callee {
+            "astc_load_function"
+        } else {
+            "astc_load"
+        };
+        let here = variable.owner == self.current_lambda();
+        match variable.storage {
+            Storage::Local(repr) => Code::new(variable.name.clone(), repr, 16),
+            Storage::Cell if here => Code::new(format!("*{}", variable.name), Repr::Value, 14),
+            Storage::Cell => Code::new(
+                format!("{}({}, {})", load, variable.name, c_string(name)),
+                Repr::Value,
+                16,
+            ),
+            Storage::Global if here => Code::new(variable.name.clone(), Repr::Value, 16),
+            Storage::Global => Code::new(
+                format!("{}(&{}, {})", load, variable.name, c_string(name)),
+                Repr::Value,
+                16,
+            ),
+        }
+    }
+
+    /*
+    A lambda becomes a C function taking the cells it captured and its
+    arguments, and evaluates to a closure pairing the two:
+
+        static astc_value lambda_add_fn(astc_value **captures, astc_value *args) {
+            int32_t a = astc_to_i32(astc_as_number(args[0]));
+            ...
+        }
+    */
+    fn lambda(
+        &mut self,
+        expr: &Expression,
+        params: &[Param],
+        body: &Expression,
+        hint: Option<&str>,
+    ) -> Code {
+        let base = match hint {
+            Some(name) => format!("{}_{}_fn", self.stem, name),
+            None => format!(
+                "{}_lambda_{}_{}",
+                self.stem, expr.span.line, expr.span.column
+            ),
+        };
+        let function = self.file_name(&base);
+        let captures = self.captures.get(&expr.id).cloned().unwrap_or_default();
+        let cells: Vec<String> = captures
+            .iter()
+            .map(|decl| self.variables[decl].name.clone())
+            .collect();
+
+        self.stack.push(Function {
+            lambda: Some(expr.id),
+            indent: 1,
+            ..Function::default()
+        });
+        for (index, cell) in cells.iter().enumerate() {
+            self.line(&format!("astc_value *{} = captures[{}];", cell, index));
+        }
+        if cells.is_empty() {
+            self.line("(void)captures;");
+        }
+        if params.is_empty() {
+            self.line("(void)args;");
+        }
+        for (index, param) in params.iter().enumerate() {
+            self.parameter(param, &format!("args[{}]", index));
+        }
+        let result = boxed(self.expression(body, None));
+        self.line(&format!("return {};", result.text));
+        let code = self.stack.pop().unwrap_or_default();
+
+        self.functions.push(format!(
+            "static astc_value {}(astc_value **captures, astc_value *args) {{\n{}}}\n",
+            function,
+            function_body(&code)
+        ));
+
+        let names: Vec<&str> = params.iter().map(|p| p.name.as_str()).collect();
+        let cells = if cells.is_empty() {
+            "NULL".to_string()
+        } else {
+            format!("(astc_value *[]){{{}}}", cells.join(", "))
+        };
+        Code::new(
+            format!(
+                "astc_closure_new({}, {}, {}, {}, {})",
+                function,
+                c_string(&names.join(", ")),
+                params.len(),
+                captures.len(),
+                cells
+            ),
+            Repr::Value,
+            16,
+        )
+    }
+
+    // Typed parameters are unboxed, failing if the argument has another type.
+    fn parameter(&mut self, param: &Param, arg: &str) {
+        let Some(&decl) = self.declarations.get(&param.id) else {
+            return;
+        };
+        let name = identifier(&param.name);
+        let ty = param.param_type.as_deref();
+
+        if self.captured.contains(&decl) {
+            let value = match Repr::from_type(ty) {
+                Some(Repr::Int(int)) => format!("astc_wrap_{}({})", int, arg),
+                _ => arg.to_string(),
+            };
+            self.line(&format!("astc_value *{} = astc_box({});", name, value));
+            self.set_variable(decl, name, Storage::Cell);
+            return;
+        }
+
+        let (repr, value) = match Repr::from_type(ty) {
+            Some(Repr::Int(int)) => (
+                Repr::Int(int),
+                format!("astc_to_{}(astc_as_number({}))", int, arg),
+            ),
+            Some(Repr::Number) => (Repr::Number, format!("astc_as_number({})", arg)),
+            Some(Repr::String) => (Repr::String, format!("astc_as_string({})", arg)),
+            Some(Repr::Bool) => (Repr::Bool, format!("astc_as_bool({})", arg)),
+            _ => (Repr::Value, arg.to_string()),
+        };
+        self.line(&format!("{} {} = {};", repr.c_type(), name, value));
+        self.set_variable(decl, name, Storage::Local(repr));
+    }
+
+    /*
+    C leaves the order of operands unspecified, ASTC evaluates them left to
+    right. When several operands have side effects, all but the last are
+    spilled into temporaries first:
+
+        print(f(1), g(2));    // (astc_tmp1 = ..., astc_print(2, ...))
+    */
+    fn operands(&mut self, exprs: &[&Expression]) -> (Vec<Code>, Vec<String>) {
+        let last = exprs.iter().rposition(|e| has_call(e));
+        let mut prefix = Vec::new();
+        let mut codes = Vec::new();
+        for (index, expr) in exprs.iter().enumerate() {
+            let code = self.expression(expr, None);
+            if has_call(expr) && Some(index) < last {
+                let function = self.current();
+                let temp = format!("astc_tmp{}", function.temps.len() + 1);
+                function
+                    .temps
+                    .push(format!("{} {};", code.repr.c_type(), temp));
+                prefix.push(format!("{} = {}", temp, code.at(2)));
+                codes.push(Code::new(temp, code.repr, 16));
+            } else {
+                codes.push(code);
+            }
+        }
+        (codes, prefix)
+    }
+
+    fn is_global(&self, decl: usize) -> bool {
+        matches!(
+            self.variables.get(&decl),
+            Some(Variable {
+                storage: Storage::Global,
+                ..
+            })
+        )
+    }
+
+    fn set_variable(&mut self, decl: usize, name: String, storage: Storage) {
+        let owner = self.current_lambda();
+        self.variables.insert(
+            decl,
+            Variable {
+                name,
+                storage,
+                owner,
+            },
+        );
+    }
+
+    fn current_lambda(&self) -> Option<NodeId> {
+        self.stack.last().and_then(|f| f.lambda)
+    }
+
+    fn current(&mut self) -> &mut Function {
+        self.stack.last_mut().expect("no function being generated")
+    }
+
+    fn line(&mut self, text: &str) {
+        self.indent();
+        self.current().body.push_str(text);
+        self.current().body.push('\n');
+    }
+
+    fn indent(&mut self) {
+        let function = self.current();
+        function.body.push_str(&"    ".repeat(function.indent));
+    }
+
+    // A file-scope name not taken yet, numbered if needed.
+    // A C local named like the variable it shadows would already hide that
+    // one in its own initializer, as in `let x = x + 1;`, so it gets the
+    // declaration's index appended.
+    fn local_name(&self, decl: usize, name: &str) -> String {
+        match self.resolution.declarations[decl].shadows {
+            Some(_) => format!("{}_{}", identifier(name), decl),
+            None => identifier(name),
+        }
+    }
+
+    fn file_name(&mut self, base: &str) -> String {
+        let mut name = base.to_string();
+        let mut suffix = 2;
+        while self.names.contains(&name) {
+            name = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        self.names.insert(name.clone());
+        name
+    }
+}
+
+fn function_body(function: &Function) -> String {
+    let mut out = String::new();
+    for temp in &function.temps {
+        out.push_str(&format!("    {}\n", temp));
+    }
+    out.push_str(&function.body);
+    out
+}
+
+fn arithmetic(left: Code, op: &Operator, right: Code, nonzero: bool) -> Code {
+    if left.repr.is_numeric() && right.repr.is_numeric() {
+        if *op == Operator::Div && !nonzero {
+            return Code::new(
+                format!("astc_divide({}, {})", left.text, right.text),
+                Repr::Number,
+                16,
+            );
+        }
+        let (symbol, precedence) = match op {
+            Operator::Add => ("+", 12),
+            Operator::Sub => ("-", 12),
+            Operator::Mul => ("*", 13),
+            Operator::Div => ("/", 13),
+        };
+        return Code::new(
+            format!(
+                "{} {} {}",
+                as_double(&left, &right, precedence),
+                symbol,
+                right.at(precedence + 1)
+            ),
+            Repr::Number,
+            precedence,
+        );
+    }
+    if *op == Operator::Add && left.repr == Repr::String && right.repr == Repr::String {
+        return Code::new(
+            format!("astc_concat({}, {})", left.text, right.text),
+            Repr::String,
+            16,
+        );
+    }
+
+    let function = match op {
+        Operator::Add => "astc_add",
+        Operator::Sub => "astc_sub",
+        Operator::Mul => "astc_mul",
+        Operator::Div => "astc_div",
+    };
+    Code::new(
+        format!("{}({}, {})", function, boxed(left).text, boxed(right).text),
+        Repr::Value,
+        16,
+    )
+}
+
+fn comparison(left: Code, op: &ComparisonOperator, right: Code) -> Code {
+    let (symbol, precedence) = match op {
+        ComparisonOperator::Equal => ("==", 9),
+        ComparisonOperator::NotEqual => ("!=", 9),
+        ComparisonOperator::LessThan => ("<", 10),
+        ComparisonOperator::GreaterThan => (">", 10),
+        ComparisonOperator::LessThanOrEqual => ("<=", 10),
+        ComparisonOperator::GreaterThanOrEqual => (">=", 10),
+    };
+    let equality = precedence == 9;
+    let negate = *op == ComparisonOperator::NotEqual;
+
+    let text = match (left.repr, right.repr) {
+        (l, r) if l.is_numeric() && r.is_numeric() => format!(
+            "{} {} {}",
+            as_double(&left, &right, precedence),
+            symbol,
+            right.at(precedence + 1)
+        ),
+        (Repr::Bool, Repr::Bool) if equality => {
+            format!(
+                "{} {} {}",
+                left.at(precedence),
+                symbol,
+                right.at(precedence + 1)
+            )
+        }
+        (Repr::String, Repr::String) if equality => {
+            let equal = format!("astc_str_equal({}, {})", left.text, right.text);
+            return negated(equal, negate);
+        }
+        (Repr::String, Repr::String) => format!(
+            "astc_str_compare({}, {}) {} 0",
+            left.text, right.text, symbol
+        ),
+        _ if equality => {
+            let equal = format!("astc_equal({}, {})", boxed(left).text, boxed(right).text);
+            return negated(equal, negate);
+        }
+        _ => format!(
+            "astc_compare({}, {}) {} 0",
+            boxed(left).text,
+            boxed(right).text,
+            symbol
+        ),
+    };
+    Code::new(text, Repr::Bool, precedence)
+}
+
+fn negated(call: String, negate: bool) -> Code {
+    if negate {
+        Code::new(format!("!{}", call), Repr::Bool, 14)
+    } else {
+        Code::new(call, Repr::Bool, 16)
+    }
+}
+
+// Integer operands would otherwise be combined with C's integer rules, not
+// as doubles. One double operand is enough to make C convert the other.
+fn as_double(left: &Code, right: &Code, precedence: u8) -> String {
+    match (left.repr, right.repr) {
+        (Repr::Int(_), Repr::Int(_)) => format!("(double){}", left.at(14)),
+        _ => left.at(precedence),
+    }
+}
+
+// Branches of `?:` need one representation.
+fn unify(then_branch: Code, else_branch: Code) -> (Code, Code) {
+    if then_branch.repr == else_branch.repr {
+        return (then_branch, else_branch);
+    }
+    if then_branch.repr.is_numeric() && else_branch.repr.is_numeric() {
+        let double = |code: Code| match code.repr {
+            Repr::Int(_) => Code::new(format!("(double){}", code.at(14)), Repr::Number, 14),
+            _ => code,
+        };
+        return (double(then_branch), double(else_branch));
+    }
+    (boxed(then_branch), boxed(else_branch))
+}
+
+fn boxed(code: Code) -> Code {
+    let constructor = match code.repr {
+        Repr::Value => return code,
+        Repr::Number | Repr::Int(_) => "astc_num",
+        Repr::Bool => "astc_bool",
+        Repr::String => "astc_str",
+    };
+    Code::new(format!("{}({})", constructor, code.text), Repr::Value, 16)
+}
+
+fn truthy(code: Code) -> Code {
+    match code.repr {
+        Repr::Bool => code,
+        Repr::Number | Repr::Int(_) => Code::new(format!("{} != 0", code.at(10)), Repr::Bool, 9),
+        Repr::String => Code::new(format!("{}.length != 0", code.at(16)), Repr::Bool, 9),
+        Repr::Value => Code::new(format!("astc_truthy({})", code.text), Repr::Bool, 16),
+    }
+}
+
+// Boxes `code` for a global or a cell, wrapping it to a declared integer
+// type.
+fn wrap_value(code: Code, var_type: Option<&str>) -> Code {
+    match Repr::from_type(var_type) {
+        Some(Repr::Int(int)) if code.repr.is_numeric() => Code::new(
+            format!("astc_num(astc_to_{}({}))", int, code.text),
+            Repr::Value,
+            16,
+        ),
+        Some(Repr::Int(int)) if code.repr == Repr::Value => {
+            Code::new(format!("astc_wrap_{}({})", int, code.text), Repr::Value, 16)
+        }
+        _ => boxed(code),
+    }
+}
+
+fn call(callee: &str, args: Vec<Code>) -> Code {
+    Code::new(
+        format!("astc_call({}, {})", callee, arguments(args)),
+        Repr::Value,
+        16,
+    )
+}
+
+fn arguments(args: Vec<Code>) -> String {
+    if args.is_empty() {
+        return "0, NULL".to_string();
+    }
+    let texts: Vec<String> = args.into_iter().map(|code| code.text).collect();
+    format!("{}, (astc_value[]){{{}}}", texts.len(), texts.join(", "))
+}
+
+fn sequenced(prefix: Vec<String>, code: Code) -> Code {
+    if prefix.is_empty() {
+        return code;
+    }
+    Code::new(
+        format!("({}, {})", prefix.join(", "), code.text),
+        code.repr,
+        16,
+    )
+}
+
+// Calls are the only expressions with side effects.
+fn has_call(expr: &Expression) -> bool {
+    match &expr.kind {
+        ExpressionKind::FunctionCall { .. } | ExpressionKind::Call { .. } => true,
+        ExpressionKind::BinaryOp(left, _, right)
+        | ExpressionKind::LogicalOp(left, _, right)
+        | ExpressionKind::ComparisonOp(left, _, right) => has_call(left) || has_call(right),
+        ExpressionKind::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => has_call(condition) || has_call(then_branch) || has_call(else_branch),
+        _ => false,
+    }
+}
+
+fn pattern_condition(pattern: &Pattern, repr: Repr, grouped: bool) -> String {
+    match (&pattern.kind, repr) {
+        (PatternKind::Number(num), r) if r.is_numeric() => {
+            format!("match == {}", number(*num).text)
+        }
+        (PatternKind::Number(num), Repr::Value) => {
+            format!("astc_equal(match, astc_num({}))", number(*num).text)
+        }
+        (PatternKind::StringLiteral(s), Repr::String) => {
+            format!("astc_str_equal(match, ASTC_STR({}))", c_string(s))
+        }
+        (PatternKind::StringLiteral(s), Repr::Value) => {
+            format!("astc_equal(match, astc_str(ASTC_STR({})))", c_string(s))
+        }
+        (PatternKind::Boolean(true), Repr::Bool) => "match".to_string(),
+        (PatternKind::Boolean(false), Repr::Bool) => "!match".to_string(),
+        (PatternKind::Boolean(b), Repr::Value) => {
+            format!("astc_equal(match, astc_bool({}))", b)
+        }
+        (
+            PatternKind::Range {
+                start,
+                end,
+                inclusive,
+            },
+            r,
+        ) if r.is_numeric() => {
+            let condition = format!(
+                "match >= {} && match {} {}",
+                number(*start).text,
+                if *inclusive { "<=" } else { "<" },
+                number(*end).text
+            );
+            if grouped {
+                format!("({})", condition)
+            } else {
+                condition
+            }
+        }
+        (
+            PatternKind::Range {
+                start,
+                end,
+                inclusive,
+            },
+            Repr::Value,
+        ) => format!(
+            "astc_in_range(match, {}, {}, {})",
+            number(*start).text,
+            number(*end).text,
+            inclusive
+        ),
+        (PatternKind::Wildcard, _) => "true".to_string(),
+        _ => "false".to_string(), // a pattern of another type never matches
+    }
+}
+
+fn is_wildcard(arm: &MatchArm) -> bool {
+    arm.patterns.iter().any(|p| p.kind == PatternKind::Wildcard)
+}
+
+fn is_compound(stmt: &Statement) -> bool {
+    match &stmt.kind {
+        StatementKind::Export(inner) => is_compound(inner),
+        kind => matches!(
+            kind,
+            StatementKind::If { .. } | StatementKind::Match { .. } | StatementKind::Block(_)
+        ),
+    }
+}
+
+// A literal divisor other than zero needs no runtime check.
+fn nonzero(expr: &Expression) -> bool {
+    matches!(expr.kind, ExpressionKind::Number(num) if num != 0.0)
+}
+
+// Always a double literal, so C never computes in integers.
+fn number(num: f64) -> Code {
+    let text = if num.is_nan() {
+        "NAN".to_string()
+    } else if num.is_infinite() {
+        if num > 0.0 { "INFINITY" } else { "-INFINITY" }.to_string()
+    } else {
+        format!("{:?}", num)
+    };
+    let precedence = if text.starts_with('-') { 14 } else { 16 };
+    Code::new(text, Repr::Number, precedence)
+}
+
+fn c_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            // `??` could start a trigraph
+            '?' if chars.peek() == Some(&'?') => out.push_str("\\?"),
+            c if (c as u32) < 0x20 || c == '\x7f' => out.push_str(&format!("\\{:03o}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn identifier(name: &str) -> String {
+    if RESERVED.contains(&name) || name.starts_with("astc_") || name.starts_with("ASTC_") {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+// `math` for `./lib/math.astc`.
+fn stem(name: &str) -> String {
+    let file = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let stem = file.strip_suffix(".astc").unwrap_or(file);
+    let stem: String = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if stem.starts_with(|c: char| c.is_ascii_digit()) || stem.is_empty() {
+        format!("m_{}", stem)
+    } else {
+        stem
+    }
+}
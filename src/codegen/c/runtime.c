@@ -0,0 +1,448 @@
+#include <math.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+/* ASTC runtime. Memory is never freed: strings, cells and closures live
+   until the program exits. */
+
+typedef struct {
+    const char *data;
+    size_t length;
+} astc_string;
+
+#define ASTC_STR(literal) ((astc_string){(literal), sizeof(literal) - 1})
+
+typedef enum {
+    ASTC_UNDEFINED, /* a variable read before its declaration ran */
+    ASTC_UNIT,
+    ASTC_NUMBER,
+    ASTC_STRING,
+    ASTC_BOOL,
+    ASTC_FUNCTION
+} astc_tag;
+
+typedef struct astc_closure astc_closure;
+
+typedef struct {
+    astc_tag tag;
+    union {
+        double number;
+        astc_string string;
+        bool boolean;
+        astc_closure *function;
+    } as;
+} astc_value;
+
+struct astc_closure {
+    astc_value (*code)(astc_value **captures, astc_value *args);
+    const char *params; /* shown when the function is printed */
+    int arity;
+    astc_value **captures; /* cells of the outer variables the body uses */
+};
+
+static const char *astc_module = ""; /* the module being run, for errors */
+
+static inline astc_value astc_unit(void) {
+    astc_value value = {ASTC_UNIT, {0}};
+    return value;
+}
+
+static inline astc_value astc_num(double number) {
+    astc_value value;
+    value.tag = ASTC_NUMBER;
+    value.as.number = number;
+    return value;
+}
+
+static inline astc_value astc_str(astc_string string) {
+    astc_value value;
+    value.tag = ASTC_STRING;
+    value.as.string = string;
+    return value;
+}
+
+static inline astc_value astc_bool(bool boolean) {
+    astc_value value;
+    value.tag = ASTC_BOOL;
+    value.as.boolean = boolean;
+    return value;
+}
+
+/* Writes `number` the way Rust's `{}` does: the shortest digits that read
+   back as the same double, never in exponent notation. */
+static inline void astc_write_number(FILE *out, double number) {
+    char buffer[32];
+    char digits[24];
+    int precision, exponent, count = 0, i;
+    const char *p;
+
+    if (isnan(number)) {
+        fputs("NaN", out);
+        return;
+    }
+    if (isinf(number)) {
+        fputs(number > 0 ? "inf" : "-inf", out);
+        return;
+    }
+    if (number == 0) {
+        fputs(signbit(number) ? "-0" : "0", out);
+        return;
+    }
+
+    for (precision = 0; precision < 17; precision++) {
+        snprintf(buffer, sizeof buffer, "%.*e", precision, number);
+        if (strtod(buffer, NULL) == number) {
+            break;
+        }
+    }
+    p = buffer;
+    if (*p == '-') {
+        fputc('-', out);
+        p++;
+    }
+    for (; *p != 'e'; p++) {
+        if (*p != '.') {
+            digits[count++] = *p;
+        }
+    }
+    exponent = atoi(p + 1);
+
+    if (exponent >= count - 1) {
+        fwrite(digits, 1, count, out);
+        for (i = count - 1; i < exponent; i++) {
+            fputc('0', out);
+        }
+    } else if (exponent >= 0) {
+        fwrite(digits, 1, exponent + 1, out);
+        fputc('.', out);
+        fwrite(digits + exponent + 1, 1, count - exponent - 1, out);
+    } else {
+        fputs("0.", out);
+        for (i = -1; i > exponent; i--) {
+            fputc('0', out);
+        }
+        fwrite(digits, 1, count, out);
+    }
+}
+
+static inline void astc_show(FILE *out, astc_value value) {
+    switch (value.tag) {
+    case ASTC_NUMBER:
+        astc_write_number(out, value.as.number);
+        break;
+    case ASTC_STRING:
+        fwrite(value.as.string.data, 1, value.as.string.length, out);
+        break;
+    case ASTC_BOOL:
+        fputs(value.as.boolean ? "true" : "false", out);
+        break;
+    case ASTC_FUNCTION:
+        fprintf(out, "<function (%s)>", value.as.function->params);
+        break;
+    default:
+        fputs("()", out);
+        break;
+    }
+}
+
+/* Runtime errors are reported like `astc run` does and end the program. */
+static inline void astc_error_start(void) {
+    fflush(stdout);
+    fprintf(stderr, "Error: %s: ", astc_module);
+}
+
+static inline void astc_error_end(void) {
+    fputc('\n', stderr);
+    exit(1);
+}
+
+static inline void astc_fail(const char *message) {
+    astc_error_start();
+    fputs(message, stderr);
+    astc_error_end();
+}
+
+static inline void astc_fail_value(astc_value value, const char *message) {
+    astc_error_start();
+    astc_show(stderr, value);
+    fputs(message, stderr);
+    astc_error_end();
+}
+
+static inline void astc_fail_values(const char *before, astc_value left, const char *middle,
+                                    astc_value right) {
+    astc_error_start();
+    fputs(before, stderr);
+    astc_show(stderr, left);
+    fputs(middle, stderr);
+    astc_show(stderr, right);
+    astc_error_end();
+}
+
+static inline astc_value astc_print(int count, const astc_value *args) {
+    int i;
+    for (i = 0; i < count; i++) {
+        if (i > 0) {
+            putchar(' ');
+        }
+        astc_show(stdout, args[i]);
+    }
+    putchar('\n');
+    return astc_unit();
+}
+
+static inline bool astc_truthy(astc_value value) {
+    switch (value.tag) {
+    case ASTC_NUMBER:
+        return value.as.number != 0;
+    case ASTC_STRING:
+        return value.as.string.length != 0;
+    case ASTC_BOOL:
+        return value.as.boolean;
+    case ASTC_FUNCTION:
+        return true;
+    default:
+        return false;
+    }
+}
+
+/* Strings */
+
+static inline astc_string astc_concat(astc_string left, astc_string right) {
+    char *data = malloc(left.length + right.length + 1);
+    astc_string result;
+    memcpy(data, left.data, left.length);
+    memcpy(data + left.length, right.data, right.length);
+    data[left.length + right.length] = '\0';
+    result.data = data;
+    result.length = left.length + right.length;
+    return result;
+}
+
+static inline bool astc_str_equal(astc_string left, astc_string right) {
+    return left.length == right.length && memcmp(left.data, right.data, left.length) == 0;
+}
+
+/* Byte-wise, like Rust orders strings. */
+static inline int astc_str_compare(astc_string left, astc_string right) {
+    size_t length = left.length < right.length ? left.length : right.length;
+    int order = memcmp(left.data, right.data, length);
+    if (order != 0) {
+        return order;
+    }
+    return left.length < right.length ? -1 : left.length > right.length ? 1 : 0;
+}
+
+/* Operators on values whose type is only known at run time */
+
+static inline double astc_divide(double left, double right) {
+    if (right == 0) {
+        astc_fail("Division by zero");
+    }
+    return left / right;
+}
+
+static inline astc_value astc_apply(char op, astc_value left, astc_value right) {
+    char message[] = "Cannot apply `?` to ";
+    if (left.tag == ASTC_NUMBER && right.tag == ASTC_NUMBER) {
+        switch (op) {
+        case '+':
+            return astc_num(left.as.number + right.as.number);
+        case '-':
+            return astc_num(left.as.number - right.as.number);
+        case '*':
+            return astc_num(left.as.number * right.as.number);
+        default:
+            return astc_num(astc_divide(left.as.number, right.as.number));
+        }
+    }
+    if (op == '+' && left.tag == ASTC_STRING && right.tag == ASTC_STRING) {
+        return astc_str(astc_concat(left.as.string, right.as.string));
+    }
+    message[14] = op;
+    astc_fail_values(message, left, " and ", right);
+    return astc_unit();
+}
+
+static inline astc_value astc_add(astc_value left, astc_value right) {
+    return astc_apply('+', left, right);
+}
+
+static inline astc_value astc_sub(astc_value left, astc_value right) {
+    return astc_apply('-', left, right);
+}
+
+static inline astc_value astc_mul(astc_value left, astc_value right) {
+    return astc_apply('*', left, right);
+}
+
+static inline astc_value astc_div(astc_value left, astc_value right) {
+    return astc_apply('/', left, right);
+}
+
+static inline bool astc_equal(astc_value left, astc_value right) {
+    if (left.tag != right.tag) {
+        return false;
+    }
+    switch (left.tag) {
+    case ASTC_NUMBER:
+        return left.as.number == right.as.number;
+    case ASTC_STRING:
+        return astc_str_equal(left.as.string, right.as.string);
+    case ASTC_BOOL:
+        return left.as.boolean == right.as.boolean;
+    case ASTC_FUNCTION:
+        return left.as.function == right.as.function;
+    default:
+        return true;
+    }
+}
+
+/* Orders numbers with numbers and strings with strings; anything else,
+   including NaN, is an error. */
+static inline int astc_compare(astc_value left, astc_value right) {
+    if (left.tag == ASTC_NUMBER && right.tag == ASTC_NUMBER) {
+        if (left.as.number < right.as.number) {
+            return -1;
+        }
+        if (left.as.number > right.as.number) {
+            return 1;
+        }
+        if (left.as.number == right.as.number) {
+            return 0;
+        }
+    }
+    if (left.tag == ASTC_STRING && right.tag == ASTC_STRING) {
+        return astc_str_compare(left.as.string, right.as.string);
+    }
+    astc_fail_values("Cannot compare ", left, " with ", right);
+    return 0;
+}
+
+static inline bool astc_in_range(astc_value value, double start, double end, bool inclusive) {
+    return value.tag == ASTC_NUMBER && value.as.number >= start &&
+           (inclusive ? value.as.number <= end : value.as.number < end);
+}
+
+/* Sized integers. Like Rust's `value as i64`, conversion truncates and
+   saturates, then the cast to the target width keeps the low bits. */
+
+static inline int64_t astc_saturate(double value) {
+    if (isnan(value)) {
+        return 0;
+    }
+    if (value >= 9223372036854775807.0) {
+        return INT64_MAX;
+    }
+    if (value <= -9223372036854775808.0) {
+        return INT64_MIN;
+    }
+    return (int64_t)value;
+}
+
+#define ASTC_INTEGER(type, name)                                                                   \
+    static inline type astc_to_##name(double value) { return (type)astc_saturate(value); }         \
+    static inline astc_value astc_wrap_##name(astc_value value) {                                  \
+        if (value.tag == ASTC_NUMBER) {                                                            \
+            value.as.number = (double)astc_to_##name(value.as.number);                             \
+        }                                                                                          \
+        return value;                                                                              \
+    }
+
+ASTC_INTEGER(int8_t, i8)
+ASTC_INTEGER(int16_t, i16)
+ASTC_INTEGER(int32_t, i32)
+ASTC_INTEGER(int64_t, i64)
+ASTC_INTEGER(uint8_t, u8)
+ASTC_INTEGER(uint16_t, u16)
+ASTC_INTEGER(uint32_t, u32)
+ASTC_INTEGER(uint64_t, u64)
+
+/* Arguments to typed parameters */
+
+static inline double astc_as_number(astc_value value) {
+    if (value.tag != ASTC_NUMBER) {
+        astc_fail_value(value, " is not a number");
+    }
+    return value.as.number;
+}
+
+static inline astc_string astc_as_string(astc_value value) {
+    if (value.tag != ASTC_STRING) {
+        astc_fail_value(value, " is not a string");
+    }
+    return value.as.string;
+}
+
+static inline bool astc_as_bool(astc_value value) {
+    if (value.tag != ASTC_BOOL) {
+        astc_fail_value(value, " is not a bool");
+    }
+    return value.as.boolean;
+}
+
+/* Variables captured by closures live in heap cells. */
+
+static inline astc_value *astc_cell(void) {
+    return calloc(1, sizeof(astc_value));
+}
+
+static inline astc_value *astc_box(astc_value value) {
+    astc_value *cell = astc_cell();
+    *cell = value;
+    return cell;
+}
+
+static inline astc_value astc_load(const astc_value *cell, const char *name) {
+    if (cell->tag == ASTC_UNDEFINED) {
+        astc_error_start();
+        fprintf(stderr, "Undefined variable `%s`", name);
+        astc_error_end();
+    }
+    return *cell;
+}
+
+static inline astc_value astc_load_function(const astc_value *cell, const char *name) {
+    if (cell->tag == ASTC_UNDEFINED) {
+        astc_error_start();
+        fprintf(stderr, "Undefined function `%s`", name);
+        astc_error_end();
+    }
+    return *cell;
+}
+
+/* Functions */
+
+static inline astc_value astc_closure_new(astc_value (*code)(astc_value **, astc_value *),
+                                          const char *params, int arity, int count,
+                                          astc_value **captures) {
+    astc_closure *closure = malloc(sizeof *closure);
+    astc_value value;
+    closure->code = code;
+    closure->params = params;
+    closure->arity = arity;
+    closure->captures = NULL;
+    if (count > 0) {
+        closure->captures = malloc(count * sizeof *captures);
+        memcpy(closure->captures, captures, count * sizeof *captures);
+    }
+    value.tag = ASTC_FUNCTION;
+    value.as.function = closure;
+    return value;
+}
+
+static inline astc_value astc_call(astc_value callee, int count, astc_value *args) {
+    if (callee.tag != ASTC_FUNCTION) {
+        astc_fail_value(callee, " is not a function");
+    }
+    if (callee.as.function->arity != count) {
+        astc_error_start();
+        fprintf(stderr, "Expected %d argument(s) but %d were given", callee.as.function->arity,
+                count);
+        astc_error_end();
+    }
+    return callee.as.function->code(callee.as.function->captures, args);
+}
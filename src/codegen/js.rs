@@ -0,0 +1,778 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::checker::checker::Checker;
+use crate::checker::types::Type;
+use crate::codegen::sourcemap::SourceMap;
+use crate::json::Json;
+use crate::lexer::token::Span;
+use crate::module::module::{Module, ModuleGraph};
+use crate::parser::statement::{
+    ComparisonOperator, Expression, ExpressionKind, LogicalOperator, MatchArm, NodeId, Operator,
+    Param, Pattern, PatternKind, Statement, StatementKind,
+};
+use crate::resolver::resolution::Resolution;
+use crate::resolver::resolver::Resolver;
+
+// Names ASTC allows but JavaScript reserves, or that the generated code
+// relies on. They get a `$` suffix, which no ASTC identifier can contain.
+const RESERVED: &[&str] = &[
+    "arguments",
+    "await",
+    "BigInt",
+    "Boolean",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "console",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "enum",
+    "Error",
+    "eval",
+    "extends",
+    "finally",
+    "for",
+    "implements",
+    "in",
+    "Infinity",
+    "instanceof",
+    "interface",
+    "Math",
+    "NaN",
+    "new",
+    "null",
+    "Number",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "try",
+    "typeof",
+    "undefined",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+const DIV_HELPER: &str = "\
+function $div(a, b) {
+    if (b === 0) {
+        throw new Error(\"Division by zero\");
+    }
+    return a / b;
+}
+";
+
+// Same as `Type::wrap`: Rust's `value as i64` truncates and saturates, then
+// the cast to the target width keeps the low bits.
+const WRAP_HELPER: &str = "\
+function $wrap(value, bits, signed) {
+    if (typeof value !== \"number\") {
+        return value;
+    }
+    if (Number.isNaN(value)) {
+        return 0;
+    }
+    const max = 2n ** 63n - 1n;
+    let int = Number.isFinite(value) ? BigInt(Math.trunc(value)) : value > 0 ? max : -max - 1n;
+    int = int > max ? max : int < -max - 1n ? -max - 1n : int;
+    return Number(signed ? BigInt.asIntN(bits, int) : BigInt.asUintN(bits, int));
+}
+";
+
+#[derive(Debug, Default)]
+pub struct JsConfig {
+    pub jsdoc: bool, // annotate declarations with JSDoc types
+}
+
+pub struct JsOutput {
+    pub code: String,
+    pub source_map: SourceMap,
+}
+
+/*
+Emits the whole graph as one script. A lone module is emitted as is; in a
+larger graph each dependency is wrapped in a function returning its exports:
+
+    const $math = (() => {
+        const square = (n) => { n = $i32(n); return n * n; };
+        return { square };
+    })();
+
+and imports destructure that object. The entry module comes last, unwrapped.
+Runtime helpers are function declarations appended at the end, so hoisting
+makes them available everywhere.
+*/
+pub fn generate(graph: &ModuleGraph, config: &JsConfig) -> JsOutput {
+    let mut generator = Generator::new(config);
+    let bundle = graph.modules.len() > 1;
+
+    let (mut types, _) = Checker::check_graph(graph);
+    let mut objects: Vec<String> = Vec::new();
+    for (index, module) in graph.modules.iter().enumerate() {
+        let source = generator.source_map.add_source(&module.name);
+        generator.source = source;
+        (generator.resolution, _) = Resolver::resolve(&module.statements);
+        generator.types = std::mem::take(&mut types[index]);
+        generator.imports = module
+            .imports
+            .iter()
+            .map(|import| objects[import.module].clone())
+            .collect();
+        generator.next_import = 0;
+
+        if bundle {
+            if index > 0 {
+                generator.newline();
+            }
+            generator.line(&format!("// {}", module.name));
+        }
+        if index + 1 == graph.modules.len() {
+            generator.statements(&module.statements);
+        } else {
+            let object = module_object(module, &objects);
+            generator.module(module, &object);
+            objects.push(object);
+        }
+    }
+
+    generator.helpers();
+    JsOutput {
+        code: generator.out,
+        source_map: generator.source_map,
+    }
+}
+
+struct Generator<'a> {
+    config: &'a JsConfig,
+    out: String,
+    line: usize,   // output position, 0-based
+    column: usize, // in UTF-16 units, as source maps count them
+    indent: usize,
+    source_map: SourceMap,
+    source: usize, // index of the current module in the source map
+    resolution: Resolution,
+    types: HashMap<NodeId, Type>,
+    imports: Vec<String>,            // exports object of each import in order
+    next_import: usize,              // import statements emitted so far
+    helpers: BTreeSet<&'static str>, // runtime helpers the code calls
+}
+
+impl<'a> Generator<'a> {
+    fn new(config: &'a JsConfig) -> Self {
+        Generator {
+            config,
+            out: String::new(),
+            line: 0,
+            column: 0,
+            indent: 0,
+            source_map: SourceMap::new(),
+            source: 0,
+            resolution: Resolution::default(),
+            types: HashMap::new(),
+            imports: Vec::new(),
+            next_import: 0,
+            helpers: BTreeSet::new(),
+        }
+    }
+
+    fn module(&mut self, module: &Module, object: &str) {
+        self.line(&format!("const {} = (() => {{", object));
+        self.indent += 1;
+        self.statements(&module.statements);
+        let exports: Vec<String> = module.exports.iter().map(|name| property(name)).collect();
+        self.line(&format!("return {{ {} }};", exports.join(", ")));
+        self.indent -= 1;
+        self.line("})();");
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for (index, stmt) in statements.iter().enumerate() {
+            // compound statements are set apart by blank lines
+            if index > 0 && (is_compound(stmt) || is_compound(&statements[index - 1])) {
+                self.newline();
+            }
+            self.statement(stmt);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match &stmt.kind {
+            StatementKind::VarDeclaration {
+                constant,
+                name,
+                var_type,
+                value,
+            } => {
+                if self.config.jsdoc {
+                    self.jsdoc(var_type.as_deref(), value);
+                }
+                self.start_line();
+                self.mark(stmt.span);
+                let keyword = if *constant { "const" } else { "let" };
+                self.write(&format!("{} {} = ", keyword, identifier(name)));
+                match wrapper(var_type.as_deref()) {
+                    Some(helper) => self.wrapped(helper, value),
+                    None => self.expression(value, 1),
+                }
+                self.write(";");
+                self.end_line();
+            }
+            StatementKind::FunctionCall { name, args } => {
+                self.start_line();
+                self.mark(stmt.span);
+                self.call(stmt.id, name, args);
+                self.write(";");
+                self.end_line();
+            }
+            StatementKind::Expression(expr) => {
+                self.start_line();
+                self.mark(stmt.span);
+                self.expression(expr, 1);
+                self.write(";");
+                self.end_line();
+            }
+            StatementKind::If { .. } => {
+                self.start_line();
+                self.if_statement(stmt);
+                self.end_line();
+            }
+            StatementKind::Match { scrutinee, arms } => {
+                self.start_line();
+                self.mark(stmt.span);
+                self.open_block();
+                self.start_line();
+                self.write("const $match = ");
+                self.expression(scrutinee, 1);
+                self.write(";");
+                self.end_line();
+                self.match_arms(arms);
+                self.close_block();
+                self.end_line();
+            }
+            StatementKind::Block(statements) => {
+                self.start_line();
+                self.mark(stmt.span);
+                self.block(statements);
+                self.end_line();
+            }
+            StatementKind::Import { names, .. } => {
+                let object = self.imports[self.next_import].clone();
+                self.next_import += 1;
+                let names: Vec<String> = names.iter().map(|name| property(name)).collect();
+                self.start_line();
+                self.mark(stmt.span);
+                self.write(&format!("const {{ {} }} = {};", names.join(", "), object));
+                self.end_line();
+            }
+            StatementKind::Export(inner) => self.statement(inner),
+        }
+    }
+
+    // Continues the current line, so `else if` chains stay flat.
+    fn if_statement(&mut self, stmt: &Statement) {
+        let StatementKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        } = &stmt.kind
+        else {
+            return;
+        };
+
+        self.mark(stmt.span);
+        self.write("if (");
+        self.expression(condition, 1);
+        self.write(") ");
+        self.block(then_branch);
+        match else_branch.as_deref() {
+            Some(
+                branch @ Statement {
+                    kind: StatementKind::If { .. },
+                    ..
+                },
+            ) => {
+                self.write(" else ");
+                self.if_statement(branch);
+            }
+            Some(Statement {
+                kind: StatementKind::Block(statements),
+                ..
+            }) => {
+                self.write(" else ");
+                self.block(statements);
+            }
+            Some(branch) => {
+                self.write(" else ");
+                self.block(std::slice::from_ref(branch));
+            }
+            None => {}
+        }
+    }
+
+    // An `if` chain over `$match`; a wildcard arm becomes the final `else`
+    // and ends the chain, since later arms cannot match.
+    fn match_arms(&mut self, arms: &[MatchArm]) {
+        let mut first = true;
+        for arm in arms {
+            let wildcard = arm.patterns.iter().any(|p| p.kind == PatternKind::Wildcard);
+
+            if first && wildcard {
+                self.statements(&arm.body);
+                return;
+            }
+            if first {
+                self.start_line();
+            } else {
+                self.write(" else ");
+            }
+            first = false;
+
+            self.mark(arm.span);
+            if !wildcard {
+                let conditions: Vec<String> = arm
+                    .patterns
+                    .iter()
+                    .map(|p| pattern_condition(p, arm.patterns.len() > 1))
+                    .collect();
+                self.write(&format!("if ({}) ", conditions.join(" || ")));
+            }
+            self.block(&arm.body);
+            if wildcard {
+                break;
+            }
+        }
+        if !first {
+            self.end_line();
+        }
+    }
+
+    fn block(&mut self, statements: &[Statement]) {
+        self.open_block();
+        self.statements(statements);
+        self.close_block();
+    }
+
+    fn open_block(&mut self) {
+        self.write("{");
+        self.end_line();
+        self.indent += 1;
+    }
+
+    fn close_block(&mut self) {
+        self.indent -= 1;
+        self.start_line();
+        self.write("}");
+    }
+
+    /*
+    Precedence levels, loosest first:
+
+        1  arrow function
+        2  ?:
+        3  ||
+        4  &&
+        5  === !==
+        6  < > <= >=
+        7  + -
+        8  * /, negative literals
+        9  calls
+        10 literals, names
+    */
+    fn expression(&mut self, expr: &Expression, min: u8) {
+        let precedence = self.precedence(expr);
+        let parens = precedence < min;
+        if parens {
+            self.write("(");
+        }
+        self.mark(expr.span);
+
+        match &expr.kind {
+            ExpressionKind::Identifier(name) => self.write(&identifier(name)),
+            ExpressionKind::Number(num) => self.write(&number(*num)),
+            ExpressionKind::StringLiteral(s) => self.write(&Json::String(s.clone()).to_string()),
+            ExpressionKind::Boolean(b) => self.write(&b.to_string()),
+            ExpressionKind::BinaryOp(left, Operator::Div, right) if !nonzero(right) => {
+                self.helpers.insert("$div");
+                self.write("$div(");
+                self.expression(left, 1);
+                self.write(", ");
+                self.expression(right, 1);
+                self.write(")");
+            }
+            ExpressionKind::BinaryOp(left, op, right) => {
+                let symbol = match op {
+                    Operator::Add => "+",
+                    Operator::Sub => "-",
+                    Operator::Mul => "*",
+                    Operator::Div => "/",
+                };
+                self.binary(left, symbol, right, precedence);
+            }
+            ExpressionKind::ComparisonOp(left, op, right) => {
+                let symbol = match op {
+                    ComparisonOperator::Equal => "===",
+                    ComparisonOperator::NotEqual => "!==",
+                    ComparisonOperator::LessThan => "<",
+                    ComparisonOperator::GreaterThan => ">",
+                    ComparisonOperator::LessThanOrEqual => "<=",
+                    ComparisonOperator::GreaterThanOrEqual => ">=",
+                };
+                self.binary(left, symbol, right, precedence);
+            }
+            ExpressionKind::LogicalOp(left, op, right) => {
+                let (symbol, level) = match op {
+                    LogicalOperator::And => ("&&", 4),
+                    LogicalOperator::Or => ("||", 3),
+                };
+                // JavaScript yields an operand, ASTC a boolean
+                if self.is_bool(left) && self.is_bool(right) {
+                    self.binary(left, symbol, right, level);
+                } else {
+                    self.write("Boolean(");
+                    self.binary(left, symbol, right, level);
+                    self.write(")");
+                }
+            }
+            ExpressionKind::FunctionCall { name, args } => self.call(expr.id, name, args),
+            ExpressionKind::Call { callee, args } => {
+                self.expression(callee, 9);
+                self.arguments(args);
+            }
+            ExpressionKind::Lambda { params, body } => self.lambda(params, body),
+            ExpressionKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expression(condition, 3);
+                self.write(" ? ");
+                self.expression(then_branch, 1);
+                self.write(" : ");
+                self.expression(else_branch, 1);
+            }
+        }
+
+        if parens {
+            self.write(")");
+        }
+    }
+
+    fn precedence(&self, expr: &Expression) -> u8 {
+        match &expr.kind {
+            ExpressionKind::Lambda { .. } => 1,
+            ExpressionKind::Conditional { .. } => 2,
+            ExpressionKind::LogicalOp(left, op, right) => {
+                if !self.is_bool(left) || !self.is_bool(right) {
+                    9 // wrapped in `Boolean(...)`
+                } else if *op == LogicalOperator::Or {
+                    3
+                } else {
+                    4
+                }
+            }
+            ExpressionKind::ComparisonOp(_, op, _) => match op {
+                ComparisonOperator::Equal | ComparisonOperator::NotEqual => 5,
+                _ => 6,
+            },
+            ExpressionKind::BinaryOp(_, Operator::Div, right) if !nonzero(right) => 9,
+            ExpressionKind::BinaryOp(_, Operator::Add | Operator::Sub, _) => 7,
+            ExpressionKind::BinaryOp(..) => 8,
+            ExpressionKind::Number(num) if num.is_sign_negative() && !num.is_nan() => 8,
+            ExpressionKind::FunctionCall { .. } | ExpressionKind::Call { .. } => 9,
+            _ => 10,
+        }
+    }
+
+    // All operators here are left-associative.
+    fn binary(&mut self, left: &Expression, symbol: &str, right: &Expression, precedence: u8) {
+        self.expression(left, precedence);
+        self.write(&format!(" {} ", symbol));
+        self.expression(right, precedence + 1);
+    }
+
+    // `print` is the builtin only when no declaration of that name is in scope.
+    fn call(&mut self, node: NodeId, name: &str, args: &[Expression]) {
+        if name == "print" && self.resolution.declaration_of(node).is_none() {
+            self.write("console.log");
+        } else {
+            self.write(&identifier(name));
+        }
+        self.arguments(args);
+    }
+
+    fn arguments(&mut self, args: &[Expression]) {
+        self.write("(");
+        for (index, arg) in args.iter().enumerate() {
+            if index > 0 {
+                self.write(", ");
+            }
+            self.expression(arg, 1);
+        }
+        self.write(")");
+    }
+
+    // Integer parameters are wrapped on entry, which needs a block body.
+    fn lambda(&mut self, params: &[Param], body: &Expression) {
+        let names: Vec<String> = params.iter().map(|p| identifier(&p.name)).collect();
+        self.write(&format!("({}) => ", names.join(", ")));
+
+        let wraps: Vec<String> = params
+            .iter()
+            .filter_map(|p| {
+                let helper = wrapper(p.param_type.as_deref())?;
+                self.helpers.insert(helper);
+                let name = identifier(&p.name);
+                Some(format!("{} = {}({});", name, helper, name))
+            })
+            .collect();
+        if wraps.is_empty() {
+            self.expression(body, 1);
+        } else {
+            self.write(&format!("{{ {} return ", wraps.join(" ")));
+            self.expression(body, 1);
+            self.write("; }");
+        }
+    }
+
+    fn wrapped(&mut self, helper: &'static str, value: &Expression) {
+        self.helpers.insert(helper);
+        self.write(&format!("{}(", helper));
+        self.expression(value, 1);
+        self.write(")");
+    }
+
+    fn is_bool(&self, expr: &Expression) -> bool {
+        self.types.get(&expr.id) == Some(&Type::Bool)
+    }
+
+    // A declared type wins over the inferred one. Nothing is emitted when
+    // neither is known.
+    fn jsdoc(&mut self, var_type: Option<&str>, value: &Expression) {
+        let ty = match var_type {
+            Some(name) => Type::from_name(name),
+            None => self.types.get(&value.id).cloned().unwrap_or(Type::Unknown),
+        };
+
+        match (&value.kind, &ty) {
+            (ExpressionKind::Lambda { params, .. }, Type::Function { params: types, ret }) => {
+                self.line("/**");
+                for (param, ty) in params.iter().zip(types) {
+                    self.line(&format!(
+                        " * @param {{{}}} {}",
+                        js_type(ty),
+                        identifier(&param.name)
+                    ));
+                }
+                self.line(&format!(" * @returns {{{}}}", js_type(ret)));
+                self.line(" */");
+            }
+            (_, Type::Unknown) => {}
+            _ => self.line(&format!("/** @type {{{}}} */", js_type(&ty))),
+        }
+    }
+
+    // Only the helpers the code calls, in a fixed order.
+    fn helpers(&mut self) {
+        if self.helpers.is_empty() {
+            return;
+        }
+        self.newline();
+        self.write("// ASTC runtime helpers\n");
+        if self.helpers.contains("$div") {
+            self.write("\n");
+            self.write(DIV_HELPER);
+        }
+        let wrappers: Vec<&str> = ["i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64"]
+            .into_iter()
+            .filter(|name| self.helpers.contains(format!("${}", name).as_str()))
+            .collect();
+        if wrappers.is_empty() {
+            return;
+        }
+        self.write("\n");
+        self.write(WRAP_HELPER);
+        for name in wrappers {
+            let bits = &name[1..];
+            let signed = name.starts_with('i');
+            self.write(&format!(
+                "\nfunction ${}(value) {{\n    return $wrap(value, {}, {});\n}}\n",
+                name, bits, signed
+            ));
+        }
+    }
+
+    // Maps the current output position to the start of `span`.
+    fn mark(&mut self, span: Span) {
+        self.source_map.add(
+            self.line,
+            self.column,
+            self.source,
+            span.line.saturating_sub(1),
+            span.column.saturating_sub(1),
+        );
+    }
+
+    fn line(&mut self, text: &str) {
+        self.start_line();
+        self.write(text);
+        self.end_line();
+    }
+
+    fn start_line(&mut self) {
+        self.write(&"    ".repeat(self.indent));
+    }
+
+    fn end_line(&mut self) {
+        self.write("\n");
+    }
+
+    fn newline(&mut self) {
+        self.write("\n");
+    }
+
+    fn write(&mut self, text: &str) {
+        for c in text.chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 0;
+            } else {
+                self.column += c.len_utf16();
+            }
+        }
+        self.out.push_str(text);
+    }
+}
+
+fn is_compound(stmt: &Statement) -> bool {
+    match &stmt.kind {
+        StatementKind::Export(inner) => is_compound(inner),
+        kind => matches!(
+            kind,
+            StatementKind::If { .. } | StatementKind::Match { .. } | StatementKind::Block(_)
+        ),
+    }
+}
+
+// `$math` for `./lib/math.astc`, numbered if two modules share a file name.
+fn module_object(module: &Module, taken: &[String]) -> String {
+    let file = module
+        .name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(&module.name);
+    let stem = file.strip_suffix(".astc").unwrap_or(file);
+    let stem: String = stem
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+
+    let mut name = format!("${}", stem);
+    let mut suffix = 2;
+    while taken.contains(&name) {
+        name = format!("${}{}", stem, suffix);
+        suffix += 1;
+    }
+    name
+}
+
+fn identifier(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("{}$", name)
+    } else {
+        name.to_string()
+    }
+}
+
+// An object key for `name`, renaming the binding if it is reserved.
+fn property(name: &str) -> String {
+    let binding = identifier(name);
+    if binding == name {
+        binding
+    } else {
+        format!("{}: {}", name, binding)
+    }
+}
+
+// The helper narrowing values of a sized integer type, if `name` is one.
+fn wrapper(name: Option<&str>) -> Option<&'static str> {
+    match Type::from_name(name?) {
+        Type::I8 => Some("$i8"),
+        Type::I16 => Some("$i16"),
+        Type::I32 => Some("$i32"),
+        Type::I64 => Some("$i64"),
+        Type::U8 => Some("$u8"),
+        Type::U16 => Some("$u16"),
+        Type::U32 => Some("$u32"),
+        Type::U64 => Some("$u64"),
+        _ => None,
+    }
+}
+
+// A literal divisor other than zero needs no runtime check.
+fn nonzero(expr: &Expression) -> bool {
+    matches!(expr.kind, ExpressionKind::Number(num) if num != 0.0)
+}
+
+fn number(num: f64) -> String {
+    if num.is_nan() {
+        "NaN".to_string()
+    } else if num.is_infinite() {
+        if num > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else {
+        num.to_string()
+    }
+}
+
+// Multiple patterns are joined with `||`, so ranges need parentheses there.
+fn pattern_condition(pattern: &Pattern, grouped: bool) -> String {
+    match &pattern.kind {
+        PatternKind::Number(num) => format!("$match === {}", number(*num)),
+        PatternKind::StringLiteral(s) => {
+            format!("$match === {}", Json::String(s.clone()))
+        }
+        PatternKind::Boolean(b) => format!("$match === {}", b),
+        PatternKind::Range {
+            start,
+            end,
+            inclusive,
+        } => {
+            let condition = format!(
+                "$match >= {} && $match {} {}",
+                number(*start),
+                if *inclusive { "<=" } else { "<" },
+                number(*end)
+            );
+            if grouped {
+                format!("({})", condition)
+            } else {
+                condition
+            }
+        }
+        PatternKind::Wildcard => "true".to_string(),
+    }
+}
+
+fn js_type(ty: &Type) -> String {
+    match ty {
+        Type::String => "string".to_string(),
+        Type::Bool => "boolean".to_string(),
+        Type::Function { params, ret } => {
+            let params: Vec<String> = params.iter().map(js_type).collect();
+            format!("function({}): {}", params.join(", "), js_type(ret))
+        }
+        Type::Unknown => "*".to_string(),
+        _ => "number".to_string(), // every numeric type
+    }
+}
@@ -0,0 +1,144 @@
+use crate::json::Json;
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Where a piece of generated code came from. Lines and columns are 0-based,
+// as in the source map format.
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    column: usize,
+    source: usize,
+    source_line: usize,
+    source_column: usize,
+}
+
+// A version 3 source map, built up while the code is generated.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    pub file: Option<String>,
+    sources: Vec<String>,
+    lines: Vec<Vec<Segment>>, // segments of each generated line
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap::default()
+    }
+
+    // Returns the index later mappings refer to `name` by.
+    pub fn add_source(&mut self, name: &str) -> usize {
+        self.sources.push(name.to_string());
+        self.sources.len() - 1
+    }
+
+    pub fn add(
+        &mut self,
+        line: usize,
+        column: usize,
+        source: usize,
+        source_line: usize,
+        source_column: usize,
+    ) {
+        if self.lines.len() <= line {
+            self.lines.resize_with(line + 1, Vec::new);
+        }
+        let segments = &mut self.lines[line];
+        // one mapping per position is enough
+        if segments.last().is_some_and(|s| s.column == column) {
+            return;
+        }
+        segments.push(Segment {
+            column,
+            source,
+            source_line,
+            source_column,
+        });
+    }
+
+    pub fn to_json(&self) -> Json {
+        let mut fields = vec![("version".to_string(), Json::Number(3.0))];
+        if let Some(file) = &self.file {
+            fields.push(("file".to_string(), Json::String(file.clone())));
+        }
+        fields.push((
+            "sources".to_string(),
+            Json::Array(self.sources.iter().cloned().map(Json::String).collect()),
+        ));
+        fields.push(("names".to_string(), Json::Array(Vec::new())));
+        fields.push(("mappings".to_string(), Json::String(self.mappings())));
+        Json::Object(fields)
+    }
+
+    /*
+    Lines are separated by `;` and segments by `,`. Each segment holds four
+    base64 VLQ numbers: the generated column, relative to the previous
+    segment on the line, then the source index, source line and source
+    column, relative to the previous segment anywhere.
+    */
+    fn mappings(&self) -> String {
+        let mut out = String::new();
+        let (mut source, mut source_line, mut source_column) = (0, 0, 0);
+        for (index, segments) in self.lines.iter().enumerate() {
+            if index > 0 {
+                out.push(';');
+            }
+            let mut column = 0;
+            for (i, segment) in segments.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                vlq(segment.column as i64 - column, &mut out);
+                vlq(segment.source as i64 - source, &mut out);
+                vlq(segment.source_line as i64 - source_line, &mut out);
+                vlq(segment.source_column as i64 - source_column, &mut out);
+                column = segment.column as i64;
+                source = segment.source as i64;
+                source_line = segment.source_line as i64;
+                source_column = segment.source_column as i64;
+            }
+        }
+        out
+    }
+}
+
+// The sign goes in the lowest bit, then 5 bits per digit, least significant
+// first, with bit 6 marking a continuation.
+fn vlq(value: i64, out: &mut String) {
+    let mut rest = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+    loop {
+        let mut digit = rest & 0b11111;
+        rest >>= 5;
+        if rest > 0 {
+            digit |= 0b100000;
+        }
+        out.push(BASE64[digit as usize] as char);
+        if rest == 0 {
+            break;
+        }
+    }
+}
+
+// For inline maps in a `data:` URL.
+pub fn base64(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as usize) << 16 | (b[1] as usize) << 8 | b[2] as usize;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i)) & 0b111111] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
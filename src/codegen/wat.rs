@@ -0,0 +1,1096 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::checker::types::Type;
+use crate::diagnostic::Diagnostic;
+use crate::lexer::token::Span;
+use crate::module::module::{Module, ModuleGraph};
+use crate::parser::statement::{
+    ComparisonOperator, Expression, ExpressionKind, LogicalOperator, MatchArm, NodeId, Operator,
+    Param, Pattern, PatternKind, Statement, StatementKind,
+};
+use crate::resolver::resolution::{DeclarationKind, Resolution};
+use crate::resolver::resolver::Resolver;
+
+const WIDTH: usize = 80;
+
+// The wasm value type holding an ASTC type. Integers narrower than 32 bits
+// live in an `i32` and are narrowed whenever they are stored.
+fn value_type(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::I8 | Type::I16 | Type::I32 | Type::U8 | Type::U16 | Type::U32 | Type::Bool => {
+            Some("i32")
+        }
+        Type::I64 | Type::U64 => Some("i64"),
+        Type::Number => Some("f64"),
+        _ => None,
+    }
+}
+
+fn is_unsigned(ty: &Type) -> bool {
+    matches!(ty, Type::U8 | Type::U16 | Type::U32 | Type::U64)
+}
+
+// A folded instruction or module field, `(head children...)`.
+#[derive(Debug, Clone)]
+struct Node {
+    head: String,
+    children: Vec<Node>,
+    multiline: bool, // always one child per line, as for functions
+}
+
+impl Node {
+    fn new(head: impl Into<String>, children: Vec<Node>) -> Self {
+        Node {
+            head: head.into(),
+            children,
+            multiline: false,
+        }
+    }
+
+    fn leaf(head: impl Into<String>) -> Self {
+        Node::new(head, Vec::new())
+    }
+
+    fn flat(&self) -> String {
+        let mut text = format!("({}", self.head);
+        for child in &self.children {
+            text.push(' ');
+            text.push_str(&child.flat());
+        }
+        text.push(')');
+        text
+    }
+
+    // On one line if it fits, otherwise one child per line.
+    fn render(&self, indent: usize, out: &mut String) {
+        let flat = self.flat();
+        if self.children.is_empty() || !self.multiline && indent * 2 + flat.len() <= WIDTH {
+            out.push_str(&flat);
+            return;
+        }
+        out.push('(');
+        out.push_str(&self.head);
+        for child in &self.children {
+            out.push('\n');
+            out.push_str(&"  ".repeat(indent + 1));
+            child.render(indent + 1, out);
+        }
+        out.push(')');
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Signature {
+    name: String,
+    params: Vec<Type>,
+    ret: Option<Type>, // `None` for functions whose body is a `print`
+}
+
+#[derive(Debug, Clone)]
+enum Variable {
+    Local(String, Type), // params, and variables declared in blocks
+    Global(String, Type),
+    Function(Signature),
+}
+
+// Locals of the function being generated, and the names taken by its params.
+#[derive(Default)]
+struct Locals {
+    names: HashSet<String>,
+    declared: Vec<Node>,
+}
+
+impl Locals {
+    fn fresh(&mut self, name: &str) -> String {
+        let mut fresh = format!("${}", name);
+        let mut suffix = 0;
+        while self.names.contains(&fresh) {
+            suffix += 1;
+            fresh = format!("${}_{}", name, suffix);
+        }
+        self.names.insert(fresh.clone());
+        fresh
+    }
+
+    fn declare(&mut self, name: &str, value_type: &str) -> String {
+        let name = self.fresh(name);
+        self.declared
+            .push(Node::leaf(format!("local {} {}", name, value_type)));
+        name
+    }
+}
+
+/*
+Lowers a typed program to one WebAssembly text module. Top-level lambdas
+whose params all have types become wasm functions, exported under their
+name when they belong to the entry module. Other top-level variables become
+mutable globals, set by the module's code, which runs from an exported
+`main` after each dependency's init function:
+
+    let double = (a: i32) => a * 2;
+    print(double(21));
+
+    (func $double (export "double") (param $a i32) (result i32)
+      (i32.mul (local.get $a) (i32.const 2)))
+    (func $main (export "main")
+      (call $astc.print_i32 (call $double (i32.const 21)))
+      (call $astc.print_newline))
+
+Integers use the wasm type of their width, so unlike the interpreter their
+arithmetic wraps and division truncates. `print` calls imported host
+functions, one per kind of value. Everything that needs values the static
+types don't describe, strings outside `print` and first-class functions, is
+reported rather than compiled.
+*/
+pub fn generate(graph: &ModuleGraph) -> Result<String, Vec<Diagnostic>> {
+    let mut generator = Generator::default();
+    generator.names.insert("$main".to_string());
+
+    let mut inits = Vec::new();
+    for (index, module) in graph.modules.iter().enumerate() {
+        let entry = index + 1 == graph.modules.len();
+        generator.module(module, entry, &inits);
+        if !entry {
+            inits.push(format!("${}init", generator.prefix));
+        }
+    }
+    if !generator.diagnostics.is_empty() {
+        return Err(generator.diagnostics);
+    }
+    Ok(generator.finish(graph))
+}
+
+#[derive(Default)]
+struct Generator {
+    diagnostics: Vec<Diagnostic>,
+    file: String,                            // the current module, for diagnostics
+    prefix: String,                          // of the current module's names
+    names: HashSet<String>,                  // module-level names in use
+    exports: Vec<HashMap<String, Variable>>, // per module
+    resolution: Resolution,
+    declarations: HashMap<NodeId, usize>, // declaring node -> declaration
+    variables: HashMap<usize, Variable>,
+    imports: BTreeSet<&'static str>,          // host functions used
+    strings: HashMap<String, (usize, usize)>, // offset and length in memory
+    data: Vec<Node>,
+    data_size: usize,
+    divide: bool, // whether the checked `f64` division is used
+    globals: Vec<Node>,
+    functions: Vec<Node>,
+    locals: Locals,
+}
+
+impl Generator {
+    fn finish(&self, graph: &ModuleGraph) -> String {
+        let mut fields = Vec::new();
+        for import in &self.imports {
+            let params = match *import {
+                "print_i32" | "print_u32" | "print_bool" => " (param i32)",
+                "print_i64" | "print_u64" => " (param i64)",
+                "print_f64" => " (param f64)",
+                "print_str" => " (param i32 i32)",
+                _ => "",
+            };
+            fields.push(Node::new(
+                format!("import \"astc\" \"{}\"", import),
+                vec![Node::leaf(format!("func $astc.{}{}", import, params))],
+            ));
+        }
+        if !self.data.is_empty() {
+            let pages = self.data_size / 65536 + 1;
+            fields.push(Node::leaf(format!("memory (export \"memory\") {}", pages)));
+        }
+        fields.extend(self.globals.iter().cloned());
+        if self.divide {
+            fields.push(divide());
+        }
+        fields.extend(self.functions.iter().cloned());
+        fields.extend(self.data.iter().cloned());
+
+        let mut out = format!(
+            ";; Generated by astc from {}.\n(module",
+            graph.entry().map(|m| m.name.as_str()).unwrap_or("")
+        );
+        for field in &fields {
+            out.push_str("\n  ");
+            field.render(1, &mut out);
+        }
+        out.push_str(")\n");
+        out
+    }
+
+    fn module(&mut self, module: &Module, entry: bool, inits: &[String]) {
+        self.file = module.name.clone();
+        self.prefix = if entry {
+            String::new()
+        } else {
+            let unique = self.unique(&stem(&module.name));
+            format!("{}.", &unique[1..])
+        };
+        (self.resolution, _) = Resolver::resolve(&module.statements);
+        self.declarations = self
+            .resolution
+            .declarations
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| d.kind != DeclarationKind::Import)
+            .map(|(index, d)| (d.node, index))
+            .collect();
+        self.variables.clear();
+        self.bind_imports(module);
+        self.declare(module, entry);
+        self.infer(module);
+
+        for stmt in &module.statements {
+            let stmt = match &stmt.kind {
+                StatementKind::Export(inner) => inner,
+                _ => stmt,
+            };
+            if let StatementKind::VarDeclaration { name, value, .. } = &stmt.kind {
+                if let ExpressionKind::Lambda { params, body } = &value.kind {
+                    self.function(stmt.id, name, params, body, entry);
+                }
+            }
+        }
+
+        self.locals = Locals::default();
+        let mut body: Vec<Node> = inits
+            .iter()
+            .map(|init| Node::leaf(format!("call {}", init)))
+            .collect();
+        for stmt in &module.statements {
+            body.extend(self.statement(stmt, true));
+        }
+        let head = if entry {
+            "func $main (export \"main\")".to_string()
+        } else {
+            format!("func ${}init", self.prefix)
+        };
+        let mut children = std::mem::take(&mut self.locals.declared);
+        children.extend(body);
+        self.functions.push(function_node(head, children));
+
+        let exports = module
+            .statements
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StatementKind::Export(inner) => match &inner.kind {
+                    StatementKind::VarDeclaration { name, .. } => {
+                        let variable = self.variables.get(self.declarations.get(&inner.id)?)?;
+                        Some((name.clone(), variable.clone()))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        self.exports.push(exports);
+    }
+
+    // Imported names refer to the exporting module's functions and globals.
+    fn bind_imports(&mut self, module: &Module) {
+        for (index, decl) in self.resolution.declarations.iter().enumerate() {
+            if decl.kind != DeclarationKind::Import {
+                continue;
+            }
+            let variable = module
+                .imports
+                .iter()
+                .filter(|import| import.names.contains(&decl.name))
+                .find_map(|import| self.exports[import.module].get(&decl.name));
+            if let Some(variable) = variable {
+                self.variables.insert(index, variable.clone());
+            }
+        }
+    }
+
+    // Gives every top-level declaration its function or global.
+    fn declare(&mut self, module: &Module, entry: bool) {
+        for stmt in &module.statements {
+            let stmt = match &stmt.kind {
+                StatementKind::Export(inner) => inner,
+                _ => stmt,
+            };
+            let StatementKind::VarDeclaration {
+                name,
+                var_type,
+                value,
+                ..
+            } = &stmt.kind
+            else {
+                continue;
+            };
+            let Some(&index) = self.declarations.get(&stmt.id) else {
+                continue;
+            };
+            if entry && name == "main" {
+                self.unsupported(
+                    "A top-level declaration named `main`".to_string(),
+                    stmt.span,
+                );
+                continue;
+            }
+            let variable = match &value.kind {
+                ExpressionKind::Lambda { params, body } => {
+                    let mut types = Vec::new();
+                    for param in params {
+                        let ty = param
+                            .param_type
+                            .as_deref()
+                            .map(Type::from_name)
+                            .unwrap_or(Type::Unknown);
+                        if value_type(&ty).is_none() {
+                            let what = match &param.param_type {
+                                Some(t) => format!("Parameter `{}` of type `{}`", param.name, t),
+                                None => format!("Parameter `{}` without a type", param.name),
+                            };
+                            self.unsupported(what, param.span);
+                        }
+                        if let Some(&param_index) = self.declarations.get(&param.id) {
+                            let local = Variable::Local(format!("${}", param.name), ty.clone());
+                            self.variables.insert(param_index, local);
+                        }
+                        types.push(ty);
+                    }
+                    let ret = if self.is_print(body) {
+                        None
+                    } else {
+                        Some(Type::Unknown)
+                    };
+                    Variable::Function(Signature {
+                        name: self.unique(&format!("{}{}", self.prefix, name)),
+                        params: types,
+                        ret,
+                    })
+                }
+                _ => {
+                    let ty = var_type
+                        .as_deref()
+                        .map(Type::from_name)
+                        .unwrap_or(Type::Unknown);
+                    Variable::Global(self.unique(&format!("{}{}", self.prefix, name)), ty)
+                }
+            };
+            self.variables.insert(index, variable);
+        }
+    }
+
+    // Infers the return types of functions and the types of untyped
+    // globals. Calls to functions declared later need another round.
+    fn infer(&mut self, module: &Module) {
+        for _ in 0..3 {
+            for stmt in &module.statements {
+                let stmt = match &stmt.kind {
+                    StatementKind::Export(inner) => inner,
+                    _ => stmt,
+                };
+                let StatementKind::VarDeclaration {
+                    var_type, value, ..
+                } = &stmt.kind
+                else {
+                    continue;
+                };
+                let Some(&index) = self.declarations.get(&stmt.id) else {
+                    continue;
+                };
+                let inferred = match &value.kind {
+                    ExpressionKind::Lambda { body, .. } => self.type_of(body),
+                    _ => self.type_of(value),
+                };
+                match self.variables.get_mut(&index) {
+                    Some(Variable::Function(signature)) if signature.ret.is_some() => {
+                        signature.ret = Some(inferred);
+                    }
+                    Some(Variable::Global(_, ty)) if var_type.is_none() => *ty = inferred,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn function(
+        &mut self,
+        id: NodeId,
+        name: &str,
+        params: &[Param],
+        body: &Expression,
+        entry: bool,
+    ) {
+        let Some(Variable::Function(signature)) = self
+            .declarations
+            .get(&id)
+            .and_then(|index| self.variables.get(index))
+            .cloned()
+        else {
+            return;
+        };
+        self.locals = Locals::default();
+
+        let mut head = format!("func {}", signature.name);
+        if entry {
+            head.push_str(&format!(" (export \"{}\")", name));
+        }
+        for (param, ty) in params.iter().zip(&signature.params) {
+            let name = self.locals.fresh(&param.name);
+            head.push_str(&format!(
+                " (param {} {})",
+                name,
+                value_type(ty).unwrap_or("i32")
+            ));
+        }
+
+        let body = match &signature.ret {
+            None => match &body.kind {
+                ExpressionKind::FunctionCall { args, .. } => self.print(args),
+                _ => Vec::new(),
+            },
+            Some(ret) => match value_type(ret) {
+                Some(value_type) => {
+                    head.push_str(&format!(" (result {})", value_type));
+                    vec![self.expression(body, ret)]
+                }
+                None => {
+                    let what = match ret {
+                        Type::Unknown => format!("Function `{}` of unknown return type", name),
+                        _ => format!("Function `{}` returning `{}`", name, ret),
+                    };
+                    self.unsupported(what, body.span);
+                    Vec::new()
+                }
+            },
+        };
+        let mut children = std::mem::take(&mut self.locals.declared);
+        children.extend(body);
+        self.functions.push(function_node(head, children));
+    }
+
+    fn statement(&mut self, stmt: &Statement, top_level: bool) -> Vec<Node> {
+        match &stmt.kind {
+            StatementKind::VarDeclaration {
+                name,
+                var_type,
+                value,
+                ..
+            } => {
+                if let ExpressionKind::Lambda { .. } = value.kind {
+                    if !top_level {
+                        self.unsupported("A function declared in a block".to_string(), stmt.span);
+                    }
+                    return Vec::new();
+                }
+                let Some(&index) = self.declarations.get(&stmt.id) else {
+                    return Vec::new();
+                };
+                let ty = match self.variables.get(&index) {
+                    Some(Variable::Global(_, ty)) => ty.clone(),
+                    _ => match var_type {
+                        Some(var_type) => Type::from_name(var_type),
+                        None => self.type_of(value),
+                    },
+                };
+                let Some(value_type) = value_type(&ty) else {
+                    self.unsupported(format!("Variable `{}` of type `{}`", name, ty), stmt.span);
+                    return Vec::new();
+                };
+                let mut node = self.expression(value, &ty);
+                if var_type.is_some() {
+                    node = narrow(node, &ty);
+                }
+                match self.variables.get(&index) {
+                    Some(Variable::Global(global, _)) => {
+                        let global = global.clone();
+                        self.globals.push(Node::new(
+                            format!("global {} (mut {})", global, value_type),
+                            vec![constant(0.0, &ty)],
+                        ));
+                        vec![Node::new(format!("global.set {}", global), vec![node])]
+                    }
+                    _ => {
+                        let local = self.locals.declare(name, value_type);
+                        self.variables
+                            .insert(index, Variable::Local(local.clone(), ty));
+                        vec![Node::new(format!("local.set {}", local), vec![node])]
+                    }
+                }
+            }
+            StatementKind::FunctionCall { name, args } => {
+                self.call_statement(stmt.id, name, args, stmt.span)
+            }
+            StatementKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let mut children = vec![self.truth(condition)];
+                let then = self.block(then_branch);
+                children.push(Node::new("then", then));
+                if let Some(else_branch) = else_branch {
+                    let otherwise = self.statement(else_branch, false);
+                    children.push(Node::new("else", otherwise));
+                }
+                vec![Node::new("if", children)]
+            }
+            StatementKind::Match { scrutinee, arms } => self.match_statement(scrutinee, arms),
+            StatementKind::Block(statements) => self.block(statements),
+            StatementKind::Export(inner) => self.statement(inner, top_level),
+            StatementKind::Expression(expr) => match &expr.kind {
+                ExpressionKind::FunctionCall { name, args } => {
+                    self.call_statement(expr.id, name, args, expr.span)
+                }
+                _ => {
+                    let ty = self.type_of(expr);
+                    vec![Node::new("drop", vec![self.value(expr, &ty)])]
+                }
+            },
+            StatementKind::Import { .. } => Vec::new(),
+        }
+    }
+
+    fn block(&mut self, statements: &[Statement]) -> Vec<Node> {
+        statements
+            .iter()
+            .flat_map(|stmt| self.statement(stmt, false))
+            .collect()
+    }
+
+    // A call whose result, if any, is dropped.
+    fn call_statement(
+        &mut self,
+        id: NodeId,
+        name: &str,
+        args: &[Expression],
+        span: Span,
+    ) -> Vec<Node> {
+        if name == "print" && !self.resolution.uses.contains_key(&id) {
+            return self.print(args);
+        }
+        match self.call(id, name, args, span) {
+            Some((node, Some(_))) => vec![Node::new("drop", vec![node])],
+            Some((node, None)) => vec![node],
+            None => Vec::new(),
+        }
+    }
+
+    // Each value goes to the host function printing its type, separated by
+    // spaces and followed by a newline, as the interpreter prints them.
+    fn print(&mut self, args: &[Expression]) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                nodes.push(self.host("print_space", Vec::new()));
+            }
+            if let ExpressionKind::StringLiteral(s) = &arg.kind {
+                let (offset, length) = self.string(s);
+                let args = vec![
+                    Node::leaf(format!("i32.const {}", offset)),
+                    Node::leaf(format!("i32.const {}", length)),
+                ];
+                nodes.push(self.host("print_str", args));
+                continue;
+            }
+            let ty = self.type_of(arg);
+            let function = match ty {
+                Type::Bool => "print_bool",
+                Type::I8 | Type::I16 | Type::I32 => "print_i32",
+                Type::U8 | Type::U16 | Type::U32 => "print_u32",
+                Type::I64 => "print_i64",
+                Type::U64 => "print_u64",
+                Type::Number => "print_f64",
+                Type::String => {
+                    self.unsupported("A string outside a literal".to_string(), arg.span);
+                    continue;
+                }
+                _ => {
+                    let value = self.value(arg, &ty);
+                    nodes.push(Node::new("drop", vec![value]));
+                    continue;
+                }
+            };
+            let value = self.value(arg, &ty);
+            nodes.push(self.host(function, vec![value]));
+        }
+        nodes.push(self.host("print_newline", Vec::new()));
+        nodes
+    }
+
+    fn host(&mut self, function: &'static str, args: Vec<Node>) -> Node {
+        self.imports.insert(function);
+        Node::new(format!("call $astc.{}", function), args)
+    }
+
+    // Stores `s` in memory once, returning its offset and length.
+    fn string(&mut self, s: &str) -> (usize, usize) {
+        if let Some(&location) = self.strings.get(s) {
+            return location;
+        }
+        let location = (self.data_size, s.len());
+        self.data.push(Node::leaf(format!(
+            "data (i32.const {}) \"{}\"",
+            self.data_size,
+            escape(s)
+        )));
+        self.data_size += s.len();
+        self.strings.insert(s.to_string(), location);
+        location
+    }
+
+    /*
+    Stores the scrutinee in a local, and tests the arms in order inside a
+    block each matching arm breaks out of:
+
+        (block $match
+          (local.set $match (global.get $x))
+          (if (i32.eq (local.get $match) (i32.const 1))
+            (then ... (br $match)))
+          ...)
+    */
+    fn match_statement(&mut self, scrutinee: &Expression, arms: &[MatchArm]) -> Vec<Node> {
+        let ty = self.type_of(scrutinee);
+        let Some(value_type) = value_type(&ty) else {
+            self.unsupported(format!("Matching on `{}`", ty), scrutinee.span);
+            return Vec::new();
+        };
+        let value = self.value(scrutinee, &ty);
+        let name = self.locals.declare("match", value_type);
+
+        let mut children = vec![Node::new(format!("local.set {}", name), vec![value])];
+        for arm in arms {
+            let body = self.block(&arm.body);
+            if arm
+                .patterns
+                .iter()
+                .any(|pattern| pattern.kind == PatternKind::Wildcard)
+            {
+                children.extend(body);
+                break;
+            }
+            let conditions: Vec<Node> = arm
+                .patterns
+                .iter()
+                .map(|pattern| self.pattern_condition(pattern, &name, &ty))
+                .collect();
+            let condition = conditions
+                .into_iter()
+                .reduce(|l, r| Node::new("i32.or", vec![l, r]))
+                .unwrap_or_else(|| Node::leaf("i32.const 0"));
+            let mut then = body;
+            then.push(Node::leaf(format!("br {}", name)));
+            children.push(Node::new("if", vec![condition, Node::new("then", then)]));
+        }
+        vec![Node::new(format!("block {}", name), children)]
+    }
+
+    fn pattern_condition(&mut self, pattern: &Pattern, name: &str, ty: &Type) -> Node {
+        let value_type = value_type(ty).unwrap_or("i32");
+        let get = Node::leaf(format!("local.get {}", name));
+        match &pattern.kind {
+            PatternKind::Number(n) => {
+                Node::new(format!("{}.eq", value_type), vec![get, constant(*n, ty)])
+            }
+            PatternKind::Boolean(b) => Node::new(
+                "i32.eq",
+                vec![get, Node::leaf(format!("i32.const {}", *b as i32))],
+            ),
+            PatternKind::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let upper = if *inclusive {
+                    ComparisonOperator::LessThanOrEqual
+                } else {
+                    ComparisonOperator::LessThan
+                };
+                let lower = Node::new(
+                    comparison(&ComparisonOperator::GreaterThanOrEqual, ty),
+                    vec![get.clone(), constant(*start, ty)],
+                );
+                let upper = Node::new(comparison(&upper, ty), vec![get, constant(*end, ty)]);
+                Node::new("i32.and", vec![lower, upper])
+            }
+            PatternKind::StringLiteral(_) => {
+                self.unsupported("A string pattern".to_string(), pattern.span);
+                Node::leaf("i32.const 0")
+            }
+            PatternKind::Wildcard => Node::leaf("i32.const 1"),
+        }
+    }
+
+    // `expr` as a value of `ty`'s wasm type.
+    fn expression(&mut self, expr: &Expression, ty: &Type) -> Node {
+        if let ExpressionKind::Number(n) = expr.kind {
+            if value_type(ty).is_some() {
+                return constant(n, ty);
+            }
+        }
+        let from = self.type_of(expr);
+        let node = self.value(expr, &from);
+        convert(node, &from, ty)
+    }
+
+    // `expr` as a value of its own type `ty`.
+    fn value(&mut self, expr: &Expression, ty: &Type) -> Node {
+        match &expr.kind {
+            ExpressionKind::Identifier(name) => match self.variable(expr.id) {
+                Some(Variable::Local(local, _)) => Node::leaf(format!("local.get {}", local)),
+                Some(Variable::Global(global, _)) => Node::leaf(format!("global.get {}", global)),
+                Some(Variable::Function(_)) => {
+                    self.unsupported(format!("Using function `{}` as a value", name), expr.span);
+                    Node::leaf("unreachable")
+                }
+                None => Node::leaf("unreachable"),
+            },
+            ExpressionKind::Number(n) => constant(*n, &Type::Number),
+            ExpressionKind::Boolean(b) => Node::leaf(format!("i32.const {}", *b as i32)),
+            ExpressionKind::StringLiteral(_) => {
+                self.unsupported("A string outside `print`".to_string(), expr.span);
+                Node::leaf("unreachable")
+            }
+            ExpressionKind::BinaryOp(left, op, right) => {
+                let Some(value_type) = value_type(ty) else {
+                    self.unsupported(format!("`{}` on `{}`", operator(op), ty), expr.span);
+                    return Node::leaf("unreachable");
+                };
+                let nonzero = matches!(right.kind, ExpressionKind::Number(n) if n != 0.0);
+                let left = self.expression(left, ty);
+                let right = self.expression(right, ty);
+                let instruction = match op {
+                    Operator::Add => "add",
+                    Operator::Sub => "sub",
+                    Operator::Mul => "mul",
+                    Operator::Div if value_type != "f64" => {
+                        if is_unsigned(ty) {
+                            "div_u"
+                        } else {
+                            "div_s"
+                        }
+                    }
+                    Operator::Div if nonzero => "div",
+                    Operator::Div => {
+                        self.divide = true;
+                        return Node::new("call $astc.divide", vec![left, right]);
+                    }
+                };
+                Node::new(format!("{}.{}", value_type, instruction), vec![left, right])
+            }
+            ExpressionKind::LogicalOp(left, op, right) => {
+                let left = self.truth(left);
+                let right = self.truth(right);
+                let (then, otherwise) = match op {
+                    LogicalOperator::And => (right, Node::leaf("i32.const 0")),
+                    LogicalOperator::Or => (Node::leaf("i32.const 1"), right),
+                };
+                Node::new(
+                    "if (result i32)",
+                    vec![
+                        left,
+                        Node::new("then", vec![then]),
+                        Node::new("else", vec![otherwise]),
+                    ],
+                )
+            }
+            ExpressionKind::ComparisonOp(left, op, right) => {
+                let operands = self
+                    .type_of(left)
+                    .unify(&self.type_of(right))
+                    .unwrap_or(Type::Unknown);
+                if value_type(&operands).is_none() {
+                    self.unsupported(format!("Comparing `{}` values", operands), expr.span);
+                    return Node::leaf("unreachable");
+                }
+                let left = self.expression(left, &operands);
+                let right = self.expression(right, &operands);
+                Node::new(comparison(op, &operands), vec![left, right])
+            }
+            ExpressionKind::FunctionCall { name, args } => {
+                if name == "print" && !self.resolution.uses.contains_key(&expr.id) {
+                    self.unsupported("Using the result of `print`".to_string(), expr.span);
+                    return Node::leaf("unreachable");
+                }
+                match self.call(expr.id, name, args, expr.span) {
+                    Some((node, Some(_))) => node,
+                    Some((_, None)) => {
+                        self.unsupported(
+                            format!("Using the result of `{}`, which prints", name),
+                            expr.span,
+                        );
+                        Node::leaf("unreachable")
+                    }
+                    None => Node::leaf("unreachable"),
+                }
+            }
+            ExpressionKind::Call { .. } => {
+                self.unsupported("Calling a function value".to_string(), expr.span);
+                Node::leaf("unreachable")
+            }
+            ExpressionKind::Lambda { .. } => {
+                self.unsupported(
+                    "A lambda outside a top-level declaration".to_string(),
+                    expr.span,
+                );
+                Node::leaf("unreachable")
+            }
+            ExpressionKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let Some(value_type) = value_type(ty) else {
+                    self.unsupported(format!("A conditional of type `{}`", ty), expr.span);
+                    return Node::leaf("unreachable");
+                };
+                let condition = self.truth(condition);
+                let then = self.expression(then_branch, ty);
+                let otherwise = self.expression(else_branch, ty);
+                Node::new(
+                    format!("if (result {})", value_type),
+                    vec![
+                        condition,
+                        Node::new("then", vec![then]),
+                        Node::new("else", vec![otherwise]),
+                    ],
+                )
+            }
+        }
+    }
+
+    // `expr` as an `i32` that is 1 when it is truthy, and 0 otherwise.
+    fn truth(&mut self, expr: &Expression) -> Node {
+        let ty = self.type_of(expr);
+        let value = self.value(expr, &ty);
+        match (&ty, value_type(&ty)) {
+            (Type::Bool, _) => value,
+            (_, Some(value_type)) => Node::new(
+                format!("{}.ne", value_type),
+                vec![value, constant(0.0, &ty)],
+            ),
+            (Type::String, None) => {
+                self.unsupported("The truthiness of a string".to_string(), expr.span);
+                value
+            }
+            _ => value,
+        }
+    }
+
+    // A call to a named function with its arguments stored as its params,
+    // and the function's return type. `None` if it is not a function.
+    fn call(
+        &mut self,
+        id: NodeId,
+        name: &str,
+        args: &[Expression],
+        span: Span,
+    ) -> Option<(Node, Option<Type>)> {
+        let Some(Variable::Function(signature)) = self.variable(id) else {
+            if self.resolution.uses.contains_key(&id) {
+                self.unsupported(format!("Calling `{}`, which is not a function", name), span);
+            }
+            return None;
+        };
+        let args = args
+            .iter()
+            .zip(&signature.params)
+            .map(|(arg, ty)| {
+                let node = self.expression(arg, ty);
+                narrow(node, ty)
+            })
+            .collect();
+        let node = Node::new(format!("call {}", signature.name), args);
+        Some((node, signature.ret))
+    }
+
+    // The static type of `expr`, through the types of the functions and
+    // globals generated so far rather than the checker's, so calls have the
+    // return type inferred for their function.
+    fn type_of(&self, expr: &Expression) -> Type {
+        match &expr.kind {
+            ExpressionKind::Identifier(_) => match self.variable(expr.id) {
+                Some(Variable::Local(_, ty)) | Some(Variable::Global(_, ty)) => ty,
+                _ => Type::Unknown,
+            },
+            ExpressionKind::Number(_) => Type::Number,
+            ExpressionKind::StringLiteral(_) => Type::String,
+            ExpressionKind::Boolean(_) => Type::Bool,
+            ExpressionKind::BinaryOp(left, _, right) => self
+                .type_of(left)
+                .unify(&self.type_of(right))
+                .unwrap_or(Type::Unknown),
+            ExpressionKind::LogicalOp(..) | ExpressionKind::ComparisonOp(..) => Type::Bool,
+            ExpressionKind::FunctionCall { .. } => match self.variable(expr.id) {
+                Some(Variable::Function(signature)) => signature.ret.unwrap_or(Type::Unknown),
+                _ => Type::Unknown,
+            },
+            ExpressionKind::Conditional {
+                then_branch,
+                else_branch,
+                ..
+            } => self
+                .type_of(then_branch)
+                .unify(&self.type_of(else_branch))
+                .unwrap_or(Type::Unknown),
+            ExpressionKind::Call { .. } | ExpressionKind::Lambda { .. } => Type::Unknown,
+        }
+    }
+
+    fn variable(&self, node: NodeId) -> Option<Variable> {
+        let index = self.resolution.uses.get(&node)?;
+        self.variables.get(index).cloned()
+    }
+
+    fn is_print(&self, expr: &Expression) -> bool {
+        matches!(&expr.kind, ExpressionKind::FunctionCall { name, .. }
+            if name == "print" && !self.resolution.uses.contains_key(&expr.id))
+    }
+
+    fn unique(&mut self, name: &str) -> String {
+        let mut unique = format!("${}", name);
+        let mut suffix = 0;
+        while self.names.contains(&unique) {
+            suffix += 1;
+            unique = format!("${}_{}", name, suffix);
+        }
+        self.names.insert(unique.clone());
+        unique
+    }
+
+    fn unsupported(&mut self, what: String, span: Span) {
+        self.diagnostics.push(
+            Diagnostic::error(format!(
+                "{} is not supported by the WebAssembly backend",
+                what
+            ))
+            .at(span)
+            .in_file(&self.file),
+        );
+    }
+}
+
+fn function_node(head: String, children: Vec<Node>) -> Node {
+    let mut node = Node::new(head, children);
+    node.multiline = true;
+    node
+}
+
+// Divides two `f64`s, trapping on a zero divisor as the interpreter fails.
+fn divide() -> Node {
+    function_node(
+        "func $astc.divide (param $a f64) (param $b f64) (result f64)".to_string(),
+        vec![
+            Node::new(
+                "if",
+                vec![
+                    Node::new(
+                        "f64.eq",
+                        vec![Node::leaf("local.get $b"), Node::leaf("f64.const 0")],
+                    ),
+                    Node::new("then", vec![Node::leaf("unreachable")]),
+                ],
+            ),
+            Node::new(
+                "f64.div",
+                vec![Node::leaf("local.get $a"), Node::leaf("local.get $b")],
+            ),
+        ],
+    )
+}
+
+// A literal of `ty`'s wasm type. Integers keep the bits of their wasm
+// type, as arithmetic on narrow integers happens at 32 bits.
+fn constant(n: f64, ty: &Type) -> Node {
+    let int = n as i64;
+    let text = match (value_type(ty), is_unsigned(ty)) {
+        (Some("i32"), true) => format!("i32.const {}", int as u32),
+        (Some("i32"), false) => format!("i32.const {}", int as i32),
+        (Some("i64"), true) => format!("i64.const {}", int as u64),
+        (Some("i64"), false) => format!("i64.const {}", int),
+        _ => format!("f64.const {:?}", n),
+    };
+    Node::leaf(text)
+}
+
+// Converts between wasm types the way `Type::wrap` does: floats saturate
+// to an `i64` first, then keep the low bits.
+fn convert(node: Node, from: &Type, to: &Type) -> Node {
+    let (Some(source), Some(target)) = (value_type(from), value_type(to)) else {
+        return node;
+    };
+    let unsigned = is_unsigned(from);
+    let instruction = match (source, target) {
+        ("i32", "i64") if unsigned => "i64.extend_i32_u",
+        ("i32", "i64") => "i64.extend_i32_s",
+        ("i64", "i32") => "i32.wrap_i64",
+        ("i32", "f64") if unsigned => "f64.convert_i32_u",
+        ("i32", "f64") => "f64.convert_i32_s",
+        ("i64", "f64") if unsigned => "f64.convert_i64_u",
+        ("i64", "f64") => "f64.convert_i64_s",
+        ("f64", "i64") => "i64.trunc_sat_f64_s",
+        ("f64", "i32") => {
+            let int = Node::new("i64.trunc_sat_f64_s", vec![node]);
+            return Node::new("i32.wrap_i64", vec![int]);
+        }
+        _ => return node,
+    };
+    Node::new(instruction, vec![node])
+}
+
+// Wraps an `i32` to the width of an integer type narrower than 32 bits.
+fn narrow(node: Node, ty: &Type) -> Node {
+    match ty {
+        Type::I8 => Node::new("i32.extend8_s", vec![node]),
+        Type::I16 => Node::new("i32.extend16_s", vec![node]),
+        Type::U8 => Node::new("i32.and", vec![node, Node::leaf("i32.const 255")]),
+        Type::U16 => Node::new("i32.and", vec![node, Node::leaf("i32.const 65535")]),
+        _ => node,
+    }
+}
+
+fn comparison(op: &ComparisonOperator, ty: &Type) -> String {
+    let value_type = value_type(ty).unwrap_or("i32");
+    let name = match op {
+        ComparisonOperator::Equal => "eq",
+        ComparisonOperator::NotEqual => "ne",
+        ComparisonOperator::LessThan => "lt",
+        ComparisonOperator::GreaterThan => "gt",
+        ComparisonOperator::LessThanOrEqual => "le",
+        ComparisonOperator::GreaterThanOrEqual => "ge",
+    };
+    let sign = match op {
+        ComparisonOperator::Equal | ComparisonOperator::NotEqual => "",
+        _ if value_type == "f64" => "",
+        _ if is_unsigned(ty) => "_u",
+        _ => "_s",
+    };
+    format!("{}.{}{}", value_type, name, sign)
+}
+
+fn operator(op: &Operator) -> &'static str {
+    match op {
+        Operator::Add => "+",
+        Operator::Sub => "-",
+        Operator::Mul => "*",
+        Operator::Div => "/",
+    }
+}
+
+// Escapes a string for a WAT string literal, bytes outside printable ASCII
+// as `\hh`.
+fn escape(s: &str) -> String {
+    let mut escaped = String::new();
+    for byte in s.bytes() {
+        match byte {
+            b'"' => escaped.push_str("\\\""),
+            b'\\' => escaped.push_str("\\\\"),
+            0x20..=0x7e => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("\\{:02x}", byte)),
+        }
+    }
+    escaped
+}
+
+fn stem(name: &str) -> String {
+    let file = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let stem = file.strip_suffix(".astc").unwrap_or(file);
+    stem.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,71 @@
+use std::fmt;
+
+use crate::lexer::token::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub file: Option<String>,
+    pub span: Option<Span>,
+}
+
+impl Diagnostic {
+    pub fn error(message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message,
+            file: None,
+            span: None,
+        }
+    }
+
+    pub fn warning(message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            message,
+            file: None,
+            span: None,
+        }
+    }
+
+    pub fn in_file(mut self, file: &str) -> Self {
+        self.file = Some(file.to_string());
+        self
+    }
+
+    pub fn at(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity_str = match self.severity {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+        };
+        write!(f, "{}: ", severity_str)?;
+
+        // file:line:column
+        match (&self.file, &self.span) {
+            (Some(file), Some(span)) => write!(f, "{}:{}:{}: ", file, span.line, span.column)?,
+            (Some(file), None) => write!(f, "{}: ", file)?,
+            (None, Some(span)) => write!(f, "{}:{}: ", span.line, span.column)?,
+            (None, None) => {}
+        }
+
+        write!(f, "{}", self.message)
+    }
+}
@@ -0,0 +1,173 @@
+use crate::formatter::formatter::Formatter;
+use crate::parser::statement::{Expression, ExpressionKind, Operator, Pattern, PatternKind};
+
+/*
+Binding strength of each expression form, loosest first. A child that binds
+looser than its position requires is wrapped in parentheses.
+   0  lambda (its body extends as far right as possible)
+   1  ?:
+   2  && ||
+   3  == != < > <= >=
+   4  + -
+   5  * /
+   6  calls, literals, identifiers
+*/
+fn precedence(expr: &Expression) -> u8 {
+    match &expr.kind {
+        ExpressionKind::Lambda { .. } => 0,
+        ExpressionKind::Conditional { .. } => 1,
+        ExpressionKind::LogicalOp(..) => 2,
+        ExpressionKind::ComparisonOp(..) => 3,
+        ExpressionKind::BinaryOp(_, Operator::Add | Operator::Sub, _) => 4,
+        ExpressionKind::BinaryOp(_, Operator::Mul | Operator::Div, _) => 5,
+        _ => 6,
+    }
+}
+
+impl<'a> Formatter<'a> {
+    // Renders `expr` starting at `column`, parenthesized if it binds looser
+    // than `min_precedence`. Comments before it are kept in front of it.
+    pub fn expression(&mut self, expr: &Expression, min_precedence: u8, column: usize) -> String {
+        let comments = self.inline_comments(expr.span.start);
+        let column = last_line_width(&comments, column);
+        let level = precedence(expr);
+        if level < min_precedence {
+            return format!("{}({})", comments, self.expression(expr, 0, column + 1));
+        }
+
+        let text = match &expr.kind {
+            ExpressionKind::Identifier(name) => name.clone(),
+            ExpressionKind::Number(num) => format!("{}", num),
+            ExpressionKind::StringLiteral(s) => self.string(s),
+            ExpressionKind::Boolean(b) => format!("{}", b),
+            ExpressionKind::BinaryOp(left, op, right) => {
+                self.infix(left, &op.to_string(), right, level, column)
+            }
+            ExpressionKind::LogicalOp(left, op, right) => {
+                self.infix(left, &op.to_string(), right, level, column)
+            }
+            ExpressionKind::ComparisonOp(left, op, right) => {
+                self.infix(left, &op.to_string(), right, level, column)
+            }
+            ExpressionKind::FunctionCall { name, args } => {
+                self.call(name.clone(), args, column + name.len(), expr.span.end)
+            }
+            ExpressionKind::Call { callee, args } => {
+                let callee = self.expression(callee, 6, column);
+                let callee_end = last_line_width(&callee, column);
+                self.call(callee, args, callee_end, expr.span.end)
+            }
+            ExpressionKind::Lambda { params, body } => {
+                let mut params_str = Vec::new();
+                for param in params {
+                    let comments = self.inline_comments(param.span.start);
+                    params_str.push(format!("{}{}", comments, param));
+                }
+                let head = format!("({}) => ", params_str.join(", "));
+                let body = self.expression(body, 0, last_line_width(&head, column));
+                format!("{}{}", head, body)
+            }
+            ExpressionKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = self.expression(condition, 2, column);
+                let column = last_line_width(&condition, column) + 3;
+                let then_branch = self.expression(then_branch, 1, column);
+                let column = last_line_width(&then_branch, column) + 3;
+                let else_branch = self.expression(else_branch, 1, column);
+                format!("{} ? {} : {}", condition, then_branch, else_branch)
+            }
+        };
+        format!("{}{}", comments, text)
+    }
+
+    // Operators are left associative, so the right operand has to bind
+    // tighter than the operator itself.
+    fn infix(
+        &mut self,
+        left: &Expression,
+        op: &str,
+        right: &Expression,
+        level: u8,
+        column: usize,
+    ) -> String {
+        let left = self.expression(left, level, column);
+        let column = last_line_width(&left, column) + op.len() + 2;
+        let right = self.expression(right, level + 1, column);
+        format!("{} {} {}", left, op, right)
+    }
+
+    // `callee(a, b)` starting at `column` (just past the callee), or one
+    // argument per line with a trailing comma when that doesn't fit or a
+    // comment ends a line before `end`, the end of the call. A comment after
+    // an argument stays on its line.
+    pub fn call(
+        &mut self,
+        callee: String,
+        args: &[Expression],
+        column: usize,
+        end: usize,
+    ) -> String {
+        let first_comment = self.next_comment;
+        let mut flat = Vec::new();
+        let mut arg_column = column + 1;
+        for arg in args {
+            let arg = self.expression(arg, 0, arg_column);
+            arg_column = last_line_width(&arg, arg_column) + 2;
+            flat.push(arg);
+        }
+        let flat = flat.join(", ");
+        // `(`, `)` and the `;` or `,` that usually follows
+        let fits = !flat.contains('\n') && column + flat.chars().count() + 3 <= self.config.width;
+        if !self.has_comment_before(end) && (args.is_empty() || fits) {
+            return format!("{}({})", callee, flat);
+        }
+
+        // the comments go again, where this layout puts them
+        self.next_comment = first_comment;
+        self.depth += 1;
+        let indent = self.indent(self.depth);
+        let mut args_str = String::new();
+        for (index, arg) in args.iter().enumerate() {
+            let next = args.get(index + 1).map_or(end, |next| next.span.start);
+            args_str.push_str(&self.comment_lines(arg.span.start));
+            args_str.push_str(&indent);
+            args_str.push_str(&self.expression(arg, 0, indent.len()));
+            args_str.push(',');
+            if let Some(comment) = self.trailing_comment(arg.span.end, next) {
+                args_str.push(' ');
+                args_str.push_str(&comment);
+            }
+            args_str.push('\n');
+        }
+        args_str.push_str(&self.comment_lines(end));
+        self.depth -= 1;
+        format!("{}(\n{}{})", callee, args_str, self.indent(self.depth))
+    }
+
+    pub fn pattern(&self, pattern: &Pattern) -> String {
+        match &pattern.kind {
+            PatternKind::StringLiteral(s) => self.string(s),
+            _ => format!("{}", pattern),
+        }
+    }
+
+    // Strings have no escapes, so pick a quote the string doesn't contain.
+    pub fn string(&self, s: &str) -> String {
+        let quote = ['"', '`', '\'']
+            .into_iter()
+            .find(|q| !s.contains(*q))
+            .unwrap_or('"');
+        format!("{}{}{}", quote, s, quote)
+    }
+}
+
+// Column just past `text` when it was written starting at `column`.
+fn last_line_width(text: &str, column: usize) -> usize {
+    match text.rfind('\n') {
+        Some(i) => text[i + 1..].chars().count(),
+        None => column + text.chars().count(),
+    }
+}
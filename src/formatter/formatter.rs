@@ -0,0 +1,326 @@
+use crate::diagnostic::Diagnostic;
+use crate::lexer::token::{Comment, Lexer};
+use crate::parser::parser::Parser;
+use crate::parser::statement::{Statement, StatementKind};
+
+pub struct FormatConfig {
+    pub indent: usize, // spaces per level
+    pub width: usize,  // call arguments are split one per line past this
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        FormatConfig {
+            indent: 4,
+            width: 100,
+        }
+    }
+}
+
+// Formats ASTC source into canonical form, keeping comments and single blank
+// lines between statements. Sources with parse errors are left alone.
+pub fn format(source: &str, config: &FormatConfig) -> Result<String, Vec<Diagnostic>> {
+    let mut parser = Parser::new(Lexer::new(source));
+    let statements = parser.parse();
+    if parser.diagnostics.iter().any(|d| d.is_error()) {
+        return Err(parser.diagnostics);
+    }
+
+    let mut formatter = Formatter {
+        config,
+        source,
+        comments: parser.lexer.comments,
+        next_comment: 0,
+        line_starts: line_starts(source),
+        last_line: None,
+        depth: 0,
+        out: String::new(),
+    };
+    formatter.statements(&statements, usize::MAX);
+    Ok(formatter.out)
+}
+
+pub struct Formatter<'a> {
+    pub config: &'a FormatConfig,
+    source: &'a str,
+    comments: Vec<Comment>,
+    pub next_comment: usize,  // the first comment not yet written
+    line_starts: Vec<usize>,  // byte offset of each source line
+    last_line: Option<usize>, // source line of the last item written
+    pub depth: usize,
+    out: String,
+}
+
+impl<'a> Formatter<'a> {
+    // Writes `statements` one per line, followed by any comments left
+    // before `end`, where their block closes.
+    fn statements(&mut self, statements: &[Statement], end: usize) {
+        for stmt in statements {
+            self.comments_before(stmt.span.start);
+            self.separate(stmt.span.line);
+            self.write_indent();
+            self.statement(stmt);
+            self.end_line(stmt.span.end, end);
+        }
+        self.comments_before(end);
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        let column = self.column();
+        match &stmt.kind {
+            StatementKind::VarDeclaration { .. } => {
+                let decl = self.var_declaration(stmt, column);
+                self.out.push_str(&decl);
+            }
+            StatementKind::FunctionCall { name, args } => {
+                let call = self.call(name.clone(), args, column + name.len(), stmt.span.end);
+                self.out.push_str(&call);
+                self.out.push(';');
+            }
+            StatementKind::If { .. } => self.if_statement(stmt),
+            StatementKind::Match { scrutinee, arms } => {
+                let scrutinee = self.expression(scrutinee, 0, column + 7);
+                self.out.push_str(&format!("match ({}) {{", scrutinee));
+                if arms.is_empty() && !self.has_comment_before(stmt.span.end) {
+                    self.out.push('}');
+                    return;
+                }
+                self.out.push('\n');
+
+                self.depth += 1;
+                self.last_line = None;
+                for arm in arms {
+                    self.comments_before(arm.span.start);
+                    self.separate(arm.span.line);
+                    self.write_indent();
+                    let patterns_str = arm
+                        .patterns
+                        .iter()
+                        .map(|pattern| self.pattern(pattern))
+                        .collect::<Vec<String>>()
+                        .join(" | ");
+                    self.out.push_str(&format!("{} => ", patterns_str));
+                    self.block(&arm.body, arm.span.end);
+                    self.end_line(arm.span.end, stmt.span.end - 1);
+                }
+                self.comments_before(stmt.span.end);
+                self.depth -= 1;
+
+                self.write_indent();
+                self.out.push('}');
+            }
+            StatementKind::Block(stmts) => self.block(stmts, stmt.span.end),
+            StatementKind::Import { names, path } => {
+                self.out.push_str(&format!(
+                    "import {{ {} }} from {};",
+                    names.join(", "),
+                    self.string(path)
+                ));
+            }
+            StatementKind::Export(decl) => {
+                self.out.push_str("export ");
+                let decl = self.var_declaration(decl, column + 7);
+                self.out.push_str(&decl);
+            }
+            StatementKind::Expression(expr) => {
+                let expr = self.expression(expr, 0, column);
+                self.out.push_str(&expr);
+                self.out.push(';');
+            }
+        }
+    }
+
+    fn var_declaration(&mut self, stmt: &Statement, column: usize) -> String {
+        let StatementKind::VarDeclaration {
+            constant,
+            name,
+            var_type,
+            value,
+        } = &stmt.kind
+        else {
+            return String::new();
+        };
+
+        let keyword = if *constant { "const" } else { "let" };
+        let head = match var_type {
+            Some(t) => format!("{} {}: {} = ", keyword, name, t),
+            None => format!("{} {} = ", keyword, name),
+        };
+        let value = self.expression(value, 0, column + head.len());
+        format!("{}{};", head, value)
+    }
+
+    // if (...) { ... } else if (...) { ... } else { ... }
+    fn if_statement(&mut self, stmt: &Statement) {
+        let mut current = stmt;
+        while let StatementKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        } = &current.kind
+        {
+            let column = self.column();
+            let condition = self.expression(condition, 0, column + 4);
+            self.out.push_str(&format!("if ({}) ", condition));
+            let end = match else_branch {
+                Some(else_branch) => else_branch.span.start,
+                None => current.span.end,
+            };
+            self.block(then_branch, end);
+
+            match else_branch.as_deref() {
+                Some(else_branch) => {
+                    self.out.push_str(" else ");
+                    if let StatementKind::Block(stmts) = &else_branch.kind {
+                        self.block(stmts, else_branch.span.end);
+                        return;
+                    }
+                    current = else_branch;
+                }
+                None => return,
+            }
+        }
+    }
+
+    // Writes `{`, the indented statements and the closing `}`, leaving the
+    // line open after it.
+    // Writes a block that closes before `end`.
+    fn block(&mut self, statements: &[Statement], end: usize) {
+        let end = self.closing_brace(end);
+        self.out.push('{');
+        if statements.is_empty() && !self.has_comment_before(end) {
+            self.out.push('}');
+            return;
+        }
+        self.out.push('\n');
+
+        self.depth += 1;
+        self.last_line = None;
+        self.statements(statements, end);
+        self.depth -= 1;
+
+        self.write_indent();
+        self.out.push('}');
+    }
+
+    fn comments_before(&mut self, pos: usize) {
+        while self.has_comment_before(pos) {
+            let comment = self.comments[self.next_comment].clone();
+            self.next_comment += 1;
+            self.separate(comment.span.line);
+            self.write_indent();
+            self.out.push_str(&comment.text);
+            self.out.push('\n');
+            self.last_line = Some(comment.span.line);
+        }
+    }
+
+    // Comments before `pos`, each on a line of its own at the current depth.
+    pub fn comment_lines(&mut self, pos: usize) -> String {
+        let mut out = String::new();
+        while self.has_comment_before(pos) {
+            out.push_str(&self.indent(self.depth));
+            out.push_str(&self.comments[self.next_comment].text);
+            out.push('\n');
+            self.next_comment += 1;
+        }
+        out
+    }
+
+    // Comments before `pos` that fall inside an expression. Each ends its
+    // line, so what follows continues one level deeper.
+    pub fn inline_comments(&mut self, pos: usize) -> String {
+        let mut out = String::new();
+        while self.has_comment_before(pos) {
+            out.push_str(&self.comments[self.next_comment].text);
+            out.push('\n');
+            out.push_str(&self.indent(self.depth + 1));
+            self.next_comment += 1;
+        }
+        out
+    }
+
+    // The comment after an item ending at `end`, if one follows on the same
+    // source line before `limit`.
+    pub fn trailing_comment(&mut self, end: usize, limit: usize) -> Option<String> {
+        let line = self.line_of(end.saturating_sub(1));
+        let comment = self.comments.get(self.next_comment)?;
+        if comment.span.line != line || comment.span.start >= limit {
+            return None;
+        }
+        self.next_comment += 1;
+        Some(comment.text.clone())
+    }
+
+    pub fn has_comment_before(&self, pos: usize) -> bool {
+        self.comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.span.start < pos)
+    }
+
+    // Offset of the `}` that closes a block before `end`, looking past the
+    // comments and the `else` that can follow it.
+    fn closing_brace(&self, end: usize) -> usize {
+        let mut pos = end;
+        while let Some(brace) = self.source[..pos].rfind('}') {
+            let comment = self
+                .comments
+                .iter()
+                .find(|c| c.span.start <= brace && brace < c.span.end);
+            match comment {
+                Some(comment) => pos = comment.span.start,
+                None => return brace,
+            }
+        }
+        end
+    }
+
+    // Ends the line of an item spanning up to `end`, pulling up a comment
+    // that followed it on the same source line before `limit`.
+    fn end_line(&mut self, end: usize, limit: usize) {
+        if let Some(comment) = self.trailing_comment(end, limit) {
+            self.out.push(' ');
+            self.out.push_str(&comment);
+        }
+        self.out.push('\n');
+        self.last_line = Some(self.line_of(end.saturating_sub(1)));
+    }
+
+    // Keeps (at most one) blank line between items that had one.
+    fn separate(&mut self, line: usize) {
+        if let Some(last_line) = self.last_line {
+            if line > last_line + 1 {
+                self.out.push('\n');
+            }
+        }
+    }
+
+    fn write_indent(&mut self) {
+        let indent = self.indent(self.depth);
+        self.out.push_str(&indent);
+    }
+
+    pub fn indent(&self, depth: usize) -> String {
+        " ".repeat(depth * self.config.indent)
+    }
+
+    // Column the next character written to the current line will land on.
+    fn column(&self) -> usize {
+        let line_start = self.out.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        self.out[line_start..].chars().count()
+    }
+
+    // 1-based source line containing byte offset `pos`.
+    fn line_of(&self, pos: usize) -> usize {
+        match self.line_starts.binary_search(&pos) {
+            Ok(line) => line + 1,
+            Err(line) => line,
+        }
+    }
+}
+
+fn line_starts(source: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    starts.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+    starts
+}
@@ -0,0 +1,3 @@
+pub mod environment;
+pub mod interpreter;
+pub mod value;
@@ -0,0 +1,37 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::interpreter::value::Value;
+
+// A scope of bindings. Closures keep a reference to the scope they were
+// created in, so scopes are shared rather than pushed and popped.
+#[derive(Default)]
+pub struct Environment {
+    values: HashMap<String, Value>,
+    parent: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Environment::default()
+    }
+
+    pub fn with_parent(parent: Rc<RefCell<Environment>>) -> Self {
+        Environment {
+            values: HashMap::new(),
+            parent: Some(parent),
+        }
+    }
+
+    pub fn declare(&mut self, name: &str, value: Value) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<Value> {
+        match self.values.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.parent.as_ref()?.borrow().lookup(name),
+        }
+    }
+}
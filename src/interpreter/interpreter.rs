@@ -0,0 +1,289 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::checker::types::Type;
+use crate::diagnostic::Diagnostic;
+use crate::interpreter::environment::Environment;
+use crate::interpreter::value::{Closure, Value};
+use crate::module::module::ModuleGraph;
+use crate::parser::statement::{
+    ComparisonOperator, Expression, ExpressionKind, LogicalOperator, Operator, Pattern,
+    PatternKind, Statement, StatementKind,
+};
+
+pub struct Interpreter {
+    env: Rc<RefCell<Environment>>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Interpreter {
+            env: Rc::new(RefCell::new(Environment::new())),
+        }
+    }
+
+    pub fn run(&mut self, statements: &[Statement]) -> Result<(), String> {
+        for stmt in statements {
+            self.execute(stmt)?;
+        }
+        Ok(())
+    }
+
+    // Runs every module of the graph, dependencies first. Each module gets its
+    // own global scope holding the values imported from its dependencies.
+    pub fn run_graph(graph: &ModuleGraph) -> Result<(), Diagnostic> {
+        let mut exports: Vec<HashMap<String, Value>> = Vec::new();
+
+        for module in &graph.modules {
+            let mut interpreter = Interpreter::new();
+            for import in &module.imports {
+                for name in &import.names {
+                    if let Some(value) = exports[import.module].get(name) {
+                        interpreter.env.borrow_mut().declare(name, value.clone());
+                    }
+                }
+            }
+
+            interpreter
+                .run(&module.statements)
+                .map_err(|e| Diagnostic::error(e).in_file(&module.name))?;
+
+            let module_exports = module
+                .exports
+                .iter()
+                .filter_map(|name| Some((name.clone(), interpreter.lookup(name).ok()?)))
+                .collect();
+            exports.push(module_exports);
+        }
+
+        Ok(())
+    }
+
+    fn execute_block(&mut self, statements: &[Statement]) -> Result<(), String> {
+        let scope = Environment::with_parent(self.env.clone());
+        let previous = std::mem::replace(&mut self.env, Rc::new(RefCell::new(scope)));
+        let result = self.run(statements);
+        self.env = previous;
+        result
+    }
+
+    fn execute(&mut self, stmt: &Statement) -> Result<(), String> {
+        match &stmt.kind {
+            StatementKind::VarDeclaration {
+                name,
+                var_type,
+                value,
+                ..
+            } => {
+                let value = self.evaluate(value)?;
+                self.declare(name, var_type, value);
+            }
+            StatementKind::FunctionCall { name, args } => {
+                self.call_function(name, args)?;
+            }
+            StatementKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                if self.evaluate(condition)?.is_truthy() {
+                    self.execute_block(then_branch)?;
+                } else if let Some(else_branch) = else_branch {
+                    self.execute(else_branch)?;
+                }
+            }
+            StatementKind::Match { scrutinee, arms } => {
+                let value = self.evaluate(scrutinee)?;
+                let arm = arms
+                    .iter()
+                    .find(|arm| arm.patterns.iter().any(|p| matches_pattern(p, &value)));
+                if let Some(arm) = arm {
+                    self.execute_block(&arm.body)?;
+                }
+            }
+            StatementKind::Block(stmts) => self.execute_block(stmts)?,
+            // imported names are bound by `run_graph`
+            StatementKind::Import { .. } => {}
+            StatementKind::Export(stmt) => self.execute(stmt)?,
+            StatementKind::Expression(expr) => {
+                self.evaluate(expr)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn evaluate(&mut self, expr: &Expression) -> Result<Value, String> {
+        match &expr.kind {
+            ExpressionKind::Identifier(name) => self.lookup(name),
+            ExpressionKind::Number(num) => Ok(Value::Number(*num)),
+            ExpressionKind::StringLiteral(s) => Ok(Value::String(s.clone())),
+            ExpressionKind::Boolean(b) => Ok(Value::Bool(*b)),
+            ExpressionKind::BinaryOp(left, op, right) => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                match (left, op, right) {
+                    (Value::Number(l), Operator::Add, Value::Number(r)) => Ok(Value::Number(l + r)),
+                    (Value::Number(l), Operator::Sub, Value::Number(r)) => Ok(Value::Number(l - r)),
+                    (Value::Number(l), Operator::Mul, Value::Number(r)) => Ok(Value::Number(l * r)),
+                    (Value::Number(_), Operator::Div, Value::Number(0.0)) => {
+                        Err("Division by zero".to_string())
+                    }
+                    (Value::Number(l), Operator::Div, Value::Number(r)) => Ok(Value::Number(l / r)),
+                    (Value::String(l), Operator::Add, Value::String(r)) => {
+                        Ok(Value::String(l + &r))
+                    }
+                    (l, op, r) => Err(format!("Cannot apply `{}` to {} and {}", op, l, r)),
+                }
+            }
+            ExpressionKind::ComparisonOp(left, op, right) => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                let ordering = match (&left, &right) {
+                    (Value::Number(l), Value::Number(r)) => l.partial_cmp(r),
+                    (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+                    _ => None,
+                };
+                let result = match op {
+                    ComparisonOperator::Equal => left == right,
+                    ComparisonOperator::NotEqual => left != right,
+                    _ => {
+                        let ordering = ordering
+                            .ok_or_else(|| format!("Cannot compare {} with {}", left, right))?;
+                        match op {
+                            ComparisonOperator::LessThan => ordering.is_lt(),
+                            ComparisonOperator::GreaterThan => ordering.is_gt(),
+                            ComparisonOperator::LessThanOrEqual => ordering.is_le(),
+                            _ => ordering.is_ge(),
+                        }
+                    }
+                };
+                Ok(Value::Bool(result))
+            }
+            ExpressionKind::LogicalOp(left, op, right) => {
+                let left = self.evaluate(left)?.is_truthy();
+                let result = match op {
+                    LogicalOperator::And => left && self.evaluate(right)?.is_truthy(),
+                    LogicalOperator::Or => left || self.evaluate(right)?.is_truthy(),
+                };
+                Ok(Value::Bool(result))
+            }
+            ExpressionKind::FunctionCall { name, args } => self.call_function(name, args),
+            ExpressionKind::Call { callee, args } => {
+                let callee = self.evaluate(callee)?;
+                let args = self.evaluate_args(args)?;
+                self.call_value(callee, args)
+            }
+            ExpressionKind::Lambda { params, body } => Ok(Value::Function(Rc::new(Closure {
+                params: params.clone(),
+                body: (**body).clone(),
+                env: self.env.clone(),
+            }))),
+            ExpressionKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                if self.evaluate(condition)?.is_truthy() {
+                    self.evaluate(then_branch)
+                } else {
+                    self.evaluate(else_branch)
+                }
+            }
+        }
+    }
+
+    fn call_function(&mut self, name: &str, args: &[Expression]) -> Result<Value, String> {
+        let values = self.evaluate_args(args)?;
+
+        let callee = self.env.borrow().lookup(name);
+        if let Some(callee) = callee {
+            return self.call_value(callee, values);
+        }
+
+        match name {
+            "print" => {
+                let line = values
+                    .iter()
+                    .map(|value| format!("{}", value))
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                println!("{}", line);
+                Ok(Value::Unit)
+            }
+            _ => Err(format!("Undefined function `{}`", name)),
+        }
+    }
+
+    fn call_value(&mut self, callee: Value, args: Vec<Value>) -> Result<Value, String> {
+        let closure = match callee {
+            Value::Function(closure) => closure,
+            other => return Err(format!("{} is not a function", other)),
+        };
+
+        if closure.params.len() != args.len() {
+            return Err(format!(
+                "Expected {} argument(s) but {} were given",
+                closure.params.len(),
+                args.len()
+            ));
+        }
+
+        let scope = Environment::with_parent(closure.env.clone());
+        let previous = std::mem::replace(&mut self.env, Rc::new(RefCell::new(scope)));
+        for (param, arg) in closure.params.iter().zip(args) {
+            self.declare(&param.name, &param.param_type, arg);
+        }
+        let result = self.evaluate(&closure.body);
+        self.env = previous;
+        result
+    }
+
+    fn evaluate_args(&mut self, args: &[Expression]) -> Result<Vec<Value>, String> {
+        let mut values = Vec::new();
+        for arg in args {
+            values.push(self.evaluate(arg)?);
+        }
+        Ok(values)
+    }
+
+    // Numbers bound to a sized integer type are wrapped to its width.
+    fn declare(&mut self, name: &str, var_type: &Option<String>, value: Value) {
+        let value = match (value, var_type) {
+            (Value::Number(num), Some(t)) => Value::Number(Type::from_name(t).wrap(num)),
+            (value, _) => value,
+        };
+        self.env.borrow_mut().declare(name, value);
+    }
+
+    fn lookup(&self, name: &str) -> Result<Value, String> {
+        self.env
+            .borrow()
+            .lookup(name)
+            .ok_or_else(|| format!("Undefined variable `{}`", name))
+    }
+}
+
+fn matches_pattern(pattern: &Pattern, value: &Value) -> bool {
+    match (&pattern.kind, value) {
+        (PatternKind::Wildcard, _) => true,
+        (PatternKind::Number(n), Value::Number(v)) => n == v,
+        (PatternKind::StringLiteral(s), Value::String(v)) => s == v,
+        (PatternKind::Boolean(b), Value::Bool(v)) => b == v,
+        (
+            PatternKind::Range {
+                start,
+                end,
+                inclusive,
+            },
+            Value::Number(v),
+        ) => *v >= *start && if *inclusive { *v <= *end } else { *v < *end },
+        _ => false,
+    }
+}
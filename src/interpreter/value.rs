@@ -0,0 +1,66 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::interpreter::environment::Environment;
+use crate::parser::statement::{Expression, Param};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    String(String),
+    Bool(bool),
+    Function(Rc<Closure>),
+    Unit,
+}
+
+pub struct Closure {
+    pub params: Vec<Param>,
+    pub body: Expression,
+    pub env: Rc<RefCell<Environment>>, // captured enclosing scope
+}
+
+// Closures compare by identity.
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Closure({:?})", self.params)
+    }
+}
+
+impl Value {
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Number(num) => *num != 0.0,
+            Value::String(s) => !s.is_empty(),
+            Value::Bool(b) => *b,
+            Value::Function(_) => true,
+            Value::Unit => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(num) => write!(f, "{}", num),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Function(closure) => {
+                let params_str = closure
+                    .params
+                    .iter()
+                    .map(|param| param.name.clone())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "<function ({})>", params_str)
+            }
+            Value::Unit => write!(f, "()"),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod builder;
+pub mod instruction;
+pub mod passes;
+pub mod printer;
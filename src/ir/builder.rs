@@ -0,0 +1,1119 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::checker::checker::Checker;
+use crate::checker::types::Type;
+use crate::ir::instruction::{
+    Block, BlockId, Constant, Function, Instruction, InstructionKind, Module, Terminator, ValueId,
+};
+use crate::module::module::{Module as SourceModule, ModuleGraph};
+use crate::parser::statement::{
+    ComparisonOperator, Expression, ExpressionKind, LogicalOperator, MatchArm, NodeId, Param,
+    PatternKind, Statement, StatementKind,
+};
+use crate::resolver::resolution::{DeclarationKind, Resolution};
+use crate::resolver::resolver::Resolver;
+
+// Where a variable lives when it is not just an SSA value.
+#[derive(Debug, Clone)]
+enum Storage {
+    Global(String), // top-level, and imported or read by closures
+    Cell,           // read by a closure created before it is defined
+}
+
+// A function being built, with the state of the SSA construction: the value
+// of each variable at the end of each block, and the phis of blocks whose
+// predecessors are not all known yet.
+struct State {
+    lambda: Option<NodeId>,
+    index: usize, // in the module's functions
+    function: Function,
+    current: BlockId,
+    definitions: HashMap<(usize, BlockId), ValueId>,
+    sealed: HashSet<BlockId>,
+    incomplete: HashMap<BlockId, Vec<(usize, ValueId)>>,
+    forwarded: HashMap<ValueId, ValueId>, // removed trivial phis
+    captures: Vec<usize>,                 // declarations in environment slots
+    captured: HashMap<usize, ValueId>,
+    cells: HashMap<usize, ValueId>,
+}
+
+/*
+Lowers every module of the graph, dependencies first. Variables become SSA
+values as in Braun et al., "Simple and Efficient Construction of Static
+Single Assignment Form": a read looks the variable up in the current block,
+then in its predecessors, placing a phi where paths merge. Phis that end up
+merging a single value are removed again.
+
+    let x = c ? 1 : 2;    // bb0: branch %1, bb1, bb2
+                          // bb3: %4 = phi [bb1: %2], [bb2: %3]
+*/
+pub fn build_graph(graph: &ModuleGraph) -> Vec<Module> {
+    let (types, _) = Checker::check_graph(graph);
+    let mut builder = Builder::default();
+    graph
+        .modules
+        .iter()
+        .zip(types)
+        .map(|(module, types)| builder.module(module, types))
+        .collect()
+}
+
+#[derive(Default)]
+struct Builder {
+    stem: String,
+    exports: Vec<HashMap<String, (String, Type)>>, // global and type of each export
+    resolution: Resolution,
+    declarations: HashMap<NodeId, usize>, // declaring node -> declaration
+    types: HashMap<NodeId, Type>,
+    owners: HashMap<usize, Option<NodeId>>, // declaring lambda of each declaration
+    uses: Vec<(usize, Vec<NodeId>)>,        // name uses and their enclosing lambdas
+    storage: HashMap<usize, Storage>,
+    variable_types: HashMap<usize, Type>,
+    globals: Vec<String>,
+    functions: Vec<Option<Function>>,
+    names: HashSet<String>, // function names in use
+    stack: Vec<State>,
+}
+
+impl Builder {
+    fn module(&mut self, module: &SourceModule, types: HashMap<NodeId, Type>) -> Module {
+        self.stem = stem(&module.name);
+        self.types = types;
+        (self.resolution, _) = Resolver::resolve(&module.statements);
+        self.declarations = self
+            .resolution
+            .declarations
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| d.kind != DeclarationKind::Import)
+            .map(|(index, d)| (d.node, index))
+            .collect();
+        self.owners.clear();
+        self.storage.clear();
+        self.variable_types.clear();
+        self.globals.clear();
+        self.functions.clear();
+        self.names.clear();
+
+        self.analyze_block(&module.statements, &mut Vec::new());
+        self.place(module);
+        self.bind_imports(module);
+
+        self.begin(None, "<script>".to_string(), Vec::new(), Type::Unknown);
+        for stmt in &module.statements {
+            self.statement(stmt);
+        }
+        self.terminate(Terminator::Return(None));
+        self.end();
+
+        let exports = module
+            .exports
+            .iter()
+            .filter_map(|name| {
+                let index = self.top_level(module, name)?;
+                match self.storage.get(&index)? {
+                    Storage::Global(global) => {
+                        let ty = self.variable_types.get(&index).cloned();
+                        Some((name.clone(), (global.clone(), ty.unwrap_or(Type::Unknown))))
+                    }
+                    Storage::Cell => None,
+                }
+            })
+            .collect();
+        self.exports.push(exports);
+
+        Module {
+            name: module.name.clone(),
+            globals: std::mem::take(&mut self.globals),
+            functions: self.functions.drain(..).flatten().collect(),
+        }
+    }
+
+    // The declaration of the top-level variable `name`, the last if it is
+    // declared twice.
+    fn top_level(&self, module: &SourceModule, name: &str) -> Option<usize> {
+        module
+            .statements
+            .iter()
+            .rev()
+            .find_map(|stmt| match &stmt.kind {
+                StatementKind::Export(inner) => match &inner.kind {
+                    StatementKind::VarDeclaration { name: n, .. } if n == name => {
+                        self.declarations.get(&inner.id).copied()
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+    }
+
+    // Records the lambda declaring each variable and the lambdas each name
+    // is used from.
+    fn analyze_block(&mut self, statements: &[Statement], stack: &mut Vec<NodeId>) {
+        for stmt in statements {
+            self.analyze_statement(stmt, stack);
+        }
+    }
+
+    fn analyze_statement(&mut self, stmt: &Statement, stack: &mut Vec<NodeId>) {
+        match &stmt.kind {
+            StatementKind::VarDeclaration { value, .. } => {
+                self.analyze_expression(value, stack);
+                if let Some(&index) = self.declarations.get(&stmt.id) {
+                    self.owners.insert(index, stack.last().copied());
+                }
+            }
+            StatementKind::FunctionCall { args, .. } => {
+                self.analyze_use(stmt.id, stack);
+                for arg in args {
+                    self.analyze_expression(arg, stack);
+                }
+            }
+            StatementKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.analyze_expression(condition, stack);
+                self.analyze_block(then_branch, stack);
+                if let Some(else_branch) = else_branch {
+                    self.analyze_statement(else_branch, stack);
+                }
+            }
+            StatementKind::Match { scrutinee, arms } => {
+                self.analyze_expression(scrutinee, stack);
+                for arm in arms {
+                    self.analyze_block(&arm.body, stack);
+                }
+            }
+            StatementKind::Block(statements) => self.analyze_block(statements, stack),
+            StatementKind::Export(inner) => self.analyze_statement(inner, stack),
+            StatementKind::Expression(expr) => self.analyze_expression(expr, stack),
+            StatementKind::Import { .. } => {}
+        }
+    }
+
+    fn analyze_expression(&mut self, expr: &Expression, stack: &mut Vec<NodeId>) {
+        match &expr.kind {
+            ExpressionKind::Identifier(_) => self.analyze_use(expr.id, stack),
+            ExpressionKind::FunctionCall { args, .. } => {
+                self.analyze_use(expr.id, stack);
+                for arg in args {
+                    self.analyze_expression(arg, stack);
+                }
+            }
+            ExpressionKind::BinaryOp(left, _, right)
+            | ExpressionKind::LogicalOp(left, _, right)
+            | ExpressionKind::ComparisonOp(left, _, right) => {
+                self.analyze_expression(left, stack);
+                self.analyze_expression(right, stack);
+            }
+            ExpressionKind::Call { callee, args } => {
+                self.analyze_expression(callee, stack);
+                for arg in args {
+                    self.analyze_expression(arg, stack);
+                }
+            }
+            ExpressionKind::Lambda { params, body } => {
+                stack.push(expr.id);
+                for param in params {
+                    if let Some(&index) = self.declarations.get(&param.id) {
+                        self.owners.insert(index, Some(expr.id));
+                    }
+                }
+                self.analyze_expression(body, stack);
+                stack.pop();
+            }
+            ExpressionKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.analyze_expression(condition, stack);
+                self.analyze_expression(then_branch, stack);
+                self.analyze_expression(else_branch, stack);
+            }
+            ExpressionKind::Number(_)
+            | ExpressionKind::StringLiteral(_)
+            | ExpressionKind::Boolean(_) => {}
+        }
+    }
+
+    fn analyze_use(&mut self, node: NodeId, stack: &[NodeId]) {
+        if let Some(&index) = self.resolution.uses.get(&node) {
+            self.uses.push((index, stack.to_vec()));
+        }
+    }
+
+    // Top-level variables that are exported or read by closures become
+    // globals. Other variables a closure reads before they are defined,
+    // the closure being created first, become cells.
+    fn place(&mut self, module: &SourceModule) {
+        let mut top_level = HashSet::new();
+        for stmt in &module.statements {
+            let (exported, stmt) = match &stmt.kind {
+                StatementKind::Export(inner) => (true, inner.as_ref()),
+                _ => (false, stmt),
+            };
+            if let Some(&index) = self.declarations.get(&stmt.id) {
+                top_level.insert(index);
+                if exported {
+                    self.global(index);
+                }
+            }
+        }
+
+        for (index, stack) in std::mem::take(&mut self.uses) {
+            let Some(&owner) = self.owners.get(&index) else {
+                continue;
+            };
+            let depth = match owner {
+                Some(owner) => match stack.iter().position(|&lambda| lambda == owner) {
+                    Some(position) => position + 1,
+                    None => continue,
+                },
+                None => 0,
+            };
+            // the closure created by the declaring function
+            let Some(&closure) = stack.get(depth) else {
+                continue;
+            };
+            if top_level.contains(&index) {
+                self.global(index);
+            } else if closure < self.resolution.declarations[index].node
+                && !self.storage.contains_key(&index)
+            {
+                self.storage.insert(index, Storage::Cell);
+            }
+        }
+    }
+
+    fn global(&mut self, index: usize) {
+        if self.storage.contains_key(&index) {
+            return;
+        }
+        let name = format!("{}.{}", self.stem, self.resolution.declarations[index].name);
+        let mut global = name.clone();
+        let mut suffix = 0;
+        while self.globals.contains(&global) {
+            suffix += 1;
+            global = format!("{}.{}", name, suffix);
+        }
+        self.globals.push(global.clone());
+        self.storage.insert(index, Storage::Global(global));
+    }
+
+    // Imported names read the exporting module's globals.
+    fn bind_imports(&mut self, module: &SourceModule) {
+        for (index, decl) in self.resolution.declarations.iter().enumerate() {
+            if decl.kind != DeclarationKind::Import {
+                continue;
+            }
+            let export = module
+                .imports
+                .iter()
+                .filter(|import| import.names.contains(&decl.name))
+                .find_map(|import| self.exports[import.module].get(&decl.name));
+            if let Some((global, ty)) = export {
+                self.storage.insert(index, Storage::Global(global.clone()));
+                self.variable_types.insert(index, ty.clone());
+            }
+        }
+    }
+
+    fn begin(
+        &mut self,
+        lambda: Option<NodeId>,
+        name: String,
+        params: Vec<(String, Type)>,
+        ret: Type,
+    ) {
+        let mut unique = name.clone();
+        let mut suffix = 0;
+        while self.names.contains(&unique) {
+            suffix += 1;
+            unique = format!("{}.{}", name, suffix);
+        }
+        self.names.insert(unique.clone());
+
+        self.functions.push(None);
+        let mut state = State {
+            lambda,
+            index: self.functions.len() - 1,
+            function: Function {
+                name: unique,
+                params,
+                captures: Vec::new(),
+                ret,
+                blocks: Vec::new(),
+                next_value: 0,
+            },
+            current: BlockId(0),
+            definitions: HashMap::new(),
+            sealed: HashSet::new(),
+            incomplete: HashMap::new(),
+            forwarded: HashMap::new(),
+            captures: Vec::new(),
+            captured: HashMap::new(),
+            cells: HashMap::new(),
+        };
+        state.function.blocks.push(Block {
+            id: BlockId(0),
+            instructions: Vec::new(),
+            terminator: Terminator::Return(None),
+            predecessors: Vec::new(),
+        });
+        state.sealed.insert(BlockId(0));
+        self.stack.push(state);
+
+        // cells of this function's variables exist from the start
+        let mut cells: Vec<usize> = self
+            .storage
+            .iter()
+            .filter(|(index, storage)| {
+                matches!(storage, Storage::Cell) && self.owners.get(index) == Some(&lambda)
+            })
+            .map(|(&index, _)| index)
+            .collect();
+        cells.sort();
+        for index in cells {
+            let name = self.resolution.declarations[index].name.clone();
+            let cell = self.emit_named(InstructionKind::Cell, Type::Unknown, Some(name));
+            self.state().cells.insert(index, cell);
+        }
+    }
+
+    // Finishes the current function, forwarding the operands of removed
+    // phis, and returns its index and the declarations it captures.
+    fn end(&mut self) -> (usize, Vec<usize>) {
+        self.remove_trivial_phis();
+        let mut state = self.stack.pop().expect("no function being built");
+        state.function.replace_uses(&state.forwarded);
+        state.function.renumber();
+        state.function.captures = state
+            .captures
+            .iter()
+            .map(|&index| self.resolution.declarations[index].name.clone())
+            .collect();
+        self.functions[state.index] = Some(state.function);
+        (state.index, state.captures)
+    }
+
+    fn state(&mut self) -> &mut State {
+        self.stack.last_mut().expect("no function being built")
+    }
+
+    fn new_block(&mut self) -> BlockId {
+        let function = &mut self.state().function;
+        let id = BlockId(function.blocks.len() as u32);
+        function.blocks.push(Block {
+            id,
+            instructions: Vec::new(),
+            terminator: Terminator::Return(None),
+            predecessors: Vec::new(),
+        });
+        id
+    }
+
+    fn terminate(&mut self, terminator: Terminator) {
+        let state = self.state();
+        let current = state.current;
+        for successor in terminator.successors() {
+            state
+                .function
+                .block_mut(successor)
+                .predecessors
+                .push(current);
+        }
+        state.function.block_mut(current).terminator = terminator;
+    }
+
+    fn switch_to(&mut self, block: BlockId) {
+        self.state().current = block;
+    }
+
+    fn emit(&mut self, kind: InstructionKind, ty: Type) -> ValueId {
+        self.emit_named(kind, ty, None)
+    }
+
+    fn emit_named(&mut self, kind: InstructionKind, ty: Type, name: Option<String>) -> ValueId {
+        let state = self.state();
+        let value = state.function.new_value();
+        let current = state.current;
+        state
+            .function
+            .block_mut(current)
+            .instructions
+            .push(Instruction {
+                value,
+                ty,
+                kind,
+                name,
+            });
+        value
+    }
+
+    fn constant(&mut self, constant: Constant) -> ValueId {
+        let ty = match constant {
+            Constant::Number(_) => Type::Number,
+            Constant::String(_) => Type::String,
+            Constant::Bool(_) => Type::Bool,
+            Constant::Unit => Type::Unknown,
+        };
+        self.emit(InstructionKind::Const(constant), ty)
+    }
+
+    // A bool for branching on `value`.
+    fn truthy(&mut self, value: ValueId, ty: &Type) -> ValueId {
+        if *ty == Type::Bool {
+            value
+        } else {
+            self.emit(InstructionKind::Truthy(value), Type::Bool)
+        }
+    }
+
+    fn seal(&mut self, block: BlockId) {
+        let incomplete = self.state().incomplete.remove(&block).unwrap_or_default();
+        for (index, phi) in incomplete {
+            self.add_phi_operands(index, phi, block);
+        }
+        self.state().sealed.insert(block);
+    }
+
+    fn write_variable(&mut self, index: usize, block: BlockId, value: ValueId) {
+        self.state().definitions.insert((index, block), value);
+    }
+
+    fn read_variable(&mut self, index: usize, block: BlockId) -> ValueId {
+        let state = self.state();
+        match state.definitions.get(&(index, block)) {
+            Some(&value) => resolve(&state.forwarded, value),
+            None => self.read_variable_recursive(index, block),
+        }
+    }
+
+    fn read_variable_recursive(&mut self, index: usize, block: BlockId) -> ValueId {
+        let predecessors = self.state().function.block(block).predecessors.clone();
+        let value = if !self.state().sealed.contains(&block) {
+            let phi = self.new_phi(index, block);
+            self.state()
+                .incomplete
+                .entry(block)
+                .or_default()
+                .push((index, phi));
+            phi
+        } else if predecessors.len() == 1 {
+            self.read_variable(index, predecessors[0])
+        } else if predecessors.is_empty() {
+            // not defined on this path, which the resolver rules out
+            let current = self.state().current;
+            self.switch_to(block);
+            let value = self.constant(Constant::Unit);
+            self.switch_to(current);
+            value
+        } else {
+            let phi = self.new_phi(index, block);
+            self.write_variable(index, block, phi);
+            self.add_phi_operands(index, phi, block)
+        };
+        self.write_variable(index, block, value);
+        value
+    }
+
+    fn new_phi(&mut self, index: usize, block: BlockId) -> ValueId {
+        let ty = self
+            .variable_types
+            .get(&index)
+            .cloned()
+            .unwrap_or(Type::Unknown);
+        let name = self.resolution.declarations[index].name.clone();
+        let state = self.state();
+        let value = state.function.new_value();
+        let instructions = &mut state.function.block_mut(block).instructions;
+        let position = instructions
+            .iter()
+            .take_while(|i| matches!(i.kind, InstructionKind::Phi(_)))
+            .count();
+        instructions.insert(
+            position,
+            Instruction {
+                value,
+                ty,
+                kind: InstructionKind::Phi(Vec::new()),
+                name: Some(name),
+            },
+        );
+        value
+    }
+
+    fn add_phi_operands(&mut self, index: usize, phi: ValueId, block: BlockId) -> ValueId {
+        let predecessors = self.state().function.block(block).predecessors.clone();
+        let mut operands = Vec::new();
+        for predecessor in predecessors {
+            operands.push((predecessor, self.read_variable(index, predecessor)));
+        }
+        if let Some(instruction) = self.phi_mut(phi) {
+            instruction.kind = InstructionKind::Phi(operands);
+        }
+        self.remove_trivial_phi(phi)
+    }
+
+    fn phi_mut(&mut self, phi: ValueId) -> Option<&mut Instruction> {
+        self.state()
+            .function
+            .blocks
+            .iter_mut()
+            .flat_map(|block| &mut block.instructions)
+            .find(|instruction| instruction.value == phi)
+    }
+
+    // A phi merging only itself and one other value is that value.
+    fn remove_trivial_phi(&mut self, phi: ValueId) -> ValueId {
+        let state = self.state();
+        let Some(InstructionKind::Phi(operands)) = state
+            .function
+            .instruction(phi)
+            .map(|instruction| instruction.kind.clone())
+        else {
+            return resolve(&state.forwarded, phi);
+        };
+        let mut same = None;
+        for (_, operand) in operands {
+            let operand = resolve(&state.forwarded, operand);
+            if Some(operand) == same || operand == phi {
+                continue;
+            }
+            if same.is_some() {
+                return phi;
+            }
+            same = Some(operand);
+        }
+        // a phi with no other operand is in a block no path reaches
+        let Some(same) = same else {
+            return phi;
+        };
+        for block in &mut state.function.blocks {
+            block
+                .instructions
+                .retain(|instruction| instruction.value != phi);
+        }
+        state.forwarded.insert(phi, same);
+        same
+    }
+
+    // Removing a phi can make the phis using it trivial in turn.
+    fn remove_trivial_phis(&mut self) {
+        loop {
+            let phis: Vec<ValueId> = self
+                .state()
+                .function
+                .blocks
+                .iter()
+                .flat_map(|block| &block.instructions)
+                .filter(|instruction| matches!(instruction.kind, InstructionKind::Phi(_)))
+                .map(|instruction| instruction.value)
+                .collect();
+            let before = self.state().forwarded.len();
+            for phi in phis {
+                self.remove_trivial_phi(phi);
+            }
+            if self.state().forwarded.len() == before {
+                break;
+            }
+        }
+    }
+
+    // The value of a variable where the current function reads it.
+    fn read(&mut self, index: usize) -> ValueId {
+        let ty = self
+            .variable_types
+            .get(&index)
+            .cloned()
+            .unwrap_or(Type::Unknown);
+        let imported = self.resolution.declarations[index].kind == DeclarationKind::Import;
+        let owner = self.owners.get(&index).copied().flatten();
+        if !imported && owner == self.state().lambda {
+            let current = self.state().current;
+            return self.read_variable(index, current);
+        }
+        match self.storage.get(&index).cloned() {
+            Some(Storage::Global(global)) => self.emit(InstructionKind::LoadGlobal(global), ty),
+            Some(Storage::Cell) => {
+                let cell = self.capture(index);
+                self.emit(InstructionKind::Load(cell), ty)
+            }
+            None if imported => self.constant(Constant::Unit),
+            None => self.capture(index),
+        }
+    }
+
+    // The environment slot holding an outer variable, or its cell, loaded
+    // once at the start of the current function.
+    fn capture(&mut self, index: usize) -> ValueId {
+        if let Some(&value) = self.state().captured.get(&index) {
+            return value;
+        }
+        let ty = match self.storage.get(&index) {
+            Some(Storage::Cell) => Type::Unknown,
+            _ => self
+                .variable_types
+                .get(&index)
+                .cloned()
+                .unwrap_or(Type::Unknown),
+        };
+        let name = self.resolution.declarations[index].name.clone();
+        let state = self.state();
+        let slot = state.captures.len();
+        state.captures.push(index);
+        let value = state.function.new_value();
+        let entry = &mut state.function.blocks[0].instructions;
+        let position = entry
+            .iter()
+            .take_while(|i| {
+                matches!(
+                    i.kind,
+                    InstructionKind::Param(_) | InstructionKind::Capture(_) | InstructionKind::Cell
+                )
+            })
+            .count();
+        entry.insert(
+            position,
+            Instruction {
+                value,
+                ty,
+                kind: InstructionKind::Capture(slot),
+                name: Some(name),
+            },
+        );
+        state.captured.insert(index, value);
+        value
+    }
+
+    // Binds a declared variable to `value` in the current block, storing it
+    // in its global or cell if it has one.
+    fn define(&mut self, index: usize, value: ValueId) {
+        let current = self.state().current;
+        self.write_variable(index, current, value);
+        match self.storage.get(&index).cloned() {
+            Some(Storage::Global(global)) => {
+                self.emit(InstructionKind::StoreGlobal(global, value), Type::Unknown);
+            }
+            Some(Storage::Cell) => {
+                let cell = self.state().cells[&index];
+                self.emit(InstructionKind::Store(cell, value), Type::Unknown);
+            }
+            None => {}
+        }
+    }
+
+    fn block(&mut self, statements: &[Statement]) {
+        for stmt in statements {
+            self.statement(stmt);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match &stmt.kind {
+            StatementKind::VarDeclaration {
+                name,
+                var_type,
+                value,
+                ..
+            } => {
+                // typed before the value, which may read it recursively
+                let declared = var_type.as_deref().map(Type::from_name);
+                let ty = declared
+                    .clone()
+                    .or_else(|| self.types.get(&value.id).cloned())
+                    .unwrap_or(Type::Unknown);
+                let index = self.declarations.get(&stmt.id).copied();
+                if let Some(index) = index {
+                    self.variable_types.insert(index, ty.clone());
+                }
+                let mut result = match &value.kind {
+                    ExpressionKind::Lambda { params, body } => {
+                        self.lambda(value, params, body, Some(name.clone()))
+                    }
+                    _ => self.expression(value),
+                };
+                let named = Some(name.clone());
+                if declared.as_ref().is_some_and(|t| t.bounds().is_some()) {
+                    let wrap = InstructionKind::Wrap(ty.clone(), result);
+                    result = self.emit_named(wrap, ty.clone(), named);
+                } else if matches!(value.kind, ExpressionKind::Identifier(_)) {
+                    result = self.emit_named(InstructionKind::Copy(result), ty.clone(), named);
+                } else {
+                    let current = self.state().current;
+                    let instructions = &mut self.state().function.block_mut(current).instructions;
+                    if let Some(instruction) = instructions
+                        .iter_mut()
+                        .rev()
+                        .find(|instruction| instruction.value == result)
+                    {
+                        if instruction.name.is_none() {
+                            instruction.name = named;
+                        }
+                    }
+                }
+                if let Some(index) = index {
+                    self.define(index, result);
+                }
+            }
+            StatementKind::FunctionCall { name, args } => {
+                self.call(stmt.id, name, args);
+            }
+            StatementKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let value = self.expression(condition);
+                let ty = self.type_of(condition);
+                let condition = self.truthy(value, &ty);
+                let then = self.new_block();
+                let merge = self.new_block();
+                let otherwise = match else_branch {
+                    Some(_) => self.new_block(),
+                    None => merge,
+                };
+                self.terminate(Terminator::Branch {
+                    condition,
+                    then,
+                    otherwise,
+                });
+                self.seal(then);
+
+                self.switch_to(then);
+                self.block(then_branch);
+                self.terminate(Terminator::Jump(merge));
+                if let Some(else_branch) = else_branch {
+                    self.seal(otherwise);
+                    self.switch_to(otherwise);
+                    self.statement(else_branch);
+                    self.terminate(Terminator::Jump(merge));
+                }
+                self.seal(merge);
+                self.switch_to(merge);
+            }
+            StatementKind::Match { scrutinee, arms } => self.match_statement(scrutinee, arms),
+            StatementKind::Block(statements) => self.block(statements),
+            StatementKind::Export(inner) => self.statement(inner),
+            StatementKind::Expression(expr) => match &expr.kind {
+                ExpressionKind::FunctionCall { name, args } => {
+                    self.call(expr.id, name, args);
+                }
+                _ => {
+                    self.expression(expr);
+                }
+            },
+            StatementKind::Import { .. } => {}
+        }
+    }
+
+    /*
+    Tests the patterns of each arm in turn, branching to the arm's body on
+    the first that matches and to the next pattern otherwise. A range tests
+    both of its ends:
+
+        1..5 => ...     // bb1: %3 = ge %0, %2
+                        //      branch %3, bb2, bb4
+                        // bb2: %5 = lt %0, %4
+                        //      branch %5, bb3, bb4
+    */
+    fn match_statement(&mut self, scrutinee: &Expression, arms: &[MatchArm]) {
+        let value = self.expression(scrutinee);
+        let merge = self.new_block();
+        let mut exhaustive = false;
+        for arm in arms {
+            let body = self.new_block();
+            for pattern in &arm.patterns {
+                if pattern.kind == PatternKind::Wildcard {
+                    // later patterns and arms are unreachable
+                    self.terminate(Terminator::Jump(body));
+                    exhaustive = true;
+                    break;
+                }
+                let next = self.new_block();
+                match &pattern.kind {
+                    PatternKind::Range {
+                        start,
+                        end,
+                        inclusive,
+                    } => {
+                        let start = self.constant(Constant::Number(*start));
+                        let above =
+                            self.compare(ComparisonOperator::GreaterThanOrEqual, value, start);
+                        let upper = self.new_block();
+                        self.terminate(Terminator::Branch {
+                            condition: above,
+                            then: upper,
+                            otherwise: next,
+                        });
+                        self.seal(upper);
+                        self.switch_to(upper);
+                        let end = self.constant(Constant::Number(*end));
+                        let op = if *inclusive {
+                            ComparisonOperator::LessThanOrEqual
+                        } else {
+                            ComparisonOperator::LessThan
+                        };
+                        let below = self.compare(op, value, end);
+                        self.terminate(Terminator::Branch {
+                            condition: below,
+                            then: body,
+                            otherwise: next,
+                        });
+                    }
+                    kind => {
+                        let constant = match kind {
+                            PatternKind::Number(n) => Constant::Number(*n),
+                            PatternKind::StringLiteral(s) => Constant::String(s.clone()),
+                            PatternKind::Boolean(b) => Constant::Bool(*b),
+                            _ => Constant::Unit,
+                        };
+                        let constant = self.constant(constant);
+                        let equal = self.compare(ComparisonOperator::Equal, value, constant);
+                        self.terminate(Terminator::Branch {
+                            condition: equal,
+                            then: body,
+                            otherwise: next,
+                        });
+                    }
+                }
+                self.seal(next);
+                self.switch_to(next);
+            }
+            let next = self.state().current;
+
+            self.seal(body);
+            self.switch_to(body);
+            self.block(&arm.body);
+            self.terminate(Terminator::Jump(merge));
+            if exhaustive {
+                break;
+            }
+            self.switch_to(next);
+        }
+        if !exhaustive {
+            self.terminate(Terminator::Jump(merge));
+        }
+        self.seal(merge);
+        self.switch_to(merge);
+    }
+
+    fn compare(&mut self, op: ComparisonOperator, left: ValueId, right: ValueId) -> ValueId {
+        self.emit(InstructionKind::Compare(op, left, right), Type::Bool)
+    }
+
+    fn type_of(&self, expr: &Expression) -> Type {
+        self.types.get(&expr.id).cloned().unwrap_or(Type::Unknown)
+    }
+
+    fn expression(&mut self, expr: &Expression) -> ValueId {
+        let ty = self.type_of(expr);
+        match &expr.kind {
+            ExpressionKind::Identifier(_) => match self.resolution.uses.get(&expr.id) {
+                Some(&index) => self.read(index),
+                None => self.constant(Constant::Unit),
+            },
+            ExpressionKind::Number(n) => self.constant(Constant::Number(*n)),
+            ExpressionKind::StringLiteral(s) => self.constant(Constant::String(s.clone())),
+            ExpressionKind::Boolean(b) => self.constant(Constant::Bool(*b)),
+            ExpressionKind::BinaryOp(left, op, right) => {
+                let left = self.expression(left);
+                let right = self.expression(right);
+                self.emit(InstructionKind::Binary(op.clone(), left, right), ty)
+            }
+            ExpressionKind::ComparisonOp(left, op, right) => {
+                let left = self.expression(left);
+                let right = self.expression(right);
+                self.emit(
+                    InstructionKind::Compare(op.clone(), left, right),
+                    Type::Bool,
+                )
+            }
+            // The right operand is only evaluated if the left one does not
+            // decide the result, which is then the left one's truthiness.
+            ExpressionKind::LogicalOp(left, op, right) => {
+                let value = self.expression(left);
+                let left_type = self.type_of(left);
+                let left = self.truthy(value, &left_type);
+                let evaluate = self.new_block();
+                let merge = self.new_block();
+                let (then, otherwise) = match op {
+                    LogicalOperator::And => (evaluate, merge),
+                    LogicalOperator::Or => (merge, evaluate),
+                };
+                let decided = self.state().current;
+                self.terminate(Terminator::Branch {
+                    condition: left,
+                    then,
+                    otherwise,
+                });
+                self.seal(evaluate);
+
+                self.switch_to(evaluate);
+                let value = self.expression(right);
+                let right_type = self.type_of(right);
+                let right = self.truthy(value, &right_type);
+                let evaluated = self.state().current;
+                self.terminate(Terminator::Jump(merge));
+                self.seal(merge);
+
+                self.switch_to(merge);
+                self.phi(vec![(decided, left), (evaluated, right)], Type::Bool)
+            }
+            ExpressionKind::FunctionCall { name, args } => match self.call(expr.id, name, args) {
+                Some(value) => value,
+                None => self.constant(Constant::Unit),
+            },
+            ExpressionKind::Call { callee, args } => {
+                let callee = self.expression(callee);
+                let args = args.iter().map(|arg| self.expression(arg)).collect();
+                self.emit(InstructionKind::Call(callee, args), ty)
+            }
+            ExpressionKind::Lambda { params, body } => self.lambda(expr, params, body, None),
+            ExpressionKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let value = self.expression(condition);
+                let condition_type = self.type_of(condition);
+                let condition = self.truthy(value, &condition_type);
+                let then = self.new_block();
+                let otherwise = self.new_block();
+                let merge = self.new_block();
+                self.terminate(Terminator::Branch {
+                    condition,
+                    then,
+                    otherwise,
+                });
+                self.seal(then);
+                self.seal(otherwise);
+
+                self.switch_to(then);
+                let then_value = self.expression(then_branch);
+                let then_end = self.state().current;
+                self.terminate(Terminator::Jump(merge));
+
+                self.switch_to(otherwise);
+                let else_value = self.expression(else_branch);
+                let else_end = self.state().current;
+                self.terminate(Terminator::Jump(merge));
+                self.seal(merge);
+
+                self.switch_to(merge);
+                self.phi(vec![(then_end, then_value), (else_end, else_value)], ty)
+            }
+        }
+    }
+
+    // A phi of expression values, which no variable names.
+    fn phi(&mut self, operands: Vec<(BlockId, ValueId)>, ty: Type) -> ValueId {
+        let state = self.state();
+        let value = state.function.new_value();
+        let current = state.current;
+        let instructions = &mut state.function.block_mut(current).instructions;
+        let position = instructions
+            .iter()
+            .take_while(|i| matches!(i.kind, InstructionKind::Phi(_)))
+            .count();
+        instructions.insert(
+            position,
+            Instruction {
+                value,
+                ty,
+                kind: InstructionKind::Phi(operands),
+                name: None,
+            },
+        );
+        value
+    }
+
+    // Arguments are evaluated before the callee is looked up, as the
+    // interpreter does. `None` for `print`, which defines no value.
+    fn call(&mut self, id: NodeId, name: &str, args: &[Expression]) -> Option<ValueId> {
+        let args: Vec<ValueId> = args.iter().map(|arg| self.expression(arg)).collect();
+        let ty = self.types.get(&id).cloned().unwrap_or(Type::Unknown);
+        match self.resolution.uses.get(&id) {
+            Some(&index) => {
+                let callee = self.read(index);
+                Some(self.emit(InstructionKind::Call(callee, args), ty))
+            }
+            None if name == "print" => {
+                self.emit(InstructionKind::Print(args), Type::Unknown);
+                None
+            }
+            None => Some(self.constant(Constant::Unit)),
+        }
+    }
+
+    fn lambda(
+        &mut self,
+        expr: &Expression,
+        params: &[Param],
+        body: &Expression,
+        name: Option<String>,
+    ) -> ValueId {
+        let name =
+            name.unwrap_or_else(|| format!("lambda@{}:{}", expr.span.line, expr.span.column));
+        let ret = match self.types.get(&expr.id) {
+            Some(Type::Function { ret, .. }) => ret.as_ref().clone(),
+            _ => Type::Unknown,
+        };
+        let signature = params
+            .iter()
+            .map(|param| {
+                let ty = param.param_type.as_deref().map(Type::from_name);
+                (param.name.clone(), ty.unwrap_or(Type::Unknown))
+            })
+            .collect();
+        self.begin(Some(expr.id), name, signature, ret);
+
+        for (i, param) in params.iter().enumerate() {
+            let declared = param.param_type.as_deref().map(Type::from_name);
+            let ty = declared.clone().unwrap_or(Type::Unknown);
+            let wraps = declared.as_ref().is_some_and(|t| t.bounds().is_some());
+            let name = Some(param.name.clone());
+            let param_type = if wraps { Type::Unknown } else { ty.clone() };
+            let mut value = self.emit_named(InstructionKind::Param(i), param_type, name.clone());
+            if wraps {
+                value = self.emit_named(InstructionKind::Wrap(ty.clone(), value), ty.clone(), name);
+            }
+            if let Some(&index) = self.declarations.get(&param.id) {
+                self.variable_types.insert(index, ty);
+                self.define(index, value);
+            }
+        }
+        let result = self.expression(body);
+        self.terminate(Terminator::Return(Some(result)));
+        let (index, captures) = self.end();
+
+        // the outer function passes the captured values, or their cells
+        let values = captures
+            .into_iter()
+            .map(|capture| match self.storage.get(&capture) {
+                Some(Storage::Cell) => match self.state().cells.get(&capture) {
+                    Some(&cell) => cell,
+                    None => self.capture(capture),
+                },
+                _ => self.read(capture),
+            })
+            .collect();
+        let ty = self.type_of(expr);
+        self.emit(InstructionKind::Closure(index, values), ty)
+    }
+}
+
+fn resolve(forwarded: &HashMap<ValueId, ValueId>, mut value: ValueId) -> ValueId {
+    while let Some(&next) = forwarded.get(&value) {
+        value = next;
+    }
+    value
+}
+
+fn stem(name: &str) -> String {
+    let file = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    file.strip_suffix(".astc").unwrap_or(file).to_string()
+}
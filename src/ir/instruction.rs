@@ -0,0 +1,337 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::checker::types::Type;
+use crate::parser::statement::{ComparisonOperator, Operator};
+
+// `%n`, the value an instruction defines. Numbered per function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ValueId(pub u32);
+
+// `bbN`, an index into the blocks of a function. `bb0` is the entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(pub u32);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Number(f64),
+    String(String),
+    Bool(bool),
+    Unit,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstructionKind {
+    Const(Constant),
+    Param(usize),   // argument i of the function
+    Capture(usize), // slot i of the closure's environment
+    Copy(ValueId),
+    Binary(Operator, ValueId, ValueId),
+    Compare(ComparisonOperator, ValueId, ValueId),
+    Truthy(ValueId),     // the bool of a value's truthiness
+    Wrap(Type, ValueId), // truncate a number to a sized integer type
+    Call(ValueId, Vec<ValueId>),
+    Print(Vec<ValueId>),
+    Closure(usize, Vec<ValueId>), // functions[i] over captured values
+    LoadGlobal(String),
+    StoreGlobal(String, ValueId),
+    Cell, // a variable closures read before it is defined
+    Load(ValueId),
+    Store(ValueId, ValueId), // cell, value
+    Phi(Vec<(BlockId, ValueId)>),
+}
+
+impl InstructionKind {
+    pub fn operands(&self) -> Vec<ValueId> {
+        let mut kind = self.clone();
+        kind.operands_mut()
+            .into_iter()
+            .map(|value| *value)
+            .collect()
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut ValueId> {
+        match self {
+            InstructionKind::Const(_)
+            | InstructionKind::Param(_)
+            | InstructionKind::Capture(_)
+            | InstructionKind::LoadGlobal(_)
+            | InstructionKind::Cell => Vec::new(),
+            InstructionKind::Copy(value)
+            | InstructionKind::Truthy(value)
+            | InstructionKind::Wrap(_, value)
+            | InstructionKind::StoreGlobal(_, value)
+            | InstructionKind::Load(value) => vec![value],
+            InstructionKind::Binary(_, left, right)
+            | InstructionKind::Compare(_, left, right)
+            | InstructionKind::Store(left, right) => vec![left, right],
+            InstructionKind::Call(callee, args) => {
+                let mut operands = vec![callee];
+                operands.extend(args.iter_mut());
+                operands
+            }
+            InstructionKind::Print(values) | InstructionKind::Closure(_, values) => {
+                values.iter_mut().collect()
+            }
+            InstructionKind::Phi(operands) => operands.iter_mut().map(|(_, value)| value).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Instruction {
+    pub value: ValueId,
+    pub ty: Type,
+    pub kind: InstructionKind,
+    pub name: Option<String>, // the variable it defines, if any
+}
+
+impl Instruction {
+    // `Print`, `StoreGlobal` and `Store` have an id but define no value.
+    pub fn defines_value(&self) -> bool {
+        !matches!(
+            self.kind,
+            InstructionKind::Print(_)
+                | InstructionKind::StoreGlobal(..)
+                | InstructionKind::Store(..)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Jump(BlockId),
+    Branch {
+        condition: ValueId, // a bool
+        then: BlockId,
+        otherwise: BlockId,
+    },
+    Return(Option<ValueId>),
+}
+
+impl Terminator {
+    pub fn operand_mut(&mut self) -> Option<&mut ValueId> {
+        match self {
+            Terminator::Branch { condition, .. } => Some(condition),
+            Terminator::Return(Some(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Jump(target) => vec![*target],
+            Terminator::Branch {
+                then, otherwise, ..
+            } => vec![*then, *otherwise],
+            Terminator::Return(_) => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub id: BlockId,
+    pub instructions: Vec<Instruction>, // phis first
+    pub terminator: Terminator,
+    pub predecessors: Vec<BlockId>,
+}
+
+// The code of a module or the body of a lambda, as a control-flow graph of
+// basic blocks in SSA form: every value is defined by one instruction, and
+// values that depend on the path taken are merged by phis.
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub name: String, // `<script>`, the variable a lambda is bound to, or `lambda@line:col`
+    pub params: Vec<(String, Type)>,
+    pub captures: Vec<String>, // names of the environment slots
+    pub ret: Type,
+    pub blocks: Vec<Block>,
+    pub next_value: u32,
+}
+
+impl Function {
+    pub fn block(&self, id: BlockId) -> &Block {
+        &self.blocks[id.0 as usize]
+    }
+
+    pub fn block_mut(&mut self, id: BlockId) -> &mut Block {
+        &mut self.blocks[id.0 as usize]
+    }
+
+    pub fn new_value(&mut self) -> ValueId {
+        let value = ValueId(self.next_value);
+        self.next_value += 1;
+        value
+    }
+
+    pub fn instruction(&self, value: ValueId) -> Option<&Instruction> {
+        self.blocks
+            .iter()
+            .flat_map(|block| &block.instructions)
+            .find(|instruction| instruction.value == value)
+    }
+
+    pub fn instructions(&self) -> impl Iterator<Item = &Instruction> {
+        self.blocks.iter().flat_map(|block| &block.instructions)
+    }
+
+    // How many instructions and terminators use each value.
+    pub fn use_counts(&self) -> HashMap<ValueId, usize> {
+        let mut counts = HashMap::new();
+        for block in &self.blocks {
+            let mut terminator = block.terminator.clone();
+            let operands = block
+                .instructions
+                .iter()
+                .flat_map(|instruction| instruction.kind.operands())
+                .chain(terminator.operand_mut().map(|value| *value));
+            for operand in operands {
+                *counts.entry(operand).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    // Rewrites every use of a value in `replacements` to its replacement,
+    // following chains of them.
+    pub fn replace_uses(&mut self, replacements: &HashMap<ValueId, ValueId>) {
+        let resolve = |mut value: ValueId| {
+            while let Some(&next) = replacements.get(&value) {
+                value = next;
+            }
+            value
+        };
+        for block in &mut self.blocks {
+            for instruction in &mut block.instructions {
+                for operand in instruction.kind.operands_mut() {
+                    *operand = resolve(*operand);
+                }
+            }
+            if let Some(operand) = block.terminator.operand_mut() {
+                *operand = resolve(*operand);
+            }
+        }
+    }
+
+    // Forgets the edge from `from` to `to`, and the operands the phis of
+    // `to` had for it.
+    pub fn remove_edge(&mut self, from: BlockId, to: BlockId) {
+        let block = self.block_mut(to);
+        block.predecessors.retain(|&p| p != from);
+        for instruction in &mut block.instructions {
+            if let InstructionKind::Phi(operands) = &mut instruction.kind {
+                operands.retain(|&(p, _)| p != from);
+            }
+        }
+    }
+
+    // The blocks reachable from the entry, each after all the blocks that
+    // jump to it except through a loop.
+    pub fn reverse_postorder(&self) -> Vec<BlockId> {
+        fn visit(
+            function: &Function,
+            block: BlockId,
+            visited: &mut HashSet<BlockId>,
+            order: &mut Vec<BlockId>,
+        ) {
+            if !visited.insert(block) {
+                return;
+            }
+            // the first successor, the `then` of a branch, comes first
+            for successor in function
+                .block(block)
+                .terminator
+                .successors()
+                .into_iter()
+                .rev()
+            {
+                visit(function, successor, visited, order);
+            }
+            order.push(block);
+        }
+        let mut order = Vec::new();
+        visit(self, BlockId(0), &mut HashSet::new(), &mut order);
+        order.reverse();
+        order
+    }
+
+    // Drops unreachable blocks, puts the others in reverse postorder and
+    // numbers the values in the order they are defined, instructions that
+    // define no value last.
+    pub fn renumber(&mut self) {
+        let order = self.reverse_postorder();
+        let blocks: HashMap<BlockId, BlockId> = order
+            .iter()
+            .enumerate()
+            .map(|(index, &old)| (old, BlockId(index as u32)))
+            .collect();
+
+        let mut old_blocks: Vec<Option<Block>> = std::mem::take(&mut self.blocks)
+            .into_iter()
+            .map(Some)
+            .collect();
+        for &old in &order {
+            let mut block = old_blocks[old.0 as usize]
+                .take()
+                .expect("block visited twice");
+            block.id = blocks[&old];
+            block.predecessors = block
+                .predecessors
+                .iter()
+                .filter_map(|p| blocks.get(p).copied())
+                .collect();
+            block.terminator = match block.terminator {
+                Terminator::Jump(target) => Terminator::Jump(blocks[&target]),
+                Terminator::Branch {
+                    condition,
+                    then,
+                    otherwise,
+                } => Terminator::Branch {
+                    condition,
+                    then: blocks[&then],
+                    otherwise: blocks[&otherwise],
+                },
+                terminator => terminator,
+            };
+            for instruction in &mut block.instructions {
+                if let InstructionKind::Phi(operands) = &mut instruction.kind {
+                    operands.retain(|(p, _)| blocks.contains_key(p));
+                    for (p, _) in operands.iter_mut() {
+                        *p = blocks[p];
+                    }
+                }
+            }
+            self.blocks.push(block);
+        }
+
+        let (defining, effects): (Vec<&Instruction>, Vec<&Instruction>) = self
+            .instructions()
+            .partition(|instruction| instruction.defines_value());
+        let mut values = HashMap::new();
+        for instruction in defining.into_iter().chain(effects) {
+            let next = ValueId(values.len() as u32);
+            values.insert(instruction.value, next);
+        }
+        for block in &mut self.blocks {
+            for instruction in &mut block.instructions {
+                instruction.value = values[&instruction.value];
+                for operand in instruction.kind.operands_mut() {
+                    *operand = values[operand];
+                }
+            }
+            if let Some(operand) = block.terminator.operand_mut() {
+                *operand = values[operand];
+            }
+        }
+        self.next_value = values.len() as u32;
+    }
+}
+
+// A lowered module. Top-level variables other modules import, or closures
+// read, live in globals named `@<stem>.<name>`.
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub name: String,
+    pub globals: Vec<String>,
+    pub functions: Vec<Function>, // the script is functions[0]
+}
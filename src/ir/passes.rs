@@ -0,0 +1,67 @@
+pub mod constant_propagation;
+pub mod copy_propagation;
+pub mod cse;
+pub mod dce;
+pub mod inline;
+
+use crate::ir::instruction::Module;
+
+pub trait Pass {
+    // Rewrites the module in place and reports whether anything changed.
+    fn run(&self, module: &mut Module) -> bool;
+}
+
+// The passes `-O<level>` enables. `-O0` leaves the IR as built, `-O1` folds
+// constants and removes copies and dead code, `-O2` also merges common
+// subexpressions and inlines small lambdas.
+pub fn for_level(level: usize) -> Vec<Box<dyn Pass>> {
+    let mut passes: Vec<Box<dyn Pass>> = Vec::new();
+    if level >= 2 {
+        passes.push(Box::new(inline::Inline));
+    }
+    if level >= 1 {
+        passes.push(Box::new(copy_propagation::CopyPropagation));
+        passes.push(Box::new(constant_propagation::ConstantPropagation));
+    }
+    if level >= 2 {
+        passes.push(Box::new(cse::Cse));
+    }
+    if level >= 1 {
+        passes.push(Box::new(dce::Dce));
+    }
+    passes
+}
+
+// Passes enable each other, a folded branch leaving a phi to propagate, so
+// they run in turn until none of them changes anything.
+pub struct PassManager {
+    passes: Vec<Box<dyn Pass>>,
+}
+
+const MAX_ROUNDS: usize = 16;
+
+impl PassManager {
+    pub fn new(level: usize) -> Self {
+        PassManager {
+            passes: for_level(level),
+        }
+    }
+
+    pub fn run(&self, module: &mut Module) {
+        if self.passes.is_empty() {
+            return;
+        }
+        for _ in 0..MAX_ROUNDS {
+            let mut changed = false;
+            for pass in &self.passes {
+                changed |= pass.run(module);
+            }
+            if !changed {
+                break;
+            }
+        }
+        for function in &mut module.functions {
+            function.renumber();
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use crate::checker::types::Type;
+use crate::interpreter::value::Value;
+use crate::ir::instruction::{Constant, Function, InstructionKind, Module, Terminator, ValueId};
+use crate::ir::passes::Pass;
+use crate::optimizer::constant_folding::{apply, compare};
+use crate::parser::statement::Operator;
+
+// Evaluates instructions whose operands are constants and turns branches on
+// a constant into jumps. Sized integer arithmetic folds the way the compiled
+// code computes it; the rest folds with the interpreter's semantics, but
+// only to integers a constant holds exactly, where the two agree.
+// Operations that would fail at runtime are left in place.
+pub struct ConstantPropagation;
+
+impl Pass for ConstantPropagation {
+    fn run(&self, module: &mut Module) -> bool {
+        let mut changed = false;
+        for function in &mut module.functions {
+            changed |= fold_instructions(function);
+            changed |= fold_branches(function);
+        }
+        changed
+    }
+}
+
+fn fold_instructions(function: &mut Function) -> bool {
+    let mut constants = HashMap::new();
+    let mut changed = false;
+    // blocks are in reverse postorder, so operands other than those of phis
+    // are seen before their uses
+    for block in &mut function.blocks {
+        for instruction in &mut block.instructions {
+            if let Some(kind) = evaluate(&instruction.kind, &instruction.ty, &constants) {
+                instruction.kind = kind;
+                changed = true;
+            }
+            if let InstructionKind::Const(constant) = &instruction.kind {
+                constants.insert(instruction.value, value_of(constant));
+            }
+        }
+    }
+    changed
+}
+
+// What an instruction over constants becomes, if it can be folded.
+fn evaluate(
+    kind: &InstructionKind,
+    ty: &Type,
+    constants: &HashMap<ValueId, Value>,
+) -> Option<InstructionKind> {
+    let value = match kind {
+        InstructionKind::Binary(op, left, right) if ty.bounds().is_some() => {
+            match (constants.get(left)?, constants.get(right)?) {
+                (Value::Number(l), Value::Number(r)) => Value::Number(integer(ty, op, *l, *r)?),
+                (l, r) => apply(l, op, r)?,
+            }
+        }
+        InstructionKind::Binary(op, left, right) => {
+            match apply(constants.get(left)?, op, constants.get(right)?)? {
+                // fractions and numbers too large to be exact are where the
+                // interpreter and the compiled code part ways
+                Value::Number(num) => Value::Number(exact(num)? as f64),
+                value => value,
+            }
+        }
+        InstructionKind::Compare(op, left, right) => {
+            Value::Bool(compare(constants.get(left)?, op, constants.get(right)?)?)
+        }
+        InstructionKind::Truthy(operand) => Value::Bool(constants.get(operand)?.is_truthy()),
+        // only numbers are wrapped
+        InstructionKind::Wrap(ty, operand) => match constants.get(operand)? {
+            Value::Number(num) => Value::Number(exact(ty.wrap(*num))? as f64),
+            _ => return Some(InstructionKind::Copy(*operand)),
+        },
+        _ => return None,
+    };
+    Some(InstructionKind::Const(constant_of(value)))
+}
+
+// Sized integers are computed in 64-bit registers by the compiled code, so
+// they fold with wrapping `i64` arithmetic, and division truncates; wrapping
+// to the type's width is left to `Wrap`. Results past 2^53, which a constant
+// cannot hold exactly, are left to run.
+fn integer(ty: &Type, op: &Operator, left: f64, right: f64) -> Option<f64> {
+    let (l, r) = (exact(left)?, exact(right)?);
+    let result = match op {
+        Operator::Add => l.wrapping_add(r),
+        Operator::Sub => l.wrapping_sub(r),
+        Operator::Mul => l.wrapping_mul(r),
+        // `u64` divides unsigned
+        Operator::Div if *ty == Type::U64 && (l < 0 || r < 0) => return None,
+        Operator::Div => l.checked_div(r)?,
+    };
+    (result.unsigned_abs() <= EXACT).then_some(result as f64)
+}
+
+// Integers up to this size are exact as `f64`.
+const EXACT: u64 = 1 << 53;
+
+// `num` as an integer, if it is one a constant holds exactly.
+fn exact(num: f64) -> Option<i64> {
+    (num.fract() == 0.0 && num.abs() <= EXACT as f64).then_some(num as i64)
+}
+
+// A branch on a constant jumps to the side it takes; the other loses the
+// edge, and is removed by dead code elimination if nothing else reaches it.
+fn fold_branches(function: &mut Function) -> bool {
+    let mut changed = false;
+    for index in 0..function.blocks.len() {
+        let block = &function.blocks[index];
+        let Terminator::Branch {
+            condition,
+            then,
+            otherwise,
+        } = block.terminator
+        else {
+            continue;
+        };
+        let Some(InstructionKind::Const(constant)) =
+            function.instruction(condition).map(|i| &i.kind)
+        else {
+            continue;
+        };
+        let (taken, skipped) = match value_of(constant).is_truthy() {
+            true => (then, otherwise),
+            false => (otherwise, then),
+        };
+        let id = block.id;
+        function.blocks[index].terminator = Terminator::Jump(taken);
+        if taken != skipped {
+            function.remove_edge(id, skipped);
+        }
+        changed = true;
+    }
+    changed
+}
+
+fn value_of(constant: &Constant) -> Value {
+    match constant {
+        Constant::Number(num) => Value::Number(*num),
+        Constant::String(s) => Value::String(s.clone()),
+        Constant::Bool(b) => Value::Bool(*b),
+        Constant::Unit => Value::Unit,
+    }
+}
+
+fn constant_of(value: Value) -> Constant {
+    match value {
+        Value::Number(num) => Constant::Number(num),
+        Value::String(s) => Constant::String(s),
+        Value::Bool(b) => Constant::Bool(b),
+        Value::Function(_) | Value::Unit => Constant::Unit,
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use crate::ir::instruction::{BlockId, Function, InstructionKind, Module, ValueId};
+use crate::ir::passes::Pass;
+
+// Replaces copies, and phis whose operands are all the same value, by the
+// value itself.
+pub struct CopyPropagation;
+
+impl Pass for CopyPropagation {
+    fn run(&self, module: &mut Module) -> bool {
+        let mut changed = false;
+        for function in &mut module.functions {
+            changed |= propagate(function);
+        }
+        changed
+    }
+}
+
+fn propagate(function: &mut Function) -> bool {
+    let mut replacements = HashMap::new();
+    for instruction in function.instructions() {
+        let source = match &instruction.kind {
+            InstructionKind::Copy(source) => Some(*source),
+            InstructionKind::Phi(operands) => same_operand(instruction.value, operands),
+            _ => None,
+        };
+        if let Some(source) = source {
+            replacements.insert(instruction.value, source);
+        }
+    }
+    if replacements.is_empty() {
+        return false;
+    }
+    for block in &mut function.blocks {
+        block
+            .instructions
+            .retain(|instruction| !replacements.contains_key(&instruction.value));
+    }
+    function.replace_uses(&replacements);
+    true
+}
+
+// The one value a phi merges, references to itself aside.
+fn same_operand(phi: ValueId, operands: &[(BlockId, ValueId)]) -> Option<ValueId> {
+    let mut same = None;
+    for &(_, operand) in operands {
+        if operand == phi || Some(operand) == same {
+            continue;
+        }
+        if same.is_some() {
+            return None;
+        }
+        same = Some(operand);
+    }
+    same
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use crate::ir::instruction::{BlockId, Function, InstructionKind, Module, ValueId};
+use crate::ir::passes::Pass;
+
+// Replaces an instruction by an equal one of the same type that dominates
+// it, one that runs on every path to it. Only instructions whose value
+// depends on nothing but their operands are merged: loads can see a store
+// in between.
+pub struct Cse;
+
+impl Pass for Cse {
+    fn run(&self, module: &mut Module) -> bool {
+        let mut changed = false;
+        for function in &mut module.functions {
+            changed |= eliminate(function);
+        }
+        changed
+    }
+}
+
+fn eliminate(function: &mut Function) -> bool {
+    let children = dominator_tree(function);
+    let mut available = HashMap::new();
+    let mut replacements = HashMap::new();
+    visit(
+        function,
+        BlockId(0),
+        &children,
+        &mut available,
+        &mut replacements,
+    );
+    if replacements.is_empty() {
+        return false;
+    }
+    for block in &mut function.blocks {
+        block
+            .instructions
+            .retain(|instruction| !replacements.contains_key(&instruction.value));
+    }
+    function.replace_uses(&replacements);
+    true
+}
+
+// Walks the dominator tree, `available` holding the instructions of the
+// blocks above the current one.
+fn visit(
+    function: &mut Function,
+    block: BlockId,
+    children: &HashMap<BlockId, Vec<BlockId>>,
+    available: &mut HashMap<String, ValueId>,
+    replacements: &mut HashMap<ValueId, ValueId>,
+) {
+    let mut added = Vec::new();
+    for instruction in &mut function.block_mut(block).instructions {
+        for operand in instruction.kind.operands_mut() {
+            if let Some(&replacement) = replacements.get(operand) {
+                *operand = replacement;
+            }
+        }
+        if !matches!(
+            instruction.kind,
+            InstructionKind::Const(_)
+                | InstructionKind::Binary(..)
+                | InstructionKind::Compare(..)
+                | InstructionKind::Truthy(_)
+                | InstructionKind::Wrap(..)
+        ) {
+            continue;
+        }
+        let key = format!("{:?} {:?}", instruction.ty, instruction.kind);
+        match available.get(&key) {
+            Some(&existing) => {
+                replacements.insert(instruction.value, existing);
+            }
+            None => {
+                available.insert(key.clone(), instruction.value);
+                added.push(key);
+            }
+        }
+    }
+    for &child in children.get(&block).into_iter().flatten() {
+        visit(function, child, children, available, replacements);
+    }
+    for key in added {
+        available.remove(&key);
+    }
+}
+
+// The blocks each block immediately dominates, by Cooper, Harvey and
+// Kennedy's "A Simple, Fast Dominance Algorithm".
+fn dominator_tree(function: &Function) -> HashMap<BlockId, Vec<BlockId>> {
+    let order = function.reverse_postorder();
+    let position: HashMap<BlockId, usize> = order
+        .iter()
+        .enumerate()
+        .map(|(index, &block)| (block, index))
+        .collect();
+    let mut idom: HashMap<BlockId, BlockId> = HashMap::new();
+    idom.insert(BlockId(0), BlockId(0));
+
+    let intersect = |idom: &HashMap<BlockId, BlockId>, mut a: BlockId, mut b: BlockId| {
+        while a != b {
+            while position[&a] > position[&b] {
+                a = idom[&a];
+            }
+            while position[&b] > position[&a] {
+                b = idom[&b];
+            }
+        }
+        a
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &block in &order[1..] {
+            let mut new_idom = None;
+            for &predecessor in &function.block(block).predecessors {
+                if !idom.contains_key(&predecessor) {
+                    continue;
+                }
+                new_idom = Some(match new_idom {
+                    None => predecessor,
+                    Some(current) => intersect(&idom, predecessor, current),
+                });
+            }
+            if let Some(new_idom) = new_idom {
+                if idom.get(&block) != Some(&new_idom) {
+                    idom.insert(block, new_idom);
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    let mut children: HashMap<BlockId, Vec<BlockId>> = HashMap::new();
+    for &block in &order[1..] {
+        children.entry(idom[&block]).or_default().push(block);
+    }
+    children
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use crate::checker::types::Type;
+use crate::ir::instruction::{
+    BlockId, Constant, Function, Instruction, InstructionKind, Module, Terminator, ValueId,
+};
+use crate::ir::passes::Pass;
+use crate::parser::statement::{ComparisonOperator, Operator};
+
+// Removes blocks nothing jumps to, merges a block into the one before it
+// when that is its only way in, and removes unused instructions that can
+// neither fail nor have an effect.
+pub struct Dce;
+
+impl Pass for Dce {
+    fn run(&self, module: &mut Module) -> bool {
+        let mut changed = false;
+        for function in &mut module.functions {
+            let blocks = function.blocks.len();
+            changed |= merge_blocks(function);
+            // drops the unreachable blocks, merged ones included
+            function.renumber();
+            changed |= function.blocks.len() != blocks;
+            changed |= remove_unused(function);
+        }
+        changed
+    }
+}
+
+fn merge_blocks(function: &mut Function) -> bool {
+    let mut changed = false;
+    for index in 0..function.blocks.len() {
+        let id = BlockId(index as u32);
+        while let Terminator::Jump(next) = function.block(id).terminator {
+            if next == id || next == BlockId(0) || function.block(next).predecessors != [id] {
+                break;
+            }
+            let merged = function.block_mut(next);
+            let mut instructions = std::mem::take(&mut merged.instructions);
+            let terminator = std::mem::replace(&mut merged.terminator, Terminator::Return(None));
+            merged.predecessors.clear();
+
+            // with one predecessor a phi has one operand
+            for instruction in &mut instructions {
+                if let InstructionKind::Phi(operands) = &instruction.kind {
+                    instruction.kind = InstructionKind::Copy(operands[0].1);
+                }
+            }
+            for successor in terminator.successors() {
+                let block = function.block_mut(successor);
+                for predecessor in &mut block.predecessors {
+                    if *predecessor == next {
+                        *predecessor = id;
+                    }
+                }
+                for instruction in &mut block.instructions {
+                    if let InstructionKind::Phi(operands) = &mut instruction.kind {
+                        for (predecessor, _) in operands {
+                            if *predecessor == next {
+                                *predecessor = id;
+                            }
+                        }
+                    }
+                }
+            }
+            let block = function.block_mut(id);
+            block.instructions.append(&mut instructions);
+            block.terminator = terminator;
+            changed = true;
+        }
+    }
+    changed
+}
+
+fn remove_unused(function: &mut Function) -> bool {
+    let types: HashMap<ValueId, Type> = function
+        .instructions()
+        .map(|instruction| (instruction.value, instruction.ty.clone()))
+        .collect();
+    let constants: HashMap<ValueId, Constant> = function
+        .instructions()
+        .filter_map(|instruction| match &instruction.kind {
+            InstructionKind::Const(constant) => Some((instruction.value, constant.clone())),
+            _ => None,
+        })
+        .collect();
+
+    let mut changed = false;
+    loop {
+        let uses = function.use_counts();
+        let mut removed = false;
+        for block in &mut function.blocks {
+            let before = block.instructions.len();
+            block.instructions.retain(|instruction| {
+                uses.contains_key(&instruction.value) || !is_pure(instruction, &types, &constants)
+            });
+            removed |= block.instructions.len() != before;
+        }
+        if !removed {
+            return changed;
+        }
+        changed = true;
+    }
+}
+
+// Whether an instruction can be dropped when its value is unused. Arithmetic
+// and ordering fail at runtime on operands of the wrong types, which the
+// checker does not always know, and division by zero fails too.
+fn is_pure(
+    instruction: &Instruction,
+    types: &HashMap<ValueId, Type>,
+    constants: &HashMap<ValueId, Constant>,
+) -> bool {
+    let numbers = |left: &ValueId, right: &ValueId| {
+        types.get(left).is_some_and(Type::is_numeric)
+            && types.get(right).is_some_and(Type::is_numeric)
+    };
+    let strings = |left: &ValueId, right: &ValueId| {
+        types.get(left) == Some(&Type::String) && types.get(right) == Some(&Type::String)
+    };
+    match &instruction.kind {
+        InstructionKind::Binary(Operator::Div, left, right) => {
+            numbers(left, right)
+                && matches!(constants.get(right), Some(Constant::Number(num)) if *num != 0.0)
+        }
+        InstructionKind::Binary(Operator::Add, left, right) => {
+            numbers(left, right) || strings(left, right)
+        }
+        InstructionKind::Binary(_, left, right) => numbers(left, right),
+        InstructionKind::Compare(
+            ComparisonOperator::Equal | ComparisonOperator::NotEqual,
+            _,
+            _,
+        ) => true,
+        // NaN does not compare
+        InstructionKind::Compare(_, left, right) => strings(left, right),
+        InstructionKind::Call(..)
+        | InstructionKind::Print(_)
+        | InstructionKind::StoreGlobal(..)
+        | InstructionKind::Store(..) => false,
+        _ => true,
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use crate::checker::types::Type;
+use crate::ir::instruction::{
+    Constant, Function, Instruction, InstructionKind, Module, Terminator, ValueId,
+};
+use crate::ir::passes::Pass;
+
+// Lambdas with more instructions than this are called, not inlined.
+const MAX_INSTRUCTIONS: usize = 16;
+
+// Replaces calls of a closure created in the same function by the body of
+// its lambda, when that is a single small block. The call's arguments stand
+// for the params and the closure's captured values for its captures.
+pub struct Inline;
+
+impl Pass for Inline {
+    fn run(&self, module: &mut Module) -> bool {
+        let inlinable: HashMap<usize, Function> = module
+            .functions
+            .iter()
+            .enumerate()
+            .skip(1) // the script is never a closure
+            .filter(|(_, function)| {
+                function.blocks.len() == 1
+                    && function.blocks[0].instructions.len() <= MAX_INSTRUCTIONS
+            })
+            .map(|(index, function)| (index, function.clone()))
+            .collect();
+
+        let mut changed = false;
+        for (index, function) in module.functions.iter_mut().enumerate() {
+            while let Some((position, callee, captures, args)) =
+                next_call(function, index, &inlinable)
+            {
+                inline_call(function, position, callee, &captures, &args);
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+type Call<'a> = (
+    (usize, usize), // block, instruction
+    &'a Function,
+    Vec<ValueId>, // captured values
+    Vec<ValueId>, // arguments
+);
+
+fn next_call<'a>(
+    function: &Function,
+    index: usize,
+    inlinable: &'a HashMap<usize, Function>,
+) -> Option<Call<'a>> {
+    for (b, block) in function.blocks.iter().enumerate() {
+        for (i, instruction) in block.instructions.iter().enumerate() {
+            let InstructionKind::Call(callee, args) = &instruction.kind else {
+                continue;
+            };
+            let Some(InstructionKind::Closure(target, captures)) =
+                function.instruction(*callee).map(|i| &i.kind)
+            else {
+                continue;
+            };
+            let Some(lambda) = inlinable.get(target) else {
+                continue;
+            };
+            if *target == index || lambda.params.len() != args.len() {
+                continue;
+            }
+            return Some(((b, i), lambda, captures.clone(), args.clone()));
+        }
+    }
+    None
+}
+
+fn inline_call(
+    function: &mut Function,
+    (block, position): (usize, usize),
+    lambda: &Function,
+    captures: &[ValueId],
+    args: &[ValueId],
+) {
+    let mut values: HashMap<ValueId, ValueId> = HashMap::new();
+    let mut inlined = Vec::new();
+    for instruction in &lambda.blocks[0].instructions {
+        let value = match instruction.kind {
+            InstructionKind::Param(i) => {
+                values.insert(instruction.value, args[i]);
+                continue;
+            }
+            InstructionKind::Capture(i) => {
+                values.insert(instruction.value, captures[i]);
+                continue;
+            }
+            _ => function.new_value(),
+        };
+        values.insert(instruction.value, value);
+        let mut kind = instruction.kind.clone();
+        for operand in kind.operands_mut() {
+            *operand = values[operand];
+        }
+        inlined.push(Instruction {
+            value,
+            kind,
+            ..instruction.clone()
+        });
+    }
+
+    let result = match lambda.blocks[0].terminator {
+        Terminator::Return(Some(result)) => values[&result],
+        _ => {
+            let unit = function.new_value();
+            inlined.push(Instruction {
+                value: unit,
+                ty: Type::Unknown,
+                kind: InstructionKind::Const(Constant::Unit),
+                name: None,
+            });
+            unit
+        }
+    };
+    let instructions = &mut function.blocks[block].instructions;
+    instructions[position].kind = InstructionKind::Copy(result);
+    instructions.splice(position..position, inlined);
+}
@@ -0,0 +1,199 @@
+use std::fmt::Write;
+
+use crate::checker::types::Type;
+use crate::ir::instruction::{
+    Constant, Function, Instruction, InstructionKind, Module, Terminator, ValueId,
+};
+use crate::parser::statement::{ComparisonOperator, Operator};
+
+// Prints a module as text, one function after another:
+//
+//     fn double(a: i32) -> i32 {
+//     bb0:
+//       %0 = param 0                    ; a
+//       %1: i32 = wrap i32 %0           ; a
+//       %2: number = const 2
+//       %3: i32 = mul %1, %2
+//       return %3
+//     }
+//
+// Values are typed where the checker knows their type, and named after the
+// variable they define, if any.
+pub fn print_module(module: &Module) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "; {}", module.name);
+    for global in &module.globals {
+        let _ = writeln!(out, "global @{}", global);
+    }
+    for function in &module.functions {
+        out.push('\n');
+        out.push_str(&print_function(module, function));
+    }
+    out
+}
+
+pub fn print_function(module: &Module, function: &Function) -> String {
+    let mut out = String::new();
+    let params = function
+        .params
+        .iter()
+        .map(|(name, ty)| match ty {
+            Type::Unknown => name.clone(),
+            _ => format!("{}: {}", name, ty),
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+    let _ = write!(out, "fn {}({})", function.name, params);
+    if function.ret != Type::Unknown {
+        let _ = write!(out, " -> {}", function.ret);
+    }
+    if !function.captures.is_empty() {
+        let _ = write!(out, " [{}]", function.captures.join(", "));
+    }
+    out.push_str(" {\n");
+
+    for block in &function.blocks {
+        if block.predecessors.is_empty() {
+            let _ = writeln!(out, "bb{}:", block.id.0);
+        } else {
+            let predecessors = block
+                .predecessors
+                .iter()
+                .map(|p| format!("bb{}", p.0))
+                .collect::<Vec<String>>()
+                .join(", ");
+            let _ = writeln!(
+                out,
+                "{:<36} ; preds {}",
+                format!("bb{}:", block.id.0),
+                predecessors
+            );
+        }
+        for instruction in &block.instructions {
+            let text = instruction_text(module, instruction);
+            match &instruction.name {
+                Some(name) => {
+                    let _ = writeln!(out, "  {:<34} ; {}", text, name);
+                }
+                None => {
+                    let _ = writeln!(out, "  {}", text);
+                }
+            }
+        }
+        let terminator = match &block.terminator {
+            Terminator::Jump(target) => format!("jump bb{}", target.0),
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => format!(
+                "branch {}, bb{}, bb{}",
+                value(*condition),
+                then.0,
+                otherwise.0
+            ),
+            Terminator::Return(Some(result)) => format!("return {}", value(*result)),
+            Terminator::Return(None) => "return".to_string(),
+        };
+        let _ = writeln!(out, "  {}", terminator);
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn instruction_text(module: &Module, instruction: &Instruction) -> String {
+    let operation = match &instruction.kind {
+        InstructionKind::Const(constant) => format!("const {}", constant_text(constant)),
+        InstructionKind::Param(index) => format!("param {}", index),
+        InstructionKind::Capture(index) => format!("capture {}", index),
+        InstructionKind::Copy(source) => format!("copy {}", value(*source)),
+        InstructionKind::Binary(op, left, right) => {
+            let name = match op {
+                Operator::Add => "add",
+                Operator::Sub => "sub",
+                Operator::Mul => "mul",
+                Operator::Div => "div",
+            };
+            format!("{} {}, {}", name, value(*left), value(*right))
+        }
+        InstructionKind::Compare(op, left, right) => {
+            let name = match op {
+                ComparisonOperator::Equal => "eq",
+                ComparisonOperator::NotEqual => "ne",
+                ComparisonOperator::LessThan => "lt",
+                ComparisonOperator::GreaterThan => "gt",
+                ComparisonOperator::LessThanOrEqual => "le",
+                ComparisonOperator::GreaterThanOrEqual => "ge",
+            };
+            format!("{} {}, {}", name, value(*left), value(*right))
+        }
+        InstructionKind::Truthy(operand) => format!("truthy {}", value(*operand)),
+        InstructionKind::Wrap(ty, operand) => format!("wrap {} {}", ty, value(*operand)),
+        InstructionKind::Call(callee, args) => {
+            format!("call {}({})", value(*callee), values(args))
+        }
+        InstructionKind::Print(args) => format!("print {}", values(args)),
+        InstructionKind::Closure(index, captures) => {
+            let name = module
+                .functions
+                .get(*index)
+                .map(|f| f.name.as_str())
+                .unwrap_or("?");
+            if captures.is_empty() {
+                format!("closure @{}", name)
+            } else {
+                format!("closure @{}({})", name, values(captures))
+            }
+        }
+        InstructionKind::LoadGlobal(global) => format!("load_global @{}", global),
+        InstructionKind::StoreGlobal(global, operand) => {
+            format!("store_global @{}, {}", global, value(*operand))
+        }
+        InstructionKind::Cell => "cell".to_string(),
+        InstructionKind::Load(cell) => format!("load {}", value(*cell)),
+        InstructionKind::Store(cell, operand) => {
+            format!("store {}, {}", value(*cell), value(*operand))
+        }
+        InstructionKind::Phi(operands) => {
+            let operands = operands
+                .iter()
+                .map(|(block, operand)| format!("[bb{}: {}]", block.0, value(*operand)))
+                .collect::<Vec<String>>()
+                .join(", ");
+            format!("phi {}", operands)
+        }
+    };
+    match &instruction.kind {
+        _ if !instruction.defines_value() => operation,
+        _ if instruction.ty == Type::Unknown => {
+            format!("{} = {}", value(instruction.value), operation)
+        }
+        _ => format!(
+            "{}: {} = {}",
+            value(instruction.value),
+            instruction.ty,
+            operation
+        ),
+    }
+}
+
+fn constant_text(constant: &Constant) -> String {
+    match constant {
+        Constant::Number(n) => format!("{}", n),
+        Constant::String(s) => format!("{:?}", s),
+        Constant::Bool(b) => format!("{}", b),
+        Constant::Unit => "()".to_string(),
+    }
+}
+
+fn value(value: ValueId) -> String {
+    format!("%{}", value.0)
+}
+
+fn values(values: &[ValueId]) -> String {
+    values
+        .iter()
+        .map(|v| value(*v))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
@@ -1,2 +1,2 @@
 pub mod lexer;
-pub mod token;
\ No newline at end of file
+pub mod token;
@@ -1,63 +1,127 @@
-use crate::lexer::token::{Lexer, Token};
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::lexer::token::{Comment, Lexer, Replay, Span, Token};
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
+        Lexer::at(input, 0, 1, 1)
+    }
+
+    // Lexes `input` from byte offset `pos`, which is at `line` and `column`.
+    pub fn at(input: &'a str, pos: usize, line: usize, column: usize) -> Self {
         let mut lexer = Lexer {
-            input: input.chars(),
+            input: input[pos..].chars(),
             curr: None,
+            pos,
+            line,
+            column,
+            comments: Vec::new(),
+            replay: None,
         };
-        lexer.advance();
+        lexer.curr = lexer.input.next();
         lexer
     }
 
-    pub fn next_token(&mut self) -> Option<Token> {
-        while let Some(c) = self.curr {
-            match c {
-                ' ' | '\n' | '\t' => {
-                    self.advance();
-                    continue;
-                }
-
-                '"' => {
-                    return Some(self.str_literal('"'));
-                }
-
-                '`' => {
-                    return Some(self.str_literal('`'));
-                }
+    // Returns `tokens` from index `next` on, then ends at `end`, the position
+    // of the end of the input.
+    pub fn replay(tokens: &'a [(Token, Span)], next: usize, end: Span) -> Self {
+        let mut lexer = Lexer::at("", 0, end.line, end.column);
+        lexer.pos = end.start;
+        lexer.replay = Some(Replay {
+            tokens,
+            next,
+            furthest: Rc::new(Cell::new(next)),
+        });
+        lexer
+    }
 
-                '\'' => {
-                    return Some(self.str_literal('\''));
-                }
+    pub fn next_token(&mut self) -> Option<(Token, Span)> {
+        if let Some(replay) = &mut self.replay {
+            let index = replay.next.min(replay.tokens.len());
+            replay.furthest.set(replay.furthest.get().max(index));
+            let token = replay.tokens.get(index)?.clone();
+            replay.next += 1;
+            return Some(token);
+        }
+        self.skip_whitespace();
+        let (pos, line, column) = (self.pos, self.line, self.column);
+        let token = self.scan_token()?;
+        let span = Span {
+            start: pos,
+            end: self.pos,
+            line,
+            column,
+        };
+        Some((token, span))
+    }
 
-                'a'..='z' | 'A'..='Z' | '_' => {
-                    return Some(self.ident_type());
-                }
+    fn skip_whitespace(&mut self) {
+        loop {
+            match self.curr {
+                Some(' ' | '\n' | '\t' | '\r') => self.advance(),
+                Some('/') if self.peek() == Some('/') => self.comment(),
+                _ => break,
+            }
+        }
+    }
 
-                '0'..='9' => {
-                    return Some(self.number());
-                }
+    fn comment(&mut self) {
+        let (start, line, column) = (self.pos, self.line, self.column);
+        let mut text = String::new();
+        while let Some(c) = self.curr {
+            if c == '\n' {
+                break;
+            }
+            text.push(c);
+            self.advance();
+        }
+        self.comments.push(Comment {
+            text: text.trim_end().to_string(),
+            span: Span {
+                start,
+                end: self.pos,
+                line,
+                column,
+            },
+        });
+    }
 
-                ':' | ';' | ',' | '{' | '}' | '(' | ')' | '+' | '-' | '*' | '/' | '=' | '<'
-                | '>' | '.' => {
-                    let symbol = Token::Symbol(c);
-                    self.advance();
-                    return Some(symbol);
-                }
-                _ => {
-                    let unknown = Token::Unknown(c);
-                    self.advance();
-                    return Some(unknown);
-                }
+    fn scan_token(&mut self) -> Option<Token> {
+        let c = self.curr?;
+        match c {
+            '"' | '`' | '\'' => Some(self.str_literal(c)),
+            'a'..='z' | 'A'..='Z' | '_' => Some(self.ident_type()),
+            '0'..='9' => Some(self.number()),
+            ':' | ';' | ',' | '{' | '}' | '(' | ')' | '+' | '-' | '*' | '/' | '=' | '<' | '>'
+            | '.' | '?' | '!' | '&' | '|' => {
+                self.advance();
+                Some(Token::Symbol(c))
+            }
+            _ => {
+                self.advance();
+                Some(Token::Unknown(c))
             }
         }
-        None
     }
 
     fn advance(&mut self) {
+        if let Some(c) = self.curr {
+            self.pos += c.len_utf8();
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
         self.curr = self.input.next();
     }
 
+    fn peek(&self) -> Option<char> {
+        self.input.clone().next()
+    }
+
     fn ident_type(&mut self) -> Token {
         let mut result = String::new();
         while let Some(c) = self.curr {
@@ -70,25 +134,33 @@ impl<'a> Lexer<'a> {
         }
 
         match result.as_str() {
-            "let" | "const" | "function" | "if" | "else" | "return" => Token::Keyword(result),
-            "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" | "string" | "number" => {
-                Token::Type(result)
-            }
+            "let" | "const" | "function" | "if" | "else" | "return" | "match" | "true"
+            | "false" | "import" | "export" | "from" => Token::Keyword(result),
+            "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" | "string" | "number"
+            | "bool" => Token::Type(result),
             _ => Token::Ident(result),
         }
     }
 
     fn number(&mut self) -> Token {
         let mut result = String::new();
+        let mut fraction = false;
         while let Some(c) = self.curr {
-            if c.is_numeric() || c == '.' {
+            // `1..5` is a range, not a malformed float, and `1.2.3` stops
+            // before the second `.`, which the parser reports
+            let is_fraction = c == '.'
+                && !fraction
+                && matches!(self.peek(), Some(d) if d.is_ascii_digit());
+            if c.is_ascii_digit() || is_fraction {
+                fraction |= is_fraction;
                 result.push(c);
                 self.advance();
             } else {
                 break;
             }
         }
-        Token::Number(result.parse::<f64>().unwrap())
+        // ASCII digits with at most one `.` between them always parse
+        Token::Number(result.parse().unwrap_or_default())
     }
 
     fn str_literal(&mut self, q: char) -> Token {
@@ -1,3 +1,5 @@
+use std::cell::Cell;
+use std::rc::Rc;
 use std::str::Chars;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -11,7 +13,51 @@ pub enum Token {
     Unknown(char),
 }
 
+// Location of a token or node in the source. `start` and `end` are byte
+// offsets; `line` and `column` (1-based) locate `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    // Span covering `self` through `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: other.end,
+            ..self
+        }
+    }
+}
+
+// A `// ...` line comment. The parser never sees comments; they are kept
+// aside so tools like the formatter can put them back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub text: String, // including the leading `//`
+    pub span: Span,
+}
+
+#[derive(Clone)]
 pub struct Lexer<'a> {
     pub input: Chars<'a>,
     pub curr: Option<char>,
-}
\ No newline at end of file
+    pub pos: usize, // byte offset of `curr`
+    pub line: usize,
+    pub column: usize,
+    pub comments: Vec<Comment>,
+    pub replay: Option<Replay<'a>>, // tokens to return instead of scanning `input`
+}
+
+// Tokens lexed earlier, handed out again so a parser can run over part of a
+// file without relexing it. Clones made for lookahead share `furthest`, so
+// it covers every token the parser looked at.
+#[derive(Clone)]
+pub struct Replay<'a> {
+    pub tokens: &'a [(Token, Span)],
+    pub next: usize,
+    pub furthest: Rc<Cell<usize>>, // highest index read; `tokens.len()` for the end
+}
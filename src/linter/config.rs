@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use crate::diagnostic::Diagnostic;
+use crate::lexer::token::Comment;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Allow, // not reported
+    Warn,
+    Deny, // reported as an error
+}
+
+impl Level {
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name {
+            "allow" => Some(Level::Allow),
+            "warn" => Some(Level::Warn),
+            "deny" => Some(Level::Deny),
+            _ => None,
+        }
+    }
+}
+
+// Rule levels for one file, set by directive comments:
+//
+//     // lint: allow(shadowing, prefer-const)
+//     // lint: deny(unused-variable)
+//
+// A directive applies to the whole file wherever it appears; a later one
+// overrides an earlier one for the same rule.
+#[derive(Debug, Default)]
+pub struct LintConfig {
+    levels: HashMap<String, Level>,
+}
+
+impl LintConfig {
+    pub fn new() -> Self {
+        LintConfig::default()
+    }
+
+    // `rules` are the known rule ids; directives naming others are reported.
+    pub fn from_comments(comments: &[Comment], rules: &[&str]) -> (LintConfig, Vec<Diagnostic>) {
+        let mut config = LintConfig::new();
+        let mut diagnostics = Vec::new();
+
+        for comment in comments {
+            let text = comment.text.trim_start_matches('/').trim();
+            let Some(directive) = text.strip_prefix("lint:") else {
+                continue;
+            };
+
+            let Some((level, names)) = parse_directive(directive.trim()) else {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        "Malformed lint directive, expected `// lint: allow(rule, ...)`"
+                            .to_string(),
+                    )
+                    .at(comment.span),
+                );
+                continue;
+            };
+
+            for name in names {
+                if rules.contains(&name) {
+                    config.set(name, level);
+                } else {
+                    diagnostics.push(
+                        Diagnostic::warning(format!("Unknown lint rule `{}`", name))
+                            .at(comment.span),
+                    );
+                }
+            }
+        }
+
+        (config, diagnostics)
+    }
+
+    pub fn set(&mut self, rule: &str, level: Level) {
+        self.levels.insert(rule.to_string(), level);
+    }
+
+    // `None` when the file leaves the rule at its default level.
+    pub fn level(&self, rule: &str) -> Option<Level> {
+        self.levels.get(rule).copied()
+    }
+}
+
+/*
+level ( rule, rule, ... )
+*/
+fn parse_directive(directive: &str) -> Option<(Level, Vec<&str>)> {
+    let (level, rest) = directive.split_once('(')?;
+    let level = Level::from_name(level.trim())?;
+    let names: Vec<&str> = rest
+        .strip_suffix(')')?
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect();
+    if names.is_empty() {
+        return None;
+    }
+    Some((level, names))
+}
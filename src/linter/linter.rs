@@ -0,0 +1,47 @@
+use crate::checker::checker::Checker;
+use crate::diagnostic::Diagnostic;
+use crate::lexer::token::Lexer;
+use crate::linter::config::{Level, LintConfig};
+use crate::linter::rules::{self, LintContext};
+use crate::parser::parser::Parser;
+use crate::resolver::resolver::Resolver;
+
+// Runs every rule over a single file at the level its directives give it,
+// falling back to the rule's default. Sources with parse errors only get
+// the parser's diagnostics; resolution and type errors are left to the
+// compiler, the rules just skip what they cannot resolve.
+pub fn lint(source: &str) -> Vec<Diagnostic> {
+    let mut parser = Parser::new(Lexer::new(source));
+    let statements = parser.parse();
+    if parser.diagnostics.iter().any(|d| d.is_error()) {
+        return parser.diagnostics;
+    }
+
+    let rules = rules::all();
+    let ids: Vec<&str> = rules.iter().map(|rule| rule.id()).collect();
+    let (config, config_diagnostics) = LintConfig::from_comments(&parser.lexer.comments, &ids);
+
+    let (resolution, _) = Resolver::resolve(&statements);
+    let (types, _) = Checker::check(&statements);
+    let context = LintContext {
+        statements: &statements,
+        resolution: &resolution,
+        types: &types,
+    };
+
+    let mut diagnostics = parser.diagnostics;
+    diagnostics.extend(config_diagnostics);
+    for rule in &rules {
+        let diagnostic = match config.level(rule.id()).unwrap_or(rule.default_level()) {
+            Level::Allow => continue,
+            Level::Warn => Diagnostic::warning,
+            Level::Deny => Diagnostic::error,
+        };
+        for lint in rule.check(&context) {
+            diagnostics.push(diagnostic(format!("{} [{}]", lint.message, rule.id())).at(lint.span));
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.map(|span| span.start));
+    diagnostics
+}
@@ -0,0 +1,46 @@
+pub mod constant_condition;
+pub mod mismatched_comparison;
+pub mod prefer_const;
+pub mod shadowing;
+pub mod unused_variable;
+
+use std::collections::HashMap;
+
+use crate::checker::types::Type;
+use crate::lexer::token::Span;
+use crate::linter::config::Level;
+use crate::parser::statement::{NodeId, Statement};
+use crate::resolver::resolution::Resolution;
+
+// What a rule gets to look at: one file's AST with its name resolution and
+// inferred expression types.
+pub struct LintContext<'a> {
+    pub statements: &'a [Statement],
+    pub resolution: &'a Resolution,
+    pub types: &'a HashMap<NodeId, Type>,
+}
+
+pub struct Lint {
+    pub message: String,
+    pub span: Span,
+}
+
+pub trait Rule {
+    // Kebab-case name used in directives and appended to messages.
+    fn id(&self) -> &'static str;
+
+    fn default_level(&self) -> Level;
+
+    fn check(&self, context: &LintContext) -> Vec<Lint>;
+}
+
+// Every rule `astc lint` runs. A new rule only needs adding here.
+pub fn all() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(unused_variable::UnusedVariable),
+        Box::new(shadowing::Shadowing),
+        Box::new(constant_condition::ConstantCondition),
+        Box::new(prefer_const::PreferConst),
+        Box::new(mismatched_comparison::MismatchedComparison),
+    ]
+}
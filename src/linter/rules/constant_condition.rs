@@ -0,0 +1,60 @@
+use crate::linter::config::Level;
+use crate::linter::rules::{Lint, LintContext, Rule};
+use crate::optimizer::constant_folding::{constant, ConstantFolder};
+use crate::parser::fold::Fold;
+use crate::parser::statement::{Expression, ExpressionKind, Statement, StatementKind};
+use crate::parser::visit::{walk_expression, walk_statement, Visitor};
+
+// `if` statements and conditional expressions whose condition folds to a
+// constant, so one branch never runs.
+pub struct ConstantCondition;
+
+impl Rule for ConstantCondition {
+    fn id(&self) -> &'static str {
+        "constant-condition"
+    }
+
+    fn default_level(&self) -> Level {
+        Level::Warn
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Lint> {
+        let mut visitor = ConditionVisitor { lints: Vec::new() };
+        for stmt in context.statements {
+            visitor.visit_statement(stmt);
+        }
+        visitor.lints
+    }
+}
+
+struct ConditionVisitor {
+    lints: Vec<Lint>,
+}
+
+impl ConditionVisitor {
+    fn check_condition(&mut self, condition: &Expression) {
+        let folded = ConstantFolder::new().fold_expression(condition.clone());
+        if let Some(value) = constant(&folded) {
+            self.lints.push(Lint {
+                message: format!("Condition is always {}", value.is_truthy()),
+                span: condition.span,
+            });
+        }
+    }
+}
+
+impl Visitor for ConditionVisitor {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        if let StatementKind::If { condition, .. } = &stmt.kind {
+            self.check_condition(condition);
+        }
+        walk_statement(self, stmt);
+    }
+
+    fn visit_expression(&mut self, expr: &Expression) {
+        if let ExpressionKind::Conditional { condition, .. } = &expr.kind {
+            self.check_condition(condition);
+        }
+        walk_expression(self, expr);
+    }
+}
@@ -0,0 +1,56 @@
+use crate::linter::config::Level;
+use crate::linter::rules::{Lint, LintContext, Rule};
+use crate::parser::statement::{ComparisonOperator, Expression, ExpressionKind};
+use crate::parser::visit::{walk_expression, Visitor};
+
+// Comparisons between operands of incompatible types. Equality between them
+// is always false; ordering fails at runtime.
+pub struct MismatchedComparison;
+
+impl Rule for MismatchedComparison {
+    fn id(&self) -> &'static str {
+        "mismatched-comparison"
+    }
+
+    fn default_level(&self) -> Level {
+        Level::Deny
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Lint> {
+        let mut visitor = ComparisonVisitor {
+            context,
+            lints: Vec::new(),
+        };
+        for stmt in context.statements {
+            visitor.visit_statement(stmt);
+        }
+        visitor.lints
+    }
+}
+
+struct ComparisonVisitor<'a> {
+    context: &'a LintContext<'a>,
+    lints: Vec<Lint>,
+}
+
+impl Visitor for ComparisonVisitor<'_> {
+    fn visit_expression(&mut self, expr: &Expression) {
+        if let ExpressionKind::ComparisonOp(left, op, right) = &expr.kind {
+            let types = self.context.types;
+            if let (Some(left), Some(right)) = (types.get(&left.id), types.get(&right.id)) {
+                if left.unify(right).is_none() {
+                    let outcome = match op {
+                        ComparisonOperator::Equal => "is always false",
+                        ComparisonOperator::NotEqual => "is always true",
+                        _ => "fails at runtime",
+                    };
+                    self.lints.push(Lint {
+                        message: format!("`{}` between {} and {} {}", op, left, right, outcome),
+                        span: expr.span,
+                    });
+                }
+            }
+        }
+        walk_expression(self, expr);
+    }
+}
@@ -0,0 +1,56 @@
+use crate::linter::config::Level;
+use crate::linter::rules::{Lint, LintContext, Rule};
+use crate::optimizer::constant_folding::{constant, ConstantFolder};
+use crate::parser::fold::Fold;
+use crate::parser::statement::{Statement, StatementKind};
+use crate::parser::visit::{walk_statement, Visitor};
+
+// `let` declarations whose initializer folds to a constant. Variables are
+// never reassigned, so these hold the same value as a `const` would.
+pub struct PreferConst;
+
+impl Rule for PreferConst {
+    fn id(&self) -> &'static str {
+        "prefer-const"
+    }
+
+    fn default_level(&self) -> Level {
+        Level::Warn
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Lint> {
+        let mut visitor = LetVisitor { lints: Vec::new() };
+        for stmt in context.statements {
+            visitor.visit_statement(stmt);
+        }
+        visitor.lints
+    }
+}
+
+struct LetVisitor {
+    lints: Vec<Lint>,
+}
+
+impl Visitor for LetVisitor {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        if let StatementKind::VarDeclaration {
+            constant: false,
+            name,
+            value,
+            ..
+        } = &stmt.kind
+        {
+            let folded = ConstantFolder::new().fold_expression(value.clone());
+            if constant(&folded).is_some() {
+                self.lints.push(Lint {
+                    message: format!(
+                        "`{}` is initialized with a constant, declare it with `const`",
+                        name
+                    ),
+                    span: stmt.span,
+                });
+            }
+        }
+        walk_statement(self, stmt);
+    }
+}
@@ -0,0 +1,32 @@
+use crate::linter::config::Level;
+use crate::linter::rules::{Lint, LintContext, Rule};
+
+// Declarations hiding a name from an enclosing scope.
+pub struct Shadowing;
+
+impl Rule for Shadowing {
+    fn id(&self) -> &'static str {
+        "shadowing"
+    }
+
+    fn default_level(&self) -> Level {
+        Level::Warn
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Lint> {
+        let declarations = &context.resolution.declarations;
+        declarations
+            .iter()
+            .filter_map(|decl| {
+                let shadowed = &declarations[decl.shadows?];
+                Some(Lint {
+                    message: format!(
+                        "`{}` shadows the declaration on line {}",
+                        decl.name, shadowed.span.line
+                    ),
+                    span: decl.span,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::HashSet;
+
+use crate::linter::config::Level;
+use crate::linter::rules::{Lint, LintContext, Rule};
+use crate::parser::statement::{NodeId, StatementKind};
+use crate::resolver::resolution::DeclarationKind;
+
+// Declarations nothing refers to. Exported names are used by importers and
+// names starting with `_` are unused on purpose.
+pub struct UnusedVariable;
+
+impl Rule for UnusedVariable {
+    fn id(&self) -> &'static str {
+        "unused-variable"
+    }
+
+    fn default_level(&self) -> Level {
+        Level::Warn
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Lint> {
+        let used: HashSet<usize> = context.resolution.uses.values().copied().collect();
+        let exported: HashSet<NodeId> = context
+            .statements
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StatementKind::Export(decl) => Some(decl.id),
+                _ => None,
+            })
+            .collect();
+
+        context
+            .resolution
+            .declarations
+            .iter()
+            .enumerate()
+            .filter(|(index, decl)| {
+                !used.contains(index)
+                    && !exported.contains(&decl.node)
+                    && !decl.name.starts_with('_')
+            })
+            .map(|(_, decl)| {
+                let what = match decl.kind {
+                    DeclarationKind::Import => "import",
+                    DeclarationKind::Parameter => "parameter",
+                    DeclarationKind::Constant => "constant",
+                    DeclarationKind::Variable => "variable",
+                };
+                Lint {
+                    message: format!("Unused {} `{}`", what, decl.name),
+                    span: decl.span,
+                }
+            })
+            .collect()
+    }
+}
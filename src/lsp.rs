@@ -0,0 +1,5 @@
+// `astc lsp`: a language server over stdin and stdout, for one file at a
+// time.
+pub mod analysis;
+pub mod protocol;
+pub mod server;
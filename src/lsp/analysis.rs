@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+
+use crate::checker::checker::Checker;
+use crate::checker::types::Type;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::json::Json;
+use crate::lexer::token::{Lexer, Span, Token};
+use crate::lsp::protocol::{object, LineIndex};
+use crate::parser::incremental::IncrementalParser;
+use crate::parser::statement::{
+    Expression, ExpressionKind, NodeId, Param, Statement, StatementKind,
+};
+use crate::parser::visit::{walk_expression, walk_statement, Visitor};
+use crate::resolver::resolution::Resolution;
+use crate::resolver::resolver::Resolver;
+
+// LSP symbol kinds
+const FUNCTION: f64 = 12.0;
+const VARIABLE: f64 = 13.0;
+const CONSTANT: f64 = 14.0;
+
+// What a document's requests are answered from: its statements, name
+// resolution and types, computed once per version of the text. Imports are
+// not followed, so imported names have unknown types.
+pub struct Analysis {
+    pub statements: Vec<Statement>,
+    pub diagnostics: Vec<Diagnostic>,
+    resolution: Resolution,
+    names: Vec<Name>, // every mention of a declared name, in source order
+    signatures: HashMap<NodeId, Signature>,
+}
+
+struct Name {
+    start: usize,
+    end: usize,
+    declaration: usize, // index into the resolution's declarations
+    declares: bool,     // the name in the declaration itself
+}
+
+// How a declaring node is shown on hover.
+enum Signature {
+    Variable { constant: bool, ty: Option<String> },
+    Parameter { ty: Option<String> },
+    Import { path: String },
+}
+
+impl Analysis {
+    // Sources with parse errors only report the parser's diagnostics, but
+    // are still resolved so what did parse can be navigated.
+    pub fn new(parser: &IncrementalParser) -> Self {
+        let source = parser.text();
+        let statements = parser.statements();
+        let (resolution, resolve_diagnostics) = Resolver::resolve(&statements);
+        let (types, check_diagnostics) = Checker::check(&statements);
+        let mut diagnostics = parser.diagnostics();
+        if !diagnostics.iter().any(|d| d.is_error()) {
+            diagnostics.extend(resolve_diagnostics);
+            diagnostics.extend(check_diagnostics);
+            diagnostics.sort_by_key(|diagnostic| diagnostic.span.map(|span| span.start));
+        }
+
+        let mut collector = Collector {
+            types: &types,
+            uses: Vec::new(),
+            signatures: HashMap::new(),
+        };
+        for stmt in &statements {
+            collector.visit_statement(stmt);
+        }
+
+        let mut names: Vec<Name> = resolution
+            .declarations
+            .iter()
+            .enumerate()
+            .map(|(index, declaration)| {
+                let span = name_span(source, declaration.span, &declaration.name);
+                Name {
+                    start: span.start,
+                    end: span.end,
+                    declaration: index,
+                    declares: true,
+                }
+            })
+            .collect();
+        for (node, start, end) in collector.uses {
+            if let Some(&declaration) = resolution.uses.get(&node) {
+                names.push(Name {
+                    start,
+                    end,
+                    declaration,
+                    declares: false,
+                });
+            }
+        }
+        names.sort_by_key(|name| name.start);
+
+        Analysis {
+            statements,
+            diagnostics,
+            resolution,
+            signatures: collector.signatures,
+            names,
+        }
+    }
+
+    pub fn diagnostics(&self, index: &LineIndex) -> Json {
+        let diagnostics = self
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let range = match diagnostic.span {
+                    Some(span) => index.span_range(span),
+                    None => index.range(0, 0),
+                };
+                let severity = match diagnostic.severity {
+                    Severity::Error => 1.0,
+                    Severity::Warning => 2.0,
+                };
+                object(vec![
+                    ("range", range),
+                    ("severity", Json::Number(severity)),
+                    ("source", Json::String("astc".to_string())),
+                    ("message", Json::String(diagnostic.message.clone())),
+                ])
+            })
+            .collect();
+        Json::Array(diagnostics)
+    }
+
+    // The declaration of the name at `offset`, shown as it would be written
+    // with its type.
+    pub fn hover(&self, offset: usize, index: &LineIndex) -> Option<Json> {
+        let name = self.name_at(offset)?;
+        let declaration = &self.resolution.declarations[name.declaration];
+        let text = match self.signatures.get(&declaration.node) {
+            Some(Signature::Import { path }) => {
+                format!("import {{ {} }} from \"{}\"", declaration.name, path)
+            }
+            Some(Signature::Parameter { ty }) => {
+                format!("(parameter) {}", typed(&declaration.name, ty))
+            }
+            Some(Signature::Variable { constant, ty }) => {
+                let keyword = if *constant { "const" } else { "let" };
+                format!("{} {}", keyword, typed(&declaration.name, ty))
+            }
+            None => declaration.name.clone(),
+        };
+        Some(object(vec![
+            (
+                "contents",
+                object(vec![
+                    ("kind", Json::String("markdown".to_string())),
+                    ("value", Json::String(format!("```astc\n{}\n```", text))),
+                ]),
+            ),
+            ("range", index.range(name.start, name.end)),
+        ]))
+    }
+
+    // Where the name at `offset` is declared, as a range in the document.
+    pub fn definition(&self, offset: usize, index: &LineIndex) -> Option<Json> {
+        let name = self.name_at(offset)?;
+        let declaration = self
+            .names
+            .iter()
+            .find(|other| other.declares && other.declaration == name.declaration)?;
+        Some(index.range(declaration.start, declaration.end))
+    }
+
+    // Declarations in statement order. Those inside blocks are listed with
+    // the others, as the language has no named scopes to nest them under.
+    pub fn symbols(&self, index: &LineIndex) -> Json {
+        let mut symbols = Vec::new();
+        for stmt in &self.statements {
+            self.collect_symbols(stmt, stmt.span, index, &mut symbols);
+        }
+        Json::Array(symbols)
+    }
+
+    fn collect_symbols(
+        &self,
+        stmt: &Statement,
+        range: Span,
+        index: &LineIndex,
+        out: &mut Vec<Json>,
+    ) {
+        match &stmt.kind {
+            StatementKind::VarDeclaration {
+                constant,
+                name,
+                value,
+                ..
+            } => {
+                let kind = match (&value.kind, constant) {
+                    (ExpressionKind::Lambda { .. }, _) => FUNCTION,
+                    (_, true) => CONSTANT,
+                    (_, false) => VARIABLE,
+                };
+                let mut fields = vec![
+                    ("name", Json::String(name.clone())),
+                    ("kind", Json::Number(kind)),
+                    ("range", index.span_range(range)),
+                    ("selectionRange", self.selection(stmt.id, range, index)),
+                ];
+                if let Some(Signature::Variable { ty: Some(ty), .. }) =
+                    self.signatures.get(&stmt.id)
+                {
+                    fields.insert(1, ("detail", Json::String(ty.clone())));
+                }
+                out.push(object(fields));
+            }
+            // the symbol covers the `export` too
+            StatementKind::Export(inner) => self.collect_symbols(inner, stmt.span, index, out),
+            StatementKind::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                for stmt in then_branch {
+                    self.collect_symbols(stmt, stmt.span, index, out);
+                }
+                if let Some(else_branch) = else_branch {
+                    self.collect_symbols(else_branch, else_branch.span, index, out);
+                }
+            }
+            StatementKind::Match { arms, .. } => {
+                for stmt in arms.iter().flat_map(|arm| &arm.body) {
+                    self.collect_symbols(stmt, stmt.span, index, out);
+                }
+            }
+            StatementKind::Block(stmts) => {
+                for stmt in stmts {
+                    self.collect_symbols(stmt, stmt.span, index, out);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // The name of the declaration made by `node`.
+    fn selection(&self, node: NodeId, fallback: Span, index: &LineIndex) -> Json {
+        self.names
+            .iter()
+            .find(|name| {
+                name.declares && self.resolution.declarations[name.declaration].node == node
+            })
+            .map(|name| index.range(name.start, name.end))
+            .unwrap_or_else(|| index.span_range(fallback))
+    }
+
+    // A cursor just past a name is still on it.
+    fn name_at(&self, offset: usize) -> Option<&Name> {
+        self.names
+            .iter()
+            .find(|name| name.start <= offset && offset <= name.end)
+    }
+}
+
+fn typed(name: &str, ty: &Option<String>) -> String {
+    match ty {
+        Some(ty) => format!("{}: {}", name, ty),
+        None => name.to_string(),
+    }
+}
+
+// Declarations span their whole statement or param; the name is the first
+// identifier in it that matches.
+fn name_span(source: &str, span: Span, name: &str) -> Span {
+    let Some(text) = source.get(span.start..span.end) else {
+        return span;
+    };
+    let mut lexer = Lexer::new(text);
+    while let Some((token, found)) = lexer.next_token() {
+        if token == Token::Ident(name.to_string()) {
+            return Span {
+                start: span.start + found.start,
+                end: span.start + found.end,
+                ..span
+            };
+        }
+    }
+    span
+}
+
+// Gathers the names used in a module, and what hover shows for each
+// declaring node.
+struct Collector<'a> {
+    types: &'a HashMap<NodeId, Type>,
+    uses: Vec<(NodeId, usize, usize)>, // node, start and end of the name
+    signatures: HashMap<NodeId, Signature>,
+}
+
+impl Collector<'_> {
+    // The declared type, or the inferred one if it is known.
+    fn type_of(&self, declared: &Option<String>, value: &Expression) -> Option<String> {
+        declared
+            .clone()
+            .or_else(|| match self.types.get(&value.id) {
+                Some(Type::Unknown) | None => None,
+                Some(ty) => Some(ty.to_string()),
+            })
+    }
+}
+
+impl Visitor for Collector<'_> {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        match &stmt.kind {
+            StatementKind::VarDeclaration {
+                constant,
+                var_type,
+                value,
+                ..
+            } => {
+                let ty = self.type_of(var_type, value);
+                let constant = *constant;
+                self.signatures
+                    .insert(stmt.id, Signature::Variable { constant, ty });
+            }
+            StatementKind::FunctionCall { name, .. } => {
+                self.uses
+                    .push((stmt.id, stmt.span.start, stmt.span.start + name.len()));
+            }
+            StatementKind::Import { path, .. } => {
+                let path = path.clone();
+                self.signatures.insert(stmt.id, Signature::Import { path });
+            }
+            _ => {}
+        }
+        walk_statement(self, stmt);
+    }
+
+    fn visit_expression(&mut self, expr: &Expression) {
+        match &expr.kind {
+            ExpressionKind::Identifier(_) => {
+                self.uses.push((expr.id, expr.span.start, expr.span.end));
+            }
+            ExpressionKind::FunctionCall { name, .. } => {
+                self.uses
+                    .push((expr.id, expr.span.start, expr.span.start + name.len()));
+            }
+            _ => {}
+        }
+        walk_expression(self, expr);
+    }
+
+    fn visit_param(&mut self, param: &Param) {
+        let ty = param.param_type.clone();
+        self.signatures
+            .insert(param.id, Signature::Parameter { ty });
+    }
+}
@@ -0,0 +1,101 @@
+use std::io::{self, BufRead, Write};
+
+use crate::json::Json;
+use crate::lexer::token::Span;
+
+// Reads one `Content-Length` framed message. `None` at end of input; a
+// message whose body is not JSON is an `Err` with the parser's message.
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Result<Json, String>>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let Some(length) = length else {
+        return Ok(Some(Err("Missing Content-Length header".to_string())));
+    };
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(Json::parse(&String::from_utf8_lossy(&body))))
+}
+
+pub fn write_message(writer: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+pub fn object(fields: Vec<(&str, Json)>) -> Json {
+    Json::Object(
+        fields
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
+}
+
+// Converts between byte offsets and LSP positions, which count lines from 0
+// and characters in UTF-16 code units.
+pub struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(index, _)| index + 1));
+        LineIndex { text, line_starts }
+    }
+
+    pub fn position(&self, offset: usize) -> Json {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let character = self.text[self.line_starts[line]..offset]
+            .encode_utf16()
+            .count();
+        object(vec![
+            ("line", Json::Number(line as f64)),
+            ("character", Json::Number(character as f64)),
+        ])
+    }
+
+    pub fn range(&self, start: usize, end: usize) -> Json {
+        object(vec![
+            ("start", self.position(start)),
+            ("end", self.position(end)),
+        ])
+    }
+
+    pub fn span_range(&self, span: Span) -> Json {
+        self.range(span.start, span.end)
+    }
+
+    // Past the end of its line, a position is the end of the line.
+    pub fn offset(&self, position: &Json) -> Option<usize> {
+        let line = position.get("line")?.as_f64()? as usize;
+        let character = position.get("character")?.as_f64()? as usize;
+        let Some(&start) = self.line_starts.get(line) else {
+            return Some(self.text.len());
+        };
+        let mut units = 0;
+        for (index, c) in self.text[start..].char_indices() {
+            if units >= character || c == '\n' {
+                return Some(start + index);
+            }
+            units += c.len_utf16();
+        }
+        Some(self.text.len())
+    }
+}
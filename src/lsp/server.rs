@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use crate::formatter::formatter::{self, FormatConfig};
+use crate::json::Json;
+use crate::lsp::analysis::Analysis;
+use crate::lsp::protocol::{object, read_message, write_message, LineIndex};
+use crate::parser::incremental::{Edit, IncrementalParser};
+
+// JSON-RPC error codes
+const PARSE_ERROR: f64 = -32700.0;
+const INVALID_REQUEST: f64 = -32600.0;
+const METHOD_NOT_FOUND: f64 = -32601.0;
+const INVALID_PARAMS: f64 = -32602.0;
+
+// Changes are sent as edits to ranges of the text.
+const INCREMENTAL_SYNC: f64 = 2.0;
+
+struct Document {
+    parser: IncrementalParser,
+    analysis: Analysis,
+}
+
+impl Document {
+    fn new(text: String) -> Self {
+        let parser = IncrementalParser::new(text);
+        let analysis = Analysis::new(&parser);
+        Document { parser, analysis }
+    }
+
+    fn text(&self) -> &str {
+        self.parser.text()
+    }
+
+    // A change replaces the range it has, or the whole text if it has none.
+    fn change(&mut self, change: &Json) -> Result<(), String> {
+        let text = change
+            .get("text")
+            .and_then(Json::as_str)
+            .ok_or("Missing text in change")?;
+        let Some(range) = change.get("range") else {
+            *self = Document::new(text.to_string());
+            return Ok(());
+        };
+        let index = LineIndex::new(self.text());
+        let offset = |key| {
+            range
+                .get(key)
+                .and_then(|position| index.offset(position))
+                .ok_or(format!("Missing range.{} in change", key))
+        };
+        let edit = Edit {
+            start: offset("start")?,
+            end: offset("end")?,
+            text: text.to_string(),
+        };
+        self.parser.edit(&edit)?;
+        self.analysis = Analysis::new(&self.parser);
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, Document>, // uri -> open document
+    shut_down: bool,
+}
+
+// Serves stdin and stdout until the client sends `exit`. Returns the exit
+// code: 0 if `shutdown` came first, 1 otherwise.
+pub fn run() -> i32 {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    match Server::default().serve(&mut stdin.lock(), &mut stdout.lock()) {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            1
+        }
+    }
+}
+
+impl Server {
+    pub fn serve(&mut self, input: &mut impl BufRead, output: &mut impl Write) -> io::Result<i32> {
+        while let Some(message) = read_message(input)? {
+            let message = match message {
+                Ok(message) => message,
+                Err(error) => {
+                    write_message(output, &error_response(Json::Null, PARSE_ERROR, error))?;
+                    continue;
+                }
+            };
+            let Some(method) = message.get("method").and_then(Json::as_str) else {
+                // responses to requests the server never sends
+                if message.get("id").is_none() {
+                    let error = "Expected a request or notification".to_string();
+                    write_message(output, &error_response(Json::Null, INVALID_REQUEST, error))?;
+                }
+                continue;
+            };
+            if method == "exit" {
+                return Ok(if self.shut_down { 0 } else { 1 });
+            }
+            let params = message.get("params").cloned().unwrap_or(Json::Null);
+            match message.get("id") {
+                Some(id) => {
+                    let response = match self.request(method, &params) {
+                        Ok(result) => object(vec![
+                            ("jsonrpc", Json::String("2.0".to_string())),
+                            ("id", id.clone()),
+                            ("result", result),
+                        ]),
+                        Err((code, error)) => error_response(id.clone(), code, error),
+                    };
+                    write_message(output, &response)?;
+                }
+                None => {
+                    for notification in self.notify(method, &params) {
+                        write_message(output, &notification)?;
+                    }
+                }
+            }
+        }
+        // the client went away without `exit`
+        Ok(1)
+    }
+
+    fn request(&mut self, method: &str, params: &Json) -> Result<Json, (f64, String)> {
+        match method {
+            "initialize" => Ok(object(vec![
+                (
+                    "capabilities",
+                    object(vec![
+                        ("textDocumentSync", Json::Number(INCREMENTAL_SYNC)),
+                        ("hoverProvider", Json::Bool(true)),
+                        ("definitionProvider", Json::Bool(true)),
+                        ("documentSymbolProvider", Json::Bool(true)),
+                        ("documentFormattingProvider", Json::Bool(true)),
+                    ]),
+                ),
+                (
+                    "serverInfo",
+                    object(vec![("name", Json::String("astc".to_string()))]),
+                ),
+            ])),
+            "shutdown" => {
+                self.shut_down = true;
+                Ok(Json::Null)
+            }
+            "textDocument/hover" => {
+                let (_, document, offset) = self.position(params)?;
+                let index = LineIndex::new(document.text());
+                let hover = document.analysis.hover(offset, &index);
+                Ok(hover.unwrap_or(Json::Null))
+            }
+            "textDocument/definition" => {
+                let (uri, document, offset) = self.position(params)?;
+                let index = LineIndex::new(document.text());
+                Ok(match document.analysis.definition(offset, &index) {
+                    Some(range) => object(vec![
+                        ("uri", Json::String(uri.to_string())),
+                        ("range", range),
+                    ]),
+                    None => Json::Null,
+                })
+            }
+            "textDocument/documentSymbol" => {
+                let (_, document) = self.document(params)?;
+                Ok(document.analysis.symbols(&LineIndex::new(document.text())))
+            }
+            "textDocument/formatting" => {
+                let (_, document) = self.document(params)?;
+                let options = params.get("options");
+                let mut config = FormatConfig::default();
+                if let Some(size) = options
+                    .and_then(|o| o.get("tabSize"))
+                    .and_then(Json::as_f64)
+                {
+                    config.indent = size as usize;
+                }
+                // sources that do not parse are left alone; their errors are
+                // already published
+                let Ok(formatted) = formatter::format(document.text(), &config) else {
+                    return Ok(Json::Null);
+                };
+                if formatted == document.text() {
+                    return Ok(Json::Array(Vec::new()));
+                }
+                let index = LineIndex::new(document.text());
+                Ok(Json::Array(vec![object(vec![
+                    ("range", index.range(0, document.text().len())),
+                    ("newText", Json::String(formatted)),
+                ])]))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method '{}'", method))),
+        }
+    }
+
+    // Notifications get no response, but changes to a document publish its
+    // diagnostics.
+    fn notify(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let Some(uri) = uri_of(params) else {
+            return Vec::new();
+        };
+        let uri = uri.to_string();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params
+                    .get("textDocument")
+                    .and_then(|document| document.get("text"))
+                    .and_then(Json::as_str)
+                    .unwrap_or_default();
+                self.documents
+                    .insert(uri.clone(), Document::new(text.to_string()));
+            }
+            "textDocument/didChange" => {
+                let Some(document) = self.documents.get_mut(&uri) else {
+                    return Vec::new();
+                };
+                let changes = params.get("contentChanges").and_then(Json::as_array);
+                for change in changes.unwrap_or_default() {
+                    if let Err(error) = document.change(change) {
+                        return vec![log_error(&format!("{}: {}", uri, error))];
+                    }
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![publish(&uri, Json::Array(Vec::new()))];
+            }
+            _ => return Vec::new(),
+        }
+        let document = &self.documents[&uri];
+        let diagnostics = document
+            .analysis
+            .diagnostics(&LineIndex::new(document.text()));
+        vec![publish(&uri, diagnostics)]
+    }
+
+    fn document<'a>(&'a self, params: &'a Json) -> Result<(&'a str, &'a Document), (f64, String)> {
+        let uri = uri_of(params).ok_or((INVALID_PARAMS, "Missing textDocument.uri".to_string()))?;
+        let document = self
+            .documents
+            .get(uri)
+            .ok_or_else(|| (INVALID_PARAMS, format!("Unknown document '{}'", uri)))?;
+        Ok((uri, document))
+    }
+
+    // The document and byte offset a `TextDocumentPositionParams` refers to.
+    fn position<'a>(
+        &'a self,
+        params: &'a Json,
+    ) -> Result<(&'a str, &'a Document, usize), (f64, String)> {
+        let (uri, document) = self.document(params)?;
+        let offset = params
+            .get("position")
+            .and_then(|position| LineIndex::new(document.text()).offset(position))
+            .ok_or((INVALID_PARAMS, "Missing position".to_string()))?;
+        Ok((uri, document, offset))
+    }
+}
+
+fn uri_of(params: &Json) -> Option<&str> {
+    params.get("textDocument")?.get("uri")?.as_str()
+}
+
+fn publish(uri: &str, diagnostics: Json) -> Json {
+    object(vec![
+        ("jsonrpc", Json::String("2.0".to_string())),
+        (
+            "method",
+            Json::String("textDocument/publishDiagnostics".to_string()),
+        ),
+        (
+            "params",
+            object(vec![
+                ("uri", Json::String(uri.to_string())),
+                ("diagnostics", diagnostics),
+            ]),
+        ),
+    ])
+}
+
+// Shown in the client's log, for failures there is no response to.
+fn log_error(message: &str) -> Json {
+    object(vec![
+        ("jsonrpc", Json::String("2.0".to_string())),
+        ("method", Json::String("window/logMessage".to_string())),
+        (
+            "params",
+            object(vec![
+                ("type", Json::Number(1.0)),
+                ("message", Json::String(message.to_string())),
+            ]),
+        ),
+    ])
+}
+
+fn error_response(id: Json, code: f64, message: String) -> Json {
+    object(vec![
+        ("jsonrpc", Json::String("2.0".to_string())),
+        ("id", id),
+        (
+            "error",
+            object(vec![
+                ("code", Json::Number(code)),
+                ("message", Json::String(message)),
+            ]),
+        ),
+    ])
+}
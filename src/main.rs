@@ -1,52 +1,457 @@
+#![allow(clippy::module_inception)]
+
 mod lexer;
 mod parser;
 mod ast;
+mod checker;
+mod diagnostic;
+mod json;
+mod interpreter;
+mod module;
+mod formatter;
+mod syntax;
+mod resolver;
+mod optimizer;
+mod linter;
+mod bytecode;
+mod repl;
+mod codegen;
+mod ir;
+mod lsp;
+
+use std::env;
+use std::fs;
+use std::process;
 
-use std::fs::File;
-use std::io::{self, Read};
+use crate::bytecode::compiler::Compiler;
+use crate::bytecode::vm::Vm;
+use crate::checker::checker::Checker;
+use crate::codegen::asm::Syntax;
+use crate::codegen::js::JsConfig;
+use crate::diagnostic::Diagnostic;
+use crate::formatter::formatter::FormatConfig;
+use crate::interpreter::interpreter::Interpreter;
+use crate::ir::passes::PassManager;
+use crate::json::Json;
+use crate::module::loader::ModuleLoader;
+use crate::module::module::ModuleGraph;
+use crate::optimizer::constant_folding::ConstantFolder;
+use crate::parser::incremental::{Edit, IncrementalParser};
+use crate::parser::statement::Statement;
+use crate::resolver::resolver::Resolver;
 
 fn main() {
-    let path = "examples/if.astc";
-    
-    let code = match read_file(path) {
-        Ok(contents) => contents,
-        Err(e) => {
-            eprintln!("Error reading file: {}", e);
-            return;
+    let mut args = env::args().skip(1);
+    let command = args.next().unwrap_or_else(|| "parse".to_string());
+
+    let mut path = "examples/if.astc".to_string();
+    let mut format = "text".to_string();
+    let mut check = false;
+    let mut fold = command == "run" || command == "disasm";
+    let mut vm = false;
+    let mut config = FormatConfig::default();
+    let mut emit = "js".to_string();
+    let mut output = None;
+    let mut source_map = false;
+    let mut js_config = JsConfig::default();
+    let mut opt_level = 0;
+    let mut syntax = "att".to_string();
+    let mut edits = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = args.next().unwrap_or_default(),
+            "--check" => check = true,
+            "--fold" => fold = true,
+            "--vm" => vm = true,
+            "--emit" => emit = args.next().unwrap_or_default(),
+            "--syntax" => syntax = args.next().unwrap_or_default(),
+            "--edits" => edits = args.next(),
+            "-o" => output = args.next(),
+            "--source-map" => source_map = true,
+            "--jsdoc" => js_config.jsdoc = true,
+            "--indent" => config.indent = number_option(&arg, args.next()),
+            "--width" => config.width = number_option(&arg, args.next()),
+            _ if arg.starts_with("-O") => opt_level = opt_level_option(&arg),
+            _ => path = arg,
+        }
+    }
+    if !["text", "json", "sexpr", "dot", "cst"].contains(&format.as_str()) {
+        eprintln!(
+            "Error: Unknown format '{}', expected 'text', 'json', 'sexpr', 'dot' or 'cst'",
+            format
+        );
+        process::exit(1);
+    }
+    if !["js", "c", "wat", "asm"].contains(&emit.as_str()) {
+        eprintln!(
+            "Error: Unknown target '{}', expected 'js', 'c', 'wat' or 'asm'",
+            emit
+        );
+        process::exit(1);
+    }
+    let syntax = match syntax.as_str() {
+        "att" => Syntax::Att,
+        "intel" => Syntax::Intel,
+        _ => {
+            eprintln!("Error: Unknown syntax '{}', expected 'att' or 'intel'", syntax);
+            process::exit(1);
         }
     };
+    if source_map && emit != "js" {
+        eprintln!("Error: Source maps are only supported for 'js'");
+        process::exit(1);
+    }
 
-    let lexer = lexer::token::Lexer::new(&code);
-    let mut parser = parser::parser::Parser::new(lexer);
+    if command == "fmt" {
+        format_file(&path, &config, check);
+        return;
+    }
+    if command == "repl" {
+        repl::Repl::new().run();
+        return;
+    }
+    if command == "lsp" {
+        process::exit(lsp::server::run());
+    }
+    if command == "tokens" {
+        repl::print_tokens(&read_source(&path));
+        return;
+    }
+    if command == "lint" {
+        lint_file(&path);
+        return;
+    }
+    if let Some(edits) = &edits {
+        if command != "parse" || format == "cst" {
+            eprintln!("Error: --edits is only supported by 'parse', without '--format cst'");
+            process::exit(1);
+        }
+        print_edited(&path, edits, &format);
+        return;
+    }
+    if command == "parse" && format == "cst" {
+        print_cst(&path);
+        return;
+    }
 
-    let statements = parser.parse();
-    ast::print_ast(statements);
-}
+    let (mut graph, mut diagnostics) = ModuleLoader::new().load(&path);
+    if graph.entry().is_some() {
+        diagnostics.extend(Resolver::resolve_graph(&graph));
+        let (_, check_diagnostics) = Checker::check_graph(&graph);
+        diagnostics.extend(check_diagnostics);
+        if fold {
+            diagnostics.extend(ConstantFolder::fold_graph(&mut graph));
+        }
+    }
+    for diagnostic in &diagnostics {
+        eprintln!("{}", diagnostic);
+    }
 
-fn read_file(path: &str) -> Result<String, io::Error> {
-    let mut file = match File::open(path) {
-        Ok(file) => file,
-        Err(error) => match error.kind() {
-            io::ErrorKind::NotFound => {
-                eprintln!("Error: File not found at path");
-                return Err(error);
+    match command.as_str() {
+        "parse" => {
+            if let Some(entry) = graph.modules.pop() {
+                print_statements(entry.statements, &format);
+            }
+            if diagnostics.iter().any(|d| d.is_error()) {
+                process::exit(1);
+            }
+        }
+        "run" => {
+            if diagnostics.iter().any(|d| d.is_error()) {
+                process::exit(1);
+            }
+            let result = if vm {
+                Vm::run_graph(&graph)
+            } else {
+                Interpreter::run_graph(&graph)
+            };
+            if let Err(e) = result {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        "disasm" => {
+            if diagnostics.iter().any(|d| d.is_error()) {
+                process::exit(1);
+            }
+            let mut failed = false;
+            for module in &graph.modules {
+                match Compiler::compile(&module.statements) {
+                    Ok(program) => {
+                        println!("; {}", module.name);
+                        print!("{}", bytecode::disassembler::disassemble(&program));
+                    }
+                    Err(e) => {
+                        eprintln!("{}", Diagnostic::error(e).in_file(&module.name));
+                        failed = true;
+                    }
+                }
             }
-            io::ErrorKind::PermissionDenied => {
-                eprintln!("Error: Permission denied for file");
-                return Err(error);
+            if failed {
+                process::exit(1);
             }
-            other_error => {
-                eprintln!("Error: Could not open file '{}': {:?}", path, other_error);
-                return Err(error);
+        }
+        "ir" => {
+            if diagnostics.iter().any(|d| d.is_error()) {
+                process::exit(1);
             }
-        },
+            let passes = PassManager::new(opt_level);
+            for (index, module) in ir::builder::build_graph(&graph).iter_mut().enumerate() {
+                passes.run(module);
+                if index > 0 {
+                    println!();
+                }
+                print!("{}", ir::printer::print_module(module));
+            }
+        }
+        "build" => {
+            if diagnostics.iter().any(|d| d.is_error()) {
+                process::exit(1);
+            }
+            let code = match emit.as_str() {
+                "c" => codegen::c::generate(&graph),
+                "wat" => match codegen::wat::generate(&graph) {
+                    Ok(code) => code,
+                    Err(diagnostics) => {
+                        for diagnostic in &diagnostics {
+                            eprintln!("{}", diagnostic);
+                        }
+                        process::exit(1);
+                    }
+                },
+                "asm" => {
+                    let passes = PassManager::new(opt_level);
+                    let mut modules = ir::builder::build_graph(&graph);
+                    for module in &mut modules {
+                        passes.run(module);
+                    }
+                    match codegen::asm::generate(&modules, syntax) {
+                        Ok(code) => code,
+                        Err(diagnostics) => {
+                            for diagnostic in &diagnostics {
+                                eprintln!("{}", diagnostic);
+                            }
+                            process::exit(1);
+                        }
+                    }
+                }
+                _ => {
+                    build_js(&graph, &js_config, output.as_deref(), source_map);
+                    return;
+                }
+            };
+            match output {
+                Some(output) => write_file(&output, &code),
+                None => print!("{}", code),
+            }
+        }
+        _ => {
+            eprintln!(
+                "Error: Unknown command '{}', expected 'parse', 'tokens', 'run', 'disasm', 'ir', 'build', 'repl', 'lsp', 'fmt' or 'lint'",
+                command
+            );
+            process::exit(1);
+        }
+    }
+}
+
+// Writes the generated JavaScript to `output`, or to stdout. A source map
+// goes next to the output file, or inline as a data URL on stdout.
+fn build_js(graph: &ModuleGraph, config: &JsConfig, output: Option<&str>, source_map: bool) {
+    let mut js = codegen::js::generate(graph, config);
+
+    let Some(output) = output else {
+        if source_map {
+            let map = js.source_map.to_json().to_string();
+            js.code.push_str(&format!(
+                "//# sourceMappingURL=data:application/json;base64,{}\n",
+                codegen::sourcemap::base64(map.as_bytes())
+            ));
+        }
+        print!("{}", js.code);
+        return;
     };
 
-    let mut contents = String::new();
-    if let Err(error) = file.read_to_string(&mut contents) {
-        eprintln!("Error: Could not read from file '{}': {:?}", path, error.kind());
-        return Err(error);
+    if source_map {
+        let file = output.rsplit(['/', '\\']).next().unwrap_or(output);
+        js.source_map.file = Some(file.to_string());
+        js.code
+            .push_str(&format!("//# sourceMappingURL={}.map\n", file));
+        write_file(
+            &format!("{}.map", output),
+            &js.source_map.to_json().to_string(),
+        );
     }
+    write_file(output, &js.code);
+}
+
+fn write_file(path: &str, contents: &str) {
+    if let Err(error) = fs::write(path, contents) {
+        eprintln!(
+            "{}",
+            Diagnostic::error(format!("Could not write file: {}", error)).in_file(path)
+        );
+        process::exit(1);
+    }
+}
+
+// Rewrites `path` in place, or with `--check` only reports whether it would
+// change, exiting with 1 if so.
+fn format_file(path: &str, config: &FormatConfig, check: bool) {
+    let source = read_source(path);
 
-    Ok(contents)
-}
\ No newline at end of file
+    let formatted = match formatter::formatter::format(&source, config) {
+        Ok(formatted) => formatted,
+        Err(diagnostics) => {
+            for diagnostic in diagnostics {
+                eprintln!("{}", diagnostic.in_file(path));
+            }
+            process::exit(1);
+        }
+    };
+
+    if formatted == source {
+        return;
+    }
+    if check {
+        eprintln!("{} is not formatted", path);
+        process::exit(1);
+    }
+    write_file(path, &formatted);
+}
+
+// Lints a single file; imports are not followed. Exits with 1 if a rule at
+// the `deny` level fired.
+fn lint_file(path: &str) {
+    let source = read_source(path);
+
+    let diagnostics = linter::linter::lint(&source);
+    for diagnostic in &diagnostics {
+        eprintln!("{}", diagnostic.clone().in_file(path));
+    }
+    if diagnostics.iter().any(|d| d.is_error()) {
+        process::exit(1);
+    }
+}
+
+fn print_statements(statements: Vec<Statement>, format: &str) {
+    match format {
+        "json" => println!("{}", ast::json::to_json(&statements).pretty()),
+        "sexpr" => ast::sexpr::print_sexpr(&statements),
+        "dot" => ast::dot::print_dot(&statements),
+        _ => ast::print_ast(statements),
+    }
+}
+
+// Parses a single file, then applies the edits listed in `edits_path` one
+// by one, reparsing incrementally, and prints the result. Imports are not
+// followed.
+fn print_edited(path: &str, edits_path: &str, format: &str) {
+    let source = read_source(path);
+    let edits = match read_edits(&read_source(edits_path)) {
+        Ok(edits) => edits,
+        Err(message) => {
+            eprintln!("{}", Diagnostic::error(message).in_file(edits_path));
+            process::exit(1);
+        }
+    };
+
+    let mut parser = IncrementalParser::new(source);
+    for (index, edit) in edits.iter().enumerate() {
+        if let Err(message) = parser.edit(edit) {
+            eprintln!("{}", Diagnostic::error(message).in_file(edits_path));
+            process::exit(1);
+        }
+        eprintln!(
+            "edit {}: relexed {} tokens, reparsed {} of {} statements",
+            index + 1,
+            parser.relexed,
+            parser.reparsed,
+            parser.items()
+        );
+    }
+    let diagnostics = parser.diagnostics();
+    let failed = diagnostics.iter().any(|d| d.is_error());
+    for diagnostic in diagnostics {
+        eprintln!("{}", diagnostic.in_file(path));
+    }
+    print_statements(parser.statements(), format);
+    if failed {
+        process::exit(1);
+    }
+}
+
+// A JSON array of `{"start": 4, "end": 5, "text": "y"}`, with byte offsets
+// into the text as the edits before it left it.
+fn read_edits(source: &str) -> Result<Vec<Edit>, String> {
+    let json = Json::parse(source)?;
+    let edits = json.as_array().ok_or("Expected an array of edits")?;
+    edits
+        .iter()
+        .map(|edit| {
+            let offset = |key| {
+                edit.get(key)
+                    .and_then(Json::as_f64)
+                    .map(|offset| offset as usize)
+                    .ok_or(format!("Expected a number for `{}`", key))
+            };
+            let text = edit.get("text").and_then(Json::as_str);
+            Ok(Edit {
+                start: offset("start")?,
+                end: offset("end")?,
+                text: text.ok_or("Expected a string for `text`")?.to_string(),
+            })
+        })
+        .collect()
+}
+
+// The lossless tree of a single file; imports are not followed.
+fn print_cst(path: &str) {
+    let source = read_source(path);
+
+    let (_, root, diagnostics) = syntax::builder::parse(&source);
+    let failed = diagnostics.iter().any(|d| d.is_error());
+    for diagnostic in diagnostics {
+        eprintln!("{}", diagnostic.in_file(path));
+    }
+    print!("{}", root);
+    if failed {
+        process::exit(1);
+    }
+}
+
+fn read_source(path: &str) -> String {
+    match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!(
+                "{}",
+                Diagnostic::error(format!("Could not read file: {}", error)).in_file(path)
+            );
+            process::exit(1);
+        }
+    }
+}
+
+// `-O0` to `-O2`.
+fn opt_level_option(arg: &str) -> usize {
+    match &arg[2..] {
+        "0" => 0,
+        "1" => 1,
+        "2" => 2,
+        _ => {
+            eprintln!("Error: Unknown optimization level '{}', expected -O0, -O1 or -O2", arg);
+            process::exit(1);
+        }
+    }
+}
+
+fn number_option(name: &str, value: Option<String>) -> usize {
+    match value.as_deref().map(str::parse) {
+        Some(Ok(num)) => num,
+        _ => {
+            eprintln!("Error: {} expects a number", name);
+            process::exit(1);
+        }
+    }
+}
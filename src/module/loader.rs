@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::ast::json::Decoder;
+use crate::diagnostic::Diagnostic;
+use crate::json::Json;
+use crate::lexer::token::Lexer;
+use crate::lexer::token::Span;
+use crate::module::module::{Import, Module, ModuleGraph};
+use crate::parser::parser::Parser;
+use crate::parser::statement::{Statement, StatementKind};
+
+#[derive(Default)]
+pub struct ModuleLoader {
+    graph: ModuleGraph,
+    indices: HashMap<PathBuf, usize>, // canonical path -> module index
+    loading: Vec<(PathBuf, String)>,  // import chain, for cycle detection
+    diagnostics: Vec<Diagnostic>,
+    next_id: u32, // keeps node ids unique across the whole graph
+}
+
+impl ModuleLoader {
+    pub fn new() -> Self {
+        ModuleLoader::default()
+    }
+
+    pub fn load(mut self, entry: &str) -> (ModuleGraph, Vec<Diagnostic>) {
+        let name = normalize(Path::new(entry));
+        match fs::canonicalize(entry) {
+            Ok(path) => {
+                self.load_module(path, name);
+            }
+            Err(error) => self
+                .diagnostics
+                .push(Diagnostic::error(read_error(error)).in_file(&name)),
+        }
+        (self.graph, self.diagnostics)
+    }
+
+    fn load_module(&mut self, path: PathBuf, name: String) -> Option<usize> {
+        if let Some(&index) = self.indices.get(&path) {
+            return Some(index);
+        }
+
+        let code = match fs::read_to_string(&path) {
+            Ok(code) => code,
+            Err(error) => {
+                self.diagnostics
+                    .push(Diagnostic::error(read_error(error)).in_file(&name));
+                return None;
+            }
+        };
+
+        // `.json` files hold a serialized AST, e.g. generated by external tools
+        let statements = if path.extension().is_some_and(|ext| ext == "json") {
+            self.decode_module(&code, &name)
+        } else {
+            self.parse_module(&code, &name)
+        };
+
+        self.loading.push((path.clone(), name.clone()));
+
+        let mut imports = Vec::new();
+        for stmt in &statements {
+            if let StatementKind::Import {
+                names,
+                path: import_path,
+            } = &stmt.kind
+            {
+                if let Some(module) = self.resolve_import(&path, &name, import_path, stmt.span) {
+                    self.check_exports(&name, names, module, stmt.span);
+                    imports.push(Import {
+                        names: names.clone(),
+                        module,
+                    });
+                }
+            }
+        }
+
+        self.loading.pop();
+
+        let exports = statements
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StatementKind::Export(decl) => match &decl.kind {
+                    StatementKind::VarDeclaration { name, .. } => Some(name.clone()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+
+        let index = self.graph.modules.len();
+        self.graph.modules.push(Module {
+            name,
+            statements,
+            imports,
+            exports,
+        });
+        self.indices.insert(path, index);
+        Some(index)
+    }
+
+    fn parse_module(&mut self, code: &str, name: &str) -> Vec<Statement> {
+        let mut parser = Parser::new(Lexer::new(code));
+        parser.next_id = self.next_id;
+        let statements = parser.parse();
+        self.next_id = parser.next_id;
+        for diagnostic in parser.diagnostics {
+            self.diagnostics.push(diagnostic.in_file(name));
+        }
+        statements
+    }
+
+    fn decode_module(&mut self, code: &str, name: &str) -> Vec<Statement> {
+        let mut decoder = Decoder::new();
+        decoder.next_id = self.next_id;
+        let result = Json::parse(code).and_then(|json| decoder.decode(&json));
+        self.next_id = decoder.next_id;
+        match result {
+            Ok(statements) => statements,
+            Err(message) => {
+                self.diagnostics.push(
+                    Diagnostic::error(format!("Invalid AST JSON: {}", message)).in_file(name),
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    fn resolve_import(
+        &mut self,
+        importer: &Path,
+        importer_name: &str,
+        import: &str,
+        span: Span,
+    ) -> Option<usize> {
+        let base = importer.parent().unwrap_or(Path::new(""));
+        let name_base = Path::new(importer_name).parent().unwrap_or(Path::new(""));
+        let name = normalize(&name_base.join(import));
+
+        let path = match fs::canonicalize(base.join(import)) {
+            Ok(path) => path,
+            Err(_) => {
+                self.diagnostics.push(
+                    Diagnostic::error(format!("Cannot find module \"{}\"", import))
+                        .in_file(importer_name)
+                        .at(span),
+                );
+                return None;
+            }
+        };
+
+        if let Some(start) = self.loading.iter().position(|(p, _)| *p == path) {
+            let mut chain: Vec<String> = self.loading[start..]
+                .iter()
+                .map(|(_, name)| name.clone())
+                .collect();
+            chain.push(name);
+            self.diagnostics.push(
+                Diagnostic::error(format!("Import cycle detected: {}", chain.join(" -> ")))
+                    .in_file(importer_name)
+                    .at(span),
+            );
+            return None;
+        }
+
+        self.load_module(path, name)
+    }
+
+    fn check_exports(&mut self, importer_name: &str, names: &[String], module: usize, span: Span) {
+        let dependency = &self.graph.modules[module];
+        let missing: Vec<String> = names
+            .iter()
+            .filter(|name| !dependency.exports.contains(name))
+            .map(|name| format!("`{}` is not exported by {}", name, dependency.name))
+            .collect();
+        for message in missing {
+            self.diagnostics
+                .push(Diagnostic::error(message).in_file(importer_name).at(span));
+        }
+    }
+}
+
+fn read_error(error: io::Error) -> String {
+    match error.kind() {
+        io::ErrorKind::NotFound => "File not found at path".to_string(),
+        io::ErrorKind::PermissionDenied => "Permission denied for file".to_string(),
+        other_error => format!("Could not read file: {:?}", other_error),
+    }
+}
+
+// Drops `.` and folds `..` so module names read like the paths in the source.
+fn normalize(path: &Path) -> String {
+    let mut parts: Vec<Component> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(parts.last(), Some(Component::Normal(_))) => {
+                parts.pop();
+            }
+            _ => parts.push(component),
+        }
+    }
+    parts.iter().collect::<PathBuf>().display().to_string()
+}
@@ -0,0 +1,27 @@
+use crate::parser::statement::Statement;
+
+pub struct Module {
+    pub name: String, // path as written relative to the entry file
+    pub statements: Vec<Statement>,
+    pub imports: Vec<Import>,
+    pub exports: Vec<String>,
+}
+
+// An `import` statement resolved to the module it refers to.
+pub struct Import {
+    pub names: Vec<String>,
+    pub module: usize,
+}
+
+// Modules are stored dependencies first, so the entry module is last and
+// every import refers to a module with a smaller index.
+#[derive(Default)]
+pub struct ModuleGraph {
+    pub modules: Vec<Module>,
+}
+
+impl ModuleGraph {
+    pub fn entry(&self) -> Option<&Module> {
+        self.modules.last()
+    }
+}
@@ -0,0 +1,257 @@
+use crate::checker::types::Type;
+use crate::diagnostic::Diagnostic;
+use crate::interpreter::value::Value;
+use crate::module::module::ModuleGraph;
+use crate::parser::fold::{fold_block, fold_expression, fold_statement, Fold};
+use crate::parser::statement::{
+    ComparisonOperator, Expression, ExpressionKind, LogicalOperator, Operator, Statement,
+    StatementKind,
+};
+
+// Evaluates operators whose operands are literals, with the interpreter's
+// semantics, and drops `if` branches that can never run. Folded literals
+// keep the span and id of the expression they replace. Operations that
+// would fail at runtime are left in place.
+#[derive(Default)]
+pub struct ConstantFolder {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ConstantFolder {
+    pub fn new() -> Self {
+        ConstantFolder::default()
+    }
+
+    pub fn fold(statements: Vec<Statement>) -> (Vec<Statement>, Vec<Diagnostic>) {
+        let mut folder = ConstantFolder::new();
+        let statements = folder.fold_block(statements);
+        (statements, folder.diagnostics)
+    }
+
+    // Folds every module of the graph in place.
+    pub fn fold_graph(graph: &mut ModuleGraph) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for module in &mut graph.modules {
+            let statements = std::mem::take(&mut module.statements);
+            let (statements, module_diagnostics) = ConstantFolder::fold(statements);
+            module.statements = statements;
+            for diagnostic in module_diagnostics {
+                diagnostics.push(diagnostic.in_file(&module.name));
+            }
+        }
+        diagnostics
+    }
+
+    fn fold_binary(&mut self, expr: Expression) -> Expression {
+        let ExpressionKind::BinaryOp(left, op, right) = expr.kind else {
+            return expr;
+        };
+        let left = self.fold_expression(*left);
+        let right = self.fold_expression(*right);
+
+        let value = match (constant(&left), &op, constant(&right)) {
+            (_, Operator::Div, Some(Value::Number(0.0))) => {
+                self.diagnostics
+                    .push(Diagnostic::error("Division by zero".to_string()).at(expr.span));
+                None
+            }
+            (Some(l), op, Some(r)) => apply(&l, op, &r),
+            _ => None,
+        };
+
+        let kind = match value {
+            Some(value) => literal(value),
+            None => ExpressionKind::BinaryOp(Box::new(left), op, Box::new(right)),
+        };
+        Expression { kind, ..expr }
+    }
+
+    fn fold_comparison(&mut self, expr: Expression) -> Expression {
+        let ExpressionKind::ComparisonOp(left, op, right) = expr.kind else {
+            return expr;
+        };
+        let left = self.fold_expression(*left);
+        let right = self.fold_expression(*right);
+
+        let result = match (constant(&left), constant(&right)) {
+            (Some(l), Some(r)) => compare(&l, &op, &r),
+            _ => None,
+        };
+
+        let kind = match result {
+            Some(result) => ExpressionKind::Boolean(result),
+            None => ExpressionKind::ComparisonOp(Box::new(left), op, Box::new(right)),
+        };
+        Expression { kind, ..expr }
+    }
+
+    // A constant left operand that decides the result makes the right one
+    // dead, so it is dropped without being folded.
+    fn fold_logical(&mut self, expr: Expression) -> Expression {
+        let ExpressionKind::LogicalOp(left, op, right) = expr.kind else {
+            return expr;
+        };
+        let left = self.fold_expression(*left);
+
+        let kind = match (constant(&left).map(|l| l.is_truthy()), &op) {
+            (Some(false), LogicalOperator::And) => ExpressionKind::Boolean(false),
+            (Some(true), LogicalOperator::Or) => ExpressionKind::Boolean(true),
+            (left_value, _) => {
+                let right = self.fold_expression(*right);
+                match (left_value, constant(&right)) {
+                    (Some(_), Some(r)) => ExpressionKind::Boolean(r.is_truthy()),
+                    _ => ExpressionKind::LogicalOp(Box::new(left), op, Box::new(right)),
+                }
+            }
+        };
+        Expression { kind, ..expr }
+    }
+
+    // Reports a constant initializer that the declared integer type cannot
+    // hold; the interpreter wraps it.
+    fn check_overflow(&mut self, var_type: &str, value: &Expression) {
+        let ty = Type::from_name(var_type);
+        if let (Some((min, max)), ExpressionKind::Number(num)) = (ty.bounds(), &value.kind) {
+            if *num < min || *num > max {
+                self.diagnostics.push(
+                    Diagnostic::warning(format!(
+                        "Constant {} overflows {} and wraps to {}",
+                        num,
+                        ty,
+                        ty.wrap(*num)
+                    ))
+                    .at(value.span),
+                );
+            }
+        }
+    }
+}
+
+impl Fold for ConstantFolder {
+    fn fold_statement(&mut self, stmt: Statement) -> Statement {
+        match stmt.kind {
+            StatementKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = self.fold_expression(condition);
+                let kind = match constant(&condition).map(|c| c.is_truthy()) {
+                    // the branch keeps its own scope
+                    Some(true) => StatementKind::Block(self.fold_block(then_branch)),
+                    Some(false) => match else_branch {
+                        Some(else_branch) => return self.fold_statement(*else_branch),
+                        // removed by `fold_block`
+                        None => StatementKind::Block(Vec::new()),
+                    },
+                    None => StatementKind::If {
+                        condition,
+                        then_branch: self.fold_block(then_branch),
+                        else_branch: else_branch.map(|stmt| Box::new(self.fold_statement(*stmt))),
+                    },
+                };
+                Statement { kind, ..stmt }
+            }
+            StatementKind::VarDeclaration { .. } => {
+                let stmt = fold_statement(self, stmt);
+                if let StatementKind::VarDeclaration {
+                    var_type: Some(var_type),
+                    value,
+                    ..
+                } = &stmt.kind
+                {
+                    self.check_overflow(var_type, value);
+                }
+                stmt
+            }
+            _ => fold_statement(self, stmt),
+        }
+    }
+
+    // Empty blocks do nothing, which includes those left by dead branches.
+    fn fold_block(&mut self, stmts: Vec<Statement>) -> Vec<Statement> {
+        fold_block(self, stmts)
+            .into_iter()
+            .filter(|stmt| !matches!(&stmt.kind, StatementKind::Block(stmts) if stmts.is_empty()))
+            .collect()
+    }
+
+    fn fold_expression(&mut self, expr: Expression) -> Expression {
+        match expr.kind {
+            ExpressionKind::BinaryOp(..) => self.fold_binary(expr),
+            ExpressionKind::ComparisonOp(..) => self.fold_comparison(expr),
+            ExpressionKind::LogicalOp(..) => self.fold_logical(expr),
+            ExpressionKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = self.fold_expression(*condition);
+                match constant(&condition).map(|c| c.is_truthy()) {
+                    Some(true) => self.fold_expression(*then_branch),
+                    Some(false) => self.fold_expression(*else_branch),
+                    None => {
+                        let kind = ExpressionKind::Conditional {
+                            condition: Box::new(condition),
+                            then_branch: Box::new(self.fold_expression(*then_branch)),
+                            else_branch: Box::new(self.fold_expression(*else_branch)),
+                        };
+                        Expression { kind, ..expr }
+                    }
+                }
+            }
+            _ => fold_expression(self, expr),
+        }
+    }
+}
+
+// The value of a literal expression.
+pub fn constant(expr: &Expression) -> Option<Value> {
+    match &expr.kind {
+        ExpressionKind::Number(num) => Some(Value::Number(*num)),
+        ExpressionKind::StringLiteral(s) => Some(Value::String(s.clone())),
+        ExpressionKind::Boolean(b) => Some(Value::Bool(*b)),
+        _ => None,
+    }
+}
+
+fn literal(value: Value) -> ExpressionKind {
+    match value {
+        Value::Number(num) => ExpressionKind::Number(num),
+        Value::String(s) => ExpressionKind::StringLiteral(s),
+        Value::Bool(b) => ExpressionKind::Boolean(b),
+        Value::Function(_) | Value::Unit => unreachable!("not a literal value"),
+    }
+}
+
+// `None` where the interpreter would fail, or on division by zero.
+pub fn apply(left: &Value, op: &Operator, right: &Value) -> Option<Value> {
+    match (left, op, right) {
+        (_, Operator::Div, Value::Number(r)) if *r == 0.0 => None,
+        (Value::Number(l), op, Value::Number(r)) => Some(Value::Number(match op {
+            Operator::Add => l + r,
+            Operator::Sub => l - r,
+            Operator::Mul => l * r,
+            Operator::Div => l / r,
+        })),
+        (Value::String(l), Operator::Add, Value::String(r)) => Some(Value::String(l.clone() + r)),
+        _ => None,
+    }
+}
+
+// `None` where the interpreter would fail to compare the operands.
+pub fn compare(left: &Value, op: &ComparisonOperator, right: &Value) -> Option<bool> {
+    let ordering = match (left, right) {
+        (Value::Number(l), Value::Number(r)) => l.partial_cmp(r),
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        _ => None,
+    };
+    match op {
+        ComparisonOperator::Equal => Some(left == right),
+        ComparisonOperator::NotEqual => Some(left != right),
+        ComparisonOperator::LessThan => ordering.map(|o| o.is_lt()),
+        ComparisonOperator::GreaterThan => ordering.map(|o| o.is_gt()),
+        ComparisonOperator::LessThanOrEqual => ordering.map(|o| o.is_le()),
+        ComparisonOperator::GreaterThanOrEqual => ordering.map(|o| o.is_ge()),
+    }
+}
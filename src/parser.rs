@@ -1,4 +1,11 @@
+pub mod expr;
+pub mod fmt;
+pub mod incremental;
 pub mod parser;
+pub mod pattern;
 pub mod statement;
-pub mod fmt;
-pub mod expr;
\ No newline at end of file
+
+// Traversal API for analysis and rewriting passes.
+pub mod fold;
+pub mod visit;
+pub mod visit_mut;
@@ -2,12 +2,34 @@ use crate::lexer::token::Token;
 use crate::parser::parser::Parser;
 
 use crate::parser::statement::{
-    ComparisonOperator, Expression, LogicalOperator, Operator,
+    ComparisonOperator, Expression, ExpressionKind, LogicalOperator, Operator, Param,
 };
+use crate::syntax::kind::SyntaxKind;
 
 impl<'a> Parser<'a> {
     pub fn parse_expression(&mut self) -> Option<Expression> {
-        self.parse_logical_expression()
+        self.parse_conditional_expression()
+    }
+
+    pub fn parse_conditional_expression(&mut self) -> Option<Expression> {
+        let condition = self.parse_logical_expression()?;
+
+        // cond ? a : b (right associative)
+        if let Some(Token::Symbol('?')) = self.curr {
+            self.advance();
+            let then_branch = self.parse_expression()?;
+            self.expect(Token::Symbol(':'))?;
+            let else_branch = self.parse_conditional_expression()?;
+            let span = condition.span.to(else_branch.span);
+            let kind = ExpressionKind::Conditional {
+                condition: Box::new(condition),
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
+            };
+            return Some(self.expression(kind, span));
+        }
+
+        Some(condition)
     }
 
     pub fn parse_logical_expression(&mut self) -> Option<Expression> {
@@ -16,30 +38,38 @@ impl<'a> Parser<'a> {
         while let Some(Token::Symbol(op)) = &self.curr {
             match op {
                 '&' => {
+                    let op_span = self.span;
                     self.advance();
                     if let Some(Token::Symbol('&')) = self.curr {
                         self.advance();
                         let right = self.parse_comparison_expression()?;
-                        left = Expression::LogicalOp(
+                        let span = left.span.to(right.span);
+                        let kind = ExpressionKind::LogicalOp(
                             Box::new(left),
                             LogicalOperator::And,
                             Box::new(right),
                         );
+                        left = self.expression(kind, span);
                     } else {
+                        self.error_at(op_span, "Expected `&&`, found `&`".to_string());
                         return None;
                     }
                 }
                 '|' => {
+                    let op_span = self.span;
                     self.advance();
                     if let Some(Token::Symbol('|')) = self.curr {
                         self.advance();
                         let right = self.parse_comparison_expression()?;
-                        left = Expression::LogicalOp(
+                        let span = left.span.to(right.span);
+                        let kind = ExpressionKind::LogicalOp(
                             Box::new(left),
                             LogicalOperator::Or,
                             Box::new(right),
                         );
+                        left = self.expression(kind, span);
                     } else {
+                        self.error_at(op_span, "Expected `||`, found `|`".to_string());
                         return None;
                     }
                 }
@@ -54,6 +84,7 @@ impl<'a> Parser<'a> {
         let mut left = self.parse_additive_expression()?;
 
         while let Some(Token::Symbol(op)) = &self.curr {
+            let op_span = self.span;
             let comparison_operator = match op {
                 '=' => {
                     self.advance();
@@ -61,6 +92,8 @@ impl<'a> Parser<'a> {
                         self.advance();
                         ComparisonOperator::Equal
                     } else {
+                        // there is no assignment
+                        self.error_at(op_span, "Expected `==`, found `=`".to_string());
                         return None;
                     }
                 }
@@ -70,6 +103,7 @@ impl<'a> Parser<'a> {
                         self.advance();
                         ComparisonOperator::NotEqual
                     } else {
+                        self.error_at(op_span, "Expected `!=`, found `!`".to_string());
                         return None;
                     }
                 }
@@ -95,7 +129,10 @@ impl<'a> Parser<'a> {
             };
 
             let right = self.parse_additive_expression()?;
-            left = Expression::ComparisonOp(Box::new(left), comparison_operator, Box::new(right));
+            let span = left.span.to(right.span);
+            let kind =
+                ExpressionKind::ComparisonOp(Box::new(left), comparison_operator, Box::new(right));
+            left = self.expression(kind, span);
         }
 
         Some(left)
@@ -109,12 +146,18 @@ impl<'a> Parser<'a> {
                 '+' => {
                     self.advance();
                     let right = self.parse_term()?;
-                    left = Expression::BinaryOp(Box::new(left), Operator::Add, Box::new(right));
+                    let span = left.span.to(right.span);
+                    let kind =
+                        ExpressionKind::BinaryOp(Box::new(left), Operator::Add, Box::new(right));
+                    left = self.expression(kind, span);
                 }
                 '-' => {
                     self.advance();
                     let right = self.parse_term()?;
-                    left = Expression::BinaryOp(Box::new(left), Operator::Sub, Box::new(right));
+                    let span = left.span.to(right.span);
+                    let kind =
+                        ExpressionKind::BinaryOp(Box::new(left), Operator::Sub, Box::new(right));
+                    left = self.expression(kind, span);
                 }
                 _ => break,
             }
@@ -131,12 +174,18 @@ impl<'a> Parser<'a> {
                 '*' => {
                     self.advance();
                     let right = self.parse_factor()?;
-                    left = Expression::BinaryOp(Box::new(left), Operator::Mul, Box::new(right));
+                    let span = left.span.to(right.span);
+                    let kind =
+                        ExpressionKind::BinaryOp(Box::new(left), Operator::Mul, Box::new(right));
+                    left = self.expression(kind, span);
                 }
                 '/' => {
                     self.advance();
                     let right = self.parse_factor()?;
-                    left = Expression::BinaryOp(Box::new(left), Operator::Div, Box::new(right));
+                    let span = left.span.to(right.span);
+                    let kind =
+                        ExpressionKind::BinaryOp(Box::new(left), Operator::Div, Box::new(right));
+                    left = self.expression(kind, span);
                 }
                 _ => break,
             }
@@ -146,52 +195,197 @@ impl<'a> Parser<'a> {
     }
 
     pub fn parse_factor(&mut self) -> Option<Expression> {
-        match &self.curr {
-            Some(Token::Ident(name)) => {
-                let name = name.clone();
-                self.advance();
-                if let Some(Token::Symbol('(')) = &self.curr {
-                    self.advance();
-                    let mut args = Vec::new();
-
-                    while let Some(expr) = self.parse_expression() {
-                        args.push(expr);
-                        if let Some(Token::Symbol(',')) = &self.curr {
-                            self.advance();
-                        } else {
-                            break;
-                        }
-                    }
+        let mut expr = self.parse_primary()?;
 
-                    if let Some(Token::Symbol(')')) = &self.curr {
-                        self.advance();
-                        return Some(Expression::FunctionCall { name, args });
-                    } else {
-                        None
-                    }
-                } else {
-                    Some(Expression::Identifier(name))
-                }
+        // f(1)(2)
+        while let Some(Token::Symbol('(')) = &self.curr {
+            self.advance();
+            let args = self.parse_arguments()?;
+            let span = expr.span.to(self.prev_span);
+            let kind = match expr.kind {
+                ExpressionKind::Identifier(name) => ExpressionKind::FunctionCall { name, args },
+                _ => ExpressionKind::Call {
+                    callee: Box::new(expr),
+                    args,
+                },
+            };
+            expr = self.expression(kind, span);
+        }
+
+        Some(expr)
+    }
+
+    fn parse_primary(&mut self) -> Option<Expression> {
+        let span = self.span;
+        let kind = match &self.curr {
+            Some(Token::Ident(name)) => ExpressionKind::Identifier(name.clone()),
+            Some(Token::Number(num)) => ExpressionKind::Number(*num),
+            Some(Token::StringLiteral(s)) => ExpressionKind::StringLiteral(s.clone()),
+            Some(Token::Keyword(k)) if k == "true" || k == "false" => {
+                ExpressionKind::Boolean(k == "true")
             }
-            Some(Token::Number(num)) => {
-                let num = *num;
+            _ => return self.parse_compound_primary(),
+        };
+        self.advance();
+        Some(self.expression(kind, span))
+    }
+
+    fn parse_compound_primary(&mut self) -> Option<Expression> {
+        match &self.curr {
+            Some(Token::Keyword(k)) if k == "if" => self.parse_if_expression(),
+            Some(Token::Symbol('(')) if self.is_lambda_start() => self.parse_lambda(),
+            Some(Token::Symbol('(')) => {
+                let start = self.span;
                 self.advance();
-                Some(Expression::Number(num))
+                let mut expr = self.parse_expression()?;
+                self.expect(Token::Symbol(')'))?;
+                // the span covers the parentheses
+                expr.span = start.to(self.prev_span);
+                self.wrap(start, SyntaxKind::ParenExpr);
+                Some(expr)
             }
-            Some(Token::StringLiteral(s)) => {
-                let s = s.clone();
+            Some(token) => {
+                self.error(format!("Expected expression, found {:?}", token));
+                None
+            }
+            None => {
+                self.error("Unexpected end of input.".to_string());
+                None
+            }
+        }
+    }
+
+    fn parse_if_expression(&mut self) -> Option<Expression> {
+        let start = self.span;
+        self.advance();
+
+        /*
+        if ( condition ) { expr } else { expr }
+        */
+
+        self.expect(Token::Symbol('('))?;
+        let condition = self.parse_expression()?;
+        self.expect(Token::Symbol(')'))?;
+
+        let then_branch = self.parse_branch_expression()?;
+
+        if !matches!(&self.curr, Some(Token::Keyword(k)) if k == "else") {
+            self.error("Expected else branch in if expression".to_string());
+            return None;
+        }
+        self.advance();
+
+        let else_branch = if matches!(&self.curr, Some(Token::Keyword(k)) if k == "if") {
+            self.parse_if_expression()?
+        } else {
+            self.parse_branch_expression()?
+        };
+
+        let kind = ExpressionKind::Conditional {
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+        };
+        Some(self.expression(kind, start.to(self.prev_span)))
+    }
+
+    fn parse_branch_expression(&mut self) -> Option<Expression> {
+        self.expect(Token::Symbol('{'))?;
+        let expr = self.parse_expression()?;
+        self.expect(Token::Symbol('}'))?;
+        Some(expr)
+    }
+
+    // Called after the opening `(`; consumes the closing `)`.
+    fn parse_arguments(&mut self) -> Option<Vec<Expression>> {
+        let mut args = Vec::new();
+
+        while !matches!(self.curr, Some(Token::Symbol(')'))) {
+            args.push(self.parse_expression()?);
+            if let Some(Token::Symbol(',')) = &self.curr {
                 self.advance();
-                Some(Expression::StringLiteral(s))
+            } else {
+                break;
             }
-            Some(Token::Symbol('(')) => {
+        }
+
+        self.expect(Token::Symbol(')'))?;
+        Some(args)
+    }
+
+    // Scans ahead from `(` to its matching `)` and checks for a following `=>`.
+    fn is_lambda_start(&self) -> bool {
+        let mut lexer = self.lexer.clone();
+        let mut depth = 1;
+        while let Some((token, _)) = lexer.next_token() {
+            match token {
+                Token::Symbol('(') => depth += 1,
+                Token::Symbol(')') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return matches!(lexer.next_token(), Some((Token::Symbol('='), _)))
+                            && matches!(lexer.next_token(), Some((Token::Symbol('>'), _)));
+                    }
+                }
+                _ => {}
+            }
+        }
+        false
+    }
+
+    fn parse_lambda(&mut self) -> Option<Expression> {
+        /*
+        ( a: i32, b ) => expr
+        */
+
+        let start = self.span;
+        self.expect(Token::Symbol('('))?;
+
+        let mut params = Vec::new();
+        while let Some(Token::Ident(name)) = &self.curr {
+            let param_start = self.span;
+            let name = name.clone();
+            self.advance();
+
+            let param_type = if let Some(Token::Symbol(':')) = self.curr {
                 self.advance();
-                let expr = self.parse_expression();
-                if let Some(Token::Symbol(')')) = &self.curr {
+                if let Some(Token::Type(type_name)) = &self.curr {
+                    let type_name = type_name.clone();
                     self.advance();
+                    Some(type_name)
+                } else {
+                    self.error(format!("Expected type for parameter `{}`", name));
+                    return None;
                 }
-                expr
+            } else {
+                None
+            };
+
+            let span = param_start.to(self.prev_span);
+            self.wrap(param_start, SyntaxKind::Param);
+            params.push(Param {
+                name,
+                param_type,
+                span,
+                id: self.node_id(),
+            });
+
+            if let Some(Token::Symbol(',')) = self.curr {
+                self.advance();
+            } else {
+                break;
             }
-            _ => None,
         }
+
+        self.expect(Token::Symbol(')'))?;
+        self.expect(Token::Symbol('='))?;
+        self.expect(Token::Symbol('>'))?;
+
+        let body = self.parse_expression()?;
+        let kind = ExpressionKind::Lambda {
+            params,
+            body: Box::new(body),
+        };
+        Some(self.expression(kind, start.to(self.prev_span)))
     }
 }
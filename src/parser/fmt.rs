@@ -1,16 +1,18 @@
 use std::fmt;
 
 use crate::parser::statement::{
-    ComparisonOperator, Expression, LogicalOperator, Operator, Statement,
+    ComparisonOperator, Expression, ExpressionKind, LogicalOperator, Operator, Param, Pattern,
+    PatternKind, Statement, StatementKind,
 };
 
 impl fmt::Display for Statement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Statement::VarDeclaration {
+        match &self.kind {
+            StatementKind::VarDeclaration {
                 name,
                 var_type,
                 value,
+                ..
             } => {
                 let var_type_str = match var_type {
                     Some(t) => t.clone(),
@@ -18,7 +20,7 @@ impl fmt::Display for Statement {
                 };
                 write!(f, "Var: {}: {} = {}", name, var_type_str, value)
             }
-            Statement::FunctionCall { name, args } => {
+            StatementKind::FunctionCall { name, args } => {
                 let args_str = args
                     .iter()
                     .map(|arg| format!("{}", arg))
@@ -26,24 +28,24 @@ impl fmt::Display for Statement {
                     .join(", ");
                 write!(f, "FunctionCall: {}({})", name, args_str)
             }
-            Statement::If {
+            StatementKind::If {
                 condition,
                 then_branch,
                 else_branch,
             } => {
-                write!(f, "If: ({}) {{\n", condition)?;
+                writeln!(f, "If: ({}) {{", condition)?;
                 for stmt in then_branch {
                     writeln!(f, "    {}", stmt)?;
                 }
                 write!(f, "}}")?;
 
                 if let Some(else_branch) = else_branch {
-                    match **else_branch {
-                        Statement::If { .. } => {
+                    match else_branch.kind {
+                        StatementKind::If { .. } => {
                             write!(f, " else {}", else_branch)?;
                         }
-                        Statement::Block(ref stmts) => {
-                            write!(f, " else {{\n")?;
+                        StatementKind::Block(ref stmts) => {
+                            writeln!(f, " else {{")?;
                             for stmt in stmts {
                                 writeln!(f, "    {}", stmt)?;
                             }
@@ -55,55 +57,60 @@ impl fmt::Display for Statement {
 
                 Ok(())
             }
-            Statement::Block(stmts) => {
-                write!(f, "{{\n")?;
+            StatementKind::Match { scrutinee, arms } => {
+                writeln!(f, "Match: ({}) {{", scrutinee)?;
+                for arm in arms {
+                    let patterns_str = arm
+                        .patterns
+                        .iter()
+                        .map(|pattern| format!("{}", pattern))
+                        .collect::<Vec<String>>()
+                        .join(" | ");
+                    writeln!(f, "    {} => {{", patterns_str)?;
+                    for stmt in &arm.body {
+                        writeln!(f, "        {}", stmt)?;
+                    }
+                    writeln!(f, "    }}")?;
+                }
+                write!(f, "}}")
+            }
+            StatementKind::Block(stmts) => {
+                writeln!(f, "{{")?;
                 for stmt in stmts {
                     writeln!(f, "    {}", stmt)?;
                 }
                 write!(f, "}}")
             }
+            StatementKind::Import { names, path } => {
+                write!(f, "Import: {{{}}} from {}", names.join(", "), path)
+            }
+            StatementKind::Export(stmt) => write!(f, "Export: {}", stmt),
+            StatementKind::Expression(expr) => write!(f, "Expression: {}", expr),
         }
     }
 }
 
 impl fmt::Display for Expression {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Expression::Identifier(name) => write!(f, "Ident: {}", name),
-            Expression::Number(num) => write!(f, "{}", num),
-            Expression::StringLiteral(s) => write!(f, "StringLiteral: {}", s),
-            
-            Expression::BinaryOp(left, op, right) => {
-                let op_str = match op {
-                    Operator::Add => "+",
-                    Operator::Sub => "-",
-                    Operator::Mul => "*",
-                    Operator::Div => "/",
-                };
-                write!(f, "({} {} {})", left, op_str, right)
+        match &self.kind {
+            ExpressionKind::Identifier(name) => write!(f, "Ident: {}", name),
+            ExpressionKind::Number(num) => write!(f, "{}", num),
+            ExpressionKind::StringLiteral(s) => write!(f, "StringLiteral: {}", s),
+            ExpressionKind::Boolean(b) => write!(f, "{}", b),
+
+            ExpressionKind::BinaryOp(left, op, right) => {
+                write!(f, "({} {} {})", left, op, right)
             }
 
-            Expression::ComparisonOp(left, op, right) => {
-                let op_str = match op {
-                    ComparisonOperator::Equal => "==",
-                    ComparisonOperator::NotEqual => "!=",
-                    ComparisonOperator::LessThan => "<",
-                    ComparisonOperator::GreaterThan => ">",
-                    ComparisonOperator::LessThanOrEqual => "<=",
-                    ComparisonOperator::GreaterThanOrEqual => ">=",
-                };
-                write!(f, "({} {} {})", left, op_str, right)
+            ExpressionKind::ComparisonOp(left, op, right) => {
+                write!(f, "({} {} {})", left, op, right)
             }
 
-            Expression::LogicalOp(left, op, right) => {
-                let op_str = match op {
-                    LogicalOperator::And => "&&",
-                    LogicalOperator::Or => "||",
-                };
-                write!(f, "({} {} {})", left, op_str, right)
+            ExpressionKind::LogicalOp(left, op, right) => {
+                write!(f, "({} {} {})", left, op, right)
             }
 
-            Expression::FunctionCall { name, args } => {
+            ExpressionKind::FunctionCall { name, args } => {
                 let args_str = args
                     .iter()
                     .map(|arg| format!("{}", arg))
@@ -111,11 +118,34 @@ impl fmt::Display for Expression {
                     .join(", ");
                 write!(f, "FunctionCall: {}({})", name, args_str)
             }
+
+            ExpressionKind::Call { callee, args } => {
+                let args_str = args
+                    .iter()
+                    .map(|arg| format!("{}", arg))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "Call: {}({})", callee, args_str)
+            }
+
+            ExpressionKind::Lambda { params, body } => {
+                let params_str = params
+                    .iter()
+                    .map(|param| format!("{}", param))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "Lambda: ({}) => {}", params_str, body)
+            }
+
+            ExpressionKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => write!(f, "({} ? {} : {})", condition, then_branch, else_branch),
         }
     }
 }
 
-
 impl fmt::Display for Operator {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let op_str = match self {
@@ -127,3 +157,55 @@ impl fmt::Display for Operator {
         write!(f, "{}", op_str)
     }
 }
+
+impl fmt::Display for ComparisonOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op_str = match self {
+            ComparisonOperator::Equal => "==",
+            ComparisonOperator::NotEqual => "!=",
+            ComparisonOperator::LessThan => "<",
+            ComparisonOperator::GreaterThan => ">",
+            ComparisonOperator::LessThanOrEqual => "<=",
+            ComparisonOperator::GreaterThanOrEqual => ">=",
+        };
+        write!(f, "{}", op_str)
+    }
+}
+
+impl fmt::Display for LogicalOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op_str = match self {
+            LogicalOperator::And => "&&",
+            LogicalOperator::Or => "||",
+        };
+        write!(f, "{}", op_str)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            PatternKind::Number(num) => write!(f, "{}", num),
+            PatternKind::StringLiteral(s) => write!(f, "StringLiteral: {}", s),
+            PatternKind::Boolean(b) => write!(f, "{}", b),
+            PatternKind::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let op_str = if *inclusive { "..=" } else { ".." };
+                write!(f, "{}{}{}", start, op_str, end)
+            }
+            PatternKind::Wildcard => write!(f, "_"),
+        }
+    }
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.param_type {
+            Some(t) => write!(f, "{}: {}", self.name, t),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use crate::parser::statement::{
+    Expression, ExpressionKind, MatchArm, Param, Pattern, Statement, StatementKind,
+};
+
+// Rewriting traversal that consumes the AST and builds a new one. Every
+// method defaults to rebuilding the node from its folded children; a pass
+// overrides the nodes it rewrites. `fold_block` may return more or fewer
+// statements than it was given.
+pub trait Fold: Sized {
+    fn fold_statement(&mut self, stmt: Statement) -> Statement {
+        fold_statement(self, stmt)
+    }
+
+    // Statements of an `if` branch, `else` block, match arm or bare block.
+    fn fold_block(&mut self, stmts: Vec<Statement>) -> Vec<Statement> {
+        fold_block(self, stmts)
+    }
+
+    fn fold_expression(&mut self, expr: Expression) -> Expression {
+        fold_expression(self, expr)
+    }
+
+    fn fold_match_arm(&mut self, arm: MatchArm) -> MatchArm {
+        fold_match_arm(self, arm)
+    }
+
+    fn fold_pattern(&mut self, pattern: Pattern) -> Pattern {
+        pattern
+    }
+
+    fn fold_param(&mut self, param: Param) -> Param {
+        param
+    }
+}
+
+pub fn fold_statement<F: Fold>(folder: &mut F, stmt: Statement) -> Statement {
+    let kind = match stmt.kind {
+        StatementKind::VarDeclaration {
+            constant,
+            name,
+            var_type,
+            value,
+        } => StatementKind::VarDeclaration {
+            constant,
+            name,
+            var_type,
+            value: folder.fold_expression(value),
+        },
+        StatementKind::FunctionCall { name, args } => StatementKind::FunctionCall {
+            name,
+            args: fold_expressions(folder, args),
+        },
+        StatementKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => StatementKind::If {
+            condition: folder.fold_expression(condition),
+            then_branch: folder.fold_block(then_branch),
+            else_branch: else_branch.map(|stmt| Box::new(folder.fold_statement(*stmt))),
+        },
+        StatementKind::Match { scrutinee, arms } => StatementKind::Match {
+            scrutinee: folder.fold_expression(scrutinee),
+            arms: arms
+                .into_iter()
+                .map(|arm| folder.fold_match_arm(arm))
+                .collect(),
+        },
+        StatementKind::Block(stmts) => StatementKind::Block(folder.fold_block(stmts)),
+        StatementKind::Import { names, path } => StatementKind::Import { names, path },
+        StatementKind::Export(stmt) => {
+            StatementKind::Export(Box::new(folder.fold_statement(*stmt)))
+        }
+        StatementKind::Expression(expr) => StatementKind::Expression(folder.fold_expression(expr)),
+    };
+    Statement { kind, ..stmt }
+}
+
+pub fn fold_block<F: Fold>(folder: &mut F, stmts: Vec<Statement>) -> Vec<Statement> {
+    stmts
+        .into_iter()
+        .map(|stmt| folder.fold_statement(stmt))
+        .collect()
+}
+
+pub fn fold_expression<F: Fold>(folder: &mut F, expr: Expression) -> Expression {
+    let kind = match expr.kind {
+        kind @ (ExpressionKind::Identifier(_)
+        | ExpressionKind::Number(_)
+        | ExpressionKind::StringLiteral(_)
+        | ExpressionKind::Boolean(_)) => kind,
+        ExpressionKind::BinaryOp(left, op, right) => ExpressionKind::BinaryOp(
+            Box::new(folder.fold_expression(*left)),
+            op,
+            Box::new(folder.fold_expression(*right)),
+        ),
+        ExpressionKind::LogicalOp(left, op, right) => ExpressionKind::LogicalOp(
+            Box::new(folder.fold_expression(*left)),
+            op,
+            Box::new(folder.fold_expression(*right)),
+        ),
+        ExpressionKind::ComparisonOp(left, op, right) => ExpressionKind::ComparisonOp(
+            Box::new(folder.fold_expression(*left)),
+            op,
+            Box::new(folder.fold_expression(*right)),
+        ),
+        ExpressionKind::FunctionCall { name, args } => ExpressionKind::FunctionCall {
+            name,
+            args: fold_expressions(folder, args),
+        },
+        ExpressionKind::Call { callee, args } => ExpressionKind::Call {
+            callee: Box::new(folder.fold_expression(*callee)),
+            args: fold_expressions(folder, args),
+        },
+        ExpressionKind::Lambda { params, body } => ExpressionKind::Lambda {
+            params: params
+                .into_iter()
+                .map(|param| folder.fold_param(param))
+                .collect(),
+            body: Box::new(folder.fold_expression(*body)),
+        },
+        ExpressionKind::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => ExpressionKind::Conditional {
+            condition: Box::new(folder.fold_expression(*condition)),
+            then_branch: Box::new(folder.fold_expression(*then_branch)),
+            else_branch: Box::new(folder.fold_expression(*else_branch)),
+        },
+    };
+    Expression { kind, ..expr }
+}
+
+pub fn fold_match_arm<F: Fold>(folder: &mut F, arm: MatchArm) -> MatchArm {
+    MatchArm {
+        span: arm.span,
+        id: arm.id,
+        patterns: arm
+            .patterns
+            .into_iter()
+            .map(|pattern| folder.fold_pattern(pattern))
+            .collect(),
+        body: folder.fold_block(arm.body),
+    }
+}
+
+fn fold_expressions<F: Fold>(folder: &mut F, exprs: Vec<Expression>) -> Vec<Expression> {
+    exprs
+        .into_iter()
+        .map(|expr| folder.fold_expression(expr))
+        .collect()
+}
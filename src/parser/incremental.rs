@@ -0,0 +1,397 @@
+use std::ops::Range;
+
+use crate::diagnostic::Diagnostic;
+use crate::lexer::token::{Lexer, Span, Token};
+use crate::parser::parser::Parser;
+use crate::parser::statement::{Expression, MatchArm, NodeId, Param, Pattern, Statement};
+use crate::parser::visit_mut::{
+    walk_expression_mut, walk_match_arm_mut, walk_statement_mut, VisitorMut,
+};
+
+// Replaces the bytes `start..end` of the text with `text`.
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+/*
+Keeps a file's tokens and top-level statements across edits, so that an
+edit only relexes the tokens around it and only reparses the statements
+that read them. The result is the same as parsing the edited text from
+scratch, node ids included.
+
+    let x = 1;   print(x);   let y = x + 2;
+    ----------   ---------   --------------
+      item 0      item 1         item 2
+
+The parser keeps no state between top-level statements, so each `item` is
+parsed from its first token alone. An item depends on every token it read:
+the ones it consumed, the one after them and any the lambda lookahead
+scanned. Items that read nothing the edit touched are kept; the ones after
+the edit are moved along with the text.
+*/
+pub struct IncrementalParser {
+    text: String,
+    tokens: Vec<(Token, Span)>,
+    items: Vec<Item>,
+    pub relexed: usize,  // tokens lexed by the last edit
+    pub reparsed: usize, // items parsed by the last edit
+}
+
+struct Item {
+    tokens: Range<usize>, // tokens consumed
+    furthest: usize,      // last token read; `tokens.len()` if it reached the end
+    ids: Range<u32>,      // node ids handed out while parsing it
+    statement: Option<Statement>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl IncrementalParser {
+    pub fn new(text: String) -> Self {
+        let mut lexer = Lexer::new(&text);
+        let tokens = std::iter::from_fn(|| lexer.next_token()).collect();
+        let mut parser = IncrementalParser {
+            text,
+            tokens,
+            items: Vec::new(),
+            relexed: 0,
+            reparsed: 0,
+        };
+        (parser.items, _) = parser.parse_items(0, 0, |_| false);
+        parser.relexed = parser.tokens.len();
+        parser.reparsed = parser.items.len();
+        parser
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn statements(&self) -> Vec<Statement> {
+        self.items
+            .iter()
+            .filter_map(|item| item.statement.clone())
+            .collect()
+    }
+
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.items
+            .iter()
+            .flat_map(|item| item.diagnostics.iter().cloned())
+            .collect()
+    }
+
+    pub fn items(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn edit(&mut self, edit: &Edit) -> Result<(), String> {
+        let Edit { start, end, .. } = *edit;
+        if start > end
+            || end > self.text.len()
+            || !self.text.is_char_boundary(start)
+            || !self.text.is_char_boundary(end)
+        {
+            return Err(format!(
+                "Edit range {}..{} is not within the text ({} bytes)",
+                start,
+                end,
+                self.text.len()
+            ));
+        }
+        let mut text = String::with_capacity(self.text.len() + edit.text.len() - (end - start));
+        text.push_str(&self.text[..start]);
+        text.push_str(&edit.text);
+        text.push_str(&self.text[end..]);
+
+        // tokens from `first` on may change: a token touching the edit can
+        // merge with what is inserted. Lexing restarts after the token
+        // before, so a comment it was in is lexed again too.
+        let first = self.tokens.partition_point(|(_, span)| span.end < start);
+        let resume = match first {
+            0 => Position::START,
+            _ => Position::at(self.tokens[first - 1].1)
+                .advance(&self.text, self.tokens[first - 1].1.end),
+        };
+        let edit_start = resume.advance(&self.text, start);
+        let shift = Shift {
+            bytes: edit.text.len() as isize - (end - start) as isize,
+            from: edit_start.advance(&self.text, end),
+            to: edit_start.advance(&text, start + edit.text.len()),
+        };
+
+        // relex until a token starts where an old one after the edit did:
+        // from there on the old tokens are still right
+        let mut window = Vec::new();
+        let mut rest = self.tokens.partition_point(|(_, span)| span.start < end);
+        let mut lexer = Lexer::at(&text, resume.offset, resume.line, resume.column);
+        let mut synced = false;
+        while let Some((token, span)) = lexer.next_token() {
+            if span.start >= start + edit.text.len() {
+                let old_start = |index: usize| shift.offset(self.tokens[index].1.start);
+                while rest < self.tokens.len() && old_start(rest) < span.start {
+                    rest += 1;
+                }
+                if rest < self.tokens.len() && old_start(rest) == span.start {
+                    synced = true;
+                    break;
+                }
+            }
+            window.push((token, span));
+        }
+        if !synced {
+            rest = self.tokens.len();
+        }
+        // relexed tokens that came out as they were change nothing
+        let same = window
+            .iter()
+            .zip(&self.tokens[first..])
+            .take_while(|(new, old)| new == old)
+            .count();
+        let changed = first + same;
+        let moved = first + window.len();
+        let token_shift = moved as isize - rest as isize;
+        self.relexed = window.len();
+        let mut tokens = Vec::with_capacity(self.tokens.len());
+        tokens.extend(self.tokens.drain(..first));
+        tokens.append(&mut window);
+        tokens.extend(
+            self.tokens
+                .drain(rest - first..)
+                .map(|(token, span)| (token, shift.span(span))),
+        );
+        self.tokens = tokens;
+        self.text = text;
+
+        // items that read only tokens before the change are kept as they are
+        let kept = self
+            .items
+            .iter()
+            .take_while(|item| item.furthest < changed)
+            .count();
+        let (from, first_id) = match self.items.get(kept) {
+            Some(item) => (item.tokens.start, item.ids.start),
+            None => (first, self.items.last().map_or(0, |item| item.ids.end)),
+        };
+        // the first old item wholly after the window
+        let after = kept + self.items[kept..].partition_point(|item| item.tokens.start < rest);
+        let starts: Vec<usize> = self.items[after..]
+            .iter()
+            .map(|item| (item.tokens.start as isize + token_shift) as usize)
+            .collect();
+        let (mut items, stop) =
+            self.parse_items(from, first_id, |index| starts.binary_search(&index).is_ok());
+        self.reparsed = items.len();
+
+        // the parse stopped at the start of an old item: it and the ones
+        // after it move along
+        let next_id = items.last().map_or(first_id, |item| item.ids.end);
+        let resumed = match starts.binary_search(&stop) {
+            Ok(index) => after + index,
+            Err(_) => self.items.len(),
+        };
+        let mut moved_items: Vec<Item> = self.items.drain(resumed..).collect();
+        if let Some(first_moved) = moved_items.first() {
+            let relocate = Relocate {
+                shift: &shift,
+                ids: next_id as i64 - first_moved.ids.start as i64,
+            };
+            for item in &mut moved_items {
+                relocate.item(item, token_shift);
+            }
+        }
+        self.items.truncate(kept);
+        self.items.append(&mut items);
+        self.items.append(&mut moved_items);
+        Ok(())
+    }
+
+    // Parses items from token `from` until the end, or until `stop` holds
+    // for the index of the next token. Returns the items and where they
+    // stopped.
+    fn parse_items(
+        &self,
+        from: usize,
+        first_id: u32,
+        stop: impl Fn(usize) -> bool,
+    ) -> (Vec<Item>, usize) {
+        let end = self.end();
+        let lexer = Lexer::replay(&self.tokens, from, end);
+        let furthest = lexer
+            .replay
+            .as_ref()
+            .map(|replay| replay.furthest.clone())
+            .expect("a replaying lexer");
+        let mut parser = Parser::new(lexer);
+        parser.next_id = first_id;
+        if from > 0 {
+            parser.prev_span = self.tokens[from - 1].1;
+        }
+        // the index of the parser's current token
+        let position = |parser: &Parser| match (&parser.curr, &parser.lexer.replay) {
+            (Some(_), Some(replay)) => replay.next - 1,
+            _ => self.tokens.len(),
+        };
+
+        let mut items = Vec::new();
+        loop {
+            let start = position(&parser);
+            if parser.curr.is_none() || stop(start) {
+                return (items, start);
+            }
+            furthest.set(start);
+            let ids = parser.next_id;
+            let diagnostics = parser.diagnostics.len();
+            let statement = parser.parse_top_level();
+            items.push(Item {
+                tokens: start..position(&parser),
+                furthest: furthest.get(),
+                ids: ids..parser.next_id,
+                statement,
+                diagnostics: parser.diagnostics.drain(diagnostics..).collect(),
+            });
+        }
+    }
+
+    // Where the end of the text is, for diagnostics at the end of input.
+    fn end(&self) -> Span {
+        let position = match self.tokens.last() {
+            Some((_, span)) => Position::at(*span),
+            None => Position::START,
+        }
+        .advance(&self.text, self.text.len());
+        Span {
+            start: position.offset,
+            end: position.offset,
+            line: position.line,
+            column: position.column,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    offset: usize,
+    line: usize,
+    column: usize, // in characters, as the lexer counts them
+}
+
+impl Position {
+    const START: Position = Position {
+        offset: 0,
+        line: 1,
+        column: 1,
+    };
+
+    fn at(span: Span) -> Position {
+        Position {
+            offset: span.start,
+            line: span.line,
+            column: span.column,
+        }
+    }
+
+    // The position of `offset`, counting from `self`.
+    fn advance(self, text: &str, offset: usize) -> Position {
+        let mut position = self;
+        for c in text[self.offset..offset].chars() {
+            if c == '\n' {
+                position.line += 1;
+                position.column = 1;
+            } else {
+                position.column += 1;
+            }
+        }
+        position.offset = offset;
+        position
+    }
+}
+
+// Moves what comes after an edit to where it is in the edited text. Only
+// positions at or after the end of the edit are shifted.
+struct Shift {
+    bytes: isize,
+    from: Position, // the end of the edit, before
+    to: Position,   // and after
+}
+
+impl Shift {
+    fn offset(&self, offset: usize) -> usize {
+        (offset as isize + self.bytes) as usize
+    }
+
+    fn span(&self, span: Span) -> Span {
+        // only what shares a line with the end of the edit changes column
+        let column = match span.line == self.from.line {
+            true => span.column - self.from.column + self.to.column,
+            false => span.column,
+        };
+        Span {
+            start: self.offset(span.start),
+            end: self.offset(span.end),
+            line: span.line - self.from.line + self.to.line,
+            column,
+        }
+    }
+}
+
+// Moves a kept item: its spans by the edit and its node ids by the change
+// in how many the items before it used.
+struct Relocate<'a> {
+    shift: &'a Shift,
+    ids: i64,
+}
+
+impl Relocate<'_> {
+    fn item(&self, item: &mut Item, tokens: isize) {
+        let index = |index: usize| (index as isize + tokens) as usize;
+        item.tokens = index(item.tokens.start)..index(item.tokens.end);
+        item.furthest = index(item.furthest);
+        item.ids = self.id(NodeId(item.ids.start)).0..self.id(NodeId(item.ids.end)).0;
+        for diagnostic in &mut item.diagnostics {
+            diagnostic.span = diagnostic.span.map(|span| self.shift.span(span));
+        }
+        if let Some(statement) = &mut item.statement {
+            let mut visitor = RelocateVisitor(self);
+            visitor.visit_statement(statement);
+        }
+    }
+
+    fn id(&self, id: NodeId) -> NodeId {
+        NodeId((id.0 as i64 + self.ids) as u32)
+    }
+}
+
+struct RelocateVisitor<'a, 'b>(&'a Relocate<'b>);
+
+impl VisitorMut for RelocateVisitor<'_, '_> {
+    fn visit_statement(&mut self, stmt: &mut Statement) {
+        stmt.span = self.0.shift.span(stmt.span);
+        stmt.id = self.0.id(stmt.id);
+        walk_statement_mut(self, stmt);
+    }
+
+    fn visit_expression(&mut self, expr: &mut Expression) {
+        expr.span = self.0.shift.span(expr.span);
+        expr.id = self.0.id(expr.id);
+        walk_expression_mut(self, expr);
+    }
+
+    fn visit_match_arm(&mut self, arm: &mut MatchArm) {
+        arm.span = self.0.shift.span(arm.span);
+        arm.id = self.0.id(arm.id);
+        walk_match_arm_mut(self, arm);
+    }
+
+    fn visit_pattern(&mut self, pattern: &mut Pattern) {
+        pattern.span = self.0.shift.span(pattern.span);
+        pattern.id = self.0.id(pattern.id);
+    }
+
+    fn visit_param(&mut self, param: &mut Param) {
+        param.span = self.0.shift.span(param.span);
+        param.id = self.0.id(param.id);
+    }
+}
@@ -1,22 +1,100 @@
-use crate::lexer::token::{Lexer, Token};
+use crate::diagnostic::Diagnostic;
+use crate::lexer::token::{Lexer, Span, Token};
 // use crate::parser::expr;
 
-use crate::parser::statement::Statement;
+use crate::parser::statement::{
+    Expression, ExpressionKind, MatchArm, NodeId, Statement, StatementKind,
+};
+use crate::syntax::builder::Builder;
+use crate::syntax::kind::SyntaxKind;
 
 pub struct Parser<'a> {
     pub lexer: Lexer<'a>,
     pub curr: Option<Token>,
+    pub span: Span,      // span of `curr`
+    pub prev_span: Span, // span of the last consumed token
+    pub next_id: u32,
+    pub diagnostics: Vec<Diagnostic>,
+    pub cst: Option<Builder>, // the lossless tree, built when set
 }
 
 impl<'a> Parser<'a> {
     pub fn new(lexer: Lexer<'a>) -> Self {
-        let mut parser = Parser { lexer, curr: None };
+        let mut parser = Parser {
+            lexer,
+            curr: None,
+            span: Span::default(),
+            prev_span: Span::default(),
+            next_id: 0,
+            diagnostics: Vec::new(),
+            cst: None,
+        };
         parser.advance();
         parser
     }
 
     pub fn advance(&mut self) {
-        self.curr = self.lexer.next_token();
+        if let (Some(_), Some(cst)) = (&self.curr, &mut self.cst) {
+            cst.token();
+        }
+        self.prev_span = self.span;
+        match self.lexer.next_token() {
+            Some((token, span)) => {
+                self.curr = Some(token);
+                self.span = span;
+            }
+            None => {
+                self.curr = None;
+                self.span = Span {
+                    start: self.lexer.pos,
+                    end: self.lexer.pos,
+                    line: self.lexer.line,
+                    column: self.lexer.column,
+                };
+            }
+        }
+    }
+
+    pub fn error(&mut self, message: String) {
+        self.diagnostics
+            .push(Diagnostic::error(message).at(self.span));
+    }
+
+    pub fn error_at(&mut self, span: Span, message: String) {
+        self.diagnostics.push(Diagnostic::error(message).at(span));
+    }
+
+    pub fn node_id(&mut self) -> NodeId {
+        let id = NodeId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    // Makes what was consumed from `start` on a `kind` node of the lossless
+    // tree.
+    pub fn wrap(&mut self, start: Span, kind: SyntaxKind) {
+        if let Some(cst) = &mut self.cst {
+            cst.wrap(start.start, kind);
+        }
+    }
+
+    // Builds a statement spanning from `start` to the last consumed token.
+    pub fn statement(&mut self, kind: StatementKind, start: Span) -> Statement {
+        self.wrap(start, SyntaxKind::of_statement(&kind));
+        Statement {
+            kind,
+            span: start.to(self.prev_span),
+            id: self.node_id(),
+        }
+    }
+
+    pub fn expression(&mut self, kind: ExpressionKind, span: Span) -> Expression {
+        self.wrap(span, SyntaxKind::of_expression(&kind));
+        Expression {
+            kind,
+            span,
+            id: self.node_id(),
+        }
     }
 
     pub fn expect(&mut self, expected: Token) -> Option<()> {
@@ -25,52 +103,156 @@ impl<'a> Parser<'a> {
                 self.advance();
                 Some(())
             } else {
-                eprintln!("Error: Expected {:?}", expected);
+                self.error(format!("Expected {:?}", expected));
                 None
             }
         } else {
-            eprintln!("Error: Unexpected end of input.");
+            self.error("Unexpected end of input.".to_string());
             None
         }
     }
 
     pub fn parse(&mut self) -> Vec<Statement> {
         let mut statements = Vec::new();
-        while let Some(ref token) = self.curr.clone() {
-            println!("debug {:?}", token);
-            match token {
-                // block end
-                Token::Symbol(ref k) if *k == '}' => {
-                    break;
-                }
+        while self.curr.is_some() {
+            statements.extend(self.parse_top_level());
+        }
+        statements
+    }
 
-                Token::Keyword(ref k) if k == "if" => {
-                    if let Some(stmt) = self.parse_if_statement() {
-                        statements.push(stmt);
-                    }
+    // One step of `parse`: a statement, or nothing for a stray `;` or `}` or
+    // a statement that failed to parse. Each step starts afresh from the
+    // current token, so the incremental parser can resume between them.
+    pub fn parse_top_level(&mut self) -> Option<Statement> {
+        if let Some(Token::Symbol('}')) = self.curr {
+            self.error("Unexpected `}` without a matching `{`".to_string());
+            let start = self.span;
+            self.advance();
+            self.wrap(start, SyntaxKind::Error);
+            return None;
+        }
+        self.parse_item()
+    }
+
+    // Parses statements up to a closing `}` or the end of input.
+    pub fn parse_statements(&mut self) -> Vec<Statement> {
+        let mut statements = Vec::new();
+        // block end
+        while !matches!(self.curr, None | Some(Token::Symbol('}'))) {
+            statements.extend(self.parse_item());
+        }
+        statements
+    }
+
+    fn parse_item(&mut self) -> Option<Statement> {
+        if let Some(Token::Symbol(';')) = self.curr {
+            self.diagnostics
+                .push(Diagnostic::warning("Unnecessary `;`".to_string()).at(self.span));
+            self.advance();
+            return None;
+        }
+        let start = self.span;
+        let stmt = self.parse_statement();
+        if stmt.is_none() {
+            self.synchronize();
+            // the tokens of a broken statement, with whatever in it parsed
+            self.wrap(start, SyntaxKind::Error);
+        }
+        stmt
+    }
+
+    /*
+    Statements ending in a block (`if`, `match`, `{ ... }`) stand alone;
+    every other statement must be terminated by `;`. A `;` on its own is
+    an empty statement and is ignored with a warning.
+    */
+    fn parse_statement(&mut self) -> Option<Statement> {
+        let start = self.span;
+        let kind = match self.curr.as_ref()? {
+            Token::Keyword(k) if k == "if" => return self.parse_if_statement(),
+            Token::Keyword(k) if k == "match" => return self.parse_match_statement(),
+            Token::Symbol('{') => {
+                let block = self.parse_block()?;
+                return Some(self.statement(StatementKind::Block(block), start));
+            }
+
+            Token::Keyword(k) if k == "import" => self.parse_import()?,
+
+            Token::Keyword(k) if k == "export" => {
+                self.advance();
+                if matches!(&self.curr, Some(Token::Keyword(k)) if k == "let" || k == "const") {
+                    let decl_start = self.span;
+                    let decl = self.parse_var_decl()?;
+                    StatementKind::Export(Box::new(self.statement(decl, decl_start)))
+                } else {
+                    self.error("Expected `let` or `const` after `export`".to_string());
+                    return None;
                 }
+            }
 
-                Token::Keyword(ref k) if k == "let" || k == "const" => {
-                    self.advance();
-                    if let Some(stmt) = self.parse_var_decl() {
-                        statements.push(stmt);
+            Token::Keyword(k) if k == "let" || k == "const" => self.parse_var_decl()?,
+
+            token if starts_expression(token) => {
+                let expr = self.parse_expression()?;
+                match expr.kind {
+                    ExpressionKind::FunctionCall { name, args } => {
+                        StatementKind::FunctionCall { name, args }
                     }
+                    _ => StatementKind::Expression(expr),
                 }
+            }
+
+            token => {
+                self.error(format!(
+                    "Unexpected token {:?}, expected a statement",
+                    token
+                ));
+                self.advance();
+                return None;
+            }
+        };
+
+        if let Some(Token::Symbol(';')) = self.curr {
+            self.advance();
+        } else {
+            self.error("Expected `;` after statement".to_string());
+        }
 
-                Token::Ident(ref ident) => {
+        Some(self.statement(kind, start))
+    }
+
+    // Skips to the end of a broken statement: past the next `;`, or up to a
+    // `}` or a keyword that starts a new statement.
+    fn synchronize(&mut self) {
+        while let Some(token) = &self.curr {
+            match token {
+                Token::Symbol(';') => {
                     self.advance();
-                    if let Some(stmt) = self.parse_function_call(ident.clone()) {
-                        statements.push(stmt);
-                    }
+                    return;
+                }
+                Token::Symbol('}') => return,
+                Token::Keyword(k)
+                    if matches!(
+                        k.as_str(),
+                        "let" | "const" | "if" | "match" | "import" | "export"
+                    ) =>
+                {
+                    return
                 }
-
                 _ => self.advance(),
             }
         }
-        statements
+    }
+
+    fn parse_block(&mut self) -> Option<Vec<Statement>> {
+        self.expect(Token::Symbol('{'))?;
+        let statements = self.parse_statements();
+        self.expect(Token::Symbol('}'))?;
+        Some(statements)
     }
 
     fn parse_if_statement(&mut self) -> Option<Statement> {
+        let start = self.span;
         self.advance();
 
         /*
@@ -79,62 +261,35 @@ impl<'a> Parser<'a> {
         }
         */
 
-        if self.expect(Token::Symbol('(')).is_none() {
-            return None;
-        }
+        self.expect(Token::Symbol('('))?;
 
         let condition = self.parse_expression()?;
 
-        if self.expect(Token::Symbol(')')).is_none() {
-            return None;
-        }
-
-        if self.expect(Token::Symbol('{')).is_none() {
-            return None;
-        }
-
-        let mut then_branch = Vec::new();
-        while let Some(token) = &self.curr {
-            if let Token::Symbol('}') = token {
-                break;
-            }
-            then_branch.extend(self.parse());
-        }
+        self.expect(Token::Symbol(')'))?;
 
-        if self.expect(Token::Symbol('}')).is_none() {
-            return None;
-        }
+        let then_branch = self.parse_block()?;
 
         let else_branch = if let Some(Token::Keyword(ref k)) = self.curr {
             if k == "else" {
                 self.advance();
                 if let Some(Token::Keyword(ref k)) = self.curr {
                     if k == "if" {
-                        return Some(Statement::If {
+                        let else_if = self.parse_if_statement()?;
+                        let kind = StatementKind::If {
                             condition,
                             then_branch,
-                            else_branch: Some(Box::new(self.parse_if_statement()?)),
-                        });
-                    }
-                }
-
-                if self.expect(Token::Symbol('{')).is_none() {
-                    return None;
-                }
-
-                let mut else_branch = Vec::new();
-                while let Some(token) = &self.curr {
-                    if let Token::Symbol('}') = token {
-                        break;
+                            else_branch: Some(Box::new(else_if)),
+                        };
+                        return Some(self.statement(kind, start));
                     }
-                    else_branch.extend(self.parse());
                 }
 
-                if self.expect(Token::Symbol('}')).is_none() {
-                    return None;
-                }
+                let else_start = self.span;
+                let else_branch = self.parse_block()?;
 
-                Some(Box::new(Statement::Block(else_branch)))
+                Some(Box::new(
+                    self.statement(StatementKind::Block(else_branch), else_start),
+                ))
             } else {
                 None
             }
@@ -142,64 +297,144 @@ impl<'a> Parser<'a> {
             None
         };
 
-        Some(Statement::If {
+        let kind = StatementKind::If {
             condition,
             then_branch,
             else_branch,
-        })
+        };
+        Some(self.statement(kind, start))
     }
 
-    fn parse_var_decl(&mut self) -> Option<Statement> {
-        if let Some(Token::Ident(name)) = &self.curr {
-            let var_name = name.clone();
-            self.advance();
+    fn parse_match_statement(&mut self) -> Option<Statement> {
+        let start = self.span;
+        self.advance();
+
+        /*
+        match ( expr ) {
+           1 => { block; }
+           2 | 3 => { block; }
+           _ => { block; }
+        }
+        */
+
+        self.expect(Token::Symbol('('))?;
+        let scrutinee = self.parse_expression()?;
+        self.expect(Token::Symbol(')'))?;
+        self.expect(Token::Symbol('{'))?;
+
+        let mut arms = Vec::new();
+        while let Some(token) = &self.curr {
+            if let Token::Symbol('}') = token {
+                break;
+            }
 
-            let var_type = if let Some(Token::Symbol(':')) = self.curr {
+            let arm_start = self.span;
+            let mut patterns = vec![self.parse_pattern()?];
+            while let Some(Token::Symbol('|')) = self.curr {
                 self.advance();
-                if let Some(Token::Type(type_name)) = &self.curr {
-                    let type_name = type_name.clone();
-                    self.advance();
-                    Some(type_name)
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
+                patterns.push(self.parse_pattern()?);
+            }
+
+            self.expect(Token::Symbol('='))?;
+            self.expect(Token::Symbol('>'))?;
+            let body = self.parse_block()?;
+            let arm_span = arm_start.to(self.prev_span);
+            self.wrap(arm_start, SyntaxKind::MatchArm);
 
-            if let Some(Token::Symbol('=')) = self.curr {
+            if let Some(Token::Symbol(',')) = self.curr {
                 self.advance();
-                if let Some(value) = self.parse_expression() {
-                    return Some(Statement::VarDeclaration {
-                        name: var_name,
-                        var_type,
-                        value,
-                    });
-                }
             }
+
+            arms.push(MatchArm {
+                patterns,
+                body,
+                span: arm_span,
+                id: self.node_id(),
+            });
         }
-        None
+
+        self.expect(Token::Symbol('}'))?;
+
+        Some(self.statement(StatementKind::Match { scrutinee, arms }, start))
     }
 
-    fn parse_function_call(&mut self, name: String) -> Option<Statement> {
-        if let Some(Token::Symbol('(')) = self.curr {
-            self.advance();
-            let mut args = Vec::new();
+    fn parse_import(&mut self) -> Option<StatementKind> {
+        self.advance();
 
-            while let Some(expr) = self.parse_expression() {
-                args.push(expr);
-                if let Some(Token::Symbol(',')) = self.curr {
-                    self.advance();
-                } else {
-                    break;
-                }
-            }
+        /*
+        import { a, b } from "./path.astc";
+        */
+
+        self.expect(Token::Symbol('{'))?;
 
-            if let Some(Token::Symbol(')')) = self.curr {
+        let mut names = Vec::new();
+        while let Some(Token::Ident(name)) = &self.curr {
+            names.push(name.clone());
+            self.advance();
+            if let Some(Token::Symbol(',')) = self.curr {
                 self.advance();
-                return Some(Statement::FunctionCall { name, args });
+            } else {
+                break;
             }
         }
-        None
+
+        self.expect(Token::Symbol('}'))?;
+        self.expect(Token::Keyword("from".to_string()))?;
+
+        if let Some(Token::StringLiteral(path)) = &self.curr {
+            let path = path.clone();
+            self.advance();
+            Some(StatementKind::Import { names, path })
+        } else {
+            self.error("Expected module path after `from`".to_string());
+            None
+        }
+    }
+
+    fn parse_var_decl(&mut self) -> Option<StatementKind> {
+        let constant = matches!(&self.curr, Some(Token::Keyword(k)) if k == "const");
+        self.advance();
+
+        let var_name = if let Some(Token::Ident(name)) = &self.curr {
+            let name = name.clone();
+            self.advance();
+            name
+        } else {
+            self.error("Expected variable name".to_string());
+            return None;
+        };
+
+        let var_type = if let Some(Token::Symbol(':')) = self.curr {
+            self.advance();
+            if let Some(Token::Type(type_name)) = &self.curr {
+                let type_name = type_name.clone();
+                self.advance();
+                Some(type_name)
+            } else {
+                self.error(format!("Expected type for `{}`", var_name));
+                return None;
+            }
+        } else {
+            None
+        };
+
+        self.expect(Token::Symbol('='))?;
+        let value = self.parse_expression()?;
+
+        Some(StatementKind::VarDeclaration {
+            constant,
+            name: var_name,
+            var_type,
+            value,
+        })
+    }
+}
+
+fn starts_expression(token: &Token) -> bool {
+    match token {
+        Token::Ident(_) | Token::Number(_) | Token::StringLiteral(_) => true,
+        Token::Keyword(k) => k == "true" || k == "false",
+        Token::Symbol(c) => *c == '(',
+        _ => false,
     }
 }
@@ -0,0 +1,79 @@
+use crate::lexer::token::Token;
+use crate::parser::parser::Parser;
+use crate::parser::statement::{Pattern, PatternKind};
+use crate::syntax::kind::SyntaxKind;
+
+impl<'a> Parser<'a> {
+    pub fn parse_pattern(&mut self) -> Option<Pattern> {
+        let start = self.span;
+        let kind = self.parse_pattern_kind()?;
+        self.wrap(start, SyntaxKind::Pattern);
+        Some(Pattern {
+            kind,
+            span: start.to(self.prev_span),
+            id: self.node_id(),
+        })
+    }
+
+    fn parse_pattern_kind(&mut self) -> Option<PatternKind> {
+        match &self.curr {
+            Some(Token::Ident(name)) if name == "_" => {
+                self.advance();
+                Some(PatternKind::Wildcard)
+            }
+            Some(Token::Keyword(k)) if k == "true" || k == "false" => {
+                let value = k == "true";
+                self.advance();
+                Some(PatternKind::Boolean(value))
+            }
+            Some(Token::StringLiteral(s)) => {
+                let s = s.clone();
+                self.advance();
+                Some(PatternKind::StringLiteral(s))
+            }
+            Some(Token::Number(_)) | Some(Token::Symbol('-')) => {
+                let start = self.parse_pattern_number()?;
+
+                if let Some(Token::Symbol('.')) = self.curr {
+                    self.advance();
+                    self.expect(Token::Symbol('.'))?;
+                    let inclusive = if let Some(Token::Symbol('=')) = self.curr {
+                        self.advance();
+                        true
+                    } else {
+                        false
+                    };
+                    let end = self.parse_pattern_number()?;
+                    return Some(PatternKind::Range {
+                        start,
+                        end,
+                        inclusive,
+                    });
+                }
+
+                Some(PatternKind::Number(start))
+            }
+            _ => {
+                self.error(format!("Expected pattern, found {:?}", self.curr));
+                None
+            }
+        }
+    }
+
+    fn parse_pattern_number(&mut self) -> Option<f64> {
+        let negative = if let Some(Token::Symbol('-')) = self.curr {
+            self.advance();
+            true
+        } else {
+            false
+        };
+
+        if let Some(Token::Number(num)) = self.curr {
+            self.advance();
+            Some(if negative { -num } else { num })
+        } else {
+            self.error("Expected number in pattern".to_string());
+            None
+        }
+    }
+}
@@ -1,6 +1,21 @@
-#[derive(Debug)]
-pub enum Statement {
+use crate::lexer::token::Span;
+
+// Identifies a node across passes, so analyses can keep side tables
+// (types, resolved names, ...) keyed by node instead of mutating the AST.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub u32);
+
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+    pub id: NodeId,
+}
+
+#[derive(Debug, Clone)]
+pub enum StatementKind {
     VarDeclaration {
+        constant: bool, // `const` rather than `let`
         name: String,
         var_type: Option<String>,
         value: Expression,
@@ -14,21 +29,91 @@ pub enum Statement {
         then_branch: Vec<Statement>,
         else_branch: Option<Box<Statement>>,
     },
+    Match {
+        scrutinee: Expression,
+        arms: Vec<MatchArm>,
+    },
     Block(Vec<Statement>),
+    Import {
+        names: Vec<String>,
+        path: String,
+    }, // import { f } from "./util.astc";
+    Export(Box<Statement>), // export let x = ...;
+    Expression(Expression), // f(1)(2);
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub patterns: Vec<Pattern>, // 2 | 3 => ...
+    pub body: Vec<Statement>,
+    pub span: Span,
+    pub id: NodeId,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub span: Span,
+    pub id: NodeId,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternKind {
+    Number(f64),
+    StringLiteral(String),
+    Boolean(bool),
+    Range {
+        start: f64,
+        end: f64,
+        inclusive: bool,
+    }, // 1..5, 1..=5
+    Wildcard, // _
+}
+
+#[derive(Debug, Clone)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+    pub id: NodeId,
 }
 
-#[derive(Debug)]
-pub enum Expression {
+#[derive(Debug, Clone)]
+pub enum ExpressionKind {
     Identifier(String),
     Number(f64),
     StringLiteral(String),
+    Boolean(bool),
     BinaryOp(Box<Expression>, Operator, Box<Expression>),
     LogicalOp(Box<Expression>, LogicalOperator, Box<Expression>), // logical operator
     ComparisonOp(Box<Expression>, ComparisonOperator, Box<Expression>), // Relative operators
-    FunctionCall { name: String, args: Vec<Expression> },
+    FunctionCall {
+        name: String,
+        args: Vec<Expression>,
+    },
+    Call {
+        callee: Box<Expression>,
+        args: Vec<Expression>,
+    }, // f(1)(2)
+    Lambda {
+        params: Vec<Param>,
+        body: Box<Expression>,
+    }, // (a: i32) => a * 2
+    Conditional {
+        condition: Box<Expression>,
+        then_branch: Box<Expression>,
+        else_branch: Box<Expression>,
+    }, // cond ? a : b, if (cond) { a } else { b }
+}
+
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: String,
+    pub param_type: Option<String>,
+    pub span: Span,
+    pub id: NodeId,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Operator {
     Add,
     Sub,
@@ -36,13 +121,13 @@ pub enum Operator {
     Div,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum LogicalOperator {
     And, // &&
     Or,  // ||
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ComparisonOperator {
     Equal,              // ==
     NotEqual,           // !=
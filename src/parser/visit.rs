@@ -0,0 +1,115 @@
+use crate::parser::statement::{
+    Expression, ExpressionKind, MatchArm, Param, Pattern, Statement, StatementKind,
+};
+
+// Read-only traversal over the AST. Every method defaults to visiting the
+// node's children; a pass overrides the nodes it cares about and calls the
+// matching `walk_*` function to keep descending.
+pub trait Visitor: Sized {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        walk_statement(self, stmt);
+    }
+
+    // Statements of an `if` branch, `else` block, match arm or bare block.
+    fn visit_block(&mut self, stmts: &[Statement]) {
+        walk_block(self, stmts);
+    }
+
+    fn visit_expression(&mut self, expr: &Expression) {
+        walk_expression(self, expr);
+    }
+
+    fn visit_match_arm(&mut self, arm: &MatchArm) {
+        walk_match_arm(self, arm);
+    }
+
+    fn visit_pattern(&mut self, _pattern: &Pattern) {}
+
+    fn visit_param(&mut self, _param: &Param) {}
+}
+
+pub fn walk_statement<V: Visitor>(visitor: &mut V, stmt: &Statement) {
+    match &stmt.kind {
+        StatementKind::VarDeclaration { value, .. } => visitor.visit_expression(value),
+        StatementKind::FunctionCall { args, .. } => {
+            for arg in args {
+                visitor.visit_expression(arg);
+            }
+        }
+        StatementKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_block(then_branch);
+            if let Some(else_branch) = else_branch {
+                visitor.visit_statement(else_branch);
+            }
+        }
+        StatementKind::Match { scrutinee, arms } => {
+            visitor.visit_expression(scrutinee);
+            for arm in arms {
+                visitor.visit_match_arm(arm);
+            }
+        }
+        StatementKind::Block(stmts) => visitor.visit_block(stmts),
+        StatementKind::Import { .. } => {}
+        StatementKind::Export(stmt) => visitor.visit_statement(stmt),
+        StatementKind::Expression(expr) => visitor.visit_expression(expr),
+    }
+}
+
+pub fn walk_block<V: Visitor>(visitor: &mut V, stmts: &[Statement]) {
+    for stmt in stmts {
+        visitor.visit_statement(stmt);
+    }
+}
+
+pub fn walk_expression<V: Visitor>(visitor: &mut V, expr: &Expression) {
+    match &expr.kind {
+        ExpressionKind::Identifier(_)
+        | ExpressionKind::Number(_)
+        | ExpressionKind::StringLiteral(_)
+        | ExpressionKind::Boolean(_) => {}
+        ExpressionKind::BinaryOp(left, _, right)
+        | ExpressionKind::LogicalOp(left, _, right)
+        | ExpressionKind::ComparisonOp(left, _, right) => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        ExpressionKind::FunctionCall { args, .. } => {
+            for arg in args {
+                visitor.visit_expression(arg);
+            }
+        }
+        ExpressionKind::Call { callee, args } => {
+            visitor.visit_expression(callee);
+            for arg in args {
+                visitor.visit_expression(arg);
+            }
+        }
+        ExpressionKind::Lambda { params, body } => {
+            for param in params {
+                visitor.visit_param(param);
+            }
+            visitor.visit_expression(body);
+        }
+        ExpressionKind::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_expression(then_branch);
+            visitor.visit_expression(else_branch);
+        }
+    }
+}
+
+pub fn walk_match_arm<V: Visitor>(visitor: &mut V, arm: &MatchArm) {
+    for pattern in &arm.patterns {
+        visitor.visit_pattern(pattern);
+    }
+    visitor.visit_block(&arm.body);
+}
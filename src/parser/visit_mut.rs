@@ -0,0 +1,113 @@
+use crate::parser::statement::{
+    Expression, ExpressionKind, MatchArm, Param, Pattern, Statement, StatementKind,
+};
+
+// Like `Visitor`, but with mutable access so a pass can edit nodes in place.
+pub trait VisitorMut: Sized {
+    fn visit_statement(&mut self, stmt: &mut Statement) {
+        walk_statement_mut(self, stmt);
+    }
+
+    // Statements of an `if` branch, `else` block, match arm or bare block.
+    fn visit_block(&mut self, stmts: &mut Vec<Statement>) {
+        walk_block_mut(self, stmts);
+    }
+
+    fn visit_expression(&mut self, expr: &mut Expression) {
+        walk_expression_mut(self, expr);
+    }
+
+    fn visit_match_arm(&mut self, arm: &mut MatchArm) {
+        walk_match_arm_mut(self, arm);
+    }
+
+    fn visit_pattern(&mut self, _pattern: &mut Pattern) {}
+
+    fn visit_param(&mut self, _param: &mut Param) {}
+}
+
+pub fn walk_statement_mut<V: VisitorMut>(visitor: &mut V, stmt: &mut Statement) {
+    match &mut stmt.kind {
+        StatementKind::VarDeclaration { value, .. } => visitor.visit_expression(value),
+        StatementKind::FunctionCall { args, .. } => {
+            for arg in args {
+                visitor.visit_expression(arg);
+            }
+        }
+        StatementKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_block(then_branch);
+            if let Some(else_branch) = else_branch {
+                visitor.visit_statement(else_branch);
+            }
+        }
+        StatementKind::Match { scrutinee, arms } => {
+            visitor.visit_expression(scrutinee);
+            for arm in arms {
+                visitor.visit_match_arm(arm);
+            }
+        }
+        StatementKind::Block(stmts) => visitor.visit_block(stmts),
+        StatementKind::Import { .. } => {}
+        StatementKind::Export(stmt) => visitor.visit_statement(stmt),
+        StatementKind::Expression(expr) => visitor.visit_expression(expr),
+    }
+}
+
+pub fn walk_block_mut<V: VisitorMut>(visitor: &mut V, stmts: &mut Vec<Statement>) {
+    for stmt in stmts {
+        visitor.visit_statement(stmt);
+    }
+}
+
+pub fn walk_expression_mut<V: VisitorMut>(visitor: &mut V, expr: &mut Expression) {
+    match &mut expr.kind {
+        ExpressionKind::Identifier(_)
+        | ExpressionKind::Number(_)
+        | ExpressionKind::StringLiteral(_)
+        | ExpressionKind::Boolean(_) => {}
+        ExpressionKind::BinaryOp(left, _, right)
+        | ExpressionKind::LogicalOp(left, _, right)
+        | ExpressionKind::ComparisonOp(left, _, right) => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        ExpressionKind::FunctionCall { args, .. } => {
+            for arg in args {
+                visitor.visit_expression(arg);
+            }
+        }
+        ExpressionKind::Call { callee, args } => {
+            visitor.visit_expression(callee);
+            for arg in args {
+                visitor.visit_expression(arg);
+            }
+        }
+        ExpressionKind::Lambda { params, body } => {
+            for param in params {
+                visitor.visit_param(param);
+            }
+            visitor.visit_expression(body);
+        }
+        ExpressionKind::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_expression(then_branch);
+            visitor.visit_expression(else_branch);
+        }
+    }
+}
+
+pub fn walk_match_arm_mut<V: VisitorMut>(visitor: &mut V, arm: &mut MatchArm) {
+    for pattern in &mut arm.patterns {
+        visitor.visit_pattern(pattern);
+    }
+    visitor.visit_block(&mut arm.body);
+}
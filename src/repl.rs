@@ -0,0 +1,265 @@
+use std::io::{self, BufRead, Write};
+
+use crate::checker::checker::Checker;
+use crate::diagnostic::Diagnostic;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::value::Value;
+use crate::lexer::token::{Lexer, Token};
+use crate::parser::parser::Parser;
+use crate::parser::statement::{Expression, ExpressionKind, Statement, StatementKind};
+use crate::resolver::resolver::Resolver;
+
+const HELP: &str = "\
+:ast <code>     print the AST of <code> without running it
+:tokens <code>  print the tokens of <code>
+:type <expr>    print the type the checker infers for <expr>
+:help           print this message
+:quit           exit (or end input with Ctrl-D)";
+
+// Reads statements from stdin and runs them in one persistent environment.
+// Input continues over several lines while a `{` or `(` is still open, and
+// a missing `;` after the last statement is supplied.
+#[derive(Default)]
+pub struct Repl {
+    interpreter: Interpreter,
+    history: Vec<Statement>, // everything run so far, checked with each input
+    next_id: u32,            // keeps node ids unique across inputs
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Repl::default()
+    }
+
+    pub fn run(&mut self) {
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        let mut input = String::new();
+
+        loop {
+            prompt(if input.is_empty() { ">> " } else { ".. " });
+            let Some(Ok(line)) = lines.next() else {
+                println!();
+                break;
+            };
+            input.push_str(&line);
+            input.push('\n');
+            if open_delimiters(&input) > 0 {
+                continue;
+            }
+
+            let source = std::mem::take(&mut input);
+            let source = source.trim();
+            if let Some(command) = source.strip_prefix(':') {
+                if !self.command(command) {
+                    break;
+                }
+            } else if !source.is_empty() {
+                self.eval(source);
+            }
+        }
+    }
+
+    // Returns false to leave the REPL.
+    fn command(&mut self, command: &str) -> bool {
+        let (name, rest) = command.split_once(' ').unwrap_or((command, ""));
+        let rest = rest.trim();
+        match name {
+            "ast" => {
+                if let Some(statements) = self.parse(rest) {
+                    for stmt in statements {
+                        println!("{}", stmt);
+                    }
+                }
+            }
+            "tokens" => print_tokens(rest),
+            "type" => self.print_type(rest),
+            "help" => println!("{}", HELP),
+            "quit" | "q" => return false,
+            _ => eprintln!(
+                "{}",
+                Diagnostic::error(format!("Unknown command `:{}`, try `:help`", name))
+            ),
+        }
+        true
+    }
+
+    fn eval(&mut self, source: &str) {
+        let Some(statements) = self.parse(source) else {
+            return;
+        };
+        if !self.check(&statements) {
+            return;
+        }
+
+        for stmt in statements {
+            // calls and expressions echo their result
+            let result = match &stmt.kind {
+                StatementKind::Expression(expr) => self.interpreter.evaluate(expr).map(Some),
+                StatementKind::FunctionCall { name, args } => {
+                    let call = Expression {
+                        kind: ExpressionKind::FunctionCall {
+                            name: name.clone(),
+                            args: args.clone(),
+                        },
+                        span: stmt.span,
+                        id: stmt.id,
+                    };
+                    self.interpreter.evaluate(&call).map(Some)
+                }
+                _ => self
+                    .interpreter
+                    .run(std::slice::from_ref(&stmt))
+                    .map(|_| None),
+            };
+
+            match result {
+                Ok(Some(Value::Unit)) | Ok(None) => {}
+                Ok(Some(value)) => println!("{}", show(&value)),
+                Err(error) => {
+                    eprintln!("{}", Diagnostic::error(error));
+                    return;
+                }
+            }
+            self.history.push(stmt);
+        }
+    }
+
+    fn print_type(&mut self, source: &str) {
+        let Some(statements) = self.parse(source) else {
+            return;
+        };
+        let expr = match statements.as_slice() {
+            [Statement {
+                kind: StatementKind::Expression(expr),
+                ..
+            }] => expr.clone(),
+            [Statement {
+                kind: StatementKind::FunctionCall { name, args },
+                span,
+                id,
+            }] => Expression {
+                kind: ExpressionKind::FunctionCall {
+                    name: name.clone(),
+                    args: args.clone(),
+                },
+                span: *span,
+                id: *id,
+            },
+            _ => {
+                eprintln!(
+                    "{}",
+                    Diagnostic::error("`:type` expects a single expression".to_string())
+                );
+                return;
+            }
+        };
+
+        // the checker sees the session so far, so earlier names have types
+        let mut program = self.history.clone();
+        program.push(Statement {
+            kind: StatementKind::Expression(expr.clone()),
+            span: expr.span,
+            id: expr.id,
+        });
+        let (types, _) = Checker::check(&program);
+        if let Some(ty) = types.get(&expr.id) {
+            println!("{}", ty);
+        }
+    }
+
+    // Resolves and type checks `statements` after the session so far, like
+    // `astc run` does with a file, and reports what is new. False if there
+    // are errors, in which case nothing is run.
+    fn check(&self, statements: &[Statement]) -> bool {
+        let mut program = self.history.clone();
+        program.extend_from_slice(statements);
+
+        // the history was checked before, so its warnings were shown already
+        let mut seen: Vec<String> = diagnostics(&self.history)
+            .iter()
+            .map(Diagnostic::to_string)
+            .collect();
+        let mut ok = true;
+        for diagnostic in diagnostics(&program) {
+            let text = diagnostic.to_string();
+            if let Some(index) = seen.iter().position(|s| *s == text) {
+                seen.swap_remove(index);
+                continue;
+            }
+            eprintln!("{}", text);
+            ok &= !diagnostic.is_error();
+        }
+        ok
+    }
+
+    // Parses one input, reporting diagnostics. `None` if it has errors. If
+    // the input only parses with a `;` added at the end, that is used.
+    fn parse(&mut self, source: &str) -> Option<Vec<Statement>> {
+        let (mut statements, mut diagnostics) = self.parse_source(source);
+        if diagnostics.iter().any(|d| d.is_error()) && !source.ends_with(';') {
+            let (retry, retry_diagnostics) = self.parse_source(&format!("{};", source));
+            if !retry_diagnostics.iter().any(|d| d.is_error()) {
+                (statements, diagnostics) = (retry, retry_diagnostics);
+            }
+        }
+
+        for diagnostic in &diagnostics {
+            eprintln!("{}", diagnostic);
+        }
+        if diagnostics.iter().any(|d| d.is_error()) {
+            return None;
+        }
+        Some(statements)
+    }
+
+    fn parse_source(&mut self, source: &str) -> (Vec<Statement>, Vec<Diagnostic>) {
+        let mut parser = Parser::new(Lexer::new(source));
+        parser.next_id = self.next_id;
+        let statements = parser.parse();
+        self.next_id = parser.next_id;
+        (statements, parser.diagnostics)
+    }
+}
+
+// The resolver's and the checker's diagnostics for `program`.
+fn diagnostics(program: &[Statement]) -> Vec<Diagnostic> {
+    let (_, mut diagnostics) = Resolver::resolve(program);
+    diagnostics.extend(Checker::check(program).1);
+    diagnostics
+}
+
+// One token per line, with where it starts.
+pub fn print_tokens(source: &str) {
+    let mut lexer = Lexer::new(source);
+    while let Some((token, span)) = lexer.next_token() {
+        println!("{}:{} {:?}", span.line, span.column, token);
+    }
+}
+
+// Net count of unclosed `{` and `(`, ignoring those in strings and comments.
+fn open_delimiters(source: &str) -> i32 {
+    let mut lexer = Lexer::new(source);
+    let mut depth = 0;
+    while let Some((token, _)) = lexer.next_token() {
+        match token {
+            Token::Symbol('{' | '(') => depth += 1,
+            Token::Symbol('}' | ')') => depth -= 1,
+            _ => {}
+        }
+    }
+    depth
+}
+
+// Strings are quoted so they stand apart from other values.
+fn show(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("{:?}", s),
+        value => value.to_string(),
+    }
+}
+
+fn prompt(text: &str) {
+    print!("{}", text);
+    let _ = io::stdout().flush();
+}
@@ -0,0 +1,3 @@
+// Side table for later passes.
+pub mod resolution;
+pub mod resolver;
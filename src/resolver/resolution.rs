@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use crate::lexer::token::Span;
+use crate::parser::statement::NodeId;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeclarationKind {
+    Variable, // let
+    Constant, // const
+    Parameter,
+    Import,
+}
+
+#[derive(Debug, Clone)]
+pub struct Declaration {
+    pub name: String,
+    pub kind: DeclarationKind,
+    pub node: NodeId, // the declaring statement or param
+    pub span: Span,
+    pub shadows: Option<usize>, // same name declared in an enclosing scope
+}
+
+// Side table produced by the resolver: every declaration in a module, and the
+// declaration each name use refers to. Uses are keyed by the node that
+// mentions the name: an `Identifier` or `FunctionCall` expression, or a
+// `FunctionCall` statement. Calls to builtins have no entry.
+#[derive(Debug, Default)]
+pub struct Resolution {
+    pub declarations: Vec<Declaration>,
+    pub uses: HashMap<NodeId, usize>, // node -> index into `declarations`
+}
+
+impl Resolution {
+    pub fn declaration_of(&self, node: NodeId) -> Option<&Declaration> {
+        self.uses.get(&node).map(|&index| &self.declarations[index])
+    }
+}
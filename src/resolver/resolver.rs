@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use crate::diagnostic::Diagnostic;
+use crate::lexer::token::Span;
+use crate::module::module::ModuleGraph;
+use crate::parser::statement::{Expression, ExpressionKind, NodeId, Statement, StatementKind};
+use crate::parser::visit::{walk_expression, walk_statement, Visitor};
+use crate::resolver::resolution::{Declaration, DeclarationKind, Resolution};
+
+// Functions provided by the interpreter rather than declared in source.
+pub const BUILTINS: &[&str] = &["print"];
+
+#[derive(Default)]
+struct Scope {
+    names: HashMap<String, usize>, // name -> index into `declarations`
+    // Lambda bodies run when called, by which time the rest of the scope has
+    // been declared, so they are resolved when the scope closes. This lets
+    // `let fact = (n) => ... fact(n - 1)` refer to itself.
+    deferred: Vec<Expression>,
+}
+
+#[derive(Default)]
+pub struct Resolver {
+    scopes: Vec<Scope>,
+    resolution: Resolution,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Resolver::default()
+    }
+
+    pub fn resolve(statements: &[Statement]) -> (Resolution, Vec<Diagnostic>) {
+        let mut resolver = Resolver::new();
+        resolver.push_scope();
+        for stmt in statements {
+            resolver.visit_statement(stmt);
+        }
+        resolver.pop_scope();
+
+        // deferred lambda bodies are reported out of order
+        let mut diagnostics = resolver.diagnostics;
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span.map(|span| span.start));
+        (resolver.resolution, diagnostics)
+    }
+
+    // Reports the unresolved and redeclared names of every module. The
+    // tables are dropped: the passes that need one resolve their module
+    // themselves.
+    pub fn resolve_graph(graph: &ModuleGraph) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for module in &graph.modules {
+            let (_, module_diagnostics) = Resolver::resolve(&module.statements);
+            for diagnostic in module_diagnostics {
+                diagnostics.push(diagnostic.in_file(&module.name));
+            }
+        }
+        diagnostics
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(Scope::default());
+    }
+
+    fn pop_scope(&mut self) {
+        while let Some(lambda) = self
+            .scopes
+            .last_mut()
+            .and_then(|scope| scope.deferred.pop())
+        {
+            self.resolve_lambda(&lambda);
+        }
+        self.scopes.pop();
+    }
+
+    fn resolve_lambda(&mut self, lambda: &Expression) {
+        if let ExpressionKind::Lambda { params, body } = &lambda.kind {
+            self.push_scope();
+            for param in params {
+                self.declare(
+                    &param.name,
+                    DeclarationKind::Parameter,
+                    param.id,
+                    param.span,
+                );
+            }
+            self.visit_expression(body);
+            self.pop_scope();
+        }
+    }
+
+    fn declare(&mut self, name: &str, kind: DeclarationKind, node: NodeId, span: Span) {
+        if self
+            .scopes
+            .last()
+            .is_some_and(|scope| scope.names.contains_key(name))
+        {
+            self.diagnostics.push(
+                Diagnostic::error(format!("`{}` is already declared in this scope", name)).at(span),
+            );
+            return;
+        }
+
+        let shadows = self.lookup(name);
+        let index = self.resolution.declarations.len();
+        self.resolution.declarations.push(Declaration {
+            name: name.to_string(),
+            kind,
+            node,
+            span,
+            shadows,
+        });
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.names.insert(name.to_string(), index);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.names.get(name).copied())
+    }
+
+    // Links a use of `name` by `node` to its declaration.
+    fn use_name(&mut self, name: &str, node: NodeId, span: Span) {
+        match self.lookup(name) {
+            Some(index) => {
+                self.resolution.uses.insert(node, index);
+            }
+            None if BUILTINS.contains(&name) => {}
+            None => self
+                .diagnostics
+                .push(Diagnostic::error(format!("Cannot find `{}` in this scope", name)).at(span)),
+        }
+    }
+}
+
+impl Visitor for Resolver {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        match &stmt.kind {
+            StatementKind::VarDeclaration {
+                constant,
+                name,
+                value,
+                ..
+            } => {
+                // the value is resolved before the name exists, so
+                // `let x = x + 1;` refers to an outer `x`
+                self.visit_expression(value);
+                let kind = if *constant {
+                    DeclarationKind::Constant
+                } else {
+                    DeclarationKind::Variable
+                };
+                self.declare(name, kind, stmt.id, stmt.span);
+            }
+            StatementKind::FunctionCall { name, .. } => {
+                self.use_name(name, stmt.id, stmt.span);
+                walk_statement(self, stmt);
+            }
+            StatementKind::Import { names, .. } => {
+                for name in names {
+                    self.declare(name, DeclarationKind::Import, stmt.id, stmt.span);
+                }
+            }
+            _ => walk_statement(self, stmt),
+        }
+    }
+
+    fn visit_block(&mut self, stmts: &[Statement]) {
+        self.push_scope();
+        for stmt in stmts {
+            self.visit_statement(stmt);
+        }
+        self.pop_scope();
+    }
+
+    fn visit_expression(&mut self, expr: &Expression) {
+        match &expr.kind {
+            ExpressionKind::Identifier(name) => self.use_name(name, expr.id, expr.span),
+            ExpressionKind::FunctionCall { name, .. } => {
+                self.use_name(name, expr.id, expr.span);
+                walk_expression(self, expr);
+            }
+            ExpressionKind::Lambda { .. } => {
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.deferred.push(expr.clone());
+                }
+            }
+            _ => walk_expression(self, expr),
+        }
+    }
+}
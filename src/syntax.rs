@@ -0,0 +1,7 @@
+// Lossless syntax tree, which keeps whitespace and comments; the AST parser
+// builds it alongside the AST.
+
+pub mod builder;
+pub mod green;
+pub mod kind;
+pub mod red;
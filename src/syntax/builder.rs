@@ -0,0 +1,166 @@
+use std::rc::Rc;
+
+use crate::diagnostic::Diagnostic;
+use crate::lexer::token::{Lexer, Token};
+use crate::parser::parser::Parser;
+use crate::parser::statement::Statement;
+use crate::syntax::green::{GreenElement, GreenNode, GreenToken};
+use crate::syntax::kind::SyntaxKind;
+use crate::syntax::red::SyntaxNode;
+
+// Parses `source` into its AST and its lossless tree: the text of the
+// returned root is exactly `source`.
+pub fn parse(source: &str) -> (Vec<Statement>, SyntaxNode, Vec<Diagnostic>) {
+    let mut parser = Parser::new(Lexer::new(source));
+    parser.cst = Some(Builder::new(source));
+    let statements = parser.parse();
+    let builder = parser.cst.take().expect("the builder was set");
+    (
+        statements,
+        SyntaxNode::new_root(builder.finish()),
+        parser.diagnostics,
+    )
+}
+
+/*
+The AST parser builds the tree alongside the AST, bottom-up: `token`
+appends the token it consumes, after the whitespace and comments in front
+of it, and where it builds an AST node `wrap` turns everything appended
+from the node's first token on into a node of the matching kind. So every
+AST node has a tree node with the same range, and the trivia between two
+nodes goes to their parent.
+
+    let x = 1;    token `let`, token `x`, token `=`,
+                  token `1`, wrap NumberExpr at 8,
+                  token `;`, wrap VarDeclaration at 0
+*/
+pub struct Builder {
+    pieces: Vec<Piece>, // every byte of the input, as tokens and trivia
+    next: usize,        // the first piece not appended yet
+    elements: Vec<GreenElement>,
+    starts: Vec<usize>, // byte offset of each of `elements`
+    pos: usize,         // byte offset of the first piece not appended yet
+}
+
+struct Piece {
+    token: bool, // false for whitespace and comments
+    green: Rc<GreenToken>,
+}
+
+impl Builder {
+    pub fn new(source: &str) -> Self {
+        Builder {
+            pieces: tokenize(source),
+            next: 0,
+            elements: Vec::new(),
+            starts: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    // Appends the trivia before the next token, then the token.
+    pub fn token(&mut self) {
+        self.trivia();
+        if self.next < self.pieces.len() {
+            self.push_piece();
+        }
+    }
+
+    // Turns what was appended from byte offset `start` on into a `kind`
+    // node.
+    pub fn wrap(&mut self, start: usize, kind: SyntaxKind) {
+        let index = self.starts.partition_point(|&s| s < start);
+        if index == self.elements.len() {
+            return;
+        }
+        let children = self.elements.split_off(index);
+        self.starts.truncate(index + 1);
+        let node = GreenNode::new(kind, children);
+        self.elements.push(GreenElement::Node(Rc::new(node)));
+    }
+
+    pub fn finish(mut self) -> GreenNode {
+        while self.next < self.pieces.len() {
+            self.push_piece();
+        }
+        GreenNode::new(SyntaxKind::SourceFile, self.elements)
+    }
+
+    fn trivia(&mut self) {
+        while self.pieces.get(self.next).is_some_and(|piece| !piece.token) {
+            self.push_piece();
+        }
+    }
+
+    fn push_piece(&mut self) {
+        let green = self.pieces[self.next].green.clone();
+        self.starts.push(self.pos);
+        self.pos += green.text.len();
+        self.elements.push(GreenElement::Token(green));
+        self.next += 1;
+    }
+}
+
+// Splits `source` into pieces that cover every byte: the lexer's tokens,
+// and whitespace and comments in the gaps between them.
+fn tokenize(source: &str) -> Vec<Piece> {
+    let mut lexer = Lexer::new(source);
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.next_token() {
+        tokens.push(token);
+    }
+    let mut comments = lexer.comments.into_iter().peekable();
+
+    let mut pieces = Vec::new();
+    let mut push = |kind: SyntaxKind, start: usize, end: usize, token: bool| {
+        if start < end {
+            let text = source[start..end].to_string();
+            let green = Rc::new(GreenToken { kind, text });
+            pieces.push(Piece { token, green });
+        }
+    };
+
+    let mut pos = 0;
+    for (token, span) in tokens {
+        // the gap before each token holds whitespace and comments
+        while let Some(comment) = comments.next_if(|c| c.span.start < span.start) {
+            push(SyntaxKind::Whitespace, pos, comment.span.start, false);
+            push(
+                SyntaxKind::Comment,
+                comment.span.start,
+                comment.span.end,
+                false,
+            );
+            pos = comment.span.end;
+        }
+        push(SyntaxKind::Whitespace, pos, span.start, false);
+        let kind = token_kind(&token);
+        push(kind, span.start, span.end, true);
+        pos = span.end;
+    }
+    for comment in comments {
+        push(SyntaxKind::Whitespace, pos, comment.span.start, false);
+        push(
+            SyntaxKind::Comment,
+            comment.span.start,
+            comment.span.end,
+            false,
+        );
+        pos = comment.span.end;
+    }
+    push(SyntaxKind::Whitespace, pos, source.len(), false);
+
+    pieces
+}
+
+fn token_kind(token: &Token) -> SyntaxKind {
+    match token {
+        Token::Keyword(_) => SyntaxKind::Keyword,
+        Token::Ident(_) => SyntaxKind::Ident,
+        Token::Number(_) => SyntaxKind::Number,
+        Token::StringLiteral(_) => SyntaxKind::String,
+        Token::Type(_) => SyntaxKind::Type,
+        Token::Symbol(_) => SyntaxKind::Symbol,
+        Token::Unknown(_) => SyntaxKind::Unknown,
+    }
+}
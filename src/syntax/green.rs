@@ -0,0 +1,48 @@
+use std::rc::Rc;
+
+use crate::syntax::kind::SyntaxKind;
+
+/*
+Green nodes are the immutable, position-independent half of the tree: they
+only know their kind, their text width and their children. Positions come
+from the red nodes built over them.
+*/
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreenToken {
+    pub kind: SyntaxKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreenNode {
+    pub kind: SyntaxKind,
+    pub width: usize, // byte length of the text below this node
+    pub children: Vec<GreenElement>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+        let width = children.iter().map(GreenElement::width).sum();
+        GreenNode {
+            kind,
+            width,
+            children,
+        }
+    }
+}
+
+impl GreenElement {
+    pub fn width(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.width,
+            GreenElement::Token(token) => token.text.len(),
+        }
+    }
+}
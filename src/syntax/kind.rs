@@ -0,0 +1,73 @@
+use crate::parser::statement::{ExpressionKind, StatementKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    // tokens
+    Whitespace,
+    Comment,
+    Keyword,
+    Ident,
+    Number,
+    String,
+    Type,
+    Symbol,
+    Unknown,
+
+    // nodes
+    SourceFile,
+    Error, // tokens the parser could not make sense of
+    VarDeclaration,
+    FunctionCallStmt,
+    IfStmt,
+    MatchStmt,
+    MatchArm,
+    BlockStmt,
+    ImportStmt,
+    ExportStmt,
+    ExpressionStmt,
+    Pattern,
+    Param,
+    NameExpr,
+    NumberExpr,
+    StringExpr,
+    BooleanExpr,
+    BinaryExpr,
+    LogicalExpr,
+    ComparisonExpr,
+    FunctionCallExpr,
+    CallExpr,
+    ParenExpr,
+    LambdaExpr,
+    ConditionalExpr,
+}
+
+impl SyntaxKind {
+    pub fn of_statement(kind: &StatementKind) -> SyntaxKind {
+        match kind {
+            StatementKind::VarDeclaration { .. } => SyntaxKind::VarDeclaration,
+            StatementKind::FunctionCall { .. } => SyntaxKind::FunctionCallStmt,
+            StatementKind::If { .. } => SyntaxKind::IfStmt,
+            StatementKind::Match { .. } => SyntaxKind::MatchStmt,
+            StatementKind::Block(_) => SyntaxKind::BlockStmt,
+            StatementKind::Import { .. } => SyntaxKind::ImportStmt,
+            StatementKind::Export(_) => SyntaxKind::ExportStmt,
+            StatementKind::Expression(_) => SyntaxKind::ExpressionStmt,
+        }
+    }
+
+    pub fn of_expression(kind: &ExpressionKind) -> SyntaxKind {
+        match kind {
+            ExpressionKind::Identifier(_) => SyntaxKind::NameExpr,
+            ExpressionKind::Number(_) => SyntaxKind::NumberExpr,
+            ExpressionKind::StringLiteral(_) => SyntaxKind::StringExpr,
+            ExpressionKind::Boolean(_) => SyntaxKind::BooleanExpr,
+            ExpressionKind::BinaryOp(..) => SyntaxKind::BinaryExpr,
+            ExpressionKind::LogicalOp(..) => SyntaxKind::LogicalExpr,
+            ExpressionKind::ComparisonOp(..) => SyntaxKind::ComparisonExpr,
+            ExpressionKind::FunctionCall { .. } => SyntaxKind::FunctionCallExpr,
+            ExpressionKind::Call { .. } => SyntaxKind::CallExpr,
+            ExpressionKind::Lambda { .. } => SyntaxKind::LambdaExpr,
+            ExpressionKind::Conditional { .. } => SyntaxKind::ConditionalExpr,
+        }
+    }
+}
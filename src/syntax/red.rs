@@ -0,0 +1,117 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::syntax::green::{GreenElement, GreenNode, GreenToken};
+use crate::syntax::kind::SyntaxKind;
+
+/*
+Red nodes are cheap cursors over the green tree that add what green nodes
+leave out: the absolute offset. They are created on demand while walking
+down from the root.
+*/
+
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+struct NodeData {
+    green: Rc<GreenNode>,
+    offset: usize,
+}
+
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green: Rc<GreenToken>,
+    offset: usize,
+}
+
+#[derive(Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxNode {
+    pub fn new_root(green: GreenNode) -> SyntaxNode {
+        SyntaxNode(Rc::new(NodeData {
+            green: Rc::new(green),
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind
+    }
+
+    // Byte range of the node's text in the root's text.
+    pub fn range(&self) -> (usize, usize) {
+        (self.0.offset, self.0.offset + self.0.green.width)
+    }
+
+    pub fn children(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        let mut children = Vec::new();
+        for child in &self.0.green.children {
+            children.push(match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                    green: green.clone(),
+                    offset,
+                }))),
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: green.clone(),
+                    offset,
+                }),
+            });
+            offset += child.width();
+        }
+        children
+    }
+
+    fn fmt_tree(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        let (start, end) = self.range();
+        writeln!(
+            f,
+            "{}{:?}@{}..{}",
+            "  ".repeat(depth),
+            self.kind(),
+            start,
+            end
+        )?;
+        for child in self.children() {
+            match child {
+                SyntaxElement::Node(node) => node.fmt_tree(f, depth + 1)?,
+                SyntaxElement::Token(token) => writeln!(f, "{}{}", "  ".repeat(depth + 1), token)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.green.text
+    }
+
+    pub fn range(&self) -> (usize, usize) {
+        (self.offset, self.offset + self.green.text.len())
+    }
+}
+
+// Indented dump of the tree, one node or token per line:
+// VarDeclaration@0..27
+//   Keyword@0..3 "let"
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_tree(f, 0)
+    }
+}
+
+impl fmt::Display for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (start, end) = self.range();
+        write!(f, "{:?}@{}..{} {:?}", self.kind(), start, end, self.text())
+    }
+}
@@ -0,0 +1,57 @@
+// Compiles the programs under tests/asm to x86-64 assembly and compares the
+// output with the `.s` file next to each of them, or `.intel.s` for Intel
+// syntax. The assembly is not run.
+
+mod common;
+
+use common::{assert_snapshot, astc};
+
+fn golden(name: &str, syntax: &str) {
+    let extension = match syntax {
+        "intel" => "intel.s",
+        _ => "s",
+    };
+    assert_snapshot(
+        &[
+            "build",
+            "--emit",
+            "asm",
+            "--syntax",
+            syntax,
+            &format!("tests/asm/{}.astc", name),
+        ],
+        &format!("tests/asm/{}.{}", name, extension),
+    );
+}
+
+#[test]
+fn functions() {
+    golden("functions", "att");
+}
+
+#[test]
+fn functions_intel() {
+    golden("functions", "intel");
+}
+
+#[test]
+fn control_flow() {
+    golden("control", "att");
+}
+
+#[test]
+fn modules() {
+    golden("modules/main", "att");
+}
+
+#[test]
+fn unsupported_closure() {
+    let output = astc(&["build", "--emit", "asm", "examples/lambda.astc"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("A closure capturing `a` in `add` is not supported by the x86-64 backend"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,269 @@
+    .text
+
+    .globl main
+main:
+    pushq   %rbp
+    movq    %rsp, %rbp
+    pushq   %rbx
+    pushq   %r12
+    pushq   %r13
+    pushq   %r14
+    pushq   %r15
+    subq    $8, %rsp
+    movq    $250, %rbx
+    movq    $10, %r12
+    movq    %rbx, %rax
+    addq    %r12, %rax
+    movq    %rax, %r13
+    movq    %r13, %rax
+    movzbl  %al, %eax
+    movq    %rax, %rbx
+    movq    $3, %r12
+    movq    %rbx, %rax
+    cmpq    %r12, %rax
+    setg    %al
+    movzbl  %al, %eax
+    movq    %rax, %r13
+    cmpq    $0, %r13
+    je      .Lmain.bb2
+.Lmain.bb1:
+    movq    $1, %r12
+    movq    %r12, %r13
+    jmp     .Lmain.bb3
+.Lmain.bb2:
+    movq    $0, %r12
+    movq    $1, %r14
+    movq    %r12, %rax
+    subq    %r14, %rax
+    movq    %rax, %r15
+    movq    %r15, %r13
+.Lmain.bb3:
+    movq    $3, %r12
+    movq    %rbx, %rax
+    cmpq    %r12, %rax
+    setg    %al
+    movzbl  %al, %eax
+    movq    %rax, %r14
+    cmpq    $0, %r14
+    je      .Lmain.bb3.bb5
+.Lmain.bb4:
+    movq    $0, %r14
+    movq    %r13, %rax
+    cmpq    %r14, %rax
+    setl    %al
+    movzbl  %al, %eax
+    movq    %rax, %r15
+    movq    %r15, %r12
+.Lmain.bb5:
+    cmpq    $0, %r12
+    je      .Lmain.bb7
+.Lmain.bb6:
+    leaq    .Lstr0(%rip), %r14
+    movq    %r14, %rsi
+    leaq    .Lstr1(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+    jmp     .Lmain.bb10
+.Lmain.bb7:
+    movq    $4, %r14
+    movq    %rbx, %rax
+    cmpq    %r14, %rax
+    sete    %al
+    movzbl  %al, %eax
+    movq    %rax, %r15
+    cmpq    $0, %r15
+    je      .Lmain.bb9
+.Lmain.bb8:
+    leaq    .Lstr2(%rip), %r14
+    movq    %r14, %rsi
+    leaq    .Lstr3(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+    movq    %r13, %rsi
+    leaq    .Lstr4(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+.Lmain.bb9:
+.Lmain.bb10:
+    movq    $0, %r13
+    movq    %rbx, %rax
+    cmpq    %r13, %rax
+    setge   %al
+    movzbl  %al, %eax
+    movq    %rax, %r14
+    cmpq    $0, %r14
+    je      .Lmain.bb13
+.Lmain.bb11:
+    movq    $4, %r13
+    movq    %rbx, %rax
+    cmpq    %r13, %rax
+    setl    %al
+    movzbl  %al, %eax
+    movq    %rax, %r14
+    cmpq    $0, %r14
+    je      .Lmain.bb13
+.Lmain.bb12:
+    leaq    .Lstr5(%rip), %r13
+    movq    %r13, %rsi
+    leaq    .Lstr1(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+    jmp     .Lmain.bb18
+.Lmain.bb13:
+    movq    $4, %r13
+    movq    %rbx, %rax
+    cmpq    %r13, %rax
+    sete    %al
+    movzbl  %al, %eax
+    movq    %rax, %r14
+    cmpq    $0, %r14
+    jne     .Lmain.bb15
+.Lmain.bb14:
+    movq    $5, %r13
+    movq    %rbx, %rax
+    cmpq    %r13, %rax
+    sete    %al
+    movzbl  %al, %eax
+    movq    %rax, %r14
+    cmpq    $0, %r14
+    je      .Lmain.bb16
+.Lmain.bb15:
+    leaq    .Lstr6(%rip), %rbx
+    movq    %rbx, %rsi
+    leaq    .Lstr1(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+    jmp     .Lmain.bb18
+.Lmain.bb16:
+.Lmain.bb17:
+    leaq    .Lstr7(%rip), %rbx
+    movq    %rbx, %rsi
+    leaq    .Lstr1(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+.Lmain.bb18:
+    leaq    control.fn.clamp(%rip), %rbx
+    movq    $0, %r13
+    movq    $5, %r14
+    movq    %r13, %rax
+    subq    %r14, %rax
+    movq    %rax, %r15
+    movq    %r15, %rdi
+    call    control.fn.clamp
+    movq    %rax, %r13
+    movq    $50, %r14
+    movq    %r14, %rdi
+    call    control.fn.clamp
+    movq    %rax, %r15
+    movq    $500, %r14
+    movq    %r14, %rdi
+    call    control.fn.clamp
+    movq    %rax, -48(%rbp)
+    movq    %r13, %rsi
+    leaq    .Lstr8(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+    movq    %r15, %rsi
+    leaq    .Lstr8(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+    movq    -48(%rbp), %rsi
+    leaq    .Lstr8(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+    movq    %r12, %rax
+    leaq    .Ltrue(%rip), %rsi
+    leaq    .Lfalse(%rip), %rcx
+    testq   %rax, %rax
+    cmoveq  %rcx, %rsi
+    leaq    .Lstr1(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+    xorl    %eax, %eax
+    leaq    -40(%rbp), %rsp
+    popq    %r15
+    popq    %r14
+    popq    %r13
+    popq    %r12
+    popq    %rbx
+    popq    %rbp
+    ret
+.Lmain.bb3.bb5:
+    movq    %r14, %r12
+    jmp     .Lmain.bb5
+
+control.fn.clamp:
+    pushq   %rbp
+    movq    %rsp, %rbp
+    pushq   %rbx
+    pushq   %r12
+    pushq   %r13
+    pushq   %r14
+    movq    %rdi, %rbx
+    movq    %rbx, %rax
+    movswq  %ax, %rax
+    movq    %rax, %r12
+    movq    $0, %rbx
+    movq    %r12, %rax
+    cmpq    %rbx, %rax
+    setl    %al
+    movzbl  %al, %eax
+    movq    %rax, %r13
+    cmpq    $0, %r13
+    je      .Lcontrol.fn.clamp.bb2
+.Lcontrol.fn.clamp.bb1:
+    movq    $0, %rbx
+    movq    %rbx, %r13
+    jmp     .Lcontrol.fn.clamp.bb6
+.Lcontrol.fn.clamp.bb2:
+    movq    $100, %rbx
+    movq    %r12, %rax
+    cmpq    %rbx, %rax
+    setg    %al
+    movzbl  %al, %eax
+    movq    %rax, %r14
+    cmpq    $0, %r14
+    je      .Lcontrol.fn.clamp.bb4
+.Lcontrol.fn.clamp.bb3:
+    movq    $100, %rbx
+    movq    %rbx, %r14
+    jmp     .Lcontrol.fn.clamp.bb5
+.Lcontrol.fn.clamp.bb4:
+    movq    %r12, %r14
+.Lcontrol.fn.clamp.bb5:
+    movq    %r14, %r13
+.Lcontrol.fn.clamp.bb6:
+    movq    %r13, %rax
+    leaq    -32(%rbp), %rsp
+    popq    %r14
+    popq    %r13
+    popq    %r12
+    popq    %rbx
+    popq    %rbp
+    ret
+
+    .section .rodata
+.Lstr0:
+    .string "both"
+.Lstr1:
+    .string "%s\n"
+.Lstr2:
+    .string "four"
+.Lstr3:
+    .string "%s "
+.Lstr4:
+    .string "%ld\n"
+.Lstr5:
+    .string "low"
+.Lstr6:
+    .string "mid"
+.Lstr7:
+    .string "high"
+.Lstr8:
+    .string "%ld "
+.Ltrue:
+    .string "true"
+.Lfalse:
+    .string "false"
+
+    .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,306 @@
+    .intel_syntax noprefix
+    .text
+
+    .globl main
+main:
+    push    rbp
+    mov     rbp, rsp
+    push    rbx
+    push    r12
+    push    r13
+    push    r14
+    push    r15
+    sub     rsp, 56
+    lea     rbx, [rip+functions.fn.double]
+    lea     r12, [rip+functions.fn.fact]
+    mov     QWORD PTR [rip+functions.var.fact], r12
+    lea     r13, [rip+functions.fn.weigh]
+    lea     rax, [rip+functions.fn.half]
+    mov     QWORD PTR [rbp-48], rax
+    mov     r15, 21
+    mov     rdi, r15
+    call    functions.fn.double
+    mov     r14, rax
+    mov     rbx, 20
+    mov     rdi, rbx
+    call    functions.fn.fact
+    mov     r15, rax
+    mov     rsi, r14
+    lea     rdi, [rip+.Lstr0]
+    xor     eax, eax
+    call    printf
+    mov     rsi, r15
+    lea     rdi, [rip+.Lstr1]
+    xor     eax, eax
+    call    printf
+    lea     rax, [rip+.Lstr2]
+    mov     QWORD PTR [rbp-56], rax
+    mov     r12, 1
+    mov     r14, 2
+    mov     r15, 3
+    mov     rbx, 4
+    mov     QWORD PTR [rbp-64], 5
+    mov     QWORD PTR [rbp-72], 6
+    mov     QWORD PTR [rbp-80], 7
+    sub     rsp, 8
+    push    QWORD PTR [rbp-80]
+    mov     rdi, r12
+    mov     rsi, r14
+    mov     rdx, r15
+    mov     rcx, rbx
+    mov     r8, QWORD PTR [rbp-64]
+    mov     r9, QWORD PTR [rbp-72]
+    call    functions.fn.weigh
+    add     rsp, 16
+    mov     QWORD PTR [rbp-88], rax
+    mov     rsi, QWORD PTR [rbp-56]
+    lea     rdi, [rip+.Lstr3]
+    xor     eax, eax
+    call    printf
+    mov     rsi, QWORD PTR [rbp-88]
+    lea     rdi, [rip+.Lstr1]
+    xor     eax, eax
+    call    printf
+    movabs  rbx, 9000000000000000000
+    mov     rdi, rbx
+    call    functions.fn.half
+    mov     r12, rax
+    mov     rsi, r12
+    lea     rdi, [rip+.Lstr4]
+    xor     eax, eax
+    call    printf
+    xor     eax, eax
+    lea     rsp, QWORD PTR [rbp-40]
+    pop     r15
+    pop     r14
+    pop     r13
+    pop     r12
+    pop     rbx
+    pop     rbp
+    ret
+
+functions.fn.double:
+    push    rbp
+    mov     rbp, rsp
+    push    rbx
+    push    r12
+    push    r13
+    sub     rsp, 8
+    mov     rbx, rdi
+    mov     rax, rbx
+    mov     r12, rax
+    mov     rbx, 2
+    mov     rax, r12
+    imul    rax, rbx
+    mov     r13, rax
+    mov     rax, r13
+    lea     rsp, QWORD PTR [rbp-24]
+    pop     r13
+    pop     r12
+    pop     rbx
+    pop     rbp
+    ret
+
+functions.fn.fact:
+    push    rbp
+    mov     rbp, rsp
+    push    rbx
+    push    r12
+    push    r13
+    push    r14
+    push    r15
+    sub     rsp, 8
+    mov     rbx, rdi
+    mov     rax, rbx
+    mov     r12, rax
+    mov     rbx, 1
+    mov     rax, r12
+    cmp     rax, rbx
+    setle   al
+    movzx   eax, al
+    mov     r13, rax
+    cmp     r13, 0
+    je      .Lfunctions.fn.fact.bb2
+.Lfunctions.fn.fact.bb1:
+    mov     rbx, 1
+    mov     r13, rbx
+    jmp     .Lfunctions.fn.fact.bb3
+.Lfunctions.fn.fact.bb2:
+    mov     rbx, 1
+    mov     rax, r12
+    sub     rax, rbx
+    mov     r14, rax
+    mov     rbx, QWORD PTR [rip+functions.var.fact]
+    mov     rdi, r14
+    mov     r11, rbx
+    call    r11
+    mov     r15, rax
+    mov     rax, r12
+    imul    rax, r15
+    mov     rbx, rax
+    mov     r13, rbx
+.Lfunctions.fn.fact.bb3:
+    mov     rax, r13
+    lea     rsp, QWORD PTR [rbp-40]
+    pop     r15
+    pop     r14
+    pop     r13
+    pop     r12
+    pop     rbx
+    pop     rbp
+    ret
+
+functions.fn.weigh:
+    push    rbp
+    mov     rbp, rsp
+    push    rbx
+    push    r12
+    push    r13
+    push    r14
+    push    r15
+    sub     rsp, 56
+    mov     rbx, rdi
+    mov     r12, rsi
+    mov     r13, rdx
+    mov     r14, rcx
+    mov     r15, r8
+    mov     QWORD PTR [rbp-48], r9
+    mov     rax, QWORD PTR [rbp+16]
+    mov     QWORD PTR [rbp-56], rax
+    mov     rax, rbx
+    movsxd  rax, eax
+    mov     QWORD PTR [rbp-64], rax
+    mov     rax, r12
+    movsxd  rax, eax
+    mov     rbx, rax
+    mov     rax, r13
+    movsxd  rax, eax
+    mov     r12, rax
+    mov     rax, r14
+    movsxd  rax, eax
+    mov     r13, rax
+    mov     rax, r15
+    movsxd  rax, eax
+    mov     QWORD PTR [rbp-88], rax
+    mov     rax, QWORD PTR [rbp-48]
+    movsxd  rax, eax
+    mov     QWORD PTR [rbp-80], rax
+    mov     rax, QWORD PTR [rbp-56]
+    movsxd  rax, eax
+    mov     QWORD PTR [rbp-72], rax
+    mov     r15, 2
+    mov     rax, rbx
+    imul    rax, r15
+    mov     r14, rax
+    mov     rax, QWORD PTR [rbp-64]
+    add     rax, r14
+    mov     rbx, rax
+    mov     r14, 3
+    mov     rax, r12
+    imul    rax, r14
+    mov     r15, rax
+    mov     rax, rbx
+    add     rax, r15
+    mov     r12, rax
+    mov     rbx, 4
+    mov     rax, r13
+    imul    rax, rbx
+    mov     r14, rax
+    mov     rax, r12
+    add     rax, r14
+    mov     rbx, rax
+    mov     r12, 5
+    mov     rax, QWORD PTR [rbp-88]
+    imul    rax, r12
+    mov     r13, rax
+    mov     rax, rbx
+    add     rax, r13
+    mov     r12, rax
+    mov     rbx, 6
+    mov     rax, QWORD PTR [rbp-80]
+    imul    rax, rbx
+    mov     r13, rax
+    mov     rax, r12
+    add     rax, r13
+    mov     rbx, rax
+    mov     r12, 7
+    mov     rax, QWORD PTR [rbp-72]
+    imul    rax, r12
+    mov     r13, rax
+    mov     rax, rbx
+    add     rax, r13
+    mov     r12, rax
+    mov     rax, r12
+    lea     rsp, QWORD PTR [rbp-40]
+    pop     r15
+    pop     r14
+    pop     r13
+    pop     r12
+    pop     rbx
+    pop     rbp
+    ret
+
+functions.fn.half:
+    push    rbp
+    mov     rbp, rsp
+    push    rbx
+    push    r12
+    push    r13
+    sub     rsp, 8
+    mov     rbx, rdi
+    mov     rax, rbx
+    mov     r12, rax
+    mov     rbx, 2
+    mov     rcx, rbx
+    test    rcx, rcx
+    je      .Ldivide_by_zero0
+    mov     rax, r12
+    xor     edx, edx
+    div     rcx
+    mov     r13, rax
+    mov     rax, r13
+    lea     rsp, QWORD PTR [rbp-24]
+    pop     r13
+    pop     r12
+    pop     rbx
+    pop     rbp
+    ret
+
+.Ldivide_by_zero0:
+    lea     rsi, [rip+.Lstr6]
+    jmp     astc.divide_by_zero
+
+astc.divide_by_zero:
+    mov     rbx, rsi
+    xor     edi, edi
+    call    fflush
+    mov     edi, 2
+    lea     rsi, [rip+.Lstr5]
+    mov     rdx, rbx
+    xor     eax, eax
+    call    dprintf
+    mov     edi, 1
+    call    exit
+
+    .bss
+    .p2align 3
+functions.var.fact:
+    .zero 8
+
+    .section .rodata
+.Lstr0:
+    .string "%ld "
+.Lstr1:
+    .string "%ld\n"
+.Lstr2:
+    .string "weighed"
+.Lstr3:
+    .string "%s "
+.Lstr4:
+    .string "%lu\n"
+.Lstr5:
+    .string "Error: %s: Division by zero\n"
+.Lstr6:
+    .string "tests/asm/functions.astc"
+
+    .section .note.GNU-stack,"",@progbits
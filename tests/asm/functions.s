@@ -0,0 +1,305 @@
+    .text
+
+    .globl main
+main:
+    pushq   %rbp
+    movq    %rsp, %rbp
+    pushq   %rbx
+    pushq   %r12
+    pushq   %r13
+    pushq   %r14
+    pushq   %r15
+    subq    $56, %rsp
+    leaq    functions.fn.double(%rip), %rbx
+    leaq    functions.fn.fact(%rip), %r12
+    movq    %r12, functions.var.fact(%rip)
+    leaq    functions.fn.weigh(%rip), %r13
+    leaq    functions.fn.half(%rip), %rax
+    movq    %rax, -48(%rbp)
+    movq    $21, %r15
+    movq    %r15, %rdi
+    call    functions.fn.double
+    movq    %rax, %r14
+    movq    $20, %rbx
+    movq    %rbx, %rdi
+    call    functions.fn.fact
+    movq    %rax, %r15
+    movq    %r14, %rsi
+    leaq    .Lstr0(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+    movq    %r15, %rsi
+    leaq    .Lstr1(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+    leaq    .Lstr2(%rip), %rax
+    movq    %rax, -56(%rbp)
+    movq    $1, %r12
+    movq    $2, %r14
+    movq    $3, %r15
+    movq    $4, %rbx
+    movq    $5, -64(%rbp)
+    movq    $6, -72(%rbp)
+    movq    $7, -80(%rbp)
+    subq    $8, %rsp
+    pushq   -80(%rbp)
+    movq    %r12, %rdi
+    movq    %r14, %rsi
+    movq    %r15, %rdx
+    movq    %rbx, %rcx
+    movq    -64(%rbp), %r8
+    movq    -72(%rbp), %r9
+    call    functions.fn.weigh
+    addq    $16, %rsp
+    movq    %rax, -88(%rbp)
+    movq    -56(%rbp), %rsi
+    leaq    .Lstr3(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+    movq    -88(%rbp), %rsi
+    leaq    .Lstr1(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+    movabsq $9000000000000000000, %rbx
+    movq    %rbx, %rdi
+    call    functions.fn.half
+    movq    %rax, %r12
+    movq    %r12, %rsi
+    leaq    .Lstr4(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+    xorl    %eax, %eax
+    leaq    -40(%rbp), %rsp
+    popq    %r15
+    popq    %r14
+    popq    %r13
+    popq    %r12
+    popq    %rbx
+    popq    %rbp
+    ret
+
+functions.fn.double:
+    pushq   %rbp
+    movq    %rsp, %rbp
+    pushq   %rbx
+    pushq   %r12
+    pushq   %r13
+    subq    $8, %rsp
+    movq    %rdi, %rbx
+    movq    %rbx, %rax
+    movq    %rax, %r12
+    movq    $2, %rbx
+    movq    %r12, %rax
+    imulq   %rbx, %rax
+    movq    %rax, %r13
+    movq    %r13, %rax
+    leaq    -24(%rbp), %rsp
+    popq    %r13
+    popq    %r12
+    popq    %rbx
+    popq    %rbp
+    ret
+
+functions.fn.fact:
+    pushq   %rbp
+    movq    %rsp, %rbp
+    pushq   %rbx
+    pushq   %r12
+    pushq   %r13
+    pushq   %r14
+    pushq   %r15
+    subq    $8, %rsp
+    movq    %rdi, %rbx
+    movq    %rbx, %rax
+    movq    %rax, %r12
+    movq    $1, %rbx
+    movq    %r12, %rax
+    cmpq    %rbx, %rax
+    setle   %al
+    movzbl  %al, %eax
+    movq    %rax, %r13
+    cmpq    $0, %r13
+    je      .Lfunctions.fn.fact.bb2
+.Lfunctions.fn.fact.bb1:
+    movq    $1, %rbx
+    movq    %rbx, %r13
+    jmp     .Lfunctions.fn.fact.bb3
+.Lfunctions.fn.fact.bb2:
+    movq    $1, %rbx
+    movq    %r12, %rax
+    subq    %rbx, %rax
+    movq    %rax, %r14
+    movq    functions.var.fact(%rip), %rbx
+    movq    %r14, %rdi
+    movq    %rbx, %r11
+    call    *%r11
+    movq    %rax, %r15
+    movq    %r12, %rax
+    imulq   %r15, %rax
+    movq    %rax, %rbx
+    movq    %rbx, %r13
+.Lfunctions.fn.fact.bb3:
+    movq    %r13, %rax
+    leaq    -40(%rbp), %rsp
+    popq    %r15
+    popq    %r14
+    popq    %r13
+    popq    %r12
+    popq    %rbx
+    popq    %rbp
+    ret
+
+functions.fn.weigh:
+    pushq   %rbp
+    movq    %rsp, %rbp
+    pushq   %rbx
+    pushq   %r12
+    pushq   %r13
+    pushq   %r14
+    pushq   %r15
+    subq    $56, %rsp
+    movq    %rdi, %rbx
+    movq    %rsi, %r12
+    movq    %rdx, %r13
+    movq    %rcx, %r14
+    movq    %r8, %r15
+    movq    %r9, -48(%rbp)
+    movq    16(%rbp), %rax
+    movq    %rax, -56(%rbp)
+    movq    %rbx, %rax
+    movslq  %eax, %rax
+    movq    %rax, -64(%rbp)
+    movq    %r12, %rax
+    movslq  %eax, %rax
+    movq    %rax, %rbx
+    movq    %r13, %rax
+    movslq  %eax, %rax
+    movq    %rax, %r12
+    movq    %r14, %rax
+    movslq  %eax, %rax
+    movq    %rax, %r13
+    movq    %r15, %rax
+    movslq  %eax, %rax
+    movq    %rax, -88(%rbp)
+    movq    -48(%rbp), %rax
+    movslq  %eax, %rax
+    movq    %rax, -80(%rbp)
+    movq    -56(%rbp), %rax
+    movslq  %eax, %rax
+    movq    %rax, -72(%rbp)
+    movq    $2, %r15
+    movq    %rbx, %rax
+    imulq   %r15, %rax
+    movq    %rax, %r14
+    movq    -64(%rbp), %rax
+    addq    %r14, %rax
+    movq    %rax, %rbx
+    movq    $3, %r14
+    movq    %r12, %rax
+    imulq   %r14, %rax
+    movq    %rax, %r15
+    movq    %rbx, %rax
+    addq    %r15, %rax
+    movq    %rax, %r12
+    movq    $4, %rbx
+    movq    %r13, %rax
+    imulq   %rbx, %rax
+    movq    %rax, %r14
+    movq    %r12, %rax
+    addq    %r14, %rax
+    movq    %rax, %rbx
+    movq    $5, %r12
+    movq    -88(%rbp), %rax
+    imulq   %r12, %rax
+    movq    %rax, %r13
+    movq    %rbx, %rax
+    addq    %r13, %rax
+    movq    %rax, %r12
+    movq    $6, %rbx
+    movq    -80(%rbp), %rax
+    imulq   %rbx, %rax
+    movq    %rax, %r13
+    movq    %r12, %rax
+    addq    %r13, %rax
+    movq    %rax, %rbx
+    movq    $7, %r12
+    movq    -72(%rbp), %rax
+    imulq   %r12, %rax
+    movq    %rax, %r13
+    movq    %rbx, %rax
+    addq    %r13, %rax
+    movq    %rax, %r12
+    movq    %r12, %rax
+    leaq    -40(%rbp), %rsp
+    popq    %r15
+    popq    %r14
+    popq    %r13
+    popq    %r12
+    popq    %rbx
+    popq    %rbp
+    ret
+
+functions.fn.half:
+    pushq   %rbp
+    movq    %rsp, %rbp
+    pushq   %rbx
+    pushq   %r12
+    pushq   %r13
+    subq    $8, %rsp
+    movq    %rdi, %rbx
+    movq    %rbx, %rax
+    movq    %rax, %r12
+    movq    $2, %rbx
+    movq    %rbx, %rcx
+    testq   %rcx, %rcx
+    je      .Ldivide_by_zero0
+    movq    %r12, %rax
+    xorl    %edx, %edx
+    divq    %rcx
+    movq    %rax, %r13
+    movq    %r13, %rax
+    leaq    -24(%rbp), %rsp
+    popq    %r13
+    popq    %r12
+    popq    %rbx
+    popq    %rbp
+    ret
+
+.Ldivide_by_zero0:
+    leaq    .Lstr6(%rip), %rsi
+    jmp     astc.divide_by_zero
+
+astc.divide_by_zero:
+    movq    %rsi, %rbx
+    xorl    %edi, %edi
+    call    fflush
+    movl    $2, %edi
+    leaq    .Lstr5(%rip), %rsi
+    movq    %rbx, %rdx
+    xorl    %eax, %eax
+    call    dprintf
+    movl    $1, %edi
+    call    exit
+
+    .bss
+    .p2align 3
+functions.var.fact:
+    .zero 8
+
+    .section .rodata
+.Lstr0:
+    .string "%ld "
+.Lstr1:
+    .string "%ld\n"
+.Lstr2:
+    .string "weighed"
+.Lstr3:
+    .string "%s "
+.Lstr4:
+    .string "%lu\n"
+.Lstr5:
+    .string "Error: %s: Division by zero\n"
+.Lstr6:
+    .string "tests/asm/functions.astc"
+
+    .section .note.GNU-stack,"",@progbits
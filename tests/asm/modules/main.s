@@ -0,0 +1,125 @@
+    .text
+
+math.init:
+    pushq   %rbp
+    movq    %rsp, %rbp
+    pushq   %rbx
+    pushq   %r12
+    leaq    math.fn.square(%rip), %rbx
+    movq    %rbx, math.var.square(%rip)
+    movq    $250, %rbx
+    movq    %rbx, %rax
+    movzbl  %al, %eax
+    movq    %rax, %r12
+    movq    %r12, math.var.limit(%rip)
+    xorl    %eax, %eax
+    leaq    -16(%rbp), %rsp
+    popq    %r12
+    popq    %rbx
+    popq    %rbp
+    ret
+
+math.fn.square:
+    pushq   %rbp
+    movq    %rsp, %rbp
+    pushq   %rbx
+    pushq   %r12
+    movq    %rdi, %rbx
+    movq    %rbx, %rax
+    movslq  %eax, %rax
+    movq    %rax, %r12
+    movq    %r12, %rax
+    imulq   %r12, %rax
+    movq    %rax, %rbx
+    movq    %rbx, %rax
+    leaq    -16(%rbp), %rsp
+    popq    %r12
+    popq    %rbx
+    popq    %rbp
+    ret
+
+    .globl main
+main:
+    pushq   %rbp
+    movq    %rsp, %rbp
+    pushq   %rbx
+    pushq   %r12
+    pushq   %r13
+    pushq   %r14
+    call    math.init
+    leaq    main.fn.area(%rip), %rbx
+    movq    $5, %r12
+    movq    $3, %r13
+    movq    %r12, %rdi
+    movq    %r13, %rsi
+    call    main.fn.area
+    movq    %rax, %r14
+    movq    math.var.limit(%rip), %rbx
+    movq    %r14, %rsi
+    leaq    .Lstr0(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+    movq    %rbx, %rsi
+    leaq    .Lstr1(%rip), %rdi
+    xorl    %eax, %eax
+    call    printf
+    xorl    %eax, %eax
+    leaq    -32(%rbp), %rsp
+    popq    %r14
+    popq    %r13
+    popq    %r12
+    popq    %rbx
+    popq    %rbp
+    ret
+
+main.fn.area:
+    pushq   %rbp
+    movq    %rsp, %rbp
+    pushq   %rbx
+    pushq   %r12
+    pushq   %r13
+    pushq   %r14
+    movq    %rdi, %rbx
+    movq    %rsi, %r12
+    movq    %rbx, %rax
+    movslq  %eax, %rax
+    movq    %rax, %r13
+    movq    %r12, %rax
+    movslq  %eax, %rax
+    movq    %rax, %rbx
+    movq    math.var.square(%rip), %r12
+    movq    %r13, %rdi
+    movq    %r12, %r11
+    call    *%r11
+    movq    %rax, %r14
+    movq    math.var.square(%rip), %r12
+    movq    %rbx, %rdi
+    movq    %r12, %r11
+    call    *%r11
+    movq    %rax, %r13
+    movq    %r14, %rax
+    subq    %r13, %rax
+    movq    %rax, %rbx
+    movq    %rbx, %rax
+    leaq    -32(%rbp), %rsp
+    popq    %r14
+    popq    %r13
+    popq    %r12
+    popq    %rbx
+    popq    %rbp
+    ret
+
+    .bss
+    .p2align 3
+math.var.square:
+    .zero 8
+math.var.limit:
+    .zero 8
+
+    .section .rodata
+.Lstr0:
+    .string "%ld "
+.Lstr1:
+    .string "%ld\n"
+
+    .section .note.GNU-stack,"",@progbits
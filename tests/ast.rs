@@ -0,0 +1,38 @@
+// Prints examples with `astc parse --format sexpr` and `--format dot` and
+// compares the output with the snapshots under tests/ast.
+
+mod common;
+
+use common::assert_snapshot;
+
+fn snapshot(name: &str) {
+    for format in ["sexpr", "dot"] {
+        assert_snapshot(
+            &[
+                "parse",
+                "--format",
+                format,
+                &format!("examples/{}.astc", name),
+            ],
+            &format!("tests/ast/{}.{}", name, format),
+        );
+    }
+}
+
+// `else if` chains and the `then` and `else` edges
+#[test]
+fn if_else() {
+    snapshot("if");
+}
+
+// alternatives, ranges, wildcards and booleans in patterns
+#[test]
+fn match_patterns() {
+    snapshot("match");
+}
+
+// parameters, curried and recursive lambdas, calls of calls
+#[test]
+fn lambdas() {
+    snapshot("lambda");
+}
@@ -0,0 +1,93 @@
+// Emits C with `astc build --emit c`, compiles it with warnings as errors
+// and runs it, which must print what `astc run` prints. Skipped when there
+// is no `cc`.
+
+mod common;
+
+use std::fs;
+use std::process::Command;
+
+use common::{astc, astc_text, scratch};
+
+fn cc_available() -> bool {
+    Command::new("cc").arg("--version").output().is_ok()
+}
+
+// Compiles the C for `source` and returns what the program prints.
+fn compile_and_run(name: &str, source: &str) -> String {
+    let c = scratch(&format!("c_{}.c", name));
+    let program = scratch(&format!("c_{}", name));
+    let build = astc(&["build", "--emit", "c", "-o", &c, source]);
+    assert!(
+        build.status.success(),
+        "{}",
+        String::from_utf8_lossy(&build.stderr)
+    );
+
+    let cc = Command::new("cc")
+        .args([
+            "-std=c99", "-Wall", "-Wextra", "-Werror", "-o", &program, &c, "-lm",
+        ])
+        .output()
+        .expect("failed to run cc");
+    assert!(
+        cc.status.success(),
+        "{} does not compile cleanly:\n{}",
+        source,
+        String::from_utf8_lossy(&cc.stderr)
+    );
+
+    let output = Command::new(&program).output().expect("failed to run");
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn assert_runs_the_same(name: &str, source: &str) {
+    let (expected, _) = astc_text(&["run", source]);
+    assert_eq!(
+        compile_and_run(name, source),
+        expected,
+        "{} prints something else",
+        source
+    );
+}
+
+#[test]
+fn examples_print_what_run_prints() {
+    if !cc_available() {
+        eprintln!("cc not found, skipping");
+        return;
+    }
+    for name in ["conditional", "example", "if", "lambda", "match"] {
+        assert_runs_the_same(name, &format!("examples/{}.astc", name));
+    }
+    assert_runs_the_same("modules", "examples/modules/main.astc");
+    for name in ["control", "functions", "integers"] {
+        assert_runs_the_same(name, &format!("tests/wat/{}.astc", name));
+    }
+}
+
+// `let x = x + 1;` reads the outer `x`, in a plain local and in a cell a
+// closure captures.
+#[test]
+fn shadowing_reads_the_outer_variable() {
+    if !cc_available() {
+        eprintln!("cc not found, skipping");
+        return;
+    }
+    let source = scratch("c_shadowing.astc");
+    fs::write(
+        &source,
+        "let x = 1;\nif (x == 1) {\n    let x = x + 1;\n    const f = () => x * 10;\n    print(x, f());\n    {\n        let x: u8 = x + 253;\n        print(x);\n    }\n}\nprint(x);\n",
+    )
+    .unwrap();
+    assert_runs_the_same("shadowing", &source);
+    assert_eq!(compile_and_run("shadowing", &source), "2 20\n255\n1\n");
+}
+
+// Locals that are never read are cast to void rather than left to warn.
+#[test]
+fn unused_locals_are_voided() {
+    let (code, _) = astc_text(&["build", "--emit", "c", "examples/if.astc"]);
+    let main = &code[code.find("int main(void)").expect("no main")..];
+    assert_eq!(main.matches("(void)x;").count(), 3, "{}", main);
+}
@@ -0,0 +1,55 @@
+// Checks the exit status of the commands: 1 whenever they report an error.
+
+mod common;
+
+use std::fs;
+
+use common::{astc, scratch};
+
+fn status(args: &[&str]) -> Option<i32> {
+    astc(args).status.code()
+}
+
+#[test]
+fn errors_exit_with_one() {
+    let bad = scratch("cli_bad.astc");
+    fs::write(&bad, "let a: i32 = \"a\";\nprint(a);\n").unwrap();
+    let zero = scratch("cli_zero.astc");
+    fs::write(&zero, "let a = 0;\nprint(1 / a);\n").unwrap();
+    let missing = scratch("cli_missing.astc");
+
+    for command in ["parse", "run", "ir", "disasm"] {
+        assert_eq!(
+            status(&[command, &bad]),
+            Some(1),
+            "{} with an error",
+            command
+        );
+        assert_eq!(
+            status(&[command, &missing]),
+            Some(1),
+            "{} without a file",
+            command
+        );
+    }
+    assert_eq!(status(&["run", &zero]), Some(1));
+    assert_eq!(status(&["run", "--vm", &zero]), Some(1));
+    assert_eq!(
+        status(&["parse", "--format", "xml", "examples/if.astc"]),
+        Some(1)
+    );
+    assert_eq!(status(&["compile", "examples/if.astc"]), Some(1));
+}
+
+#[test]
+fn success_exits_with_zero() {
+    for command in ["parse", "run", "ir", "disasm"] {
+        assert_eq!(
+            status(&[command, "examples/if.astc"]),
+            Some(0),
+            "{}",
+            command
+        );
+    }
+    assert_eq!(status(&["run", "--vm", "examples/if.astc"]), Some(0));
+}
@@ -0,0 +1,72 @@
+// Helpers shared by the integration tests. Every test binary compiles its own
+// copy of this module and uses only some of it.
+#![allow(dead_code)]
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+// Runs the compiler from the manifest directory, so paths in `args` are
+// relative to it.
+pub fn astc(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_astc"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("failed to run astc")
+}
+
+// Like `astc(args)`, with `input` written to stdin and then closed.
+pub fn astc_with_input(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_astc"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run astc");
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    stdin.write_all(input.as_bytes()).unwrap();
+    drop(stdin);
+    child.wait_with_output().expect("failed to run astc")
+}
+
+// stdout and stderr of `astc(args)`, as text.
+pub fn astc_text(args: &[&str]) -> (String, String) {
+    let output = astc(args);
+    (
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+// A path for a file a test writes, in cargo's scratch directory.
+pub fn scratch(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    path.to_string_lossy().into_owned()
+}
+
+// Runs `astc(args)`, which must succeed, and compares what it prints with
+// the file at `expected`, relative to the manifest directory.
+pub fn assert_snapshot(args: &[&str], expected: &str) {
+    let output = astc(args);
+    let command = args.join(" ");
+    assert!(
+        output.status.success(),
+        "astc {} failed:\n{}",
+        command,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let expected_path = format!("{}/{}", env!("CARGO_MANIFEST_DIR"), expected);
+    let expected_text = fs::read_to_string(&expected_path)
+        .unwrap_or_else(|_| panic!("missing snapshot {}", expected));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        expected_text,
+        "astc {} differs from {}",
+        command,
+        expected
+    );
+}
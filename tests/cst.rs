@@ -0,0 +1,165 @@
+// Builds the lossless tree with `astc parse --format cst` and checks that
+// its tokens are the file, byte for byte, and that it has a node for every
+// node of the AST.
+
+mod common;
+
+use std::fs;
+
+use common::{astc_text, scratch};
+
+const SOURCES: [(&str, &str); 4] = [
+    (
+        "comments",
+        "// header\n\nlet x: i32 = (1 + 2) * 3; // trailing\nprint( x ,\t// argument\n  x);\n// end",
+    ),
+    (
+        "whitespace",
+        "  \r\nconst f = ( a: i32 ,b )  =>\n\n  a > b ? a : b ;\r\n\tprint(f(1, 2), (f)(3, 4));\n\n\n",
+    ),
+    (
+        "blocks",
+        "if (true) {\n    // inside\n    print(\"ä\");\n} else {\n}\nmatch (2) { 1 | 2 => { print(1); }, 3..=5 => { } _ => { } }\nexport let z = if (true) { 1 } else { 2 };\n",
+    ),
+    (
+        "errors",
+        "let a = 1 +;\n}\nif (a) { let b = ; print(b); }\nlet c = 1 & 2;\n;\nprint((a)\nlet s = \"unterminated",
+    ),
+];
+
+fn examples() -> Vec<(String, String)> {
+    ["conditional", "example", "if", "lambda", "match"]
+        .iter()
+        .map(|name| {
+            let path = format!("examples/{}.astc", name);
+            let source =
+                fs::read_to_string(format!("{}/{}", env!("CARGO_MANIFEST_DIR"), path)).unwrap();
+            (path, source)
+        })
+        .collect()
+}
+
+fn sources() -> Vec<(String, String)> {
+    let mut sources = examples();
+    for (name, source) in SOURCES {
+        let path = scratch(&format!("cst_{}.astc", name));
+        fs::write(&path, source).unwrap();
+        sources.push((path, source.to_string()));
+    }
+    sources
+}
+
+// The tokens of a `--format cst` dump, e.g. `Ident@4..5 "x"`, as their
+// range and quoted text.
+fn tokens(dump: &str) -> Vec<(usize, usize, &str)> {
+    dump.lines()
+        .filter_map(|line| {
+            let (_, rest) = line.trim_start().split_once('@')?;
+            let (range, text) = rest.split_once(' ')?;
+            let (start, end) = range.split_once("..")?;
+            Some((start.parse().unwrap(), end.parse().unwrap(), text))
+        })
+        .collect()
+}
+
+#[test]
+fn tokens_cover_the_source() {
+    for (path, source) in sources() {
+        let (dump, _) = astc_text(&["parse", "--format", "cst", &path]);
+        let mut pos = 0;
+        for (start, end, text) in tokens(&dump) {
+            assert_eq!(start, pos, "gap before {} in {}", text, path);
+            assert_eq!(
+                text,
+                format!("{:?}", &source[start..end]),
+                "token at {} in {}",
+                start,
+                path
+            );
+            pos = end;
+        }
+        assert_eq!(pos, source.len(), "{} ends early", path);
+    }
+}
+
+// The ranges of the nodes in a `--format cst` dump, e.g. `NameExpr@4..5`.
+fn nodes(dump: &str) -> Vec<(usize, usize)> {
+    dump.lines()
+        .filter_map(|line| {
+            let (_, range) = line.trim_start().split_once('@')?;
+            let (start, end) = range.split_once("..")?;
+            Some((start.parse().ok()?, end.parse().ok()?))
+        })
+        .collect()
+}
+
+// The spans of the nodes in a `--format json` dump, as byte ranges.
+fn spans(json: &str) -> Vec<(usize, usize)> {
+    let lines: Vec<&str> = json.lines().map(str::trim).collect();
+    let number = |line: &str, key: &str| -> usize {
+        let value = line.strip_prefix(key).expect("a span field");
+        value.trim_end_matches(',').parse().unwrap()
+    };
+    lines
+        .windows(3)
+        .filter(|window| window[0] == "\"span\": {")
+        .map(|window| {
+            (
+                number(window[1], "\"start\": "),
+                number(window[2], "\"end\": "),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn ast_nodes_have_tree_nodes() {
+    let empty = scratch("cst_no_edits.json");
+    fs::write(&empty, "[]").unwrap();
+
+    for (path, _) in sources() {
+        let (json, _) = astc_text(&["parse", "--format", "json", &path]);
+        let (dump, diagnostics) = astc_text(&["parse", "--format", "cst", &path]);
+        let nodes = nodes(&dump);
+        for span in spans(&json) {
+            assert!(nodes.contains(&span), "no node at {:?} in {}", span, path);
+        }
+
+        // the parser's diagnostics, without the checker's
+        let (_, expected) = astc_text(&["parse", "--edits", &empty, &path]);
+        assert_eq!(diagnostics, expected, "{}", path);
+    }
+}
+
+#[test]
+fn errors_keep_their_tokens() {
+    let path = scratch("cst_stray.astc");
+    fs::write(&path, "let a = 1 +;\nprint(a);\n").unwrap();
+    let (dump, _) = astc_text(&["parse", "--format", "cst", &path]);
+    let expected = "SourceFile@0..23
+  Error@0..12
+    Keyword@0..3 \"let\"
+    Whitespace@3..4 \" \"
+    Ident@4..5 \"a\"
+    Whitespace@5..6 \" \"
+    Symbol@6..7 \"=\"
+    Whitespace@7..8 \" \"
+    NumberExpr@8..9
+      Number@8..9 \"1\"
+    Whitespace@9..10 \" \"
+    Symbol@10..11 \"+\"
+    Symbol@11..12 \";\"
+  Whitespace@12..13 \"\\n\"
+  FunctionCallStmt@13..22
+    FunctionCallExpr@13..21
+      NameExpr@13..18
+        Ident@13..18 \"print\"
+      Symbol@18..19 \"(\"
+      NameExpr@19..20
+        Ident@19..20 \"a\"
+      Symbol@20..21 \")\"
+    Symbol@21..22 \";\"
+  Whitespace@22..23 \"\\n\"
+";
+    assert_eq!(dump, expected);
+}
@@ -0,0 +1,111 @@
+// Formats files with `astc fmt`, which rewrites them in place, and checks
+// where comments end up and that a second run changes nothing.
+
+mod common;
+
+use std::fs;
+
+use common::{astc, scratch};
+
+fn format(name: &str, source: &str) -> String {
+    let path = scratch(&format!("fmt_{}.astc", name));
+    fs::write(&path, source).unwrap();
+    let output = astc(&["fmt", &path]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let formatted = fs::read_to_string(&path).unwrap();
+
+    let check = astc(&["fmt", "--check", &path]);
+    assert!(
+        check.status.success(),
+        "formatting {} again changes it:\n{}",
+        name,
+        formatted
+    );
+    formatted
+}
+
+#[test]
+fn comments_in_arguments() {
+    let formatted = format(
+        "arguments",
+        "print(1, // first\n  2 // second\n);\nprint(3,\n// before\n4);\n",
+    );
+    assert_eq!(
+        formatted,
+        "print(\n    1, // first\n    2, // second\n);\nprint(\n    3,\n    // before\n    4,\n);\n"
+    );
+}
+
+#[test]
+fn comments_in_nested_calls() {
+    let formatted = format(
+        "nested",
+        "const add = (a: i32, b: i32) => a + b;\nprint(add(1, // inner\n2), 3);\nprint(\n// nothing\n);\n",
+    );
+    assert_eq!(
+        formatted,
+        "const add = (a: i32, b: i32) => a + b;\nprint(\n    add(\n        1, // inner\n        2,\n    ),\n    3,\n);\nprint(\n    // nothing\n);\n"
+    );
+}
+
+#[test]
+fn comments_in_expressions() {
+    let formatted = format(
+        "expressions",
+        "let x = 1 + // why\n2;\nlet f = (a: i32, // left\nb: i32) => // body\na * b;\nlet y = x > 2 ? // big\n1 : 2;\nprint(f(x, y)); // done\n",
+    );
+    assert_eq!(
+        formatted,
+        "let x = 1 + // why\n    2;\nlet f = (a: i32, // left\n    b: i32) => // body\n    a * b;\nlet y = x > 2 ? // big\n    1 : 2;\nprint(f(x, y)); // done\n"
+    );
+}
+
+#[test]
+fn comments_between_statements() {
+    // runs of blank lines shrink to one
+    let source = "// header\n\nlet a = 1; // one\n\n\n// two\nlet b = 2;\nif (a < b) {\n    // inside\n    print(a);\n}\n// end\n";
+    assert_eq!(
+        format("statements", source),
+        source.replace("\n\n\n", "\n\n")
+    );
+}
+
+// A comment after a closing `}` stays after it rather than moving into the
+// block; one before an `else` opens the `else` block.
+#[test]
+fn comments_after_blocks() {
+    let formatted = format(
+        "after_blocks",
+        "if (true) { print(1); } // after if\nmatch (1) { 1 => { print(2); } } // after match\n{ print(3); } // after block\nif (false) { print(4); } // before else\nelse { print(5); }\n",
+    );
+    assert_eq!(
+        formatted,
+        "if (true) {\n    print(1);\n} // after if\nmatch (1) {\n    1 => {\n        print(2);\n    }\n} // after match\n{\n    print(3);\n} // after block\nif (false) {\n    print(4);\n} else {\n    // before else\n    print(5);\n}\n"
+    );
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    for name in ["conditional", "example", "if", "lambda", "match"] {
+        let source = fs::read_to_string(format!(
+            "{}/examples/{}.astc",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        ))
+        .unwrap();
+        format(name, &source);
+    }
+}
+
+#[test]
+fn unformatted_files_fail_the_check() {
+    let path = scratch("fmt_unformatted.astc");
+    fs::write(&path, "let a=1;\n").unwrap();
+    let output = astc(&["fmt", "--check", &path]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "let a=1;\n");
+}
@@ -0,0 +1,168 @@
+// Runs every `.astc` file under tests/ and examples/ through the lexer, the
+// parser and checker, and the interpreter, and compares what they print
+// with the `.expected` file next to it:
+//
+//     cargo test --test golden               # check every file
+//     cargo test --test golden -- if         # only paths containing `if`
+//     cargo test --test golden -- --skip wat # all but paths containing `wat`
+//     cargo test --test golden -- --bless    # rewrite the `.expected` files
+//
+// Each `.expected` file has a section per stage, headed `== tokens ==`,
+// `== ast ==`, `== diagnostics ==` and `== output ==`.
+
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use common::astc_text as astc;
+
+const ROOTS: &[&str] = &["tests", "examples"];
+
+// libtest flags besides `--skip` whose value comes as the next argument,
+// which is not a filter
+const FLAGS_WITH_VALUES: &[&str] = &[
+    "--test-threads",
+    "--format",
+    "--color",
+    "--logfile",
+    "--shuffle-seed",
+    "-Z",
+];
+
+fn main() {
+    let mut bless = false;
+    let mut filters = Vec::new();
+    let mut skips = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bless" => bless = true,
+            "--skip" => skips.extend(args.next()),
+            // flags cargo passes to test binaries
+            _ if FLAGS_WITH_VALUES.contains(&arg.as_str()) => {
+                args.next();
+            }
+            _ if arg.starts_with('-') => {}
+            _ => filters.push(arg),
+        }
+    }
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut files = Vec::new();
+    for dir in ROOTS {
+        discover(&root.join(dir), &mut files);
+    }
+    files.sort();
+
+    let mut failures = 0;
+    let mut checked = 0;
+    for file in files {
+        let relative = file
+            .strip_prefix(root)
+            .expect("under the manifest directory")
+            .to_string_lossy()
+            .replace('\\', "/");
+        if !filters.is_empty() && !filters.iter().any(|f| relative.contains(f.as_str())) {
+            continue;
+        }
+        if skips.iter().any(|s| relative.contains(s.as_str())) {
+            continue;
+        }
+        checked += 1;
+        let actual = snapshot(&relative);
+        let expected_path = file.with_extension("expected");
+        if bless {
+            fs::write(&expected_path, &actual).expect("failed to write snapshot");
+            println!("blessed {}", relative);
+            continue;
+        }
+        match fs::read_to_string(&expected_path) {
+            Ok(expected) if expected == actual => println!("ok      {}", relative),
+            Ok(expected) => {
+                failures += 1;
+                println!("FAILED  {}", relative);
+                print_difference(&expected, &actual);
+            }
+            Err(_) => {
+                failures += 1;
+                println!("FAILED  {} has no .expected file", relative);
+            }
+        }
+    }
+
+    println!();
+    if bless {
+        println!("{} snapshots written", checked);
+    } else if failures > 0 {
+        println!(
+            "{} of {} golden files differ; run `cargo test --test golden -- --bless` to update them",
+            failures, checked
+        );
+        process::exit(1);
+    } else {
+        println!("{} golden files match", checked);
+    }
+}
+
+fn discover(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            discover(&path, files);
+        } else if path.extension().is_some_and(|e| e == "astc") {
+            files.push(path);
+        }
+    }
+}
+
+// Everything the stages print for a file, with the interpreter's runtime
+// errors after its output.
+fn snapshot(path: &str) -> String {
+    let (tokens, _) = astc(&["tokens", path]);
+    let (ast, diagnostics) = astc(&["parse", path]);
+    let (output, errors) = astc(&["run", path]);
+    // `run` repeats the diagnostics before running
+    let errors = errors.strip_prefix(diagnostics.as_str()).unwrap_or(&errors);
+
+    let mut snapshot = String::new();
+    for (name, text) in [
+        ("tokens", tokens.as_str()),
+        ("ast", ast.as_str()),
+        ("diagnostics", diagnostics.as_str()),
+        ("output", &format!("{}{}", output, errors)),
+    ] {
+        snapshot.push_str(&format!("== {} ==\n", name));
+        snapshot.push_str(text);
+        if !text.is_empty() && !text.ends_with('\n') {
+            snapshot.push('\n');
+        }
+    }
+    snapshot
+}
+
+// The first line that differs, with its section.
+fn print_difference(expected: &str, actual: &str) {
+    let mut section = "";
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    for number in 1.. {
+        let (e, a) = (expected_lines.next(), actual_lines.next());
+        if e.is_none() && a.is_none() {
+            return;
+        }
+        if let Some(line) = a.filter(|line| line.starts_with("== ")) {
+            section = line;
+        }
+        if e != a {
+            println!("        {} line {}", section, number);
+            println!("        expected: {}", e.unwrap_or("<end of file>"));
+            println!("        actual:   {}", a.unwrap_or("<end of file>"));
+            return;
+        }
+    }
+}
@@ -0,0 +1,264 @@
+// Applies edits to a file with `astc parse --edits`, which reparses
+// incrementally, and checks that the AST, node ids and spans included, and
+// the diagnostics are the same as parsing the edited text from scratch.
+
+mod common;
+
+use std::fs;
+
+use common::{astc, scratch};
+
+// (start, end, text), with byte offsets into the text the edits before
+// left.
+type Edit<'a> = (usize, usize, &'a str);
+
+fn apply(source: &str, edits: &[Edit]) -> String {
+    let mut text = source.to_string();
+    for &(start, end, replacement) in edits {
+        text.replace_range(start..end, replacement);
+    }
+    text
+}
+
+fn edits_json(edits: &[Edit]) -> String {
+    let edits: Vec<String> = edits
+        .iter()
+        .map(|(start, end, text)| {
+            format!(r#"{{"start":{},"end":{},"text":{:?}}}"#, start, end, text)
+        })
+        .collect();
+    format!("[{}]", edits.join(","))
+}
+
+// Checks the incremental result against a full parse and returns what was
+// relexed and reparsed for each edit.
+fn assert_same(name: &str, source: &str, edits: &[Edit]) -> Vec<String> {
+    let path = scratch(&format!("{}.astc", name));
+    let edits_path = scratch(&format!("{}.json", name));
+    let final_path = scratch(&format!("{}.final.astc", name));
+    fs::write(&path, source).unwrap();
+    fs::write(&edits_path, edits_json(edits)).unwrap();
+    let text = apply(source, edits);
+    fs::write(&final_path, &text).unwrap();
+
+    let incremental = astc(&["parse", "--format", "json", "--edits", &edits_path, &path]);
+    let full = astc(&["parse", "--format", "json", &final_path]);
+    assert_eq!(
+        String::from_utf8_lossy(&incremental.stdout),
+        String::from_utf8_lossy(&full.stdout),
+        "the AST of {:?} differs",
+        text
+    );
+
+    // the lossless tree reports the parser's diagnostics only
+    let full = astc(&["parse", "--format", "cst", &final_path]);
+    // and both fail if there are errors
+    assert_eq!(
+        incremental.status.code(),
+        full.status.code(),
+        "{}",
+        String::from_utf8_lossy(&incremental.stderr)
+    );
+    let expected = String::from_utf8_lossy(&full.stderr).replace(&final_path, &path);
+    let stderr = String::from_utf8_lossy(&incremental.stderr);
+    let (stats, diagnostics): (Vec<&str>, Vec<&str>) =
+        stderr.lines().partition(|line| line.starts_with("edit "));
+    assert_eq!(
+        diagnostics,
+        expected.lines().collect::<Vec<_>>(),
+        "the diagnostics of {:?} differ",
+        text
+    );
+    stats.iter().map(|line| line.to_string()).collect()
+}
+
+const PROGRAM: &str = "let a = 1;\nlet b = a + 2;\nprint(b);\nlet c = (x) => x * b;\n";
+
+#[test]
+fn edit_reparses_one_statement() {
+    // `a + 2` to `a + 20`
+    let stats = assert_same("one_statement", PROGRAM, &[(23, 24, "20")]);
+    assert_eq!(
+        stats,
+        ["edit 1: relexed 1 tokens, reparsed 1 of 4 statements"]
+    );
+}
+
+#[test]
+fn inserted_lines_move_later_statements() {
+    let stats = assert_same(
+        "inserted_lines",
+        PROGRAM,
+        &[(11, 11, "let d = \"é\";\n\n"), (0, 3, "const")],
+    );
+    assert_eq!(
+        stats,
+        [
+            "edit 1: relexed 5 tokens, reparsed 1 of 5 statements",
+            "edit 2: relexed 1 tokens, reparsed 1 of 5 statements",
+        ]
+    );
+}
+
+#[test]
+fn whitespace_moves_statements() {
+    // the `;` touching the edit is relexed, and the statement it ends read
+    // the `let` that moved
+    let stats = assert_same("whitespace", PROGRAM, &[(10, 10, "\n   "), (4, 4, " ")]);
+    assert_eq!(
+        stats,
+        [
+            "edit 1: relexed 1 tokens, reparsed 1 of 4 statements",
+            "edit 2: relexed 0 tokens, reparsed 1 of 4 statements",
+        ]
+    );
+}
+
+#[test]
+fn tokens_merge_and_split() {
+    assert_same(
+        "merge_and_split",
+        PROGRAM,
+        &[
+            // `let a` to `leta`, then back
+            (3, 4, ""),
+            (3, 3, " "),
+            // `print(b)` to `print(bb)`
+            (33, 33, "b"),
+        ],
+    );
+}
+
+#[test]
+fn unclosed_string_swallows_the_rest() {
+    let stats = assert_same("unclosed_string", PROGRAM, &[(8, 9, "\"1"), (10, 10, "\"")]);
+    assert_eq!(
+        stats,
+        [
+            "edit 1: relexed 1 tokens, reparsed 1 of 1 statements",
+            "edit 2: relexed 26 tokens, reparsed 4 of 4 statements",
+        ]
+    );
+}
+
+#[test]
+fn comments_hide_statements() {
+    assert_same(
+        "comments",
+        PROGRAM,
+        &[(11, 11, "// "), (11, 14, ""), (20, 20, "//")],
+    );
+}
+
+#[test]
+fn broken_statements_recover() {
+    assert_same(
+        "broken",
+        PROGRAM,
+        &[
+            // drop the `;` after `a + 2`
+            (24, 25, ""),
+            (25, 25, "}"),
+            (25, 26, ";"),
+            (24, 24, ";"),
+        ],
+    );
+}
+
+#[test]
+fn lambda_lookahead_reaches_later_statements() {
+    // looking for the `)` that would make `(x` a lambda reads to the end,
+    // so adding a statement there reparses the first one too
+    let edit = [(23, 23, "print(d);\n")];
+    let stats = assert_same("lookahead", "let f = (x;\nlet d = 1;\n", &edit);
+    assert_eq!(
+        stats,
+        ["edit 1: relexed 5 tokens, reparsed 3 of 3 statements"]
+    );
+
+    let edit = [(24, 24, "print(d);\n")];
+    let stats = assert_same("no_lookahead", "let f = (x);\nlet d = 1;\n", &edit);
+    assert_eq!(
+        stats,
+        ["edit 1: relexed 5 tokens, reparsed 2 of 3 statements"]
+    );
+}
+
+#[test]
+fn errors_at_the_end_follow_it() {
+    assert_same(
+        "end",
+        "let a = (1 + \n",
+        &[(14, 14, "\n\n// done"), (0, 0, "print(1);\n")],
+    );
+}
+
+#[test]
+fn random_edits_match_a_full_parse() {
+    const SNIPPETS: &[&str] = &[
+        ";",
+        "(",
+        ")",
+        "{",
+        "}",
+        "\"",
+        "//",
+        "\n",
+        " ",
+        "x",
+        "=>",
+        "let",
+        "else",
+        "let q = 1;",
+        "print(q);",
+        "match (q) { 1 => {} }",
+        "é",
+    ];
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut random = |bound: usize| {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 33) as usize % bound.max(1)
+    };
+    for example in ["if", "match", "lambda", "conditional", "example"] {
+        let source = fs::read_to_string(format!(
+            "{}/examples/{}.astc",
+            env!("CARGO_MANIFEST_DIR"),
+            example
+        ))
+        .unwrap();
+        let mut text = source.clone();
+        let mut edits = Vec::new();
+        for step in 0..15 {
+            let mut start = random(text.len() + 1);
+            while !text.is_char_boundary(start) {
+                start -= 1;
+            }
+            let mut end = (start + [0, 0, 1, 3, 12][random(5)]).min(text.len());
+            while !text.is_char_boundary(end) {
+                end += 1;
+            }
+            let replacement = SNIPPETS[random(SNIPPETS.len())];
+            text.replace_range(start..end, replacement);
+            edits.push((start, end, replacement));
+            assert_same(&format!("random_{}_{}", example, step), &source, &edits);
+        }
+    }
+}
+
+#[test]
+fn edit_outside_the_text() {
+    let path = scratch("outside.astc");
+    let edits_path = scratch("outside.json");
+    fs::write(&path, "let a = 1;\n").unwrap();
+    fs::write(&edits_path, edits_json(&[(4, 40, "b")])).unwrap();
+    let output = astc(&["parse", "--edits", &edits_path, &path]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Edit range 4..40 is not within the text (11 bytes)"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,58 @@
+// Lowers the programs under tests/ir at each optimization level and compares
+// the output with the `<name>.O<level>.ir` file next to each of them.
+
+mod common;
+
+use common::{assert_snapshot, astc};
+
+fn snapshot(name: &str) {
+    let source = format!("tests/ir/{}.astc", name);
+    for level in 0..=2 {
+        assert_snapshot(
+            &["ir", &format!("-O{}", level), &source],
+            &format!("tests/ir/{}.O{}.ir", name, level),
+        );
+    }
+}
+
+#[test]
+fn constants() {
+    snapshot("constants");
+}
+
+#[test]
+fn common_subexpressions() {
+    snapshot("common");
+}
+
+#[test]
+fn inlining() {
+    snapshot("inline");
+}
+
+#[test]
+fn branches_meet_in_phis() {
+    snapshot("branches");
+}
+
+#[test]
+fn closures_capture() {
+    snapshot("closures");
+}
+
+#[test]
+fn integer_division_truncates() {
+    snapshot("integers");
+}
+
+#[test]
+fn unknown_level() {
+    let output = astc(&["ir", "-O3", "tests/ir/constants.astc"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Unknown optimization level '-O3'"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,211 @@
+// Transpiles programs with `astc build` and runs the JavaScript with node,
+// which must print what `astc run` prints, then checks the source maps
+// against positions worked out by hand.
+
+mod common;
+
+use std::fs;
+use std::process::Command;
+
+use common::{astc, astc_text, scratch};
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn node_available() -> bool {
+    Command::new("node").arg("--version").output().is_ok()
+}
+
+// What node prints running the file at `path`.
+fn node(path: &str) -> String {
+    let output = Command::new("node")
+        .arg(path)
+        .output()
+        .expect("failed to run node");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn assert_runs_the_same(name: &str, source: &str) {
+    let js = scratch(&format!("js_{}.js", name));
+    let build = astc(&["build", "-o", &js, source]);
+    assert!(
+        build.status.success(),
+        "{}",
+        String::from_utf8_lossy(&build.stderr)
+    );
+    let (expected, _) = astc_text(&["run", source]);
+    assert_eq!(node(&js), expected, "{} prints something else", source);
+}
+
+#[test]
+fn examples_print_what_run_prints() {
+    if !node_available() {
+        eprintln!("node not found, skipping");
+        return;
+    }
+    for name in ["conditional", "example", "if", "lambda", "match"] {
+        assert_runs_the_same(name, &format!("examples/{}.astc", name));
+    }
+    assert_runs_the_same("modules", "examples/modules/main.astc");
+    assert_runs_the_same("integers", "tests/wat/integers.astc");
+}
+
+#[test]
+fn sized_integers_wrap_around() {
+    if !node_available() {
+        eprintln!("node not found, skipping");
+        return;
+    }
+    let source = scratch("js_wrap.astc");
+    fs::write(
+        &source,
+        "let big: u8 = 250;\nlet x: u8 = big + 10;\nlet y: i8 = 100 + 100;\nlet z: u16 = 0 - 1;\nconst inc = (n: u8) => n + 1;\nlet w: u8 = inc(255);\nprint(x, y, z, w, inc(255), 7 / 2);\n",
+    )
+    .unwrap();
+    assert_runs_the_same("wrap", &source);
+    let (output, _) = astc_text(&["run", &source]);
+    assert_eq!(output, "4 -56 65535 0 256 3.5\n");
+}
+
+#[test]
+fn division_by_zero_fails_like_run() {
+    if !node_available() {
+        eprintln!("node not found, skipping");
+        return;
+    }
+    let source = scratch("js_zero.astc");
+    fs::write(&source, "let a = 0;\nprint(1);\nprint(1 / a);\n").unwrap();
+    let js = scratch("js_zero.js");
+    assert!(astc(&["build", "-o", &js, &source]).status.success());
+    let output = Command::new("node").arg(&js).output().unwrap();
+    assert!(!output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Division by zero"));
+}
+
+// One base64 VLQ number from the front of `text`.
+fn vlq(text: &mut &[u8]) -> i64 {
+    let (mut value, mut shift) = (0, 0);
+    loop {
+        let digit = BASE64.iter().position(|&b| b == text[0]).unwrap() as i64;
+        *text = &text[1..];
+        value |= (digit & 0b11111) << shift;
+        shift += 5;
+        if digit & 0b100000 == 0 {
+            break;
+        }
+    }
+    if value & 1 == 1 {
+        -(value >> 1)
+    } else {
+        value >> 1
+    }
+}
+
+// The mappings as absolute (line, column, source, source line, source
+// column) segments.
+fn decode_mappings(mappings: &str) -> Vec<[i64; 5]> {
+    let mut segments = Vec::new();
+    let mut previous = [0; 3];
+    for (line, text) in mappings.split(';').enumerate() {
+        let mut column = 0;
+        for segment in text.split(',').filter(|s| !s.is_empty()) {
+            let mut bytes = segment.as_bytes();
+            column += vlq(&mut bytes);
+            for value in previous.iter_mut() {
+                *value += vlq(&mut bytes);
+            }
+            assert!(bytes.is_empty(), "segment {} has extra fields", segment);
+            let [source, source_line, source_column] = previous;
+            segments.push([line as i64, column, source, source_line, source_column]);
+        }
+    }
+    segments
+}
+
+fn decode_base64(text: &str) -> Vec<u8> {
+    let digits: Vec<u32> = text
+        .bytes()
+        .filter(|&b| b != b'=')
+        .map(|b| BASE64.iter().position(|&c| c == b).unwrap() as u32)
+        .collect();
+    let mut bytes = Vec::new();
+    for chunk in digits.chunks(4) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |n, (i, digit)| n | digit << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            bytes.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    bytes
+}
+
+#[test]
+fn source_map_points_at_the_source() {
+    let source = scratch("js_map.astc");
+    fs::write(
+        &source,
+        "let a: i32 = 1;\nprint(a + 2);\nconst a_rather_long_name_that_moves_the_column = a;\n",
+    )
+    .unwrap();
+    let js = scratch("js_map.js");
+    assert!(astc(&["build", "--source-map", "-o", &js, &source])
+        .status
+        .success());
+
+    let code = fs::read_to_string(&js).unwrap();
+    assert!(code.starts_with("let a = $i32(1);\nconsole.log(a + 2);\n"));
+    assert!(code.ends_with("//# sourceMappingURL=js_map.js.map\n"));
+
+    let map = fs::read_to_string(format!("{}.map", js)).unwrap();
+    let mappings = "AAAA,aAAa;AACb,YAAM,IAAI;AACV,iDAAiD";
+    assert_eq!(
+        map,
+        format!(
+            r#"{{"version":3,"file":"js_map.js","sources":["{}"],"names":[],"mappings":"{}"}}"#,
+            source, mappings
+        )
+    );
+    // `let` and `1`, then `console.log`, `a` and `2`, then `const` and `a`
+    // past column 15, which takes two digits
+    assert_eq!(
+        decode_mappings(mappings),
+        [
+            [0, 0, 0, 0, 0],
+            [0, 13, 0, 0, 13],
+            [1, 0, 0, 1, 0],
+            [1, 12, 0, 1, 6],
+            [1, 16, 0, 1, 10],
+            [2, 0, 0, 2, 0],
+            [2, 49, 0, 2, 49],
+        ]
+    );
+}
+
+#[test]
+fn inline_source_map_is_base64() {
+    let source = scratch("js_inline.astc");
+    fs::write(&source, "let a: i32 = 1;\nprint(a + 2);\n").unwrap();
+    let (code, _) = astc_text(&["build", "--source-map", &source]);
+    let encoded = code
+        .lines()
+        .last()
+        .and_then(|line| line.strip_prefix("//# sourceMappingURL=data:application/json;base64,"))
+        .expect("no inline source map");
+
+    let map = format!(
+        r#"{{"version":3,"sources":["{}"],"names":[],"mappings":"AAAA,aAAa;AACb,YAAM,IAAI"}}"#,
+        source
+    );
+    assert_eq!(String::from_utf8(decode_base64(encoded)).unwrap(), map);
+    // padded to a multiple of 4 digits
+    assert_eq!(encoded.len() % 4, 0);
+    let padding = encoded.bytes().rev().take_while(|&b| b == b'=').count();
+    assert_eq!(padding, (3 - map.len() % 3) % 3);
+}
@@ -0,0 +1,121 @@
+// Reads back the AST JSON that `astc parse --format json` writes, on its own
+// and as a module imported by source files.
+
+mod common;
+
+use std::fs;
+
+use common::{astc_text, scratch};
+
+#[test]
+fn round_trip_keeps_ids_and_spans() {
+    for name in ["conditional", "example", "if", "lambda", "match"] {
+        let source = format!("examples/{}.astc", name);
+        let (json, diagnostics) = astc_text(&["parse", "--format", "json", &source]);
+        assert_eq!(diagnostics, "", "{}", source);
+        let path = scratch(&format!("json_{}.json", name));
+        fs::write(&path, &json).unwrap();
+
+        let (decoded, diagnostics) = astc_text(&["parse", "--format", "json", &path]);
+        assert_eq!(diagnostics, "", "{}", path);
+        assert_eq!(decoded, json, "{} changed on the way back", source);
+
+        let (expected, _) = astc_text(&["run", &source]);
+        assert_eq!(astc_text(&["run", &path]).0, expected, "{}", path);
+    }
+}
+
+#[test]
+fn missing_ids_follow_the_largest() {
+    let path = scratch("json_missing_ids.json");
+    fs::write(
+        &path,
+        r#"{"version": 1, "statements": [
+            {"kind": "Expression", "expression": {"kind": "Number", "value": 1}},
+            {"kind": "Expression", "id": 4, "expression": {"kind": "Number", "value": 2}}
+        ]}"#,
+    )
+    .unwrap();
+    let (json, diagnostics) = astc_text(&["parse", "--format", "json", &path]);
+    assert_eq!(diagnostics, "");
+    let ids: Vec<&str> = json
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("\"id\": "))
+        .collect();
+    assert_eq!(ids, ["5,", "6,", "4,", "7,"]);
+}
+
+#[test]
+fn duplicate_ids_are_rejected() {
+    let path = scratch("json_duplicate.json");
+    fs::write(
+        &path,
+        r#"{"version": 1, "statements": [
+            {"kind": "Expression", "id": 0, "expression": {"kind": "Number", "id": 1, "value": 1}},
+            {"kind": "Expression", "id": 2, "expression": {"kind": "Number", "id": 1, "value": 2}}
+        ]}"#,
+    )
+    .unwrap();
+    let (_, diagnostics) = astc_text(&["parse", &path]);
+    assert!(
+        diagnostics.contains("Invalid AST JSON: Duplicate node id 1"),
+        "{}",
+        diagnostics
+    );
+}
+
+#[test]
+fn invalid_documents_are_rejected() {
+    let cases = [
+        (
+            r#"{"version": 1, "statements": [{"kind": "VarDeclaration", "name": "a", "var_type": "i33", "value": {"kind": "Number", "value": 1}}]}"#.to_string(),
+            "Field `var_type` has unknown type `i33`",
+        ),
+        (
+            r#"{"version": 1, "statements": [{"kind": "Expression", "expression": {"kind": "Lambda", "params": [{"name": "x", "param_type": "int"}], "body": {"kind": "Identifier", "name": "x"}}}]}"#.to_string(),
+            "Field `param_type` has unknown type `int`",
+        ),
+        (
+            r#"{"version": 1, "statements": [{"kind": "Expression", "id": 1e30, "expression": {"kind": "Number", "value": 1}}]}"#.to_string(),
+            "Field `id` must be a non-negative integer",
+        ),
+        (
+            r#"{"version": 1, "statements": [{"kind": "Expression", "id": 4294967295, "expression": {"kind": "Number", "value": 1}}]}"#.to_string(),
+            "Node id 4294967295 is too large",
+        ),
+        (
+            "[".repeat(100_000),
+            "JSON nested more than 512 levels deep at offset 512",
+        ),
+    ];
+    for (i, (json, message)) in cases.iter().enumerate() {
+        let path = scratch(&format!("json_invalid_{}.json", i));
+        fs::write(&path, json).unwrap();
+        let (_, diagnostics) = astc_text(&["parse", &path]);
+        assert!(diagnostics.contains(message), "{}", diagnostics);
+    }
+}
+
+// Both modules number their nodes from 0; the imported one is moved past
+// the importer's ids when it is loaded.
+#[test]
+fn imported_modules_get_their_own_ids() {
+    let library = scratch("json_library.astc");
+    fs::write(
+        &library,
+        "export const label = (flag: bool) => flag ? \"on\" : \"off\";\n",
+    )
+    .unwrap();
+    let (json, _) = astc_text(&["parse", "--format", "json", &library]);
+    fs::write(scratch("json_library.json"), json).unwrap();
+
+    let main = scratch("json_main.astc");
+    fs::write(
+        &main,
+        "import { label } from \"./json_library.json\";\nlet count: i32 = 2;\nprint(label(count > 1), count + 1);\n",
+    )
+    .unwrap();
+    let (output, diagnostics) = astc_text(&["run", &main]);
+    assert_eq!(diagnostics, "");
+    assert_eq!(output, "on 3\n");
+}
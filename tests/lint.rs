@@ -0,0 +1,115 @@
+// Lints files with `astc lint`, one test per rule, and checks the directive
+// comments that change a rule's level and the exit status they lead to.
+
+mod common;
+
+use std::fs;
+
+use common::{astc, scratch};
+
+// What `astc lint` reports for `source`, and its exit status.
+fn lint(name: &str, source: &str) -> (String, Option<i32>) {
+    let path = scratch(&format!("lint_{}.astc", name));
+    fs::write(&path, source).unwrap();
+    let output = astc(&["lint", &path]);
+    let diagnostics = String::from_utf8_lossy(&output.stderr).replace(&path, "main.astc");
+    (diagnostics, output.status.code())
+}
+
+#[test]
+fn unused_variable() {
+    let (diagnostics, status) = lint(
+        "unused",
+        "const a = 1;\nexport const b = 2;\nconst _c = 3;\nconst d = 4;\nprint(d);\n",
+    );
+    assert_eq!(
+        diagnostics,
+        "Warning: main.astc:1:1: Unused constant `a` [unused-variable]\n"
+    );
+    assert_eq!(status, Some(0));
+}
+
+#[test]
+fn shadowing() {
+    let (diagnostics, _) = lint(
+        "shadowing",
+        "const x = 1;\n{\n    const x = 2;\n    print(x);\n}\nconst f = (x: i32) => x;\nprint(x, f(3));\n",
+    );
+    assert_eq!(
+        diagnostics,
+        "Warning: main.astc:3:5: `x` shadows the declaration on line 1 [shadowing]\nWarning: main.astc:6:12: `x` shadows the declaration on line 1 [shadowing]\n"
+    );
+}
+
+#[test]
+fn constant_condition() {
+    let (diagnostics, _) = lint(
+        "condition",
+        "const n = 2;\nif (1 < 2) {\n    print(n);\n}\nif (n < 2) {\n    print(n);\n}\nprint(false ? n : 0);\n",
+    );
+    assert_eq!(
+        diagnostics,
+        "Warning: main.astc:2:5: Condition is always true [constant-condition]\nWarning: main.astc:8:7: Condition is always false [constant-condition]\n"
+    );
+}
+
+#[test]
+fn prefer_const() {
+    let (diagnostics, _) = lint(
+        "prefer_const",
+        "let a = 1 + 2;\nlet b = a * 2;\nconst c = 3;\nprint(a, b, c);\n",
+    );
+    assert_eq!(
+        diagnostics,
+        "Warning: main.astc:1:1: `a` is initialized with a constant, declare it with `const` [prefer-const]\n"
+    );
+}
+
+#[test]
+fn mismatched_comparison() {
+    let (diagnostics, status) = lint(
+        "mismatched",
+        "const s = \"a\";\nprint(s == 1, s == \"b\");\n",
+    );
+    assert_eq!(
+        diagnostics,
+        "Error: main.astc:2:7: `==` between string and number is always false [mismatched-comparison]\n"
+    );
+    assert_eq!(status, Some(1));
+}
+
+#[test]
+fn directives_override_the_defaults() {
+    let (diagnostics, status) = lint(
+        "directives",
+        "// lint: allow(unused-variable, prefer-const)\nlet a = 1;\nconst s = \"a\";\nprint(s == 1);\n// lint: warn(mismatched-comparison)\n",
+    );
+    assert_eq!(
+        diagnostics,
+        "Warning: main.astc:4:7: `==` between string and number is always false [mismatched-comparison]\n"
+    );
+    assert_eq!(status, Some(0));
+}
+
+#[test]
+fn deny_fails_the_lint() {
+    let (diagnostics, status) = lint("deny", "// lint: deny(unused-variable)\nconst a = 1;\n");
+    assert_eq!(
+        diagnostics,
+        "Error: main.astc:2:1: Unused constant `a` [unused-variable]\n"
+    );
+    assert_eq!(status, Some(1));
+}
+
+#[test]
+fn bad_directives_are_reported() {
+    let (diagnostics, status) = lint(
+        "bad_directives",
+        "// lint: forbid(shadowing)\n// lint: allow(nope)\nconst a = 1;\nprint(a);\n",
+    );
+    assert_eq!(
+        diagnostics,
+        "Warning: main.astc:1:1: Malformed lint directive, expected `// lint: allow(rule, ...)`\nWarning: main.astc:2:1: Unknown lint rule `nope`\n"
+    );
+    assert_eq!(status, Some(0));
+}
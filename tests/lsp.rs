@@ -0,0 +1,480 @@
+// Drives `astc lsp` through scripted JSON-RPC sessions: every message is
+// written up front, and the server's messages are compared as the compact
+// JSON it prints, in order.
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+
+const URI: &str = "file:///main.astc";
+
+// The messages the server sends in answer to `messages`, and its exit code.
+fn session(messages: &[String]) -> (Vec<String>, i32) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_astc"))
+        .arg("lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run astc");
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    for message in messages {
+        write!(
+            stdin,
+            "Content-Length: {}\r\n\r\n{}",
+            message.len(),
+            message
+        )
+        .unwrap();
+    }
+    drop(stdin);
+
+    let mut output = String::new();
+    child
+        .stdout
+        .take()
+        .expect("stdout is piped")
+        .read_to_string(&mut output)
+        .unwrap();
+    let status = child.wait().unwrap();
+
+    let mut responses = Vec::new();
+    let mut rest = output.as_str();
+    while let Some(header_end) = rest.find("\r\n\r\n") {
+        let length: usize = rest[..header_end]
+            .strip_prefix("Content-Length: ")
+            .and_then(|length| length.parse().ok())
+            .expect("a Content-Length header");
+        let body = header_end + 4;
+        responses.push(rest[body..body + length].to_string());
+        rest = &rest[body + length..];
+    }
+    assert!(rest.is_empty(), "trailing output: {:?}", rest);
+    (responses, status.code().unwrap_or(-1))
+}
+
+fn request(id: u32, method: &str, params: &str) -> String {
+    format!(
+        r#"{{"jsonrpc":"2.0","id":{},"method":"{}","params":{}}}"#,
+        id, method, params
+    )
+}
+
+fn notification(method: &str, params: &str) -> String {
+    format!(
+        r#"{{"jsonrpc":"2.0","method":"{}","params":{}}}"#,
+        method, params
+    )
+}
+
+fn open(text: &str) -> String {
+    notification(
+        "textDocument/didOpen",
+        &format!(
+            r#"{{"textDocument":{{"uri":"{}","languageId":"astc","version":1,"text":{:?}}}}}"#,
+            URI, text
+        ),
+    )
+}
+
+fn change(version: u32, text: &str) -> String {
+    notification(
+        "textDocument/didChange",
+        &format!(
+            r#"{{"textDocument":{{"uri":"{}","version":{}}},"contentChanges":[{{"text":{:?}}}]}}"#,
+            URI, version, text
+        ),
+    )
+}
+
+// Replaces `start..end`, given as (line, character), with `text`.
+fn edit(version: u32, start: (u32, u32), end: (u32, u32), text: &str) -> String {
+    notification(
+        "textDocument/didChange",
+        &format!(
+            r#"{{"textDocument":{{"uri":"{}","version":{}}},"contentChanges":[{{"range":{},"text":{:?}}}]}}"#,
+            URI,
+            version,
+            range(start, end),
+            text
+        ),
+    )
+}
+
+fn at(id: u32, method: &str, line: u32, character: u32) -> String {
+    request(
+        id,
+        method,
+        &format!(
+            r#"{{"textDocument":{{"uri":"{}"}},"position":{{"line":{},"character":{}}}}}"#,
+            URI, line, character
+        ),
+    )
+}
+
+fn document(id: u32, method: &str) -> String {
+    request(
+        id,
+        method,
+        &format!(r#"{{"textDocument":{{"uri":"{}"}}}}"#, URI),
+    )
+}
+
+fn result(id: u32, result: &str) -> String {
+    format!(r#"{{"jsonrpc":"2.0","id":{},"result":{}}}"#, id, result)
+}
+
+fn published(diagnostics: &str) -> String {
+    format!(
+        r#"{{"jsonrpc":"2.0","method":"textDocument/publishDiagnostics","params":{{"uri":"{}","diagnostics":[{}]}}}}"#,
+        URI, diagnostics
+    )
+}
+
+fn range(start: (u32, u32), end: (u32, u32)) -> String {
+    format!(
+        r#"{{"start":{{"line":{},"character":{}}},"end":{{"line":{},"character":{}}}}}"#,
+        start.0, start.1, end.0, end.1
+    )
+}
+
+fn hover(text: &str, start: (u32, u32), end: (u32, u32)) -> String {
+    format!(
+        r#"{{"contents":{{"kind":"markdown","value":"```astc\n{}\n```"}},"range":{}}}"#,
+        text,
+        range(start, end)
+    )
+}
+
+// Opens `text`, sends `requests` and shuts down, returning the responses to
+// the requests.
+fn with_document(text: &str, requests: &[String]) -> Vec<String> {
+    let mut messages = vec![request(0, "initialize", "{}"), open(text)];
+    messages.extend_from_slice(requests);
+    messages.push(request(99, "shutdown", "null"));
+    messages.push(notification("exit", "null"));
+    let (responses, code) = session(&messages);
+    assert_eq!(code, 0);
+    // initialize, diagnostics, ..., shutdown
+    responses[2..responses.len() - 1].to_vec()
+}
+
+#[test]
+fn lifecycle() {
+    let (responses, code) = session(&[
+        request(1, "initialize", r#"{"capabilities":{}}"#),
+        notification("initialized", "{}"),
+        request(2, "shutdown", "null"),
+        notification("exit", "null"),
+    ]);
+    assert_eq!(
+        responses,
+        [
+            result(
+                1,
+                r#"{"capabilities":{"textDocumentSync":2,"hoverProvider":true,"definitionProvider":true,"documentSymbolProvider":true,"documentFormattingProvider":true},"serverInfo":{"name":"astc"}}"#
+            ),
+            result(2, "null"),
+        ]
+    );
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn exit_without_shutdown() {
+    let (responses, code) = session(&[notification("exit", "null")]);
+    assert!(responses.is_empty());
+    assert_eq!(code, 1);
+}
+
+#[test]
+fn diagnostics_follow_changes() {
+    let (responses, _) = session(&[
+        open("let x: i32 = \"a\";\n"),
+        change(2, "let x: i32 = 1 +;\n"),
+        change(3, "let x: i32 = 1;\nprint(y);\n"),
+        change(4, "let x: i32 = 1;\n"),
+        notification(
+            "textDocument/didClose",
+            &format!(r#"{{"textDocument":{{"uri":"{}"}}}}"#, URI),
+        ),
+    ]);
+    assert_eq!(
+        responses,
+        [
+            published(&format!(
+                r#"{{"range":{},"severity":1,"source":"astc","message":"Mismatched types: `x` is declared as i32 but initialized with string"}}"#,
+                range((0, 13), (0, 16))
+            )),
+            published(&format!(
+                r#"{{"range":{},"severity":1,"source":"astc","message":"Expected expression, found Symbol(';')"}}"#,
+                range((0, 16), (0, 17))
+            )),
+            published(&format!(
+                r#"{{"range":{},"severity":1,"source":"astc","message":"Cannot find `y` in this scope"}}"#,
+                range((1, 6), (1, 7))
+            )),
+            published(""),
+            published(""),
+        ]
+    );
+}
+
+// Typing a number can leave it malformed for a while, which the server
+// reports rather than failing on.
+#[test]
+fn malformed_numbers_are_diagnostics() {
+    let (responses, code) = session(&[
+        open("let a = 1.2.3;\n"),
+        change(2, "let a = 1\u{b2};\n"),
+        change(3, "let a = 1.2;\n"),
+        request(1, "shutdown", "null"),
+        notification("exit", "null"),
+    ]);
+    // the number ends before the character it cannot take
+    let stray = |column, token: &str| {
+        published(&format!(
+            r#"{{"range":{range},"severity":1,"source":"astc","message":"Expected `;` after statement"}},{{"range":{range},"severity":1,"source":"astc","message":"Unexpected token {token}, expected a statement"}}"#,
+            range = range((0, column), (0, column + 1)),
+            token = token
+        ))
+    };
+    assert_eq!(
+        responses,
+        [
+            stray(11, "Symbol('.')"),
+            stray(9, "Unknown('\u{b2}')"),
+            published(""),
+            result(1, "null"),
+        ]
+    );
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn ranged_changes_edit_the_document() {
+    let source = "let x: i32 = 1;\nprint(x);\n";
+    let (responses, _) = session(&[
+        open(source),
+        // `print(x);` to `print(y);`, then declare `y` before it
+        edit(2, (1, 6), (1, 7), "y"),
+        edit(3, (1, 0), (1, 0), "let y: 😀 = x;\n"),
+        // `😀` is two UTF-16 units
+        edit(4, (1, 7), (1, 9), "bool"),
+        at(1, "textDocument/hover", 2, 6),
+        at(2, "textDocument/definition", 1, 14),
+    ]);
+    assert_eq!(
+        responses,
+        [
+            published(""),
+            published(&format!(
+                r#"{{"range":{},"severity":1,"source":"astc","message":"Cannot find `y` in this scope"}}"#,
+                range((1, 6), (1, 7))
+            )),
+            published(&format!(
+                r#"{{"range":{},"severity":1,"source":"astc","message":"Expected type for `y`"}}"#,
+                range((1, 7), (1, 9))
+            )),
+            published(&format!(
+                r#"{{"range":{},"severity":1,"source":"astc","message":"Mismatched types: `y` is declared as bool but initialized with i32"}}"#,
+                range((1, 14), (1, 15))
+            )),
+            result(1, &hover("let y: bool", (2, 6), (2, 7))),
+            result(
+                2,
+                &format!(r#"{{"uri":"{}","range":{}}}"#, URI, range((0, 4), (0, 5)))
+            ),
+        ]
+    );
+}
+
+#[test]
+fn hover_shows_declarations() {
+    let source = "let x: i32 = 1;\n\
+                  const add = (a: i32, b) => a + b;\n\
+                  print(add(x, 2));\n\
+                  let s = \"hi\";\n\
+                  import { sq } from \"./math.astc\";\n";
+    let responses = with_document(
+        source,
+        &[
+            at(1, "textDocument/hover", 2, 10),
+            at(2, "textDocument/hover", 2, 7),
+            at(3, "textDocument/hover", 1, 28),
+            at(4, "textDocument/hover", 1, 32),
+            at(5, "textDocument/hover", 3, 4),
+            at(6, "textDocument/hover", 4, 10),
+            at(7, "textDocument/hover", 2, 14),
+        ],
+    );
+    assert_eq!(
+        responses,
+        [
+            result(1, &hover("let x: i32", (2, 10), (2, 11))),
+            result(
+                2,
+                &hover("const add: (i32, unknown) => i32", (2, 6), (2, 9))
+            ),
+            result(3, &hover("(parameter) a: i32", (1, 27), (1, 28))),
+            result(4, &hover("(parameter) b", (1, 31), (1, 32))),
+            result(5, &hover("let s: string", (3, 4), (3, 5))),
+            result(
+                6,
+                &hover(r#"import { sq } from \"./math.astc\""#, (4, 9), (4, 11))
+            ),
+            result(7, "null"),
+        ]
+    );
+}
+
+#[test]
+fn definition_resolves_names() {
+    let source = "let x = 1;\n\
+                  let f = (x) => x * 2;\n\
+                  print(f(x));\n\
+                  if (x > 0) {\n    let x = 2;\n    print(x);\n}\n";
+    let responses = with_document(
+        source,
+        &[
+            at(1, "textDocument/definition", 1, 15),
+            at(2, "textDocument/definition", 2, 6),
+            at(3, "textDocument/definition", 2, 8),
+            at(4, "textDocument/definition", 5, 10),
+            at(5, "textDocument/definition", 2, 1),
+        ],
+    );
+    let location = |start, end| format!(r#"{{"uri":"{}","range":{}}}"#, URI, range(start, end));
+    assert_eq!(
+        responses,
+        [
+            result(1, &location((1, 9), (1, 10))),
+            result(2, &location((1, 4), (1, 5))),
+            result(3, &location((0, 4), (0, 5))),
+            result(4, &location((4, 8), (4, 9))),
+            // `print` is a builtin
+            result(5, "null"),
+        ]
+    );
+}
+
+#[test]
+fn document_symbols() {
+    let source = "export const limit = 10;\n\
+                  let double = (n: i32) => n * 2;\n\
+                  match (limit) {\n    10 => { let ten = true; }\n    _ => {}\n}\n";
+    let responses = with_document(source, &[document(1, "textDocument/documentSymbol")]);
+    let symbol = |name: &str, detail: &str, kind, whole, selection| {
+        format!(
+            r#"{{"name":"{}","detail":"{}","kind":{},"range":{},"selectionRange":{}}}"#,
+            name, detail, kind, whole, selection
+        )
+    };
+    let symbols = [
+        symbol(
+            "limit",
+            "number",
+            14,
+            range((0, 0), (0, 24)),
+            range((0, 13), (0, 18)),
+        ),
+        symbol(
+            "double",
+            "(i32) => i32",
+            12,
+            range((1, 0), (1, 31)),
+            range((1, 4), (1, 10)),
+        ),
+        symbol(
+            "ten",
+            "bool",
+            13,
+            range((3, 12), (3, 27)),
+            range((3, 16), (3, 19)),
+        ),
+    ];
+    assert_eq!(responses, [result(1, &format!("[{}]", symbols.join(",")))]);
+}
+
+#[test]
+fn formatting_replaces_the_document() {
+    let format = |id| {
+        request(
+            id,
+            "textDocument/formatting",
+            &format!(
+                r#"{{"textDocument":{{"uri":"{}"}},"options":{{"tabSize":2,"insertSpaces":true}}}}"#,
+                URI
+            ),
+        )
+    };
+    let responses = with_document(
+        "if (true) { print(1); }\n",
+        &[
+            format(1),
+            change(2, "if (true) {\n  print(1);\n}\n"),
+            format(2),
+            change(3, "if (true) {\n"),
+            format(3),
+        ],
+    );
+    assert_eq!(
+        responses[0],
+        result(
+            1,
+            &format!(
+                r#"[{{"range":{},"newText":"if (true) {{\n  print(1);\n}}\n"}}]"#,
+                range((0, 0), (1, 0))
+            )
+        )
+    );
+    // already formatted
+    assert_eq!(responses[2], result(2, "[]"));
+    // does not parse
+    assert_eq!(responses[4], result(3, "null"));
+}
+
+#[test]
+fn positions_count_utf16_units() {
+    // `é` is two bytes and one unit, `😀` four bytes and two units
+    let responses = with_document(
+        "let s = \"é😀\"; let t = s;\n",
+        &[at(1, "textDocument/definition", 0, 24)],
+    );
+    assert_eq!(
+        responses,
+        [result(
+            1,
+            &format!(r#"{{"uri":"{}","range":{}}}"#, URI, range((0, 4), (0, 5)))
+        )]
+    );
+}
+
+#[test]
+fn protocol_errors() {
+    let (responses, _) = session(&[
+        request(1, "textDocument/rename", "{}"),
+        "{not json".to_string(),
+        "[".repeat(100_000),
+        document(2, "textDocument/documentSymbol"),
+    ]);
+    assert_eq!(responses.len(), 4);
+    assert_eq!(
+        responses[0],
+        r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"Unknown method 'textDocument/rename'"}}"#
+    );
+    assert!(
+        responses[1].starts_with(r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"#),
+        "{}",
+        responses[1]
+    );
+    assert_eq!(
+        responses[2],
+        r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"JSON nested more than 512 levels deep at offset 512"}}"#
+    );
+    assert_eq!(
+        responses[3],
+        format!(
+            r#"{{"jsonrpc":"2.0","id":2,"error":{{"code":-32602,"message":"Unknown document '{}'"}}}}"#,
+            URI
+        )
+    );
+}
@@ -0,0 +1,87 @@
+// Feeds scripted sessions to `astc repl` on stdin. Prompts go to stdout with
+// the results, so each output below starts with one `>> ` per input read.
+
+mod common;
+
+use common::astc_with_input;
+
+// stdout and stderr of a session.
+fn session(input: &str) -> (String, String) {
+    let output = astc_with_input(&["repl"], input);
+    assert!(output.status.success());
+    (
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+#[test]
+fn evaluates_in_one_environment() {
+    let (output, errors) = session(
+        "let double = (a: i32) => a * 2;\ndouble(21)\nlet s = \"hi\"\ns\nprint(s, double(2));\n",
+    );
+    assert_eq!(errors, "");
+    assert_eq!(output, ">> >> 42\n>> >> \"hi\"\n>> hi 4\n>> \n");
+}
+
+#[test]
+fn continues_while_delimiters_are_open() {
+    let (output, errors) =
+        session("let f = (a: i32) => if (a > 1) {\n  a\n} else {\n  0\n};\nf(3)\n");
+    assert_eq!(errors, "");
+    assert_eq!(output, ">> .. .. .. .. >> 3\n>> \n");
+}
+
+// Input that fails to resolve or type check is reported and not run, and
+// the session goes on without it.
+#[test]
+fn checks_input_before_running_it() {
+    let (output, errors) = session(
+        "let x: u8 = 200;\nprint(y)\nlet t: i32 = \"a\";\nt\nx\nmatch (x) { 1 => { print(1); } }\n",
+    );
+    assert_eq!(
+        errors,
+        "Error: 1:7: Cannot find `y` in this scope\n\
+         Error: 1:14: Mismatched types: `t` is declared as i32 but initialized with string\n\
+         Error: 1:1: Cannot find `t` in this scope\n\
+         Warning: 1:1: Non-exhaustive match on u8: consider adding a `_` arm\n"
+    );
+    assert_eq!(output, ">> >> >> >> >> 200\n>> >> \n");
+}
+
+#[test]
+fn type_command() {
+    let (output, errors) = session(
+        "let f = (a: i32) => a * 2;\n:type f\n:type f(1) > 2\n:type \"s\"\n:type let a = 1;\n",
+    );
+    assert_eq!(errors, "Error: `:type` expects a single expression\n");
+    assert_eq!(output, ">> >> (i32) => i32\n>> bool\n>> string\n>> >> \n");
+}
+
+#[test]
+fn ast_command() {
+    let (output, errors) = session(":ast let x: i32 = 1 + 2\n:ast print(x)\nx\n");
+    assert_eq!(
+        output,
+        ">> Var: x: i32 = (1 + 2)\n>> FunctionCall: print(Ident: x)\n>> >> \n"
+    );
+    // `:ast` does not run the code
+    assert_eq!(errors, "Error: 1:1: Cannot find `x` in this scope\n");
+}
+
+#[test]
+fn tokens_command() {
+    let (output, errors) = session(":tokens let s = \"a\"; // c\n");
+    assert_eq!(errors, "");
+    assert_eq!(
+        output,
+        ">> 1:1 Keyword(\"let\")\n1:5 Ident(\"s\")\n1:7 Symbol('=')\n1:9 StringLiteral(\"a\")\n1:12 Symbol(';')\n>> \n"
+    );
+}
+
+#[test]
+fn reports_unknown_commands() {
+    let (output, errors) = session(":nope\n:quit\nprint(1);\n");
+    assert_eq!(errors, "Error: Unknown command `:nope`, try `:help`\n");
+    assert_eq!(output, ">> >> ");
+}
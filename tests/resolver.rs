@@ -0,0 +1,64 @@
+// Runs programs through `astc run`, which resolves every name before
+// running, and checks which declaration each name finds and what is
+// reported when it finds none.
+
+mod common;
+
+use std::fs;
+
+use common::{astc_text, scratch};
+
+fn run(name: &str, source: &str) -> (String, String) {
+    let path = scratch(&format!("resolver_{}.astc", name));
+    fs::write(&path, source).unwrap();
+    let (output, diagnostics) = astc_text(&["run", &path]);
+    (output, diagnostics.replace(&path, "main.astc"))
+}
+
+#[test]
+fn inner_declarations_shadow_outer_ones() {
+    let (output, diagnostics) = run(
+        "shadowing",
+        "let x = 1;\n{\n    let x = \"inner\";\n    print(x);\n}\nprint(x);\nif (x == 1) {\n    let x = x + 1;\n    print(x);\n}\nconst f = (x: i32) => x * 10;\nprint(f(5), x);\n",
+    );
+    assert_eq!(diagnostics, "");
+    assert_eq!(output, "inner\n1\n2\n50 1\n");
+}
+
+#[test]
+fn names_are_used_after_their_declaration() {
+    let (output, diagnostics) = run(
+        "before_declaration",
+        "print(y);\nlet y = 1;\nlet z = z + 1;\n",
+    );
+    assert_eq!(output, "");
+    assert_eq!(
+        diagnostics,
+        "Error: main.astc:1:7: Cannot find `y` in this scope\nError: main.astc:3:9: Cannot find `z` in this scope\n"
+    );
+}
+
+// A lambda body runs when called, so it sees the whole enclosing scope,
+// itself included.
+#[test]
+fn lambdas_see_later_declarations() {
+    let (output, diagnostics) = run(
+        "lambdas",
+        "const fact = (n: i32) => n < 2 ? 1 : n * fact(n - 1);\nconst later = () => limit;\nlet limit = 7;\nprint(fact(5), later());\n",
+    );
+    assert_eq!(diagnostics, "");
+    assert_eq!(output, "120 7\n");
+}
+
+#[test]
+fn unresolved_names_are_reported() {
+    let (output, diagnostics) = run(
+        "unresolved",
+        "{\n    let w = 1;\n}\nprint(w);\nmissing(1);\nlet v = 2;\nlet v = 3;\n",
+    );
+    assert_eq!(output, "");
+    assert_eq!(
+        diagnostics,
+        "Error: main.astc:4:7: Cannot find `w` in this scope\nError: main.astc:5:1: Cannot find `missing` in this scope\nError: main.astc:7:1: `v` is already declared in this scope\n"
+    );
+}
@@ -0,0 +1,198 @@
+// Runs programs through both the tree-walking interpreter and the bytecode
+// VM and checks they print the same output and errors.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{astc_text as astc, scratch};
+
+fn assert_same(path: &Path) -> (String, String) {
+    let path = path.to_str().unwrap();
+    let interpreted = astc(&["run", path]);
+    let compiled = astc(&["run", "--vm", path]);
+    assert_eq!(
+        interpreted, compiled,
+        "interpreter and VM differ on {}",
+        path
+    );
+    compiled
+}
+
+// Writes `source` to a fresh file and checks both engines agree on it.
+fn check(name: &str, source: &str) -> (String, String) {
+    let path = scratch(&format!("vm_{}.astc", name));
+    fs::write(&path, source).unwrap();
+    assert_same(Path::new(&path))
+}
+
+#[test]
+fn examples() {
+    for name in [
+        "conditional",
+        "example",
+        "if",
+        "lambda",
+        "match",
+        "modules/main",
+    ] {
+        assert_same(Path::new(&format!("examples/{}.astc", name)));
+    }
+}
+
+#[test]
+fn arithmetic_and_strings() {
+    let (out, _) = check(
+        "arithmetic",
+        r#"
+        let a = 7;
+        let b = 2;
+        print(a + b, a - b, a * b, a / b);
+        let s = "ab";
+        print(s + "cd", s == "ab", s < "b", a >= b);
+        "#,
+    );
+    assert_eq!(out, "9 5 14 3.5\nabcd true true true\n");
+}
+
+#[test]
+fn integer_wrapping() {
+    let (out, _) = check(
+        "wrapping",
+        r#"
+        let big = 250;
+        let x: u8 = big + 10;
+        let y: i8 = big;
+        let f = (n: u8) => n;
+        print(x, y, f(big * 2));
+        "#,
+    );
+    assert_eq!(out, "4 -6 244\n");
+}
+
+#[test]
+fn short_circuit() {
+    let (out, _) = check(
+        "logical",
+        r#"
+        let t = true;
+        let f = false;
+        let loud = (v) => print(v) == print(v);
+        print(f && loud("and"), t || loud("or"));
+        print(t && loud("and"), f || 0, "x" && 1);
+        "#,
+    );
+    assert_eq!(out, "false true\nand\nand\ntrue false true\n");
+}
+
+#[test]
+fn branches_and_scopes() {
+    let (out, _) = check(
+        "branches",
+        r#"
+        let x = 3;
+        if (x > 5) {
+            print("big");
+        } else if (x > 2) {
+            let x = "shadow";
+            print(x);
+        } else {
+            print("small");
+        }
+        print(x);
+        let label = x == 1 ? "one" : x == 3 ? "three" : "other";
+        print(label, if (x < 0) { "neg" } else { "pos" });
+        "#,
+    );
+    assert_eq!(out, "shadow\n3\nthree pos\n");
+}
+
+#[test]
+fn match_arms() {
+    let (out, _) = check(
+        "match",
+        r#"
+        let classify = (n) => n;
+        match (classify(150)) {
+            0..100 => { print("low"); }
+            100..=200 | 300 => { print("mid"); }
+            _ => { print("high"); }
+        }
+        match ("b") {
+            "a" => { print("a"); }
+            "b" => { print("b"); }
+        }
+        match (true) {
+            false => { print("no"); }
+        }
+        print("done");
+        "#,
+    );
+    assert_eq!(out, "mid\nb\ndone\n");
+}
+
+#[test]
+fn closures() {
+    let (out, _) = check(
+        "closures",
+        r#"
+        let make = (a) => (b) => (c) => a + b + c;
+        print(make(1)(2)(3));
+        let fib = (n) => n < 2 ? n : fib(n - 1) + fib(n - 2);
+        print(fib(15));
+        let even = (n) => n == 0 ? true : odd(n - 1);
+        let odd = (n) => n == 0 ? false : even(n - 1);
+        print(even(10), odd(7));
+        {
+            let inner = 5;
+            let get = () => inner;
+            print(get(), get == get, get);
+        }
+        "#,
+    );
+    assert_eq!(out, "6\n610\ntrue true\n5 true <function ()>\n");
+}
+
+#[test]
+fn runtime_errors() {
+    // operands pass through `id` so the checker cannot see their types
+    let cases = [
+        ("division", "let z = 0;\nprint(1 / z);", "Division by zero"),
+        (
+            "not_function",
+            "let id = (v) => v;\nlet n = id(1);\nn(2);",
+            "1 is not a function",
+        ),
+        (
+            "arity",
+            "let id = (v) => v;\nlet f = id((a, b) => a);\nf(1);",
+            "Expected 2 argument(s) but 1 were given",
+        ),
+        (
+            "operands",
+            "let id = (v) => v;\nprint(id(\"a\") - 1);",
+            "Cannot apply `-` to a and 1",
+        ),
+        (
+            "ordering",
+            "let id = (v) => v;\nprint(id(true) < 1);",
+            "Cannot compare true with 1",
+        ),
+        (
+            "before_declaration",
+            "let f = () => later;\nprint(\"first\");\nf();\nlet later = 1;",
+            "Undefined variable `later`",
+        ),
+    ];
+    for (name, source, message) in cases {
+        let (_, err) = check(name, source);
+        assert!(
+            err.contains(message),
+            "{}: expected `{}` in {:?}",
+            name,
+            message,
+            err
+        );
+    }
+}
@@ -0,0 +1,60 @@
+// Compiles the programs under tests/wat to WebAssembly text and compares the
+// output with the `.wat` file next to each of them.
+
+mod common;
+
+use std::fs;
+
+use common::{assert_snapshot, astc, scratch};
+
+fn golden(name: &str) {
+    assert_snapshot(
+        &[
+            "build",
+            "--emit",
+            "wat",
+            &format!("tests/wat/{}.astc", name),
+        ],
+        &format!("tests/wat/{}.wat", name),
+    )
+}
+
+#[test]
+fn functions() {
+    golden("functions");
+}
+
+#[test]
+fn integers() {
+    golden("integers");
+}
+
+#[test]
+fn control_flow() {
+    golden("control");
+}
+
+#[test]
+fn modules() {
+    golden("modules/main");
+}
+
+#[test]
+fn unsupported_programs_are_reported() {
+    let path = scratch("wat_closure.astc");
+    fs::write(
+        &path,
+        "let add = (a: i32) => (b: i32) => a + b;\nprint(add(1)(2));\n",
+    )
+    .unwrap();
+
+    let output = astc(&["build", "--emit", "wat", &path]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(
+        stderr.contains("Calling a function value is not supported by the WebAssembly backend"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,77 @@
+;; Generated by astc from tests/wat/control.astc.
+(module
+  (import "astc" "print_newline" (func $astc.print_newline))
+  (import "astc" "print_space" (func $astc.print_space))
+  (import "astc" "print_str" (func $astc.print_str (param i32 i32)))
+  (import "astc" "print_u32" (func $astc.print_u32 (param i32)))
+  (memory (export "memory") 1)
+  (global $total (mut f64) (f64.const 0.0))
+  (global $ready (mut i32) (i32.const 0))
+  (func $main (export "main")
+    (local $total i32)
+    (local $match f64)
+    (local $match_1 i32)
+    (global.set $total (f64.const 10.0))
+    (if
+      (f64.gt (global.get $total) (f64.const 5.0))
+      (then
+        (local.set $total (i32.and (i32.const 700) (i32.const 65535)))
+        (call $astc.print_str (i32.const 0) (i32.const 5))
+        (call $astc.print_space)
+        (call $astc.print_u32 (local.get $total))
+        (call $astc.print_newline))
+      (else
+        (call $astc.print_str (i32.const 5) (i32.const 5))
+        (call $astc.print_newline)))
+    (block $match
+      (local.set $match (global.get $total))
+      (if
+        (i32.or
+          (f64.eq (local.get $match) (f64.const 1.0))
+          (f64.eq (local.get $match) (f64.const 2.0)))
+        (then
+          (call $astc.print_str (i32.const 10) (i32.const 3))
+          (call $astc.print_newline)
+          (br $match)))
+      (if
+        (i32.and
+          (f64.ge (local.get $match) (f64.const 3.0))
+          (f64.lt (local.get $match) (f64.const 10.0)))
+        (then
+          (call $astc.print_str (i32.const 13) (i32.const 4))
+          (call $astc.print_newline)
+          (br $match)))
+      (if
+        (i32.and
+          (f64.ge (local.get $match) (f64.const 10.0))
+          (f64.le (local.get $match) (f64.const 20.0)))
+        (then
+          (call $astc.print_str (i32.const 17) (i32.const 4))
+          (call $astc.print_newline)
+          (br $match)))
+      (call $astc.print_str (i32.const 21) (i32.const 4))
+      (call $astc.print_newline))
+    (global.set $ready
+      (f64.gt (f64.div (global.get $total) (f64.const 4.0)) (f64.const 2.0)))
+    (block $match_1
+      (local.set $match_1 (global.get $ready))
+      (if
+        (i32.eq (local.get $match_1) (i32.const 1))
+        (then
+          (call $astc.print_str (i32.const 25) (i32.const 5))
+          (call $astc.print_newline)
+          (br $match_1)))
+      (if
+        (i32.eq (local.get $match_1) (i32.const 0))
+        (then
+          (call $astc.print_str (i32.const 30) (i32.const 7))
+          (call $astc.print_newline)
+          (br $match_1)))))
+  (data (i32.const 0) "inner")
+  (data (i32.const 5) "small")
+  (data (i32.const 10) "few")
+  (data (i32.const 13) "some")
+  (data (i32.const 17) "many")
+  (data (i32.const 21) "lots")
+  (data (i32.const 25) "ready")
+  (data (i32.const 30) "waiting"))
@@ -0,0 +1,63 @@
+;; Generated by astc from tests/wat/functions.astc.
+(module
+  (import "astc" "print_bool" (func $astc.print_bool (param i32)))
+  (import "astc" "print_f64" (func $astc.print_f64 (param f64)))
+  (import "astc" "print_i32" (func $astc.print_i32 (param i32)))
+  (import "astc" "print_i64" (func $astc.print_i64 (param i64)))
+  (import "astc" "print_newline" (func $astc.print_newline))
+  (import "astc" "print_space" (func $astc.print_space))
+  (import "astc" "print_str" (func $astc.print_str (param i32 i32)))
+  (import "astc" "print_u32" (func $astc.print_u32 (param i32)))
+  (memory (export "memory") 1)
+  (func $double (export "double") (param $a i32) (result i32)
+    (i32.mul (local.get $a) (i32.const 2)))
+  (func $fact (export "fact") (param $n i64) (result i64)
+    (if (result i64)
+      (i64.le_s (local.get $n) (i64.const 1))
+      (then (i64.const 1))
+      (else
+        (i64.mul
+          (local.get $n)
+          (call $fact (i64.sub (local.get $n) (i64.const 1)))))))
+  (func $fib (export "fib") (param $n i32) (result i32)
+    (if (result i32)
+      (i32.lt_u (local.get $n) (i32.const 2))
+      (then (local.get $n))
+      (else
+        (i32.add
+          (call $fib (i32.sub (local.get $n) (i32.const 1)))
+          (call $fib (i32.sub (local.get $n) (i32.const 2)))))))
+  (func $average (export "average") (param $a f64) (param $b f64) (result f64)
+    (f64.div (f64.add (local.get $a) (local.get $b)) (f64.const 2.0)))
+  (func $even (export "even") (param $n i32) (result i32)
+    (if (result i32)
+      (i32.eq (local.get $n) (i32.const 0))
+      (then (i32.const 1))
+      (else (call $odd (i32.sub (local.get $n) (i32.const 1))))))
+  (func $odd (export "odd") (param $n i32) (result i32)
+    (if (result i32)
+      (i32.eq (local.get $n) (i32.const 0))
+      (then (i32.const 0))
+      (else (call $even (i32.sub (local.get $n) (i32.const 1))))))
+  (func $show (export "show") (param $label i32) (param $n i32)
+    (call $astc.print_bool (local.get $label))
+    (call $astc.print_space)
+    (call $astc.print_i32 (local.get $n))
+    (call $astc.print_newline))
+  (func $main (export "main")
+    (call $astc.print_i32 (call $double (i32.const 21)))
+    (call $astc.print_space)
+    (call $astc.print_i64 (call $fact (i64.const 20)))
+    (call $astc.print_space)
+    (call $astc.print_u32 (call $fib (i32.const 20)))
+    (call $astc.print_newline)
+    (call $astc.print_str (i32.const 0) (i32.const 7))
+    (call $astc.print_space)
+    (call $astc.print_f64 (call $average (f64.const 3.0) (f64.const 4.0)))
+    (call $astc.print_newline)
+    (call $astc.print_bool (call $even (i32.const 10)))
+    (call $astc.print_space)
+    (call $astc.print_bool (call $odd (i32.const 7)))
+    (call $astc.print_newline)
+    (call $show (i32.const 1) (i32.const 4)))
+  (data (i32.const 0) "average"))
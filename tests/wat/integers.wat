@@ -0,0 +1,45 @@
+;; Generated by astc from tests/wat/integers.astc.
+(module
+  (import "astc" "print_bool" (func $astc.print_bool (param i32)))
+  (import "astc" "print_i32" (func $astc.print_i32 (param i32)))
+  (import "astc" "print_i64" (func $astc.print_i64 (param i64)))
+  (import "astc" "print_newline" (func $astc.print_newline))
+  (import "astc" "print_space" (func $astc.print_space))
+  (import "astc" "print_u32" (func $astc.print_u32 (param i32)))
+  (global $small (mut i32) (i32.const 0))
+  (global $byte (mut i32) (i32.const 0))
+  (global $wide (mut i64) (i64.const 0))
+  (func $ratio (export "ratio") (param $a i32) (param $b i32) (result i32)
+    (i32.div_s (local.get $a) (local.get $b)))
+  (func $unsigned (export "unsigned") (param $a i32) (param $b i32) (result i32)
+    (i32.div_u (local.get $a) (local.get $b)))
+  (func $grow (export "grow") (param $n i64) (result i64)
+    (i64.mul (local.get $n) (i64.const 1000000000)))
+  (func $main (export "main")
+    (global.set $small (i32.extend8_s (i32.const 100)))
+    (global.set $byte (i32.and (i32.const 200) (i32.const 255)))
+    (global.set $wide (i64.const 3000000000))
+    (call $astc.print_i32 (global.get $small))
+    (call $astc.print_space)
+    (call $astc.print_u32 (i32.add (global.get $byte) (i32.const 100)))
+    (call $astc.print_space)
+    (call $astc.print_i64 (i64.mul (global.get $wide) (i64.const 4)))
+    (call $astc.print_newline)
+    (call $astc.print_i32 (call $ratio (i32.const 7) (i32.const 2)))
+    (call $astc.print_space)
+    (call $astc.print_u32 (call $unsigned (i32.const 4000000000) (i32.const 3)))
+    (call $astc.print_space)
+    (call $astc.print_i64 (call $grow (global.get $wide)))
+    (call $astc.print_newline)
+    (call $astc.print_bool
+      (if (result i32)
+        (i32.gt_u (global.get $byte) (i32.const 100))
+        (then (i64.ne (global.get $wide) (i64.const 0)))
+        (else (i32.const 0))))
+    (call $astc.print_space)
+    (call $astc.print_bool
+      (if (result i32)
+        (i32.lt_u (global.get $byte) (i32.const 100))
+        (then (i32.const 1))
+        (else (i32.const 0))))
+    (call $astc.print_newline)))
@@ -0,0 +1,21 @@
+;; Generated by astc from tests/wat/modules/main.astc.
+(module
+  (import "astc" "print_i32" (func $astc.print_i32 (param i32)))
+  (import "astc" "print_newline" (func $astc.print_newline))
+  (import "astc" "print_space" (func $astc.print_space))
+  (import "astc" "print_u32" (func $astc.print_u32 (param i32)))
+  (global $math.limit (mut i32) (i32.const 0))
+  (func $math.square (param $x i32) (result i32)
+    (i32.mul (local.get $x) (local.get $x)))
+  (func $math.init
+    (global.set $math.limit (i32.and (i32.const 250) (i32.const 255))))
+  (func $area (export "area") (param $w i32) (param $h i32) (result i32)
+    (i32.sub
+      (call $math.square (local.get $w))
+      (call $math.square (local.get $h))))
+  (func $main (export "main")
+    (call $math.init)
+    (call $astc.print_i32 (call $area (i32.const 5) (i32.const 3)))
+    (call $astc.print_space)
+    (call $astc.print_u32 (global.get $math.limit))
+    (call $astc.print_newline)))